@@ -0,0 +1,63 @@
+use std::fmt;
+
+pub use allocator_api2::alloc::{AllocError, Allocator, Global};
+
+/// Handle to the allocator that pools and archetype tables of a scene allocate their components with.
+/// Engines can route ECS memory through their own arenas by implementing [`Allocator`] for them.
+/// An allocator living for the whole program can be turned into a handle with `Box::leak`.
+/// Allocators have to be `Sync`, so pools can be built on other threads, e.g. while loading snapshots.
+///
+/// # Example
+///
+/// ```
+/// use std::alloc::Layout;
+/// use std::ptr::NonNull;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use yarecs::allocator::{AllocError, Allocator, Global};
+/// use yarecs::scene::Scene;
+///
+/// /// Counts the bytes currently allocated through it.
+/// #[derive(Default)]
+/// struct TrackingAllocator {
+///     allocated: AtomicUsize,
+/// }
+///
+/// unsafe impl Allocator for TrackingAllocator {
+///     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+///         self.allocated.fetch_add(layout.size(), Ordering::Relaxed);
+///         Global.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         self.allocated.fetch_sub(layout.size(), Ordering::Relaxed);
+///         Global.deallocate(ptr, layout)
+///     }
+/// }
+///
+/// let tracker: &'static TrackingAllocator = Box::leak(Box::default());
+/// let mut scene = Scene::with_allocator(tracker);
+/// let entity = scene.create_entity();
+/// scene.assign(entity, [0u8; 64]).unwrap();
+///
+/// assert!(tracker.allocated.load(Ordering::Relaxed) >= 64);
+/// ```
+pub type AllocatorRef = &'static (dyn Allocator + Sync);
+
+/// Returns a handle to the global allocator, which scenes use by default.
+pub fn global() -> AllocatorRef {
+    &Global
+}
+
+/// An allocator handle stored in a struct, so the struct can derive `Debug`.
+#[derive(Clone, Copy)]
+pub(crate) struct StoredAllocator(pub(crate) AllocatorRef);
+
+impl fmt::Debug for StoredAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Allocator")
+    }
+}
+
+/// A vector allocated through an allocator handle.
+pub(crate) type AllocVec<T> = allocator_api2::vec::Vec<T, AllocatorRef>;
@@ -0,0 +1,468 @@
+use std::{any::Any, collections::HashMap, fmt};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    allocator::{global, AllocVec, AllocatorRef},
+    component::{get_type_id, TypeIdMap},
+    entity::Entity,
+};
+
+/// Selects where the components of a type are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StorageType {
+    /// One sparse-set pool per component type. Cheap to add and remove components.
+    /// Zero-sized components are stored as bitsets of entity indices instead.
+    #[default]
+    Sparse,
+    /// Dense archetype tables shared by all entities with the same set of table components.
+    /// Iterating several components at once walks contiguous memory, adding and removing components moves the entity between tables.
+    Table,
+    /// One slab per component type, addressed directly by entity index. Lookups skip the sparse indirection,
+    /// but memory grows with the highest entity index, so it suits components that most entities have.
+    Slab,
+    /// A handful of components stored inline and found by a linear search, for types that only a few
+    /// entities have (cameras, players). The components move to a sparse pool once more than
+    /// `SMALL_POOL_CAPACITY` entities have one.
+    Small,
+    /// Equal components are stored once and shared by all entities that have them. Selected with
+    /// `Scene::set_interned`, since it needs `Eq + Hash + Clone`. Interned components are assigned with
+    /// `Scene::assign_interned` and can't be borrowed mutably.
+    Interned,
+}
+
+/// Type-erased column of a table, backed by a vector of `T`.
+pub(crate) trait Column: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Removes and drops the value at `row`, moving the last value into its place.
+    fn swap_remove(&mut self, row: usize);
+    /// Removes and returns the value at `row`, moving the last value into its place.
+    fn swap_remove_boxed(&mut self, row: usize) -> Box<dyn Any>;
+    /// Removes the value at `row`, moving the last value into its place, and pushes it onto `other`.
+    fn swap_remove_into(&mut self, row: usize, other: &mut dyn Column);
+    /// Drops all values, keeping the allocated memory.
+    fn clear(&mut self);
+    fn shrink_to_fit(&mut self);
+}
+
+impl<T: 'static> Column for AllocVec<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn swap_remove(&mut self, row: usize) {
+        AllocVec::swap_remove(self, row);
+    }
+
+    fn swap_remove_boxed(&mut self, row: usize) -> Box<dyn Any> {
+        Box::new(AllocVec::swap_remove(self, row))
+    }
+
+    fn swap_remove_into(&mut self, row: usize, other: &mut dyn Column) {
+        let value = AllocVec::swap_remove(self, row);
+        other
+            .as_any_mut()
+            .downcast_mut::<AllocVec<T>>()
+            .expect("columns of the same component type have the same type")
+            .push(value);
+    }
+
+    fn clear(&mut self) {
+        AllocVec::clear(self);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        AllocVec::shrink_to_fit(self);
+    }
+}
+
+/// Creates an empty column of a fixed component type.
+type ColumnConstructor = fn(AllocatorRef) -> Box<dyn Column>;
+
+fn new_column<T: 'static>(allocator: AllocatorRef) -> Box<dyn Column> {
+    Box::new(AllocVec::<T>::new_in(allocator))
+}
+
+/// Storage for all entities that have exactly the same set of table components.
+/// Row `i` of every column belongs to entity `i` of the table.
+pub struct Table {
+    /// Sorted type IDs of the components stored in this table
+    component_ids: Vec<usize>,
+    /// Entity owning each row
+    entities: Vec<Entity>,
+    /// Component columns keyed by type ID
+    columns: TypeIdMap<Box<dyn Column>>,
+}
+
+impl Table {
+    /// Returns the sorted type IDs of the components stored in this table.
+    pub fn component_ids(&self) -> &[usize] {
+        &self.component_ids
+    }
+
+    /// Returns the entities stored in this table, in row order.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn has_component_with_type_id(&self, type_id: usize) -> bool {
+        self.columns.contains_key(&type_id)
+    }
+
+    /// Returns the column of a component type, in row order.
+    pub fn column<T: 'static>(&self) -> Option<&[T]> {
+        self.columns
+            .get(&get_type_id::<T>())
+            .and_then(|column| column.as_any().downcast_ref::<AllocVec<T>>())
+            .map(AllocVec::as_slice)
+    }
+
+    /// Returns the column of a component type mutably, in row order.
+    pub fn column_mut<T: 'static>(&mut self) -> Option<&mut [T]> {
+        self.columns
+            .get_mut(&get_type_id::<T>())
+            .and_then(|column| column.as_any_mut().downcast_mut::<AllocVec<T>>())
+            .map(AllocVec::as_mut_slice)
+    }
+
+    /// Returns the raw bytes of the column of a plain-old-data component type, in row order,
+    /// e.g. to copy instance data straight into a GPU buffer. [`Table::entities`] has the matching entity order.
+    #[cfg(feature = "bytemuck")]
+    pub fn column_bytes<T: bytemuck::Pod>(&self) -> Option<&[u8]> {
+        self.column::<T>().map(bytemuck::cast_slice)
+    }
+
+    fn clear(&mut self) {
+        self.entities.clear();
+        for column in self.columns.values_mut() {
+            column.clear();
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.entities.shrink_to_fit();
+        for column in self.columns.values_mut() {
+            column.shrink_to_fit();
+        }
+    }
+}
+
+impl fmt::Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("component_ids", &self.component_ids)
+            .field("entities", &self.entities)
+            .finish()
+    }
+}
+
+/// Position of an entity within the archetype tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TableLocation {
+    table: usize,
+    row: usize,
+}
+
+/// Archetype storage: the table components of each entity live in the table matching its component set.
+pub struct Archetypes {
+    tables: Vec<Table>,
+    /// Map of sorted component type IDs to table index
+    table_ids: FxHashMap<Vec<usize>, usize>,
+    /// Location of each entity, indexed by entity index. `None` if the entity has no table components.
+    locations: Vec<Option<TableLocation>>,
+    /// Constructors for empty columns, keyed by type ID
+    column_constructors: TypeIdMap<ColumnConstructor>,
+    /// Allocator for the component columns
+    allocator: AllocatorRef,
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Archetypes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Archetypes")
+            .field("tables", &self.tables)
+            .field("locations", &self.locations)
+            .finish()
+    }
+}
+
+impl Archetypes {
+    pub fn new() -> Self {
+        Self::new_in(global())
+    }
+
+    /// Creates empty archetype storage whose columns allocate their memory with the given allocator.
+    pub fn new_in(allocator: AllocatorRef) -> Self {
+        Archetypes {
+            tables: Vec::new(),
+            table_ids: FxHashMap::default(),
+            locations: Vec::new(),
+            column_constructors: TypeIdMap::default(),
+            allocator,
+        }
+    }
+
+    /// Returns all tables, including empty ones.
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    /// Returns true if a component of this type has ever been stored in a table.
+    pub fn knows_type_id(&self, type_id: usize) -> bool {
+        self.column_constructors.contains_key(&type_id)
+    }
+
+    /// Returns the number of stored components per component type.
+    pub fn component_counts(&self) -> HashMap<usize, usize> {
+        let mut counts = HashMap::new();
+        for table in &self.tables {
+            for &type_id in &table.component_ids {
+                *counts.entry(type_id).or_insert(0) += table.len();
+            }
+        }
+        counts
+    }
+
+    pub fn contains<T: 'static>(&self, entity: Entity) -> bool {
+        self.location(entity)
+            .is_some_and(|location| {
+                self.tables[location.table].has_component_with_type_id(get_type_id::<T>())
+            })
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        let location = self.location(entity)?;
+        self.tables[location.table]
+            .column::<T>()
+            .map(|column| &column[location.row])
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        let location = self.location(entity)?;
+        self.tables[location.table]
+            .column_mut::<T>()
+            .map(|column| &mut column[location.row])
+    }
+
+    /// Returns the entity's component, inserting the result of `f` if it doesn't have one yet.
+    pub fn insert_with<T: 'static>(&mut self, entity: Entity, f: impl FnOnce() -> T) -> &mut T {
+        let type_id = get_type_id::<T>();
+        self.column_constructors
+            .entry(type_id)
+            .or_insert(new_column::<T>);
+
+        if !self.contains::<T>(entity) {
+            let mut component_ids = self.component_ids_of(entity);
+            component_ids.push(type_id);
+            component_ids.sort_unstable();
+            let table = self.get_or_create_table(component_ids);
+            self.move_entity(entity, Some(table), None);
+            self.tables[table]
+                .columns
+                .get_mut(&type_id)
+                .and_then(|column| column.as_any_mut().downcast_mut::<AllocVec<T>>())
+                .expect("table contains a column for each of its component types")
+                .push(f());
+        }
+
+        self.get_mut(entity)
+            .expect("component was found or inserted")
+    }
+
+    /// Removes and drops the entity's component with the given type ID.
+    pub fn remove(&mut self, entity: Entity, type_id: usize) {
+        self.remove_component(entity, type_id, false);
+    }
+
+    /// Removes the entity's component of type `T` and returns it.
+    pub fn take<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.remove_component(entity, get_type_id::<T>(), true)
+            .and_then(|component| component.downcast::<T>().ok())
+            .map(|component| *component)
+    }
+
+    /// Removes and drops all table components of the entity.
+    pub fn remove_entity(&mut self, entity: Entity) {
+        if self.location(entity).is_some() {
+            self.move_entity(entity, None, None);
+        }
+    }
+
+    /// Returns all entities with a table component of the given type.
+    pub fn entities_with(&self, type_id: usize) -> Vec<Entity> {
+        self.matching_tables(&[type_id])
+            .into_iter()
+            .flat_map(|table| self.tables[table].entities.iter().copied())
+            .collect()
+    }
+
+    /// Returns the indices of all non-empty tables containing all of the given component types.
+    pub fn matching_tables(&self, type_ids: &[usize]) -> Vec<usize> {
+        self.tables
+            .iter()
+            .enumerate()
+            .filter(|(_, table)| !table.is_empty())
+            .filter(|(_, table)| type_ids.iter().all(|&id| table.has_component_with_type_id(id)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Renames the entities in `remap` and rebuilds the entity locations, dropping unused entries at the end.
+    pub fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        self.locations.clear();
+        for (table_index, table) in self.tables.iter_mut().enumerate() {
+            for (row, entity) in table.entities.iter_mut().enumerate() {
+                if let Some(&remapped) = remap.get(entity) {
+                    *entity = remapped;
+                }
+                let index = entity.index() as usize;
+                if index >= self.locations.len() {
+                    self.locations.resize(index + 1, None);
+                }
+                self.locations[index] = Some(TableLocation { table: table_index, row });
+            }
+        }
+    }
+
+    /// Removes all entities and components, keeping the tables and their allocated memory.
+    pub fn clear(&mut self) {
+        for table in &mut self.tables {
+            table.clear();
+        }
+        self.locations.clear();
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        for table in &mut self.tables {
+            table.shrink_to_fit();
+        }
+        self.locations.shrink_to_fit();
+    }
+
+    fn location(&self, entity: Entity) -> Option<TableLocation> {
+        let location = (*self.locations.get(entity.index() as usize)?)?;
+        (self.tables[location.table].entities[location.row] == entity).then_some(location)
+    }
+
+    fn component_ids_of(&self, entity: Entity) -> Vec<usize> {
+        self.location(entity)
+            .map(|location| self.tables[location.table].component_ids.clone())
+            .unwrap_or_default()
+    }
+
+    fn get_or_create_table(&mut self, component_ids: Vec<usize>) -> usize {
+        if let Some(&table) = self.table_ids.get(&component_ids) {
+            return table;
+        }
+        let columns = component_ids
+            .iter()
+            .map(|&type_id| (type_id, self.column_constructors[&type_id](self.allocator)))
+            .collect();
+        let table = self.tables.len();
+        self.tables.push(Table {
+            component_ids: component_ids.clone(),
+            entities: Vec::new(),
+            columns,
+        });
+        self.table_ids.insert(component_ids, table);
+        table
+    }
+
+    /// Moves the entity and all components shared by both tables from its current table into `to`.
+    /// Components missing from `to` are dropped, except for the one of type `take`, which is returned.
+    /// Moving to `None` removes all table components.
+    fn move_entity(
+        &mut self,
+        entity: Entity,
+        to: Option<usize>,
+        take: Option<usize>,
+    ) -> Option<Box<dyn Any>> {
+        let index = entity.index() as usize;
+        if index >= self.locations.len() {
+            self.locations.resize(index + 1, None);
+        }
+
+        let mut taken = None;
+        if let Some(from) = self.location(entity) {
+            let (source, mut destination) = match to {
+                Some(to) => {
+                    let (source, destination) = two_tables_mut(&mut self.tables, from.table, to);
+                    (source, Some(destination))
+                }
+                None => (&mut self.tables[from.table], None),
+            };
+            for (&type_id, column) in source.columns.iter_mut() {
+                match destination
+                    .as_mut()
+                    .and_then(|destination| destination.columns.get_mut(&type_id))
+                {
+                    Some(destination_column) => {
+                        column.swap_remove_into(from.row, destination_column.as_mut())
+                    }
+                    None if take == Some(type_id) => taken = Some(column.swap_remove_boxed(from.row)),
+                    None => column.swap_remove(from.row),
+                }
+            }
+            source.entities.swap_remove(from.row);
+            if let Some(&moved) = source.entities.get(from.row) {
+                self.locations[moved.index() as usize] = Some(from);
+            }
+        }
+
+        self.locations[index] = to.map(|table| {
+            self.tables[table].entities.push(entity);
+            TableLocation {
+                table,
+                row: self.tables[table].entities.len() - 1,
+            }
+        });
+        taken
+    }
+
+    /// Moves the entity into the table without the given component type.
+    /// If `keep` is set, the removed component is returned instead of dropped.
+    fn remove_component(&mut self, entity: Entity, type_id: usize, keep: bool) -> Option<Box<dyn Any>> {
+        let location = self.location(entity)?;
+        if !self.tables[location.table].has_component_with_type_id(type_id) {
+            return None;
+        }
+        let component_ids = self.tables[location.table]
+            .component_ids
+            .iter()
+            .copied()
+            .filter(|&id| id != type_id)
+            .collect::<Vec<_>>();
+        let table = (!component_ids.is_empty()).then(|| self.get_or_create_table(component_ids));
+        self.move_entity(entity, table, keep.then_some(type_id))
+    }
+}
+
+/// Borrows two different tables mutably at the same time.
+fn two_tables_mut(tables: &mut [Table], first: usize, second: usize) -> (&mut Table, &mut Table) {
+    assert_ne!(first, second, "an entity never moves into the table it is already in");
+    if first < second {
+        let (left, right) = tables.split_at_mut(second);
+        (&mut left[first], &mut right[0])
+    } else {
+        let (left, right) = tables.split_at_mut(first);
+        (&mut right[0], &mut left[second])
+    }
+}
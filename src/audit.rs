@@ -0,0 +1,107 @@
+use std::{collections::VecDeque, panic::Location};
+
+use crate::entity::Entity;
+
+/// Kind of structural change recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructuralChange {
+    CreateEntity,
+    DestroyEntity,
+    Assign,
+    Remove,
+}
+
+/// A single entry of the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// Change tick of the scene at the time of the change
+    pub tick: u32,
+    pub entity: Entity,
+    pub change: StructuralChange,
+    /// Type name of the affected component, if the change concerned a single component
+    pub component: Option<&'static str>,
+    /// Source location of the call that caused the change
+    pub location: &'static Location<'static>,
+}
+
+/// An inspectable buffer of structural changes made to a scene.
+/// If a capacity is set, the oldest records are discarded once it is exceeded.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    records: VecDeque<AuditRecord>,
+    capacity: Option<usize>,
+}
+
+impl AuditLog {
+    /// Creates an unbounded audit log.
+    pub fn new() -> Self {
+        AuditLog {
+            records: VecDeque::new(),
+            capacity: None,
+        }
+    }
+
+    /// Creates an audit log that only keeps the most recent `capacity` records.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::audit::{AuditLog, AuditRecord, StructuralChange};
+    /// use yarecs::entity::Entity;
+    /// use std::panic::Location;
+    ///
+    /// let mut log = AuditLog::with_capacity(2);
+    /// for index in 0..3 {
+    ///     log.push(AuditRecord {
+    ///         tick: 1,
+    ///         entity: Entity::new(index),
+    ///         change: StructuralChange::CreateEntity,
+    ///         component: None,
+    ///         location: Location::caller(),
+    ///     });
+    /// }
+    ///
+    /// assert_eq!(log.len(), 2);
+    /// assert_eq!(log.records().next().unwrap().entity, Entity::new(1));
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        AuditLog {
+            records: VecDeque::with_capacity(capacity),
+            capacity: Some(capacity),
+        }
+    }
+
+    pub fn push(&mut self, record: AuditRecord) {
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            while self.records.len() >= capacity {
+                self.records.pop_front();
+            }
+        }
+        self.records.push_back(record);
+    }
+
+    /// Returns all records, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = &AuditRecord> {
+        self.records.iter()
+    }
+
+    /// Returns all records concerning the given entity, oldest first.
+    pub fn records_for(&self, entity: Entity) -> impl Iterator<Item = &AuditRecord> {
+        self.records.iter().filter(move |record| record.entity == entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
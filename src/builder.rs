@@ -0,0 +1,100 @@
+use crate::{entity::Entity, scene::Scene};
+
+/// Adds components and children to an entity created with [`Scene::spawn`], so whole objects can be built in one
+/// expression.
+///
+/// # Panics
+///
+/// Adding a component panics if its type uses interned storage, since those components can only be added with
+/// [`Scene::assign_interned`].
+///
+/// # Example
+///
+/// ```
+/// use yarecs::hierarchy::{Children, Parent};
+/// use yarecs::scene::Scene;
+///
+/// struct Vehicle;
+/// struct Wheel {
+///     radius: f32,
+/// }
+///
+/// let mut scene = Scene::new();
+/// let car = scene
+///     .spawn()
+///     .with(Vehicle)
+///     .with_children(|builder| {
+///         for _ in 0..4 {
+///             builder.spawn().with(Wheel { radius: 0.3 });
+///         }
+///     })
+///     .id();
+///
+/// let wheels = scene.get::<Children>(car).unwrap().unwrap().as_slice().to_vec();
+/// assert_eq!(wheels.len(), 4);
+/// assert_eq!(scene.get::<Parent>(wheels[0]).unwrap().unwrap().get(), car);
+/// assert_eq!(scene.get::<Wheel>(wheels[3]).unwrap().unwrap().radius, 0.3);
+/// ```
+pub struct EntityBuilder<'s> {
+    scene: &'s mut Scene,
+    entity: Entity,
+}
+
+impl<'s> EntityBuilder<'s> {
+    pub(crate) fn new(scene: &'s mut Scene, entity: Entity) -> Self {
+        EntityBuilder { scene, entity }
+    }
+
+    /// Assigns a component to the entity, replacing the component it already has.
+    #[track_caller]
+    pub fn with<T: 'static>(self, component: T) -> Self {
+        match self.scene.get_mut::<T>(self.entity) {
+            Ok(Some(existing)) => *existing = component,
+            _ => {
+                if let Err(error) = self.scene.assign(self.entity, component) {
+                    panic!("could not add a component to a spawned entity: {error}");
+                }
+            }
+        }
+        self
+    }
+
+    /// Calls `build` with a builder whose spawned entities become children of this entity, see
+    /// [`Scene::set_parent`]. Children can have children of their own.
+    pub fn with_children(self, build: impl FnOnce(&mut ChildBuilder)) -> Self {
+        build(&mut ChildBuilder { scene: &mut *self.scene, parent: self.entity });
+        self
+    }
+
+    /// Returns the entity being built.
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns the scene the entity is spawned into, e.g. to look up entities to store in components.
+    pub fn scene(&mut self) -> &mut Scene {
+        self.scene
+    }
+}
+
+/// Spawns children of an entity, passed to the closure given to [`EntityBuilder::with_children`].
+pub struct ChildBuilder<'s> {
+    scene: &'s mut Scene,
+    parent: Entity,
+}
+
+impl ChildBuilder<'_> {
+    /// Creates a child of the entity being built and returns a builder for it.
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        let child = self.scene.create_entity();
+        self.scene
+            .set_parent(child, self.parent)
+            .expect("a new entity can be the child of any living entity");
+        EntityBuilder::new(self.scene, child)
+    }
+
+    /// Returns the entity whose children are spawned.
+    pub fn parent(&self) -> Entity {
+        self.parent
+    }
+}
@@ -1,16 +1,38 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::any::TypeId;
-use std::collections::HashMap;
+use std::any::{type_name, TypeId};
+use std::cell::RefCell;
 use std::sync::Mutex;
 
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{archetype::StorageType, registry::TypeRegistration};
+
+/// Hash map keyed by component type IDs.
+/// Type IDs are looked up on every component access, so maps keyed by them use a fast non-cryptographic hasher.
+pub type TypeIdMap<V> = FxHashMap<usize, V>;
+/// Hash set of component type IDs, using the same hasher as [`TypeIdMap`].
+pub type TypeIdSet = FxHashSet<usize>;
+
 lazy_static::lazy_static! {
     // Static counter for generating unique type IDs
     static ref TYPE_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
     // Map of rust type ids to recs type ids
-    static ref TYPE_ID_MAP: Mutex<HashMap<TypeId, usize>> = Mutex::new(HashMap::new());
+    static ref TYPE_ID_MAP: Mutex<FxHashMap<TypeId, usize>> = Mutex::new(FxHashMap::default());
+    // Map of recs type ids to rust type names
+    static ref TYPE_NAME_MAP: Mutex<TypeIdMap<&'static str>> = Mutex::new(TypeIdMap::default());
+    // Map of stable type ids to recs type ids, None for stable ids shared by several types
+    static ref STABLE_TYPE_ID_MAP: Mutex<FxHashMap<u64, Option<usize>>> = Mutex::new(FxHashMap::default());
+}
+
+thread_local! {
+    // Per-thread copy of the type ID map, so known types are looked up without touching the global lock
+    static TYPE_ID_CACHE: RefCell<FxHashMap<TypeId, usize>> = RefCell::new(FxHashMap::default());
 }
 
 /// Returns a unique ID for a type
+///
+/// IDs are shared by all threads and scenes. Only the first lookup of a type on each thread takes
+/// a global lock, later lookups hit a thread-local cache, so threads working on separate scenes don't contend.
 /// 
 /// # Example
 /// 
@@ -26,9 +48,126 @@ lazy_static::lazy_static! {
 /// ```
 pub fn get_type_id<T: 'static>() -> usize {
     let type_id = TypeId::of::<T>();
+    if let Some(id) = TYPE_ID_CACHE.with(|cache| cache.borrow().get(&type_id).copied()) {
+        return id;
+    }
 
     let mut map = TYPE_ID_MAP.lock().unwrap();
-    *map.entry(type_id).or_insert_with(|| TYPE_ID_COUNTER.fetch_add(1, Ordering::Relaxed))
+    let id = *map.entry(type_id).or_insert_with(|| {
+        let id = TYPE_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+        TYPE_NAME_MAP.lock().unwrap().insert(id, type_name::<T>());
+        // Type paths aren't unique, e.g. for two versions of one crate, so a shared stable ID resolves to neither type
+        STABLE_TYPE_ID_MAP
+            .lock()
+            .unwrap()
+            .entry(stable_hash(type_name::<T>()))
+            .and_modify(|existing| *existing = None)
+            .or_insert(Some(id));
+        id
+    });
+    TYPE_ID_CACHE.with(|cache| cache.borrow_mut().insert(type_id, id));
+    id
+}
+
+/// Returns the rust type name for a type ID handed out by `get_type_id`
+/// 
+/// # Example
+/// 
+/// ```
+/// use yarecs::component::{get_type_id, get_type_name};
+/// 
+/// let id = get_type_id::<String>();
+/// assert_eq!(get_type_name(id), Some("alloc::string::String"));
+/// assert_eq!(get_type_name(usize::MAX), None);
+/// ```
+pub fn get_type_name(type_id: usize) -> Option<&'static str> {
+    TYPE_NAME_MAP.lock().unwrap().get(&type_id).copied()
+}
+
+/// Returns an ID for a type derived from a hash of its type path.
+///
+/// The IDs from `get_type_id` depend on the order in which types are first used, so they differ between
+/// processes. Stable IDs only depend on the type path, so serialized data and plugins built with the same
+/// compiler agree on them. The type path comes from `std::any::type_name`, which may change between compiler versions.
+/// Types with the same path, like the same type from two versions of one crate, share a stable ID,
+/// which [`get_type_id_from_stable`] then doesn't resolve.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::component::{get_stable_type_id, get_type_id, get_type_id_from_stable, stable_type_id_of};
+///
+/// struct Health(u32);
+///
+/// let stable_id = get_stable_type_id::<Health>();
+/// assert_eq!(get_type_id_from_stable(stable_id), Some(get_type_id::<Health>()));
+/// assert_eq!(stable_type_id_of(get_type_id::<Health>()), Some(stable_id));
+/// ```
+pub fn get_stable_type_id<T: 'static>() -> u64 {
+    get_type_id::<T>();
+    stable_hash(type_name::<T>())
+}
+
+/// Returns the stable ID of a type ID handed out by `get_type_id`.
+pub fn stable_type_id_of(type_id: usize) -> Option<u64> {
+    get_type_name(type_id).map(stable_hash)
+}
+
+/// Returns the type ID of the type with the given stable ID,
+/// if that type has been used in this process. Returns `None` if several types used in this process
+/// share the stable ID, since type paths aren't guaranteed to be unique.
+pub fn get_type_id_from_stable(stable_id: u64) -> Option<usize> {
+    STABLE_TYPE_ID_MAP.lock().unwrap().get(&stable_id).copied().flatten()
+}
+
+/// 64-bit FNV-1a hash, which is fixed by its specification and therefore the same in every process
+fn stable_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Metadata of a component type: the name it is registered under, where it prefers to be stored,
+/// and which functions to enable when registering it in a [`TypeRegistry`](crate::registry::TypeRegistry).
+///
+/// Any `'static` type can still be used as a component. Implementing this trait, usually with
+/// `#[derive(Component)]`, lets [`Scene::register_component`](crate::scene::Scene::register_component) and
+/// [`TypeRegistry::register_component`](crate::registry::TypeRegistry::register_component) set the type up
+/// without repeating its metadata at every call site.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::archetype::StorageType;
+/// use yarecs::component::Component;
+/// use yarecs::registry::{TypeRegistration, TypeRegistry};
+///
+/// #[derive(Debug, Clone)]
+/// struct Position(f32, f32);
+///
+/// impl Component for Position {
+///     const NAME: &'static str = "Position";
+///     const STORAGE: Option<StorageType> = Some(StorageType::Table);
+///
+///     fn register(registration: TypeRegistration<'_, Self>) -> TypeRegistration<'_, Self> {
+///         registration.with_clone().with_debug()
+///     }
+/// }
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.register_component::<Position>();
+/// assert!(registry.get("Position").unwrap().can_clone());
+/// ```
+pub trait Component: Sized + 'static {
+    /// Name the type is registered under.
+    const NAME: &'static str;
+    /// Storage type to use for the type instead of the scene's default, if any.
+    const STORAGE: Option<StorageType> = None;
+
+    /// Enables the functions the type supports on its registration.
+    fn register(registration: TypeRegistration<'_, Self>) -> TypeRegistration<'_, Self> {
+        registration
+    }
 }
 
 /// Collects type ids from variadic-esque type parameters 
@@ -54,7 +193,7 @@ pub fn get_type_id<T: 'static>() -> usize {
 /// 
 /// assert_eq!(
 ///    variadic_example::<()>(),
-///     Vec::new()
+///     Vec::<usize>::new()
 /// );
 /// ```
 pub trait RequireComponents {
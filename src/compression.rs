@@ -0,0 +1,168 @@
+use std::fmt;
+
+use crate::{error::RecsError, replication::ReplicationUpdate};
+
+/// Marks the start of compressed data
+const MAGIC: [u8; 2] = *b"yr";
+/// Length of the header: magic, codec and uncompressed length
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+
+/// Algorithm used to compress snapshots and replication updates.
+/// Which codecs are available depends on the enabled `lz4` and `zstd` features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    /// Stores the data as is, behind the header
+    None,
+    /// Fast compression with a moderate ratio, suited for rollback buffers and per-frame network updates
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// Slower compression with a better ratio at the given level (1 to 22), suited for autosaves
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Codec {
+    fn id(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => 1,
+            #[cfg(feature = "zstd")]
+            Codec::Zstd(_) => 2,
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Codec::None => write!(f, "none"),
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => write!(f, "lz4"),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd(level) => write!(f, "zstd (level {level})"),
+        }
+    }
+}
+
+/// Compresses the bytes with the codec. The result starts with a small header naming the codec and
+/// the uncompressed length, so [`decompress`] doesn't need to be told how the bytes were compressed.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::compression::{compress, decompress, Codec};
+/// use yarecs::scene::Scene;
+///
+/// let mut scene = Scene::new();
+/// for i in 0..1000u32 {
+///     let entity = scene.create_entity();
+///     scene.assign(entity, i / 100).unwrap();
+/// }
+/// let bytes = scene.pool::<u32>().unwrap().iter().flat_map(|(_, level)| level.to_le_bytes()).collect::<Vec<_>>();
+///
+/// # #[cfg(feature = "lz4")]
+/// let codec = Codec::Lz4;
+/// # #[cfg(not(feature = "lz4"))]
+/// # let codec = Codec::Zstd(3);
+/// let compressed = compress(codec, &bytes).unwrap();
+/// assert!(compressed.len() < bytes.len() / 4);
+/// assert_eq!(decompress(&compressed).unwrap(), bytes);
+/// ```
+pub fn compress(codec: Codec, bytes: &[u8]) -> Result<Vec<u8>, RecsError> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| RecsError::Other(format!("cannot compress {} bytes, at most 4 GiB fit", bytes.len()).into()))?;
+    let mut compressed = Vec::with_capacity(HEADER_LEN + bytes.len() / 2);
+    compressed.extend_from_slice(&MAGIC);
+    compressed.push(codec.id());
+    compressed.extend_from_slice(&len.to_le_bytes());
+    match codec {
+        Codec::None => compressed.extend_from_slice(bytes),
+        #[cfg(feature = "lz4")]
+        Codec::Lz4 => compressed.extend_from_slice(&lz4_flex::block::compress(bytes)),
+        #[cfg(feature = "zstd")]
+        Codec::Zstd(level) => compressed
+            .extend_from_slice(&zstd::bulk::compress(bytes, level).map_err(|e| RecsError::Other(Box::new(e)))?),
+    }
+    Ok(compressed)
+}
+
+/// Decompresses bytes produced by [`compress`].
+/// Fails if the header is missing, or the bytes were compressed with a codec whose feature isn't enabled.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, RecsError> {
+    let (codec, len, data) = read_header(bytes)?;
+    let decompressed = match codec {
+        0 => data.to_vec(),
+        #[cfg(feature = "lz4")]
+        1 => lz4_flex::block::decompress(data, len).map_err(|e| RecsError::Other(Box::new(e)))?,
+        #[cfg(feature = "zstd")]
+        2 => zstd::bulk::decompress(data, len).map_err(|e| RecsError::Other(Box::new(e)))?,
+        #[cfg(not(feature = "lz4"))]
+        1 => return Err(RecsError::Other("decompressing lz4 requires the lz4 feature".into())),
+        #[cfg(not(feature = "zstd"))]
+        2 => return Err(RecsError::Other("decompressing zstd requires the zstd feature".into())),
+        _ => return Err(RecsError::Other(format!("unknown compression codec {codec}").into())),
+    };
+    if decompressed.len() != len {
+        return Err(RecsError::Other(
+            format!("decompressed {} bytes, but {len} were compressed", decompressed.len()).into(),
+        ));
+    }
+    Ok(decompressed)
+}
+
+/// Returns the length the bytes produced by [`compress`] have once decompressed, read from the header.
+pub fn decompressed_len(bytes: &[u8]) -> Result<usize, RecsError> {
+    read_header(bytes).map(|(_, len, _)| len)
+}
+
+/// Splits compressed bytes into codec ID, uncompressed length and compressed data.
+fn read_header(bytes: &[u8]) -> Result<(u8, usize, &[u8]), RecsError> {
+    if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+        return Err(RecsError::Other("bytes weren't compressed by yarecs".into()));
+    }
+    let codec = bytes[MAGIC.len()];
+    let mut len = [0; 4];
+    len.copy_from_slice(&bytes[MAGIC.len() + 1..HEADER_LEN]);
+    Ok((codec, u32::from_le_bytes(len) as usize, &bytes[HEADER_LEN..]))
+}
+
+impl ReplicationUpdate {
+    /// Compresses the encoded component, if there is one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::compression::Codec;
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Inventory(Vec<u32>);
+    ///
+    /// let mut server = Scene::new();
+    /// server.register_replicated::<Inventory>("Inventory", |inventory| {
+    ///     inventory.0.iter().flat_map(|item| item.to_le_bytes()).collect()
+    /// });
+    /// let player = server.create_entity();
+    /// server.assign(player, Inventory(vec![7; 256])).unwrap();
+    ///
+    /// let mut update = server.drain_replication_updates().remove(0);
+    /// let encoded = update.bytes.clone().unwrap();
+    /// update.compress(Codec::None).unwrap();
+    /// update.decompress().unwrap();
+    /// assert_eq!(update.bytes.unwrap(), encoded);
+    /// ```
+    pub fn compress(&mut self, codec: Codec) -> Result<(), RecsError> {
+        if let Some(bytes) = &mut self.bytes {
+            *bytes = compress(codec, bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Decompresses the encoded component, if there is one.
+    pub fn decompress(&mut self) -> Result<(), RecsError> {
+        if let Some(bytes) = &mut self.bytes {
+            *bytes = decompress(bytes)?;
+        }
+        Ok(())
+    }
+}
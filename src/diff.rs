@@ -0,0 +1,189 @@
+use std::{any::Any, fmt};
+
+use crate::{entity::Entity, error::RecsError, mapping::EntityMapper, scene::Scene};
+
+/// Applies type-erased component changes, translating remote entities through the mapper if there is one
+type ApplyFn = fn(&mut Scene, &dyn Any, Option<&EntityMapper>) -> Result<(), RecsError>;
+
+/// Changes of the components of one type between two scenes.
+struct ComponentChanges<T> {
+    /// Components that were added or got a different value
+    changed: Vec<(Entity, T)>,
+    /// Entities that lost the component without being destroyed
+    removed: Vec<Entity>,
+}
+
+/// Type-erased functions to compare the components of one type and to apply the changes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DiffFns {
+    compare: fn(&Scene, &Scene) -> TypeChanges,
+    apply: ApplyFn,
+}
+
+impl DiffFns {
+    pub(crate) fn of<T: Clone + PartialEq + 'static>() -> Self {
+        DiffFns {
+            compare: |old, new| {
+                let changed = new
+                    .view::<(T, ())>()
+                    .filter_map(|entity| {
+                        let value = new.get::<T>(entity).ok()??;
+                        let old_value = old.get::<T>(entity).ok().flatten();
+                        (old_value != Some(value)).then(|| (entity, value.clone()))
+                    })
+                    .collect::<Vec<_>>();
+                let removed = old
+                    .view::<(T, ())>()
+                    .filter(|&entity| matches!(new.get::<T>(entity), Ok(None)))
+                    .collect::<Vec<_>>();
+                TypeChanges {
+                    changed: changed.len(),
+                    removed: removed.len(),
+                    changes: Box::new(ComponentChanges { changed, removed }),
+                    apply: DiffFns::of::<T>().apply,
+                }
+            },
+            apply: |scene, changes, mapper| {
+                let changes = changes
+                    .downcast_ref::<ComponentChanges<T>>()
+                    .ok_or(RecsError::PoolAccessOrCreationError)?;
+                match mapper {
+                    None => scene.assign_batch(&changes.changed)?,
+                    Some(mapper) => {
+                        let changed = changes
+                            .changed
+                            .iter()
+                            .filter_map(|(entity, component)| Some((mapper.local(*entity)?, component.clone())))
+                            .collect::<Vec<_>>();
+                        scene.assign_batch(&changed)?;
+                    }
+                }
+                for &entity in &changes.removed {
+                    match mapper.map_or(Some(entity), |mapper| mapper.local(entity)) {
+                        Some(entity) => scene.remove::<T>(entity)?,
+                        None => continue,
+                    }
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Type-erased changes of one component type, together with the function to apply them.
+pub(crate) struct TypeChanges {
+    changed: usize,
+    removed: usize,
+    changes: Box<dyn Any>,
+    apply: ApplyFn,
+}
+
+impl TypeChanges {
+    fn is_empty(&self) -> bool {
+        self.changed == 0 && self.removed == 0
+    }
+}
+
+/// The differences between two states of a scene, computed with [`Scene::diff`]: which entities were created
+/// and destroyed, and which components were added, changed or removed. Applying it with [`Scene::apply_diff`]
+/// to a scene in the old state brings it into the new state, so diffs can be used for undo stacks
+/// (by diffing in the opposite direction), replication deltas and debugging.
+///
+/// Only components of types registered with [`Scene::register_diff`] are compared.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone, PartialEq, Debug)]
+/// struct Health(u32);
+///
+/// let mut before = Scene::new();
+/// let mut after = Scene::new();
+/// after.register_diff::<Health>();
+/// for scene in [&mut before, &mut after] {
+///     let player = scene.create_entity();
+///     scene.assign(player, Health(100)).unwrap();
+/// }
+/// let player = after.entities()[0];
+///
+/// after.get_mut::<Health>(player).unwrap().unwrap().0 = 80;
+/// let monster = after.create_entity();
+/// after.assign(monster, Health(30)).unwrap();
+///
+/// // Only the damage and the new monster are part of the diff, not the whole scene
+/// let diff = Scene::diff(&before, &after).unwrap();
+/// assert_eq!(diff.created(), &[monster]);
+/// assert_eq!(diff.changed_count(), 2);
+///
+/// before.apply_diff(&diff).unwrap();
+/// assert_eq!(before.get::<Health>(player).unwrap(), Some(&Health(80)));
+/// assert_eq!(before.get::<Health>(monster).unwrap(), Some(&Health(30)));
+/// assert!(Scene::diff(&before, &after).unwrap().is_empty());
+/// ```
+pub struct SceneDiff {
+    pub(crate) created: Vec<Entity>,
+    pub(crate) destroyed: Vec<Entity>,
+    /// Changes of each compared component type, keyed by type ID
+    pub(crate) components: Vec<(usize, TypeChanges)>,
+}
+
+impl SceneDiff {
+    /// Returns the entities that are alive in the new state but not in the old one.
+    pub fn created(&self) -> &[Entity] {
+        &self.created
+    }
+
+    /// Returns the entities that are alive in the old state but not in the new one.
+    pub fn destroyed(&self) -> &[Entity] {
+        &self.destroyed
+    }
+
+    /// Returns the number of components that were added to an entity or got a different value.
+    pub fn changed_count(&self) -> usize {
+        self.components.iter().map(|(_, changes)| changes.changed).sum()
+    }
+
+    /// Returns the number of components that were removed from entities which are still alive.
+    pub fn removed_count(&self) -> usize {
+        self.components.iter().map(|(_, changes)| changes.removed).sum()
+    }
+
+    /// Returns true if both states are equal.
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.destroyed.is_empty() && self.components.is_empty()
+    }
+
+    /// Applies the changes of each component type to the scene,
+    /// translating the entities of the diff to local entities if a mapper is given.
+    /// Changes of entities without a local entity are skipped.
+    pub(crate) fn apply_components(&self, scene: &mut Scene, mapper: Option<&EntityMapper>) -> Result<(), RecsError> {
+        for (_, changes) in &self.components {
+            (changes.apply)(scene, changes.changes.as_ref(), mapper)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SceneDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SceneDiff")
+            .field("created", &self.created)
+            .field("destroyed", &self.destroyed)
+            .field("changed", &self.changed_count())
+            .field("removed", &self.removed_count())
+            .finish()
+    }
+}
+
+/// Compares the components of all registered types, skipping types without changes.
+pub(crate) fn compare_components<'a>(
+    fns: impl Iterator<Item = (&'a usize, &'a DiffFns)>,
+    old: &Scene,
+    new: &Scene,
+) -> Vec<(usize, TypeChanges)> {
+    fns.map(|(&type_id, fns)| (type_id, (fns.compare)(old, new)))
+        .filter(|(_, changes)| !changes.is_empty())
+        .collect()
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use rustc_hash::FxHashMap;
+
+use crate::{entity::Entity, error::RecsError};
+
+/// Primitive type of a field of a dynamic component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Bool,
+    I32,
+    I64,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl FieldType {
+    /// Returns the number of bytes a field of this type takes up in a row.
+    pub fn size(self) -> usize {
+        match self {
+            FieldType::Bool => 1,
+            FieldType::I32 | FieldType::U32 | FieldType::F32 => 4,
+            FieldType::I64 | FieldType::U64 | FieldType::F64 => 8,
+        }
+    }
+}
+
+/// Value of a field of a dynamic component.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+}
+
+impl Value {
+    pub fn field_type(&self) -> FieldType {
+        match self {
+            Value::Bool(_) => FieldType::Bool,
+            Value::I32(_) => FieldType::I32,
+            Value::I64(_) => FieldType::I64,
+            Value::U32(_) => FieldType::U32,
+            Value::U64(_) => FieldType::U64,
+            Value::F32(_) => FieldType::F32,
+            Value::F64(_) => FieldType::F64,
+        }
+    }
+
+    /// Reads a value of the field type from the start of `bytes`, which must be at least as long as the type.
+    fn read(field_type: FieldType, bytes: &[u8]) -> Value {
+        fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+            bytes[..N].try_into().expect("slice has the length of the array")
+        }
+        match field_type {
+            FieldType::Bool => Value::Bool(bytes[0] != 0),
+            FieldType::I32 => Value::I32(i32::from_le_bytes(array(bytes))),
+            FieldType::I64 => Value::I64(i64::from_le_bytes(array(bytes))),
+            FieldType::U32 => Value::U32(u32::from_le_bytes(array(bytes))),
+            FieldType::U64 => Value::U64(u64::from_le_bytes(array(bytes))),
+            FieldType::F32 => Value::F32(f32::from_le_bytes(array(bytes))),
+            FieldType::F64 => Value::F64(f64::from_le_bytes(array(bytes))),
+        }
+    }
+
+    /// Writes the value to the start of `bytes`, which must be at least as long as its type.
+    fn write(&self, bytes: &mut [u8]) {
+        match *self {
+            Value::Bool(value) => bytes[0] = value as u8,
+            Value::I32(value) => bytes[..4].copy_from_slice(&value.to_le_bytes()),
+            Value::I64(value) => bytes[..8].copy_from_slice(&value.to_le_bytes()),
+            Value::U32(value) => bytes[..4].copy_from_slice(&value.to_le_bytes()),
+            Value::U64(value) => bytes[..8].copy_from_slice(&value.to_le_bytes()),
+            Value::F32(value) => bytes[..4].copy_from_slice(&value.to_le_bytes()),
+            Value::F64(value) => bytes[..8].copy_from_slice(&value.to_le_bytes()),
+        }
+    }
+}
+
+/// A field of a [`DynamicLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicField {
+    name: String,
+    field_type: FieldType,
+    offset: usize,
+}
+
+impl DynamicField {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn field_type(&self) -> FieldType {
+        self.field_type
+    }
+
+    /// Returns the position of the field's bytes in a row.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Layout of a component type defined at runtime, like by a script or a mod:
+/// a name and a list of named fields of primitive types. Register it with [`Scene::register_dynamic_component`].
+///
+/// [`Scene::register_dynamic_component`]: crate::scene::Scene::register_dynamic_component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicLayout {
+    name: String,
+    fields: Vec<DynamicField>,
+    size: usize,
+}
+
+impl DynamicLayout {
+    /// Creates a layout without fields.
+    pub fn new(name: impl Into<String>) -> Self {
+        DynamicLayout { name: name.into(), fields: Vec::new(), size: 0 }
+    }
+
+    /// Appends a field to the layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout already has a field with the same name.
+    pub fn with_field(mut self, name: impl Into<String>, field_type: FieldType) -> Self {
+        let name = name.into();
+        assert!(self.field(&name).is_none(), "field {name} is already part of the layout");
+        self.fields.push(DynamicField { name, field_type, offset: self.size });
+        self.size += field_type.size();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the fields in the order they were added.
+    pub fn fields(&self) -> &[DynamicField] {
+        &self.fields
+    }
+
+    /// Returns the field with the given name.
+    pub fn field(&self, name: &str) -> Option<&DynamicField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns the number of bytes of a row holding one component.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Handle of a dynamic component type registered in a scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DynamicComponentId(usize);
+
+/// Components of one dynamic type, stored as rows of bytes without any knowledge of a rust type.
+#[derive(Debug)]
+pub(crate) struct DynamicPool {
+    layout: DynamicLayout,
+    bytes: Vec<u8>,
+    /// Owner of each row
+    entities: Vec<Entity>,
+    rows: FxHashMap<Entity, usize>,
+}
+
+impl DynamicPool {
+    fn new(layout: DynamicLayout) -> Self {
+        DynamicPool { layout, bytes: Vec::new(), entities: Vec::new(), rows: FxHashMap::default() }
+    }
+
+    pub(crate) fn layout(&self) -> &DynamicLayout {
+        &self.layout
+    }
+
+    pub(crate) fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub(crate) fn contains(&self, entity: Entity) -> bool {
+        self.rows.contains_key(&entity)
+    }
+
+    /// Adds a row of zeroes for the entity. Returns false if the entity already has one.
+    pub(crate) fn insert(&mut self, entity: Entity) -> bool {
+        if self.contains(entity) {
+            return false;
+        }
+        self.rows.insert(entity, self.entities.len());
+        self.entities.push(entity);
+        self.bytes.resize(self.bytes.len() + self.layout.size, 0);
+        true
+    }
+
+    /// Removes the row of the entity, moving the last row into its place. Returns false if it had none.
+    pub(crate) fn remove(&mut self, entity: Entity) -> bool {
+        let Some(row) = self.rows.remove(&entity) else {
+            return false;
+        };
+        let size = self.layout.size;
+        let last = self.entities.len() - 1;
+        if row != last {
+            self.bytes.copy_within(last * size..(last + 1) * size, row * size);
+            self.rows.insert(self.entities[last], row);
+        }
+        self.entities.swap_remove(row);
+        self.bytes.truncate(last * size);
+        true
+    }
+
+    /// Returns the value of a field of the entity's row, or `None` if it has none.
+    pub(crate) fn get(&self, entity: Entity, field: &DynamicField) -> Option<Value> {
+        let start = self.rows.get(&entity)? * self.layout.size + field.offset;
+        Some(Value::read(field.field_type, &self.bytes[start..]))
+    }
+
+    /// Overwrites a field of the entity's row. Returns false if it has none.
+    pub(crate) fn set(&mut self, entity: Entity, field: &DynamicField, value: Value) -> bool {
+        let Some(&row) = self.rows.get(&entity) else {
+            return false;
+        };
+        let start = row * self.layout.size + field.offset;
+        value.write(&mut self.bytes[start..]);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.entities.clear();
+        self.rows.clear();
+    }
+}
+
+/// All dynamic component types of a scene and their pools.
+#[derive(Debug, Default)]
+pub(crate) struct DynamicComponents {
+    pools: Vec<DynamicPool>,
+    by_name: FxHashMap<String, DynamicComponentId>,
+}
+
+impl DynamicComponents {
+    pub(crate) fn register(&mut self, layout: DynamicLayout) -> Result<DynamicComponentId, RecsError> {
+        if self.by_name.contains_key(layout.name()) {
+            let message = format!("dynamic component {} is already registered", layout.name());
+            return Err(RecsError::Other(message.into()));
+        }
+        let id = DynamicComponentId(self.pools.len());
+        self.by_name.insert(layout.name().to_string(), id);
+        self.pools.push(DynamicPool::new(layout));
+        Ok(id)
+    }
+
+    pub(crate) fn id(&self, name: &str) -> Option<DynamicComponentId> {
+        self.by_name.get(name).copied()
+    }
+
+    pub(crate) fn pool(&self, id: DynamicComponentId) -> Result<&DynamicPool, RecsError> {
+        self.pools.get(id.0).ok_or_else(|| RecsError::Other("unknown dynamic component".into()))
+    }
+
+    pub(crate) fn pool_mut(&mut self, id: DynamicComponentId) -> Result<&mut DynamicPool, RecsError> {
+        self.pools.get_mut(id.0).ok_or_else(|| RecsError::Other("unknown dynamic component".into()))
+    }
+
+    /// Removes all dynamic components of an entity.
+    pub(crate) fn remove_entity(&mut self, entity: Entity) {
+        for pool in &mut self.pools {
+            pool.remove(entity);
+        }
+    }
+
+    /// Removes the components of all entities, keeping the registered types.
+    pub(crate) fn clear(&mut self) {
+        for pool in &mut self.pools {
+            pool.clear();
+        }
+    }
+
+    pub(crate) fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        for pool in &mut self.pools {
+            for entity in pool.entities.clone() {
+                if !remap.contains_key(&entity) {
+                    pool.remove(entity);
+                }
+            }
+            for entity in &mut pool.entities {
+                *entity = remap[entity];
+            }
+            pool.rows = pool.entities.iter().enumerate().map(|(row, &entity)| (entity, row)).collect();
+        }
+    }
+}
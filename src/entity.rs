@@ -1,40 +1,180 @@
+use std::{fmt, num::NonZeroU32};
 
 /// Entity is a unique identifier for a game object.
+///
+/// Entities are 64 bits wide: a 32-bit index into the scene and a 32-bit version that is bumped every time
+/// the index is reused, so long-running scenes can recycle an index about four billion times before stale
+/// handles can collide. Use [`Entity::to_bits`] and [`Entity::from_bits`] to pass entities around as a `u64`.
+///
+/// Versions never reach `u32::MAX`, which leaves a niche so `Option<Entity>` is as small as `Entity`.
+///
+/// ```
+/// use yarecs::entity::Entity;
+///
+/// assert_eq!(std::mem::size_of::<Option<Entity>>(), std::mem::size_of::<Entity>());
+/// assert_eq!(format!("{:?}", Entity::new(3)), "Entity { version: 0, index: 3 }");
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::NoUninit, bytemuck::CheckedBitPattern))]
+#[repr(C)]
 pub struct Entity {
-    version: u32,
+    version: NonMaxU32,
     index: u32,
 }
 
 impl Entity {
     pub const fn new(index: u32) -> Self {
         Entity {
-            version: 0,
+            version: NonMaxU32::ZERO,
             index,
         }
     }
 
+    /// Creates an entity with the given index and version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `version` is `u32::MAX`.
     pub fn with_version(index: u32, version: u32) -> Self {
         Entity {
-            version,
+            version: NonMaxU32::new(version).expect("entity version must be less than u32::MAX"),
             index,
         }
     }
 
     pub fn version(&self) -> u32 {
-        self.version
+        self.version.get()
     }
 
     pub fn index(&self) -> u32 {
         self.index
     }
 
+    /// Marks the entity as invalid and bumps its version, wrapping around to 0 after `u32::MAX - 1`.
     pub fn invalidate(&mut self) {
         self.index = u32::MAX;
-        self.version += 1;
+        self.version = NonMaxU32::new(self.version() + 1).unwrap_or(NonMaxU32::ZERO);
     }
 
     pub fn is_valid(&self) -> bool {
         self.index != u32::MAX
     }
-}
\ No newline at end of file
+
+    /// Packs the entity into a `u64`, with the version in the high and the index in the low 32 bits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    ///
+    /// let entity = Entity::with_version(7, 3);
+    /// assert_eq!(entity.to_bits(), 3 << 32 | 7);
+    /// assert_eq!(Entity::from_bits(entity.to_bits()), Some(entity));
+    /// assert_eq!(Entity::from_bits(u64::MAX), None);
+    /// ```
+    pub fn to_bits(&self) -> u64 {
+        (self.version() as u64) << 32 | self.index as u64
+    }
+
+    /// Unpacks an entity created with [`Entity::to_bits`].
+    /// Returns `None` if the version bits are `u32::MAX`, which no entity can have.
+    pub fn from_bits(bits: u64) -> Option<Self> {
+        Some(Entity {
+            version: NonMaxU32::new((bits >> 32) as u32)?,
+            index: bits as u32,
+        })
+    }
+}
+
+/// A checked weak reference to an entity, for components that refer to entities they don't own.
+/// Create it with [`Scene::weak`] and resolve it with [`Scene::resolve`], which returns `None` once the entity
+/// was destroyed instead of handing out an entity whose slot was recycled.
+/// Handles created before [`Scene::compact_entities`] moved entities resolve to `None` as well;
+/// create new ones from the returned remap.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::WeakEntity;
+/// use yarecs::scene::Scene;
+///
+/// struct Follow(WeakEntity);
+///
+/// let mut scene = Scene::new();
+/// let leader = scene.create_entity();
+/// let follower = scene.create_entity();
+/// let handle = scene.weak(leader);
+/// scene.assign(follower, Follow(handle)).unwrap();
+/// assert_eq!(scene.resolve(scene.get::<Follow>(follower).unwrap().unwrap().0), Some(leader));
+///
+/// // The slot of the leader is recycled, but the handle doesn't resolve to the new entity
+/// scene.destroy_entity(leader);
+/// let recycled = scene.create_entity();
+/// assert_eq!(recycled.index(), leader.index());
+/// assert_eq!(scene.resolve(handle), None);
+/// ```
+///
+/// [`Scene::weak`]: crate::scene::Scene::weak
+/// [`Scene::resolve`]: crate::scene::Scene::resolve
+/// [`Scene::compact_entities`]: crate::scene::Scene::compact_entities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WeakEntity {
+    entity: Entity,
+    layout: u32,
+}
+
+impl WeakEntity {
+    pub(crate) fn new(entity: Entity, layout: u32) -> Self {
+        WeakEntity { entity, layout }
+    }
+
+    /// Returns the referenced entity without checking whether it's still alive.
+    pub fn entity_unchecked(&self) -> Entity {
+        self.entity
+    }
+
+    pub(crate) fn layout(&self) -> u32 {
+        self.layout
+    }
+}
+
+/// A `u32` that is never `u32::MAX`, stored inverted so the compiler can use `u32::MAX` as a niche.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::NoUninit, bytemuck::CheckedBitPattern))]
+#[repr(transparent)]
+struct NonMaxU32(NonZeroU32);
+
+impl NonMaxU32 {
+    const ZERO: NonMaxU32 = NonMaxU32(NonZeroU32::MAX);
+
+    fn new(value: u32) -> Option<Self> {
+        NonZeroU32::new(!value).map(NonMaxU32)
+    }
+
+    fn get(self) -> u32 {
+        !self.0.get()
+    }
+}
+
+/// Formats the value itself instead of its inverted representation.
+impl fmt::Debug for NonMaxU32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.get(), f)
+    }
+}
+
+/// Entities are serialized as the `u64` returned by [`Entity::to_bits`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for Entity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.to_bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Entity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bits = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+        Entity::from_bits(bits).ok_or_else(|| serde::de::Error::custom(format!("{bits} is not a valid entity")))
+    }
+}
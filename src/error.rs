@@ -5,6 +5,10 @@ use std::error::Error;
 pub enum RecsError {
     PoolAccessOrCreationError,
     InvalidEntityError,
+    /// Snapshot bytes don't match the checksums and counts in their header, with details on what doesn't match
+    CorruptSnapshot(String),
+    /// A component was assigned to an entity without a component it was declared to depend on
+    MissingDependency { component: &'static str, dependency: &'static str },
     Other(Box<dyn Error + Send + Sync>), // Generic error for wrapping others
 }
 
@@ -13,6 +17,10 @@ impl fmt::Display for RecsError {
         match self {
             RecsError::PoolAccessOrCreationError => write!(f, "[RecsError] Error accessing or creating pool. This error is technically impossible. Please open a bug report issue at <url>."),
             RecsError::InvalidEntityError => write!(f, "[RecsError] Invalid entity error"),
+            RecsError::CorruptSnapshot(details) => write!(f, "[RecsError] Corrupt snapshot: {}", details),
+            RecsError::MissingDependency { component, dependency } => {
+                write!(f, "[RecsError] Missing dependency: {} requires {}", component, dependency)
+            }
             RecsError::Other(e) => write!(f, "[RecsError] Other error: {}", e),
         }
     }
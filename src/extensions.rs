@@ -0,0 +1,68 @@
+use std::any::Any;
+
+use crate::component::{get_type_id, TypeIdMap};
+
+/// Type-keyed storage for arbitrary data attached to a scene by engine integrations.
+/// Extensions live separately from resources, so they never collide with user resources of the same type.
+#[derive(Debug, Default)]
+pub struct Extensions {
+    map: TypeIdMap<Box<dyn Any>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Extensions {
+            map: TypeIdMap::default(),
+        }
+    }
+
+    /// Inserts an extension, returning the previous value of the same type if there was one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::extensions::Extensions;
+    ///
+    /// struct EngineHandle(u32);
+    ///
+    /// let mut extensions = Extensions::new();
+    /// assert!(extensions.insert(EngineHandle(1)).is_none());
+    /// assert_eq!(extensions.insert(EngineHandle(2)).unwrap().0, 1);
+    /// assert_eq!(extensions.get::<EngineHandle>().unwrap().0, 2);
+    /// ```
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(get_type_id::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast::<T>().ok().map(|previous| *previous))
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&get_type_id::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&get_type_id::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map
+            .remove(&get_type_id::<T>())
+            .and_then(|value| value.downcast::<T>().ok().map(|value| *value))
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map.contains_key(&get_type_id::<T>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
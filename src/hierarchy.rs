@@ -0,0 +1,108 @@
+use crate::{entity::Entity, scene::Scene};
+
+/// The parent of an entity in a hierarchy, like the vehicle of a wheel or the panel of a button.
+/// Maintained together with the parent's [`Children`] by [`Scene::set_parent`] and [`Scene::remove_parent`].
+///
+/// [`Scene::set_parent`]: crate::scene::Scene::set_parent
+/// [`Scene::remove_parent`]: crate::scene::Scene::remove_parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Parent(pub(crate) Entity);
+
+impl Parent {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// The children of an entity in a hierarchy, in the order they were added.
+/// Maintained together with their [`Parent`] components by [`Scene::set_parent`] and [`Scene::remove_parent`];
+/// entities without children don't have this component.
+///
+/// [`Scene::set_parent`]: crate::scene::Scene::set_parent
+/// [`Scene::remove_parent`]: crate::scene::Scene::remove_parent
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(pub(crate) Vec<Entity>);
+
+impl Children {
+    pub fn as_slice(&self) -> &[Entity] {
+        &self.0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Iterator over the parent of an entity, its parent's parent and so on, returned by [`Scene::ancestors`].
+///
+/// [`Scene::ancestors`]: crate::scene::Scene::ancestors
+pub struct Ancestors<'a> {
+    scene: &'a Scene,
+    next: Option<Entity>,
+}
+
+impl<'a> Ancestors<'a> {
+    pub(crate) fn new(scene: &'a Scene, entity: Entity) -> Self {
+        Ancestors { scene, next: parent_of(scene, entity) }
+    }
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.next?;
+        self.next = parent_of(self.scene, entity);
+        Some(entity)
+    }
+}
+
+/// Iterator over the children of an entity, their children and so on, depth first and in the order children were
+/// added, returned by [`Scene::descendants`].
+///
+/// [`Scene::descendants`]: crate::scene::Scene::descendants
+pub struct Descendants<'a> {
+    scene: &'a Scene,
+    /// Entities still to visit, the next one last
+    stack: Vec<Entity>,
+}
+
+impl<'a> Descendants<'a> {
+    pub(crate) fn new(scene: &'a Scene, entity: Entity) -> Self {
+        let mut descendants = Descendants { scene, stack: Vec::new() };
+        descendants.push_children(entity);
+        descendants
+    }
+
+    fn push_children(&mut self, entity: Entity) {
+        if let Ok(Some(children)) = self.scene.get::<Children>(entity) {
+            self.stack.extend(children.0.iter().rev());
+        }
+    }
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.stack.pop()?;
+        self.push_children(entity);
+        Some(entity)
+    }
+}
+
+fn parent_of(scene: &Scene, entity: Entity) -> Option<Entity> {
+    scene.get::<Parent>(entity).ok().flatten().map(Parent::get)
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::entity::Entity;
+
+/// Callback invoked with the type ID and type name of a component the first time a pool for it is created.
+pub type PoolCreatedHook = Box<dyn FnMut(usize, &'static str)>;
+
+/// Callback invoked whenever the set of component types of an entity changes.
+pub type CompositionChangedHook = Box<dyn FnMut(&CompositionChange)>;
+
+/// Describes how the set of component types of an entity changed,
+/// so caches keyed by composition, like render batches, can update incrementally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompositionChange<'a> {
+    /// The entity whose components changed.
+    pub entity: Entity,
+    /// Type IDs of the components the entity gained.
+    pub added: &'a [usize],
+    /// Type IDs of the components the entity lost.
+    pub removed: &'a [usize],
+}
+
+/// Callbacks registered on a scene.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pool_created: Vec<PoolCreatedHook>,
+    composition_changed: Vec<CompositionChangedHook>,
+}
+
+impl Hooks {
+    pub(crate) fn add_pool_created(&mut self, hook: PoolCreatedHook) {
+        self.pool_created.push(hook);
+    }
+
+    pub(crate) fn pool_created(&mut self, type_id: usize, type_name: &'static str) {
+        for hook in &mut self.pool_created {
+            hook(type_id, type_name);
+        }
+    }
+
+    pub(crate) fn add_composition_changed(&mut self, hook: CompositionChangedHook) {
+        self.composition_changed.push(hook);
+    }
+
+    /// Returns true if any composition hook is registered, so callers can skip collecting changes otherwise.
+    pub(crate) fn observes_composition(&self) -> bool {
+        !self.composition_changed.is_empty()
+    }
+
+    pub(crate) fn composition_changed(&mut self, entity: Entity, added: &[usize], removed: &[usize]) {
+        let change = CompositionChange { entity, added, removed };
+        for hook in &mut self.composition_changed {
+            hook(&change);
+        }
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("pool_created", &self.pool_created.len())
+            .field("composition_changed", &self.composition_changed.len())
+            .finish()
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    error::RecsError,
+    prefab::{PrefabFormat, PrefabLibrary, PrefabReload},
+    registry::TypeRegistry,
+};
+
+/// Watches a directory of prefab files and reloads the ones that changed into a [`PrefabLibrary`], so content
+/// can be tweaked while the game is running. Files are checked for changes of their modification time whenever
+/// [`PrefabWatcher::poll`] is called, e.g. once per second from the main loop. Deleted files are ignored,
+/// their prefabs stay in the library.
+///
+/// # Example
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use serde::{Deserialize, Serialize};
+/// use yarecs::hot_reload::PrefabWatcher;
+/// use yarecs::prefab::{PrefabInstance, PrefabLibrary};
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Health(u32);
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Speed(f32);
+///
+/// let mut types = TypeRegistry::new();
+/// types.register::<Health>("Health").with_clone().with_serde();
+/// types.register::<Speed>("Speed").with_clone().with_serde();
+///
+/// let dir = std::env::temp_dir().join("yarecs_prefab_watcher_example");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let file = dir.join("slime.ron");
+/// std::fs::write(&file, r#"(components: { "Health": (10), "Speed": (1.0) })"#).unwrap();
+///
+/// let mut library = PrefabLibrary::new();
+/// library.load_dir(&dir, &types).unwrap();
+/// let mut watcher = PrefabWatcher::new(&dir).unwrap();
+///
+/// let mut scene = Scene::new();
+/// let slime = library.instantiate_tracked("slime", &mut scene).unwrap();
+/// let boss = library.instantiate_tracked("slime", &mut scene).unwrap();
+/// scene.get_mut::<Health>(boss).unwrap().unwrap().0 = 500;
+/// scene.get_mut::<PrefabInstance>(boss).unwrap().unwrap().mark_overridden::<Health>();
+///
+/// // A designer makes slimes tougher and slower
+/// std::fs::write(&file, r#"(components: { "Health": (25) })"#).unwrap();
+/// # let modified = SystemTime::now() + Duration::from_secs(10);
+/// # std::fs::File::options().write(true).open(&file).unwrap().set_modified(modified).unwrap();
+/// let reload = watcher.poll(&mut library, &types).unwrap();
+/// assert_eq!(reload.names().collect::<Vec<_>>(), ["slime"]);
+/// assert_eq!(library.reapply(&mut scene, &reload).unwrap(), 2);
+///
+/// assert_eq!(scene.get::<Health>(slime).unwrap().unwrap().0, 25);
+/// assert_eq!(scene.get::<Health>(boss).unwrap().unwrap().0, 500);
+/// assert!(scene.get::<Speed>(slime).unwrap().is_none());
+/// assert!(watcher.poll(&mut library, &types).unwrap().is_empty());
+/// # std::fs::remove_dir_all(dir).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PrefabWatcher {
+    dir: PathBuf,
+    /// Modification times of the prefab files when they were last checked
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl PrefabWatcher {
+    /// Starts watching the prefab files of a directory, which are expected to be loaded already.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, RecsError> {
+        let mut watcher = PrefabWatcher { dir: dir.as_ref().to_path_buf(), modified: HashMap::new() };
+        watcher.changed_files()?;
+        Ok(watcher)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Reloads the prefab files that were added or changed since the last call into the library.
+    /// Pass the result to [`PrefabLibrary::reapply`] to update the entities created from the changed prefabs.
+    /// A file that fails to load is reported once and loaded again after its next change.
+    pub fn poll(&mut self, library: &mut PrefabLibrary, types: &TypeRegistry) -> Result<PrefabReload, RecsError> {
+        let changed = self.changed_files()?;
+        if changed.is_empty() {
+            return Ok(PrefabReload::default());
+        }
+        library.reload_files(&changed, types)
+    }
+
+    /// Returns the prefab files that were added or modified since the last check and remembers their times.
+    fn changed_files(&mut self) -> Result<Vec<PathBuf>, RecsError> {
+        let mut changed = Vec::new();
+        let mut modified = HashMap::with_capacity(self.modified.len());
+        for entry in std::fs::read_dir(&self.dir).map_err(|e| RecsError::Other(Box::new(e)))? {
+            let entry = entry.map_err(|e| RecsError::Other(Box::new(e)))?;
+            let path = entry.path();
+            if PrefabFormat::of(&path).is_none() {
+                continue;
+            }
+            let time = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map_err(|e| RecsError::Other(Box::new(e)))?;
+            if self.modified.get(&path) != Some(&time) {
+                changed.push(path.clone());
+            }
+            modified.insert(path, time);
+        }
+        self.modified = modified;
+        changed.sort();
+        Ok(changed)
+    }
+}
@@ -0,0 +1,112 @@
+use std::{any::Any, fmt, hash::Hash};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::entity::Entity;
+
+/// Index from the values of components of one type to the entities that have them, added with
+/// [`Scene::add_index`]. Changes only mark entities as dirty; lookups bring the index up to date first.
+///
+/// [`Scene::add_index`]: crate::scene::Scene::add_index
+#[derive(Debug)]
+pub(crate) struct ValueIndex<T> {
+    entities: FxHashMap<T, FxHashSet<Entity>>,
+    values: FxHashMap<Entity, T>,
+    dirty: FxHashSet<Entity>,
+    rebuild: bool,
+}
+
+impl<T: Eq + Hash + Clone> ValueIndex<T> {
+    pub(crate) fn new() -> Self {
+        ValueIndex {
+            entities: FxHashMap::default(),
+            values: FxHashMap::default(),
+            dirty: FxHashSet::default(),
+            rebuild: true,
+        }
+    }
+
+    /// Re-reads the values of the dirty entities with `value_of`, or of all entities with `all` if the index
+    /// has to be rebuilt.
+    pub(crate) fn refresh(
+        &mut self,
+        value_of: impl Fn(Entity) -> Option<T>,
+        all: impl FnOnce() -> Vec<Entity>,
+    ) {
+        if self.rebuild {
+            self.rebuild = false;
+            self.dirty.clear();
+            self.entities.clear();
+            self.values.clear();
+            for entity in all() {
+                self.update(entity, value_of(entity));
+            }
+            return;
+        }
+        for entity in std::mem::take(&mut self.dirty) {
+            self.update(entity, value_of(entity));
+        }
+    }
+
+    pub(crate) fn get(&self, value: &T) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.get(value).into_iter().flatten().copied()
+    }
+
+    fn update(&mut self, entity: Entity, value: Option<T>) {
+        if let Some(old) = self.values.remove(&entity) {
+            if let Some(entities) = self.entities.get_mut(&old) {
+                entities.remove(&entity);
+                if entities.is_empty() {
+                    self.entities.remove(&old);
+                }
+            }
+        }
+        if let Some(value) = value {
+            self.entities.entry(value.clone()).or_default().insert(entity);
+            self.values.insert(entity, value);
+        }
+    }
+}
+
+/// Type-erased access to a [`ValueIndex`], for marking entities without knowing the component type.
+pub(crate) trait ErasedIndex {
+    fn mark_dirty(&mut self, entity: Entity);
+
+    fn invalidate(&mut self);
+
+    /// Returns the number of indexed entities as of the last lookup.
+    fn len(&self) -> usize;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Eq + Hash + Clone + 'static> ErasedIndex for ValueIndex<T> {
+    fn mark_dirty(&mut self, entity: Entity) {
+        if !self.rebuild {
+            self.dirty.insert(entity);
+            // Re-reading more entities than the index holds is slower than rebuilding it
+            if self.dirty.len() > self.values.len().max(64) {
+                self.invalidate();
+            }
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.rebuild = true;
+        self.dirty.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl fmt::Debug for dyn ErasedIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ErasedIndex").field("len", &self.len()).finish()
+    }
+}
@@ -0,0 +1,108 @@
+/// One of the 64 layers of a scene, registered by name with [`Scene::register_layer`].
+///
+/// [`Scene::register_layer`]: crate::scene::Scene::register_layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Layer(u8);
+
+impl Layer {
+    /// Returns the layer with the given index, or `None` if it's 64 or higher.
+    pub fn new(index: u8) -> Option<Self> {
+        (index < 64).then_some(Layer(index))
+    }
+
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+}
+
+/// The set of layers an entity belongs to, as a bitmask with bit `n` standing for the layer with index `n`.
+/// Used as a component for broad-phase categories like collision or render layers, and as a filter for views
+/// with [`SceneView::in_layers`].
+///
+/// # Example
+///
+/// ```
+/// use yarecs::layer::{Layer, Layers};
+///
+/// let players = Layer::new(0).unwrap();
+/// let projectiles = Layer::new(3).unwrap();
+/// let layers = Layers::NONE.with(players).with(projectiles);
+/// assert_eq!(layers.0, 0b1001);
+/// assert!(layers.contains(projectiles));
+/// assert!(layers.intersects(Layers::from(players)));
+/// assert!(!layers.without(players).contains(players));
+/// ```
+///
+/// [`SceneView::in_layers`]: crate::scene::SceneView::in_layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Layers(pub u64);
+
+impl Layers {
+    pub const NONE: Layers = Layers(0);
+    pub const ALL: Layers = Layers(u64::MAX);
+
+    pub fn with(self, layer: Layer) -> Self {
+        Layers(self.0 | 1 << layer.0)
+    }
+
+    pub fn without(self, layer: Layer) -> Self {
+        Layers(self.0 & !(1 << layer.0))
+    }
+
+    pub fn contains(&self, layer: Layer) -> bool {
+        self.0 & 1 << layer.0 != 0
+    }
+
+    /// Returns true if the sets share at least one layer.
+    pub fn intersects(&self, other: Layers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the layers in the set, ordered by index.
+    pub fn iter(&self) -> impl Iterator<Item = Layer> {
+        let bits = self.0;
+        (0..64).filter(move |index| bits & 1 << index != 0).map(Layer)
+    }
+}
+
+impl From<Layer> for Layers {
+    fn from(layer: Layer) -> Self {
+        Layers::NONE.with(layer)
+    }
+}
+
+impl FromIterator<Layer> for Layers {
+    fn from_iter<I: IntoIterator<Item = Layer>>(layers: I) -> Self {
+        layers.into_iter().fold(Layers::NONE, Layers::with)
+    }
+}
+
+/// Names of the layers registered in a scene, by index.
+#[derive(Debug, Default)]
+pub(crate) struct LayerRegistry {
+    names: Vec<String>,
+}
+
+impl LayerRegistry {
+    /// Returns the layer with the name, registering it if it's new. Returns `None` if all 64 layers are taken.
+    pub(crate) fn register(&mut self, name: &str) -> Option<Layer> {
+        if let Some(layer) = self.get(name) {
+            return Some(layer);
+        }
+        let layer = Layer::new(self.names.len() as u8)?;
+        self.names.push(name.to_owned());
+        Some(layer)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Layer> {
+        self.names.iter().position(|own| own == name).map(|index| Layer(index as u8))
+    }
+
+    pub(crate) fn name(&self, layer: Layer) -> Option<&str> {
+        self.names.get(layer.0 as usize).map(String::as_str)
+    }
+}
@@ -3,3 +3,149 @@ pub mod scene;
 pub mod entity;
 pub mod component;
 pub mod error;
+pub mod resource;
+pub mod extensions;
+pub mod audit;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod hooks;
+pub mod archetype;
+pub mod shared;
+pub mod soa;
+pub mod allocator;
+pub mod query;
+pub mod sharded;
+pub mod split;
+pub mod sync;
+pub mod rollback;
+pub mod diff;
+pub mod replication;
+pub mod mapping;
+pub mod persistent;
+pub mod hierarchy;
+pub mod builder;
+pub mod relationship;
+pub mod name;
+pub mod tag;
+pub mod index;
+pub mod layer;
+#[cfg(feature = "spatial")]
+pub mod spatial;
+#[cfg(feature = "transform")]
+pub mod transform;
+pub mod registry;
+#[cfg(feature = "reflect")]
+pub mod reflect;
+pub mod prefab;
+pub mod subscene;
+pub mod dynamic;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod replay;
+pub mod undo;
+#[cfg(feature = "bytemuck")]
+pub mod snapshot;
+#[cfg(feature = "serde")]
+pub mod serialization;
+#[cfg(feature = "json")]
+pub mod streaming;
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+pub mod compression;
+#[cfg(feature = "transport")]
+pub mod transport;
+
+/// Derives [`soa::SoAComponent`] for a struct with named fields, generating a `<Name>Columns` struct with one `Vec` per field.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+/// use yarecs::SoAComponent;
+///
+/// #[derive(SoAComponent)]
+/// struct Velocity {
+///     dx: f32,
+///     dy: f32,
+/// }
+///
+/// let mut scene = Scene::new();
+/// let entity = scene.create_entity();
+/// scene.assign_soa(entity, Velocity { dx: 1.0, dy: -1.0 }).unwrap();
+///
+/// let columns = scene.soa_pool::<Velocity>().unwrap().columns();
+/// assert_eq!(columns.dx, vec![1.0]);
+/// assert_eq!(columns.dy, vec![-1.0]);
+/// ```
+#[cfg(feature = "derive")]
+pub use yarecs_derive::SoAComponent;
+
+/// Derives [`component::Component`]. The `#[component(...)]` attribute sets the name (the type name by default),
+/// the preferred storage type and the functions to enable when registering the type:
+/// `default`, `clone`, `debug` and `serde`.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::archetype::StorageType;
+/// use yarecs::component::Component;
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+/// use yarecs::Component;
+///
+/// #[derive(Component, Debug, Clone, Default)]
+/// #[component(name = "position", storage = Table, default, clone, debug)]
+/// struct Position(f32, f32);
+///
+/// assert_eq!(Position::NAME, "position");
+///
+/// let mut scene = Scene::new();
+/// scene.register_component::<Position>().unwrap();
+/// assert_eq!(scene.component_storage_type::<Position>(), StorageType::Table);
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.register_component::<Position>();
+/// let info = registry.get("position").unwrap();
+/// assert!(info.can_default() && info.can_clone() && info.can_debug());
+/// ```
+#[cfg(feature = "derive")]
+pub use yarecs_derive::Component;
+
+/// Derives [`reflect::Reflect`] for a struct, describing each of its fields.
+/// Fields of tuple structs are named by their position.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+/// use yarecs::{Component, Reflect};
+///
+/// #[derive(Component, Reflect)]
+/// #[component(reflect)]
+/// struct Velocity {
+///     dx: f32,
+///     dy: f32,
+/// }
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.register_component::<Velocity>();
+///
+/// let mut scene = Scene::new();
+/// let entity = scene.create_entity();
+/// scene.assign(entity, Velocity { dx: 1.0, dy: -1.0 }).unwrap();
+///
+/// let info = registry.get("Velocity").unwrap();
+/// let value = info.get(&scene, entity).unwrap();
+/// let fields = info
+///     .fields()
+///     .unwrap()
+///     .iter()
+///     .map(|field| {
+///         let field_value = field.get(value).unwrap().downcast_ref::<f32>().unwrap();
+///         format!("{}: {} = {:?}", field.name(), field.type_name(), field_value)
+///     })
+///     .collect::<Vec<_>>();
+/// assert_eq!(fields, ["dx: f32 = 1.0", "dy: f32 = -1.0"]);
+/// ```
+#[cfg(all(feature = "derive", feature = "reflect"))]
+pub use yarecs_derive::Reflect;
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::{entity::Entity, scene::Scene};
+
+/// Records which local entity stands for which entity of a remote scene, e.g. of a server, since both scenes
+/// hand out entity IDs independently. Diffs and serialized scenes from the remote side can be applied through
+/// the mapper with [`Scene::apply_diff_mapped`] and, with the `serde` feature, `SceneUpdateDeserializer`,
+/// which create local entities for new remote ones and drop the mappings of entities that died on either side.
+///
+/// Entities stored inside components are not translated.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::{mapping::EntityMapper, scene::Scene};
+///
+/// #[derive(Clone, PartialEq)]
+/// struct Health(u32);
+///
+/// let mut server = Scene::new();
+/// server.register_diff::<Health>();
+/// let mut client = Scene::new();
+/// // The client already has entities of its own, like its camera
+/// let camera = client.create_entity();
+/// let mut mapper = EntityMapper::new();
+///
+/// let player = server.create_entity();
+/// server.assign(player, Health(100)).unwrap();
+/// client.apply_diff_mapped(&Scene::diff(&Scene::new(), &server).unwrap(), &mut mapper).unwrap();
+///
+/// let local_player = mapper.local(player).unwrap();
+/// assert_ne!(local_player, camera);
+/// assert_eq!(client.get::<Health>(local_player).unwrap().unwrap().0, 100);
+/// assert_eq!(mapper.remote(local_player), Some(player));
+///
+/// // Mappings of entities that die locally are cleaned up on the next update
+/// client.destroy_entity(local_player);
+/// mapper.retain_alive(&client);
+/// assert!(mapper.is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct EntityMapper {
+    to_local: HashMap<Entity, Entity>,
+    to_remote: HashMap<Entity, Entity>,
+}
+
+impl EntityMapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `local` stands for `remote`, replacing earlier mappings of either entity.
+    pub fn insert(&mut self, remote: Entity, local: Entity) {
+        self.remove_remote(remote);
+        self.remove_local(local);
+        self.to_local.insert(remote, local);
+        self.to_remote.insert(local, remote);
+    }
+
+    /// Returns the local entity standing for a remote entity.
+    pub fn local(&self, remote: Entity) -> Option<Entity> {
+        self.to_local.get(&remote).copied()
+    }
+
+    /// Returns the remote entity a local entity stands for.
+    pub fn remote(&self, local: Entity) -> Option<Entity> {
+        self.to_remote.get(&local).copied()
+    }
+
+    /// Returns the living local entity standing for a remote entity, creating one in the scene if there is none.
+    pub fn local_or_create(&mut self, remote: Entity, scene: &mut Scene) -> Entity {
+        match self.local(remote) {
+            Some(local) if scene.is_alive(local) => local,
+            _ => {
+                let local = scene.create_entity();
+                self.insert(remote, local);
+                local
+            }
+        }
+    }
+
+    /// Removes the mapping of a remote entity and returns the local entity it had.
+    pub fn remove_remote(&mut self, remote: Entity) -> Option<Entity> {
+        let local = self.to_local.remove(&remote)?;
+        self.to_remote.remove(&local);
+        Some(local)
+    }
+
+    /// Removes the mapping of a local entity and returns the remote entity it had.
+    pub fn remove_local(&mut self, local: Entity) -> Option<Entity> {
+        let remote = self.to_remote.remove(&local)?;
+        self.to_local.remove(&remote);
+        Some(remote)
+    }
+
+    /// Removes the mappings of local entities that are no longer alive in the scene.
+    pub fn retain_alive(&mut self, scene: &Scene) {
+        self.to_remote.retain(|local, _| scene.is_alive(*local));
+        let to_remote = &self.to_remote;
+        self.to_local.retain(|_, local| to_remote.contains_key(local));
+    }
+
+    /// Destroys the local entity standing for a remote entity that died, and removes the mapping.
+    pub fn destroy_remote(&mut self, remote: Entity, scene: &mut Scene) {
+        if let Some(local) = self.remove_remote(remote) {
+            if scene.is_alive(local) {
+                scene.destroy_entity(local);
+            }
+        }
+    }
+
+    /// Returns all mappings as pairs of remote and local entity, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.to_local.iter().map(|(&remote, &local)| (remote, local))
+    }
+
+    pub fn len(&self) -> usize {
+        self.to_local.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_local.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.to_local.clear();
+        self.to_remote.clear();
+    }
+}
@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+
+/// A snapshot of scene counters, cheap to copy out and ship to an external metrics sink.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Change tick at which the snapshot was taken
+    pub tick: u32,
+    /// Number of entities currently alive
+    pub entities_alive: usize,
+    /// Total number of entities created over the lifetime of the scene
+    pub entities_created: u64,
+    /// Total number of entities destroyed over the lifetime of the scene
+    pub entities_destroyed: u64,
+    /// Number of entities created during the current tick
+    pub spawns_this_tick: u64,
+    /// Number of entities destroyed during the current tick
+    pub despawns_this_tick: u64,
+    /// Total number of components added to entities
+    pub components_added: u64,
+    /// Total number of components removed from entities, including removals caused by destroying entities
+    pub components_removed: u64,
+    /// Number of components stored per pool, keyed by component type name
+    pub pool_sizes: Vec<(&'static str, usize)>,
+    /// Accesses to the components of each type, keyed by component type name and sorted by the number of
+    /// lookups, most accessed first. Types at the top are candidates for table storage or caching in a local.
+    pub pool_accesses: Vec<(&'static str, PoolAccess)>,
+}
+
+/// Number of accesses to the components of one type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolAccess {
+    /// Lookups through `Scene::get` and `Scene::get_mut`, including misses
+    pub gets: u64,
+    /// Lookups for entities without a component of the type
+    pub misses: u64,
+    /// Components assigned to entities, including overwrites
+    pub sets: u64,
+}
+
+/// Running counters maintained by a scene while the `metrics` feature is enabled.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    pub(crate) entities_created: u64,
+    pub(crate) entities_destroyed: u64,
+    pub(crate) spawns_this_tick: u64,
+    pub(crate) despawns_this_tick: u64,
+    pub(crate) components_added: u64,
+    pub(crate) components_removed: u64,
+    /// Accesses indexed by component type ID. Lookups only borrow the scene immutably, hence the `RefCell`.
+    pub(crate) pool_accesses: RefCell<Vec<PoolAccess>>,
+}
+
+impl MetricsCounters {
+    pub(crate) fn entity_created(&mut self) {
+        self.entities_created += 1;
+        self.spawns_this_tick += 1;
+    }
+
+    pub(crate) fn entity_destroyed(&mut self, component_count: usize) {
+        self.entities_destroyed += 1;
+        self.despawns_this_tick += 1;
+        self.components_removed += component_count as u64;
+    }
+
+    pub(crate) fn component_added(&mut self) {
+        self.components_added += 1;
+    }
+
+    pub(crate) fn component_removed(&mut self) {
+        self.components_removed += 1;
+    }
+
+    pub(crate) fn component_read(&self, type_id: usize, hit: bool) {
+        self.pool_access(type_id, |access| {
+            access.gets += 1;
+            access.misses += u64::from(!hit);
+        });
+    }
+
+    pub(crate) fn component_written(&self, type_id: usize) {
+        self.pool_access(type_id, |access| access.sets += 1);
+    }
+
+    fn pool_access(&self, type_id: usize, f: impl FnOnce(&mut PoolAccess)) {
+        let mut accesses = self.pool_accesses.borrow_mut();
+        if type_id >= accesses.len() {
+            accesses.resize(type_id + 1, PoolAccess::default());
+        }
+        f(&mut accesses[type_id]);
+    }
+
+    pub(crate) fn tick_advanced(&mut self) {
+        self.spawns_this_tick = 0;
+        self.despawns_this_tick = 0;
+    }
+}
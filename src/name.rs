@@ -0,0 +1,115 @@
+use std::{collections::HashMap, fmt};
+
+use rustc_hash::FxHashMap;
+
+use crate::entity::Entity;
+
+/// A human-readable name of an entity, like "Player" or "Main Camera". Set it with [`Scene::set_name`],
+/// which keeps the index behind [`Scene::find_by_name`] up to date; the [`NamePolicy`] of the scene decides
+/// whether several entities may share a name.
+///
+/// [`Scene::set_name`]: crate::scene::Scene::set_name
+/// [`Scene::find_by_name`]: crate::scene::Scene::find_by_name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Name(pub(crate) String);
+
+impl Name {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Names are serialized as strings. Deserialized names aren't indexed until [`Scene::reindex_names`] is called.
+///
+/// [`Scene::reindex_names`]: crate::scene::Scene::reindex_names
+#[cfg(feature = "serde")]
+impl serde::Serialize for Name {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Name {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer).map(Name)
+    }
+}
+
+/// Whether entities of a scene may share a name, set with [`Scene::set_name_policy`].
+///
+/// [`Scene::set_name_policy`]: crate::scene::Scene::set_name_policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NamePolicy {
+    /// Any number of entities may have the same name
+    #[default]
+    AllowDuplicates,
+    /// Giving an entity a name another living entity has fails
+    Unique,
+    /// A name another living entity has gets a numbered suffix, so "Enemy" becomes "Enemy (1)", "Enemy (2)" and so on
+    MakeUnique,
+}
+
+/// Index from names to the entities that were given them.
+/// Entries may be stale if names are removed directly or entities moved, so lookups have to verify them.
+#[derive(Debug, Default)]
+pub(crate) struct NameIndex {
+    entities: FxHashMap<String, Vec<Entity>>,
+}
+
+impl NameIndex {
+    pub(crate) fn insert(&mut self, name: &str, entity: Entity) {
+        match self.entities.get_mut(name) {
+            Some(entities) if entities.contains(&entity) => {}
+            Some(entities) => entities.push(entity),
+            None => {
+                self.entities.insert(name.to_owned(), vec![entity]);
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, name: &str, entity: Entity) {
+        if let Some(entities) = self.entities.get_mut(name) {
+            entities.retain(|&named| named != entity);
+            if entities.is_empty() {
+                self.entities.remove(name);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> &[Entity] {
+        self.entities.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces the entities of all entries after the entities of the scene were compacted.
+    pub(crate) fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        for entities in self.entities.values_mut() {
+            entities.retain_mut(|entity| match remap.get(entity) {
+                Some(&remapped) => {
+                    *entity = remapped;
+                    true
+                }
+                None => false,
+            });
+        }
+        self.entities.retain(|_, entities| !entities.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt,
+    hash::{BuildHasher, Hasher},
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::entity::Entity;
+
+/// A random 128 bit ID identifying an entity across saves, loads and scenes, unlike [`Entity`] IDs,
+/// whose indices and versions are recycled. Scenes with [`Scene::enable_persistent_ids`] assign one
+/// to every new entity, and [`Scene::find_by_persistent_id`] finds the entity that has it.
+///
+/// Persistent IDs are formatted like UUIDs.
+///
+/// [`Scene::enable_persistent_ids`]: crate::scene::Scene::enable_persistent_ids
+/// [`Scene::find_by_persistent_id`]: crate::scene::Scene::find_by_persistent_id
+///
+/// # Example
+///
+/// ```
+/// use yarecs::persistent::PersistentId;
+///
+/// let id = PersistentId(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+/// assert_eq!(id.to_string(), "01234567-89ab-cdef-0123-456789abcdef");
+/// assert_eq!("01234567-89ab-cdef-0123-456789abcdef".parse(), Ok(id));
+/// assert_ne!(PersistentId::generate(), PersistentId::generate());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PersistentId(pub u128);
+
+impl PersistentId {
+    /// Generates a random ID. Every call mixes a process-wide counter, the time and the random keys
+    /// of the standard library's hasher, so collisions are practically impossible.
+    pub fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos());
+        let half = |salt: u64| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(salt);
+            hasher.write_u64(count);
+            hasher.write_u128(nanos);
+            hasher.finish() as u128
+        };
+        PersistentId(half(0) << 64 | half(1))
+    }
+}
+
+impl fmt::Display for PersistentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex = format!("{:032x}", self.0);
+        write!(f, "{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+    }
+}
+
+impl FromStr for PersistentId {
+    type Err = std::num::ParseIntError;
+
+    /// Parses an ID formatted like a UUID, with or without dashes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u128::from_str_radix(&s.replace('-', ""), 16).map(PersistentId)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PersistentId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PersistentId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = <String as serde::Deserialize>::deserialize(deserializer)?;
+        id.parse().map_err(|_| serde::de::Error::custom(format!("{id} is not a valid persistent ID")))
+    }
+}
+
+/// Index from persistent IDs to the entities that were given them.
+/// Entries may be stale if IDs are reassigned or entities destroyed, so lookups have to verify them.
+#[derive(Debug, Default)]
+pub(crate) struct PersistentIdIndex {
+    entities: FxHashMap<PersistentId, Entity>,
+}
+
+impl PersistentIdIndex {
+    pub(crate) fn insert(&mut self, id: PersistentId, entity: Entity) {
+        self.entities.insert(id, entity);
+    }
+
+    pub(crate) fn remove(&mut self, id: PersistentId) {
+        self.entities.remove(&id);
+    }
+
+    pub(crate) fn get(&self, id: PersistentId) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    /// Replaces the entities with their new handles, dropping entries of entities that aren't remapped.
+    pub(crate) fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        self.entities.retain(|_, entity| match remap.get(entity) {
+            Some(&remapped) => {
+                *entity = remapped;
+                true
+            }
+            None => false,
+        });
+    }
+}
@@ -1,79 +1,2085 @@
-use std::collections::HashMap;
+use std::{
+    any::Any,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
 
-use crate::entity::Entity;
+use allocator_api2::boxed::Box as AllocBox;
+use rustc_hash::{FxBuildHasher, FxHashMap};
 
+use crate::{
+    allocator::{global, AllocVec, AllocatorRef},
+    component::get_type_id,
+    entity::Entity,
+};
+#[cfg(feature = "bytemuck")]
+use crate::error::RecsError;
+
+/// Marks an entity index without a component in the sparse array
+const EMPTY: u32 = u32::MAX;
+/// Number of entries in each page of a sparse array
+const PAGE_SIZE: usize = 4096;
+
+/// Map of entity index to dense index, split into fixed-size pages that are allocated on demand.
+/// A pool holding few components of entities with high indices only pays for the pages it touches.
+#[derive(Clone)]
+pub(crate) struct SparseArray {
+    pages: Vec<Option<AllocBox<[u32], AllocatorRef>>>,
+    allocator: AllocatorRef,
+    reuse: ReuseCounter,
+}
+
+impl Default for SparseArray {
+    fn default() -> Self {
+        Self::new_in(global())
+    }
+}
+
+impl SparseArray {
+    pub(crate) fn new_in(allocator: AllocatorRef) -> Self {
+        SparseArray {
+            pages: Vec::new(),
+            allocator,
+            reuse: ReuseCounter::default(),
+        }
+    }
+
+    /// Returns the dense index stored for the entity index, if any.
+    pub(crate) fn get(&self, index: usize) -> Option<u32> {
+        let page = self.pages.get(index / PAGE_SIZE)?.as_ref()?;
+        Some(page[index % PAGE_SIZE]).filter(|&dense_index| dense_index != EMPTY)
+    }
+
+    /// # Safety
+    ///
+    /// The array must hold a dense index for `index`.
+    #[cfg(feature = "unchecked")]
+    pub(crate) unsafe fn get_unchecked(&self, index: usize) -> u32 {
+        // SAFETY: the caller guarantees that the page exists and holds the index
+        unsafe {
+            let page = self.pages.get_unchecked(index / PAGE_SIZE).as_ref().unwrap_unchecked();
+            *page.get_unchecked(index % PAGE_SIZE)
+        }
+    }
+
+    pub(crate) fn insert(&mut self, index: usize, dense_index: u32) {
+        let page_allocated = matches!(self.pages.get(index / PAGE_SIZE), Some(Some(_)));
+        let entry = &mut self.page_mut(index / PAGE_SIZE)[index % PAGE_SIZE];
+        let was_empty = *entry == EMPTY;
+        *entry = dense_index;
+        if was_empty {
+            self.reuse.record(page_allocated);
+        }
+    }
+
+    /// Allocates the pages for all entity indices below `len`.
+    pub(crate) fn reserve(&mut self, len: usize) {
+        for page_index in 0..len.div_ceil(PAGE_SIZE) {
+            self.page_mut(page_index);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) {
+        if let Some(Some(page)) = self.pages.get_mut(index / PAGE_SIZE) {
+            page[index % PAGE_SIZE] = EMPTY;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Returns the page with the given index, allocating it if needed.
+    fn page_mut(&mut self, page_index: usize) -> &mut [u32] {
+        if page_index >= self.pages.len() {
+            self.pages.resize_with(page_index + 1, || None);
+        }
+        let allocator = self.allocator;
+        self.pages[page_index].get_or_insert_with(|| {
+            let mut page = AllocVec::with_capacity_in(PAGE_SIZE, allocator);
+            page.resize(PAGE_SIZE, EMPTY);
+            page.into_boxed_slice()
+        })
+    }
+
+    /// Reports how many entries of the allocated pages are empty.
+    pub(crate) fn fragmentation(&self) -> FragmentationReport {
+        let entries = self.pages.iter().flatten().flat_map(|page| page.iter());
+        FragmentationReport::from_slots(entries.map(|&entry| entry != EMPTY), self.reuse)
+    }
+
+    /// Releases pages without entries and trailing unused page slots.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        for page in &mut self.pages {
+            if page.as_ref().is_some_and(|entries| entries.iter().all(|&entry| entry == EMPTY)) {
+                *page = None;
+            }
+        }
+        let len = self.pages.iter().rposition(Option::is_some).map_or(0, |index| index + 1);
+        self.pages.truncate(len);
+        self.pages.shrink_to_fit();
+    }
+}
+
+impl fmt::Debug for SparseArray {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SparseArray")
+            .field("pages", &self.pages.iter().filter(|page| page.is_some()).count())
+            .finish()
+    }
+}
+
+/// Counts insertions into a pool and how many of them landed in memory the pool had already allocated.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ReuseCounter {
+    insertions: u64,
+    reused: u64,
+}
+
+impl ReuseCounter {
+    pub(crate) fn record(&mut self, reused: bool) {
+        self.insertions += 1;
+        self.reused += reused as u64;
+    }
+}
+
+/// Describes how densely a pool uses the slots it allocated, to decide when compacting or shrinking pays off.
+/// Slots are the entries the pool reserved for entity indices: sparse entries for sparse-set pools,
+/// membership bits for tag pools and component slots for slabs.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+///
+/// let mut scene = Scene::new();
+/// let entities = (0..100).map(|_| scene.create_entity()).collect::<Vec<_>>();
+/// for &entity in &entities[..50] {
+///     scene.destroy_entity(entity);
+/// }
+///
+/// let report = scene.entity_fragmentation();
+/// assert_eq!(report.slots, 100);
+/// assert_eq!(report.free_slots, 50);
+/// assert_eq!(report.largest_free_run, 50);
+/// assert_eq!(report.fragmentation(), 0.5);
+///
+/// scene.create_entity();
+/// assert!(scene.entity_fragmentation().reuse_rate > 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FragmentationReport {
+    /// Number of slots allocated
+    pub slots: usize,
+    /// Number of allocated slots without a component
+    pub free_slots: usize,
+    /// Length of the longest run of consecutive free slots
+    pub largest_free_run: usize,
+    /// Fraction of all insertions that reused allocated slots instead of growing the storage
+    pub reuse_rate: f32,
+}
+
+impl FragmentationReport {
+    pub(crate) fn from_slots(occupied: impl Iterator<Item = bool>, reuse: ReuseCounter) -> Self {
+        let mut report = FragmentationReport {
+            reuse_rate: if reuse.insertions == 0 {
+                0.0
+            } else {
+                reuse.reused as f32 / reuse.insertions as f32
+            },
+            ..Default::default()
+        };
+        let mut run = 0;
+        for occupied in occupied {
+            report.slots += 1;
+            if occupied {
+                run = 0;
+            } else {
+                report.free_slots += 1;
+                run += 1;
+                report.largest_free_run = report.largest_free_run.max(run);
+            }
+        }
+        report
+    }
+
+    /// Returns the fraction of allocated slots that are free, between 0 for fully used and 1 for empty storage.
+    pub fn fragmentation(&self) -> f32 {
+        if self.slots == 0 {
+            0.0
+        } else {
+            self.free_slots as f32 / self.slots as f32
+        }
+    }
+}
+
+/// Stores all components of one type as a sparse set.
+/// Entity indices map to positions in densely packed arrays, so lookups need no hashing
+/// and iterating over all components never touches empty slots.
+/// Freeing a component moves the last component into its slot, so the dense arrays never contain holes.
+/// The sparse array is paged, so memory grows with the number of components rather than the highest entity index.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::pool::Pool;
+///
+/// let mut pool = Pool::new();
+/// pool.assign(Entity::new(3_000_000), 'a');
+/// pool.assign(Entity::new(7), 'b');
+///
+/// assert_eq!(pool.get(Entity::new(3_000_000)), Some(&'a'));
+/// assert_eq!(pool.get(Entity::new(2_999_999)), None);
+/// assert_eq!(pool.len(), 2);
+/// ```
 #[derive(Debug)]
 pub struct Pool<T> {
-    /// Map of entity id to component index
-    entities: HashMap<u32, u32>,
-    /// List of free component indices
-    free_list: Vec<u32>,
-    /// Vector of components in the pool
-    components: Vec<T>,
+    /// Map of entity index to dense index
+    sparse: SparseArray,
+    /// Entity owning each dense slot
+    dense: AllocVec<Entity>,
+    /// Vector of components in the pool, parallel to `dense`
+    components: AllocVec<T>,
+    /// Freed components kept for reuse, only filled while a reset function is set
+    recycled: AllocVec<T>,
+    /// Reinitializes recycled components before they are reused
+    reset: Option<fn(&mut T)>,
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for Pool<T> {
+    /// Clones the components and their entities. Recycled components are not cloned.
+    fn clone(&self) -> Self {
+        let mut pool = Pool::new_in(*self.dense.allocator());
+        pool.clone_from(self);
+        pool
+    }
+
+    /// Reuses the buffers of `self`, which makes copying a pool every frame cheap.
+    fn clone_from(&mut self, source: &Self) {
+        self.sparse.clone_from(&source.sparse);
+        self.dense.clear();
+        self.dense.extend_from_slice(&source.dense);
+        self.components.clear();
+        self.components.extend_from_slice(&source.components);
+        self.reset = source.reset;
+    }
 }
 
 impl<T> Pool<T> {
     pub fn new() -> Self {
-        Pool {
-            entities: HashMap::new(),
-            free_list: Vec::new(),
-            components: Vec::new(),
-        }
+        Self::new_in(global())
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, global())
+    }
+
+    /// Creates an empty pool that allocates its memory with the given allocator.
+    pub fn new_in(allocator: AllocatorRef) -> Self {
+        Self::with_capacity_in(0, allocator)
+    }
+
+    pub fn with_capacity_in(capacity: usize, allocator: AllocatorRef) -> Self {
         Pool {
-            entities: HashMap::new(),
-            free_list: Vec::new(),
-            components: Vec::with_capacity(capacity),
+            sparse: SparseArray::new_in(allocator),
+            dense: AllocVec::with_capacity_in(capacity, allocator),
+            components: AllocVec::with_capacity_in(capacity, allocator),
+            recycled: AllocVec::new_in(allocator),
+            reset: None,
         }
     }
 
+    /// Returns the number of components in the pool.
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
     pub fn get(&self, entity: Entity) -> Option<&T> {
-        self.entities.get(&entity.index()).map(|&index| &self.components[index as usize])
+        self.dense_index(entity).map(|index| &self.components[index])
     }
 
     pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
-        self.entities.get(&entity.index()).map(|&index| &mut self.components[index as usize])
+        self.dense_index(entity).map(|index| &mut self.components[index])
     }
 
     pub fn assign(&mut self, entity: Entity, new_component: T) -> &mut T {
-        // If anyone knows a better way to do this while still satisfying the borrow checker, please let me know
-        if self.get(entity).is_some() {
-            self.get_mut(entity).unwrap()
-        } else {
-            self.insert_new_component(entity, new_component)
+        self.assign_with(entity, || new_component)
+    }
+
+    /// Returns the entity's component, inserting the result of `f` if it doesn't have one yet.
+    pub fn assign_with(&mut self, entity: Entity, f: impl FnOnce() -> T) -> &mut T {
+        match self.dense_index(entity) {
+            Some(index) => &mut self.components[index],
+            None => self.insert_new_component(entity, f()),
         }
     }
 
+    /// Removes the entity's component and moves the last component of the pool into its slot.
     pub fn free(&mut self, entity: Entity) {
-        if let Some(&index) = self.entities.get(&entity.index()) {
-            self.free_list.push(index);
-            self.entities.remove(&entity.index());
+        if let Some(index) = self.dense_index(entity) {
+            self.sparse.remove(entity.index() as usize);
+            self.dense.swap_remove(index);
+            let component = self.components.swap_remove(index);
+            if self.reset.is_some() {
+                self.recycled.push(component);
+            }
+            if let Some(&moved) = self.dense.get(index) {
+                self.sparse.insert(moved.index() as usize, index as u32);
+            }
         }
     }
 
-    fn insert_new_component(&mut self, entity: Entity, new_component: T) -> &mut T {
-        let free_index = self.free_list.pop();
-        let index = free_index.unwrap_or(self.components.len() as u32);
+    /// Keeps freed components for reuse instead of dropping them. [`Pool::assign_recycled`] hands them out again
+    /// after calling `reset` on them, which avoids reallocating components that own buffers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::<Vec<u8>>::new();
+    /// pool.set_reset(Vec::clear);
+    /// pool.assign(Entity::new(0), Vec::with_capacity(1024)).push(1);
+    /// pool.free(Entity::new(0));
+    ///
+    /// let reused = pool.assign_recycled(Entity::new(1), Vec::new);
+    /// assert!(reused.is_empty());
+    /// assert!(reused.capacity() >= 1024);
+    /// ```
+    pub fn set_reset(&mut self, reset: fn(&mut T)) {
+        self.reset = Some(reset);
+    }
+
+    /// Returns the entity's component, inserting a reset recycled component if one is available
+    /// or the result of `f` otherwise.
+    pub fn assign_recycled(&mut self, entity: Entity, f: impl FnOnce() -> T) -> &mut T {
+        if let Some(index) = self.dense_index(entity) {
+            return &mut self.components[index];
+        }
+        let component = match (self.recycled.pop(), self.reset) {
+            (Some(mut component), Some(reset)) => {
+                reset(&mut component);
+                component
+            }
+            _ => f(),
+        };
+        self.insert_new_component(entity, component)
+    }
+
+    /// Returns an iterator over all entities with a component in this pool and their components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::new();
+    /// pool.assign(Entity::new(3), "three");
+    /// pool.assign(Entity::new(1), "one");
+    /// pool.assign(Entity::new(2), "two");
+    /// pool.free(Entity::new(1));
+    ///
+    /// let contents = pool.iter().collect::<Vec<_>>();
+    /// assert_eq!(contents, vec![(Entity::new(3), &"three"), (Entity::new(2), &"two")]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.dense
+            .iter()
+            .zip(&self.components)
+            .map(|(&entity, component)| (entity, component))
+    }
+
+    /// Returns an iterator over all entities with a component in this pool and their mutable components.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.dense
+            .iter()
+            .zip(&mut self.components)
+            .map(|(&entity, component)| (entity, component))
+    }
+
+    /// Returns an iterator over all entities with a component in this pool.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.dense.iter().copied()
+    }
+
+    /// Returns all components as one contiguous slice, in iteration order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::new();
+    /// for i in 0..4 {
+    ///     pool.assign(Entity::new(i), i as f32);
+    /// }
+    /// let (entities, values) = pool.as_mut_slices();
+    /// for value in values.iter_mut() {
+    ///     *value *= 2.0;
+    /// }
+    ///
+    /// assert_eq!(entities.len(), 4);
+    /// assert_eq!(pool.as_slice(), &[0.0, 2.0, 4.0, 6.0]);
+    /// ```
+    pub fn as_slice(&self) -> &[T] {
+        &self.components
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.components
+    }
+
+    /// Returns the entities owning the components, parallel to [`Pool::as_slice`].
+    pub fn entity_slice(&self) -> &[Entity] {
+        &self.dense
+    }
+
+    /// Returns the entities and mutable components as parallel slices.
+    pub fn as_mut_slices(&mut self) -> (&[Entity], &mut [T]) {
+        (&self.dense, &mut self.components)
+    }
+
+    /// Removes all components from the pool and returns them together with their entities.
+    pub fn drain(&mut self) -> impl Iterator<Item = (Entity, T)> {
+        self.sparse.clear();
+        let allocator = *self.dense.allocator();
+        let dense = std::mem::replace(&mut self.dense, AllocVec::new_in(allocator));
+        let components = std::mem::replace(&mut self.components, AllocVec::new_in(allocator));
+        dense.into_iter().zip(components)
+    }
+
+    /// Reorders the components so that iteration follows the order given by `compare`.
+    /// Components are swapped in place, so sorting every frame doesn't reallocate the pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::new();
+    /// pool.assign(Entity::new(0), 3);
+    /// pool.assign(Entity::new(1), 1);
+    /// pool.assign(Entity::new(2), 2);
+    /// pool.sort_by(|a, b| a.cmp(b));
+    ///
+    /// let entities = pool.entities().collect::<Vec<_>>();
+    /// assert_eq!(entities, vec![Entity::new(1), Entity::new(2), Entity::new(0)]);
+    /// assert_eq!(pool.get(Entity::new(0)), Some(&3));
+    /// ```
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering) {
+        // The dense index each slot takes its component from
+        let mut order = (0..self.components.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| compare(&self.components[a], &self.components[b]));
+        // Follow each cycle of the permutation, marking slots that hold their final component
+        for start in 0..order.len() {
+            let mut current = start;
+            while order[current] != current {
+                let next = order[current];
+                order[current] = current;
+                if next == start {
+                    break;
+                }
+                self.dense.swap(current, next);
+                self.components.swap(current, next);
+                current = next;
+            }
+        }
+        for (index, entity) in self.dense.iter().enumerate() {
+            self.sparse.insert(entity.index() as usize, index as u32);
+        }
+    }
+
+    /// Moves every component to the entity returned by `remap` for its current owner.
+    /// The iteration order of the components is kept.
+    pub fn remap_entities(&mut self, mut remap: impl FnMut(Entity) -> Entity) {
+        self.sparse.clear();
+        for (index, entity) in self.dense.iter_mut().enumerate() {
+            *entity = remap(*entity);
+            self.sparse.insert(entity.index() as usize, index as u32);
+        }
+    }
 
-        if free_index.is_none() {
-            self.components.push(new_component);
+    /// Removes all components, keeping the allocated memory for reuse.
+    /// With a reset function set, the components themselves are kept for recycling as well.
+    pub fn clear(&mut self) {
+        for entity in &self.dense {
+            self.sparse.remove(entity.index() as usize);
+        }
+        self.dense.clear();
+        if self.reset.is_some() {
+            self.recycled.extend(self.components.drain(..));
         } else {
-            self.components[index as usize] = new_component;
+            self.components.clear();
         }
+    }
+
+    /// Reserves capacity for at least `additional` more components.
+    pub fn reserve(&mut self, additional: usize) {
+        self.dense.reserve(additional);
+        self.components.reserve(additional);
+    }
+
+    /// Allocates the sparse pages for all entity indices below `len`,
+    /// so assigning components to those entities won't allocate pages later.
+    pub fn reserve_indices(&mut self, len: usize) {
+        self.sparse.reserve(len);
+    }
+
+    /// Releases unused capacity of the internal buffers, including sparse pages without components.
+    /// Recycled components are dropped.
+    pub fn shrink_to_fit(&mut self) {
+        self.recycled.clear();
+        self.recycled.shrink_to_fit();
+        self.sparse.shrink_to_fit();
+        self.dense.shrink_to_fit();
+        self.components.shrink_to_fit();
+    }
 
-        self.entities.insert(entity.index(), index);
+    /// Reports how many entries of the allocated sparse pages are unused.
+    /// The dense arrays never contain holes, so the sparse pages are the only place where a pool fragments.
+    pub fn fragmentation(&self) -> FragmentationReport {
+        self.sparse.fragmentation()
+    }
+
+    /// Returns the dense index of the entity's component, if the pool holds one for this exact entity version.
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let index = self.sparse.get(entity.index() as usize)? as usize;
+        (self.dense[index] == entity).then_some(index)
+    }
+
+    /// Returns the component stored for any version of the entity, including one left behind by an older version.
+    fn get_any_version(&self, entity: Entity) -> Option<&T> {
+        let index = self.sparse.get(entity.index() as usize)? as usize;
+        Some(&self.components[index])
+    }
+
+    fn insert_new_component(&mut self, entity: Entity, new_component: T) -> &mut T {
+        let sparse_index = entity.index() as usize;
+
+        // A component left behind by an older version of this entity is simply overwritten
+        let stale_index = self.sparse.get(sparse_index);
+        let index = match stale_index {
+            Some(index) => {
+                self.components[index as usize] = new_component;
+                self.dense[index as usize] = entity;
+                index
+            }
+            None => {
+                self.components.push(new_component);
+                self.dense.push(entity);
+                self.components.len() as u32 - 1
+            }
+        };
+
+        self.sparse.insert(sparse_index, index);
         &mut self.components[index as usize]
     }
 }
 
+#[cfg(feature = "unchecked")]
+impl<T> Pool<T> {
+    /// Returns the entity's component without checking that the pool holds one.
+    ///
+    /// # Safety
+    ///
+    /// The pool must hold a component for this entity, e.g. because [`Pool::contains`] returned true
+    /// and no component has been freed since. For a stale version of the entity, the component of the
+    /// current version is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::new();
+    /// let entities = (0..100).map(Entity::new).collect::<Vec<_>>();
+    /// for &entity in &entities {
+    ///     pool.assign(entity, 1u64);
+    /// }
+    ///
+    /// // All entities were assigned a component above
+    /// let sum = entities.iter().map(|&entity| unsafe { *pool.get_unchecked(entity) }).sum::<u64>();
+    /// assert_eq!(sum, 100);
+    /// ```
+    pub unsafe fn get_unchecked(&self, entity: Entity) -> &T {
+        // SAFETY: the caller guarantees that the pool holds a component for the entity
+        unsafe {
+            let index = self.sparse.get_unchecked(entity.index() as usize);
+            self.components.get_unchecked(index as usize)
+        }
+    }
+
+    /// Returns the entity's mutable component without checking that the pool holds one.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`Pool::get_unchecked`].
+    pub unsafe fn get_unchecked_mut(&mut self, entity: Entity) -> &mut T {
+        // SAFETY: the caller guarantees that the pool holds a component for the entity
+        unsafe {
+            let index = self.sparse.get_unchecked(entity.index() as usize);
+            self.components.get_unchecked_mut(index as usize)
+        }
+    }
+}
+
 impl <T: Default> Pool<T> {
     pub fn assign_default(&mut self, entity: Entity) -> &mut T {
-        if self.get(entity).is_some() {
-            self.get_mut(entity).unwrap()
+        self.assign_with(entity, T::default)
+    }
+}
+
+impl<T: Clone> Pool<T> {
+    /// Assigns every component to its entity, overwriting existing components.
+    /// Memory for all new components and the sparse pages they need is reserved once up front,
+    /// instead of growing the pool again and again while the components are inserted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::new();
+    /// pool.assign(Entity::new(1), "old");
+    /// pool.assign_batch(&[(Entity::new(0), "a"), (Entity::new(1), "b"), (Entity::new(9000), "c")]);
+    ///
+    /// assert_eq!(pool.len(), 3);
+    /// assert_eq!(pool.get(Entity::new(1)), Some(&"b"));
+    /// ```
+    pub fn assign_batch(&mut self, pairs: &[(Entity, T)]) {
+        self.reserve(pairs.len());
+        if let Some(max_index) = pairs.iter().map(|(entity, _)| entity.index() as usize).max() {
+            self.reserve_indices(max_index + 1);
+        }
+        for (entity, component) in pairs {
+            match self.dense_index(*entity) {
+                Some(index) => self.components[index].clone_from(component),
+                None => {
+                    self.insert_new_component(*entity, component.clone());
+                }
+            }
+        }
+    }
+}
+
+impl<T: Copy> Pool<T> {
+    /// Assigns `components[i]` to `entities[i]`, overwriting existing components.
+    /// Entities that have no component yet are appended with one slice copy, so filling a pool
+    /// with freshly created entities costs little more than a `memcpy`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices have different lengths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::new();
+    /// pool.assign(Entity::new(1), 0.0);
+    /// pool.assign_slice(&[Entity::new(0), Entity::new(1)], &[1.0, 2.0]);
+    ///
+    /// assert_eq!(pool.get(Entity::new(0)), Some(&1.0));
+    /// assert_eq!(pool.get(Entity::new(1)), Some(&2.0));
+    /// ```
+    pub fn assign_slice(&mut self, entities: &[Entity], components: &[T]) {
+        assert_eq!(entities.len(), components.len(), "every entity needs exactly one component");
+        let mut appended = 0;
+        for &entity in entities {
+            let index = entity.index() as usize;
+            if self.sparse.get(index).is_some() {
+                break;
+            }
+            self.sparse.insert(index, self.dense.len() as u32);
+            self.dense.push(entity);
+            appended += 1;
+        }
+        self.components.extend_from_slice(&components[..appended]);
+        for (&entity, &component) in entities[appended..].iter().zip(&components[appended..]) {
+            *self.assign_with(entity, || component) = component;
+        }
+    }
+
+    /// Copies the components of `sources` to the entities at the same positions in `targets`.
+    /// Sources without a component are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices have different lengths.
+    pub fn copy_components(&mut self, sources: &[Entity], targets: &[Entity]) {
+        assert_eq!(sources.len(), targets.len(), "every source needs exactly one target");
+        let (targets, components): (Vec<_>, Vec<_>) = sources
+            .iter()
+            .zip(targets)
+            .filter_map(|(&source, &target)| Some((target, *self.get(source)?)))
+            .unzip();
+        self.assign_slice(&targets, &components);
+    }
+}
+
+impl<T: Ord> Pool<T> {
+    /// Reorders the components so that iteration follows their natural order.
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp)
+    }
+}
+
+/// Fast paths for plain-old-data components, which can be copied and (de)serialized as raw memory.
+#[cfg(feature = "bytemuck")]
+impl<T: bytemuck::Pod> Pool<T> {
+    /// Copies the pool with plain memory copies, e.g. to take a snapshot for rollback.
+    pub fn clone_pod(&self) -> Self {
+        Pool {
+            sparse: self.sparse.clone(),
+            dense: self.dense.clone(),
+            components: self.components.clone(),
+            recycled: AllocVec::new_in(global()),
+            reset: self.reset,
+        }
+    }
+
+    /// Assigns zeroed components to all given entities that don't have one yet.
+    pub fn assign_zeroed(&mut self, entities: &[Entity]) {
+        for &entity in entities {
+            if !self.contains(entity) {
+                self.sparse.insert(entity.index() as usize, self.dense.len() as u32);
+                self.dense.push(entity);
+            }
+        }
+        self.components.resize(self.dense.len(), T::zeroed());
+    }
+
+    /// Returns the raw bytes of all components, without copying.
+    pub fn component_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(self.components.as_slice())
+    }
+
+    /// Returns the raw bytes of the entities owning the components, without copying.
+    pub fn entity_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(self.dense.as_slice())
+    }
+
+    /// Rebuilds a pool from the bytes returned by [`Pool::entity_bytes`] and [`Pool::component_bytes`].
+    /// The bytes don't need to be aligned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::Pool;
+    ///
+    /// let mut pool = Pool::<[f32; 2]>::new();
+    /// pool.assign_zeroed(&[Entity::new(0), Entity::new(4)]);
+    /// pool.get_mut(Entity::new(4)).unwrap()[1] = 1.5;
+    ///
+    /// let restored = Pool::<[f32; 2]>::from_bytes(pool.entity_bytes(), pool.component_bytes()).unwrap();
+    /// assert_eq!(restored.get(Entity::new(0)), Some(&[0.0, 0.0]));
+    /// assert_eq!(restored.get(Entity::new(4)), Some(&[0.0, 1.5]));
+    /// ```
+    pub fn from_bytes(entity_bytes: &[u8], component_bytes: &[u8]) -> Result<Self, RecsError> {
+        let entity_size = std::mem::size_of::<Entity>();
+        let component_size = std::mem::size_of::<T>();
+        let len = entity_bytes.len() / entity_size;
+        if !entity_bytes.len().is_multiple_of(entity_size) || component_bytes.len() != len * component_size {
+            return Err(RecsError::Other("pool bytes have mismatched lengths".into()));
+        }
+
+        let mut dense = AllocVec::with_capacity_in(len, global());
+        for bytes in entity_bytes.chunks_exact(entity_size) {
+            let entity = bytemuck::checked::try_pod_read_unaligned::<Entity>(bytes)
+                .map_err(|_| RecsError::Other("pool bytes contain an invalid entity".into()))?;
+            dense.push(entity);
+        }
+        let sparse = SparseArray::from_entities(&dense)?;
+        let mut components = AllocVec::with_capacity_in(len, global());
+        if component_size == 0 {
+            components.resize(len, T::zeroed());
         } else {
-            self.insert_new_component(entity, Default::default())
+            components.extend(
+                component_bytes
+                    .chunks_exact(component_size)
+                    .map(bytemuck::pod_read_unaligned::<T>),
+            );
+        }
+        Ok(Pool {
+            sparse,
+            dense,
+            components,
+            recycled: AllocVec::new_in(global()),
+            reset: None,
+        })
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl SparseArray {
+    /// Indexes entities by their position in `dense`, failing if an entity index occurs twice.
+    fn from_entities(dense: &[Entity]) -> Result<Self, RecsError> {
+        let mut sparse = SparseArray::default();
+        for (index, entity) in dense.iter().enumerate() {
+            if sparse.get(entity.index() as usize).is_some() {
+                return Err(RecsError::Other("pool bytes contain an entity index twice".into()));
+            }
+            sparse.insert(entity.index() as usize, index as u32);
+        }
+        Ok(sparse)
+    }
+}
+
+/// A read-only pool of plain-old-data components that borrows its entities and components from raw bytes,
+/// like the ones written by [`Pool::entity_bytes`] and [`Pool::component_bytes`].
+/// Components are never copied, only the index from entities to components is built, so a memory-mapped
+/// snapshot of a huge pool loads almost instantly. [`PodSnapshot::view`] hands out views of the pools
+/// of a snapshot. Wrap a view in a [`PodCowPool`] to change components, which copies them on the first write.
+///
+/// The library doesn't map files itself; map them with a crate like `memmap2` and pass the mapped bytes.
+/// Both byte slices have to be aligned for their element type, which holds for the sections of snapshots
+/// written by [`PodSnapshotWriter`] as long as the mapping starts at a page boundary.
+///
+/// [`PodSnapshot::view`]: crate::snapshot::PodSnapshot::view
+/// [`PodSnapshotWriter`]: crate::snapshot::PodSnapshotWriter
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::pool::{PodPoolView, Pool};
+///
+/// let mut pool = Pool::<u64>::new();
+/// pool.assign(Entity::new(0), 10);
+/// pool.assign(Entity::new(3), 30);
+///
+/// let view = PodPoolView::<u64>::from_bytes(pool.entity_bytes(), pool.component_bytes()).unwrap();
+/// assert_eq!(view.get(Entity::new(3)), Some(&30));
+///
+/// let mut copy = view.to_pool();
+/// *copy.get_mut(Entity::new(3)).unwrap() += 1;
+/// assert_eq!(copy.get(Entity::new(3)), Some(&31));
+/// ```
+#[cfg(feature = "bytemuck")]
+#[derive(Debug)]
+pub struct PodPoolView<'a, T> {
+    /// Map of entity index to dense index
+    sparse: SparseArray,
+    /// Entity owning each dense slot
+    dense: &'a [Entity],
+    /// Components, parallel to `dense`
+    components: &'a [T],
+}
+
+#[cfg(feature = "bytemuck")]
+impl<'a, T: bytemuck::Pod> PodPoolView<'a, T> {
+    /// Borrows the components from the given bytes without copying them.
+    /// Fails if the bytes are misaligned, have mismatched lengths or contain invalid entities,
+    /// and for zero-sized components, which have no bytes to borrow.
+    pub fn from_bytes(entity_bytes: &'a [u8], component_bytes: &'a [u8]) -> Result<Self, RecsError> {
+        if std::mem::size_of::<T>() == 0 {
+            return Err(RecsError::Other("zero-sized components can't be borrowed from bytes".into()));
+        }
+        let dense = bytemuck::checked::try_cast_slice::<u8, Entity>(entity_bytes)
+            .map_err(|_| RecsError::Other("entity bytes are misaligned or contain an invalid entity".into()))?;
+        let components = bytemuck::try_cast_slice::<u8, T>(component_bytes)
+            .map_err(|_| RecsError::Other("component bytes are misaligned or have an invalid length".into()))?;
+        if dense.len() != components.len() {
+            return Err(RecsError::Other("pool bytes have mismatched lengths".into()));
+        }
+        Ok(PodPoolView {
+            sparse: SparseArray::from_entities(dense)?,
+            dense,
+            components,
+        })
+    }
+
+    /// Returns the number of components in the view.
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&'a T> {
+        let index = self.sparse.get(entity.index() as usize)? as usize;
+        (self.dense[index] == entity).then(|| &self.components[index])
+    }
+
+    /// Returns an iterator over all entities in the view and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &'a T)> {
+        self.dense.iter().copied().zip(self.components)
+    }
+
+    /// Returns all components as one contiguous slice, in iteration order.
+    pub fn as_slice(&self) -> &'a [T] {
+        self.components
+    }
+
+    /// Returns the entities owning the components, parallel to [`PodPoolView::as_slice`].
+    pub fn entity_slice(&self) -> &'a [Entity] {
+        self.dense
+    }
+
+    /// Copies the borrowed components into a writable pool.
+    pub fn to_pool(&self) -> Pool<T> {
+        let mut dense = AllocVec::with_capacity_in(self.dense.len(), global());
+        dense.extend_from_slice(self.dense);
+        let mut components = AllocVec::with_capacity_in(self.components.len(), global());
+        components.extend_from_slice(self.components);
+        Pool {
+            sparse: self.sparse.clone(),
+            dense,
+            components,
+            recycled: AllocVec::new_in(global()),
+            reset: None,
+        }
+    }
+}
+
+/// A pool of plain-old-data components that reads from borrowed bytes, like a [`PodPoolView`], until it is
+/// written to for the first time. The first write copies all components into an owned [`Pool`], so pools
+/// of a memory-mapped snapshot that are only read never cost more than their index.
+///
+/// Scenes own their pools, so a pool has to be copied into a scene with [`PodCowPool::into_pool`] and
+/// `Scene::restore_pod`. Read-mostly data, like static level geometry, can stay outside of the scene in
+/// a pool like this and be looked up by entity. See [`PodSnapshot::view`] for an example.
+///
+/// [`PodSnapshot::view`]: crate::snapshot::PodSnapshot::view
+#[cfg(feature = "bytemuck")]
+#[derive(Debug)]
+pub struct PodCowPool<'a, T> {
+    state: PodCowState<'a, T>,
+}
+
+#[cfg(feature = "bytemuck")]
+#[derive(Debug)]
+enum PodCowState<'a, T> {
+    Borrowed(PodPoolView<'a, T>),
+    Owned(Pool<T>),
+}
+
+#[cfg(feature = "bytemuck")]
+impl<'a, T: bytemuck::Pod> PodCowPool<'a, T> {
+    /// Creates a pool that reads from the bytes of the view until it is written to.
+    pub fn new(view: PodPoolView<'a, T>) -> Self {
+        PodCowPool { state: PodCowState::Borrowed(view) }
+    }
+
+    /// Returns true while the components are still read from the borrowed bytes.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.state, PodCowState::Borrowed(_))
+    }
+
+    /// Returns the number of components in the pool.
+    pub fn len(&self) -> usize {
+        self.entity_slice().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match &self.state {
+            PodCowState::Borrowed(view) => view.get(entity),
+            PodCowState::Owned(pool) => pool.get(entity),
+        }
+    }
+
+    /// Returns a mutable component, copying the borrowed components first if the entity has one.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.contains(entity) {
+            return None;
+        }
+        self.to_mut().get_mut(entity)
+    }
+
+    /// Removes the entity's component, copying the borrowed components first if the entity has one.
+    pub fn free(&mut self, entity: Entity) {
+        if self.contains(entity) {
+            self.to_mut().free(entity);
+        }
+    }
+
+    /// Returns an iterator over all entities with a component in this pool and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entity_slice().iter().copied().zip(self.as_slice())
+    }
+
+    /// Returns all components as one contiguous slice, in iteration order.
+    pub fn as_slice(&self) -> &[T] {
+        match &self.state {
+            PodCowState::Borrowed(view) => view.as_slice(),
+            PodCowState::Owned(pool) => pool.as_slice(),
+        }
+    }
+
+    /// Returns the entities owning the components, parallel to [`PodCowPool::as_slice`].
+    pub fn entity_slice(&self) -> &[Entity] {
+        match &self.state {
+            PodCowState::Borrowed(view) => view.entity_slice(),
+            PodCowState::Owned(pool) => pool.entity_slice(),
+        }
+    }
+
+    /// Returns the owned pool for writing, copying the borrowed components first if this is the first write.
+    pub fn to_mut(&mut self) -> &mut Pool<T> {
+        if let PodCowState::Borrowed(view) = &self.state {
+            self.state = PodCowState::Owned(view.to_pool());
+        }
+        match &mut self.state {
+            PodCowState::Owned(pool) => pool,
+            PodCowState::Borrowed(_) => unreachable!("borrowed components were just copied"),
+        }
+    }
+
+    /// Returns the components as an owned pool, copying them if they are still borrowed.
+    pub fn into_pool(self) -> Pool<T> {
+        match self.state {
+            PodCowState::Borrowed(view) => view.to_pool(),
+            PodCowState::Owned(pool) => pool,
+        }
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<'a, T: bytemuck::Pod> From<PodPoolView<'a, T>> for PodCowPool<'a, T> {
+    fn from(view: PodPoolView<'a, T>) -> Self {
+        PodCowPool::new(view)
+    }
+}
+
+/// Stores zero-sized marker components, like `Selected` or `Dead`, as one membership bit per entity index.
+/// Since all instances of a zero-sized type are indistinguishable, the instances are kept in a vector
+/// that never allocates and every entity's component refers to the same slot.
+///
+/// Only entity indices are tracked, so the caller is responsible for checking entity versions.
+/// The pool only holds zero-sized types, since all entities share one value.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::pool::TagPool;
+///
+/// struct Selected;
+///
+/// let mut pool = TagPool::new();
+/// pool.assign(Entity::new(5), Selected);
+/// pool.assign(Entity::new(70), Selected);
+/// pool.free(Entity::new(5));
+///
+/// assert!(pool.contains(Entity::new(70)));
+/// assert!(!pool.contains(Entity::new(5)));
+/// assert_eq!(pool.indices().collect::<Vec<_>>(), vec![70]);
+/// ```
+#[derive(Debug)]
+pub struct TagPool<T> {
+    /// Membership bits, indexed by entity index
+    bits: Vec<u64>,
+    /// One instance per entity with the tag
+    instances: Vec<T>,
+    reuse: ReuseCounter,
+}
+
+impl<T> Default for TagPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TagPool<T> {
+    /// Creates an empty pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't zero-sized.
+    pub fn new() -> Self {
+        assert!(std::mem::size_of::<T>() == 0, "tag pools only hold zero-sized types");
+        TagPool {
+            bits: Vec::new(),
+            instances: Vec::new(),
+            reuse: ReuseCounter::default(),
         }
     }
-}
\ No newline at end of file
+
+    /// Returns the number of entities with the tag.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        let index = entity.index() as usize;
+        self.bits
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.contains(entity).then(|| &self.instances[0])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        if self.contains(entity) {
+            Some(&mut self.instances[0])
+        } else {
+            None
+        }
+    }
+
+    pub fn assign(&mut self, entity: Entity, new_component: T) -> &mut T {
+        self.assign_with(entity, || new_component)
+    }
+
+    /// Returns the entity's tag, inserting the result of `f` if it doesn't have one yet.
+    pub fn assign_with(&mut self, entity: Entity, f: impl FnOnce() -> T) -> &mut T {
+        if !self.contains(entity) {
+            let index = entity.index() as usize;
+            self.reuse.record(index / 64 < self.bits.len());
+            if index / 64 >= self.bits.len() {
+                self.bits.resize(index / 64 + 1, 0);
+            }
+            self.bits[index / 64] |= 1 << (index % 64);
+            self.instances.push(f());
+        }
+        &mut self.instances[0]
+    }
+
+    pub fn free(&mut self, entity: Entity) {
+        if self.contains(entity) {
+            let index = entity.index() as usize;
+            self.bits[index / 64] &= !(1 << (index % 64));
+            self.instances.pop();
+        }
+    }
+
+    /// Returns an iterator over the indices of all entities with the tag, in ascending order.
+    pub fn indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.bits.iter().enumerate().flat_map(|(word_index, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| (word_index * 64 + bit) as u32)
+        })
+    }
+
+    /// Removes all tags from the pool and returns them together with their entity indices.
+    pub fn drain(&mut self) -> impl Iterator<Item = (u32, T)> {
+        let indices = self.indices().collect::<Vec<_>>();
+        self.bits.clear();
+        indices.into_iter().zip(std::mem::take(&mut self.instances))
+    }
+
+    /// Removes all tags, keeping the allocated memory for reuse.
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+        self.instances.clear();
+    }
+
+    /// Allocates the membership bits for all entity indices below `len`.
+    pub fn reserve_indices(&mut self, len: usize) {
+        let words = len.div_ceil(64);
+        if words > self.bits.len() {
+            self.bits.resize(words, 0);
+        }
+    }
+
+    /// Reports how many of the allocated membership bits are unset.
+    pub fn fragmentation(&self) -> FragmentationReport {
+        let bits = self.bits.iter().flat_map(|&word| (0..64).map(move |bit| word & (1 << bit) != 0));
+        FragmentationReport::from_slots(bits, self.reuse)
+    }
+
+    /// Releases unused capacity, including membership words past the last tagged entity.
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.bits.iter().rposition(|&word| word != 0).map_or(0, |index| index + 1);
+        self.bits.truncate(len);
+        self.bits.shrink_to_fit();
+    }
+}
+
+/// Stores components in a slab addressed directly by entity index, with the entity version kept next to each
+/// component. Lookups are a single bounds-checked array access validated against the version, without
+/// a sparse indirection. Memory grows with the highest entity index, so the slab suits components that most
+/// entities have.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::pool::SlabPool;
+///
+/// let mut pool = SlabPool::new();
+/// pool.assign(Entity::new(2), 'a');
+/// pool.assign(Entity::with_version(0, 1), 'b');
+///
+/// assert_eq!(pool.get(Entity::new(2)), Some(&'a'));
+/// assert_eq!(pool.get(Entity::new(0)), None);
+/// assert_eq!(pool.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct SlabPool<T> {
+    /// Entity version and component of each entity index
+    slots: AllocVec<Option<(u32, T)>>,
+    /// Number of occupied slots
+    len: usize,
+    reuse: ReuseCounter,
+}
+
+impl<T> Default for SlabPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SlabPool<T> {
+    pub fn new() -> Self {
+        Self::new_in(global())
+    }
+
+    /// Creates an empty slab that allocates its memory with the given allocator.
+    pub fn new_in(allocator: AllocatorRef) -> Self {
+        SlabPool {
+            slots: AllocVec::new_in(allocator),
+            len: 0,
+            reuse: ReuseCounter::default(),
+        }
+    }
+
+    /// Returns the number of components in the slab.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index() as usize)? {
+            Some((version, component)) if *version == entity.version() => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index() as usize)? {
+            Some((version, component)) if *version == entity.version() => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn assign(&mut self, entity: Entity, new_component: T) -> &mut T {
+        self.assign_with(entity, || new_component)
+    }
+
+    /// Returns the entity's component, inserting the result of `f` if it doesn't have one yet.
+    /// A component left behind by an older version of the entity is replaced.
+    pub fn assign_with(&mut self, entity: Entity, f: impl FnOnce() -> T) -> &mut T {
+        let index = entity.index() as usize;
+        let allocated = index < self.slots.len();
+        if !allocated {
+            self.slots.resize_with(index + 1, || None);
+        }
+        let slot = &mut self.slots[index];
+        match slot {
+            Some((version, _)) if *version == entity.version() => {}
+            Some(_) => *slot = Some((entity.version(), f())),
+            None => {
+                *slot = Some((entity.version(), f()));
+                self.len += 1;
+                self.reuse.record(allocated);
+            }
+        }
+        &mut slot.as_mut().unwrap().1
+    }
+
+    pub fn free(&mut self, entity: Entity) {
+        if self.contains(entity) {
+            self.slots[entity.index() as usize] = None;
+            self.len -= 1;
+        }
+    }
+
+    /// Returns an iterator over all entities with a component in this slab and their components,
+    /// in ascending order of entity index.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let (version, component) = slot.as_ref()?;
+            Some((Entity::with_version(index as u32, *version), component))
+        })
+    }
+
+    /// Returns an iterator over all entities with a component in this slab and their mutable components.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            let (version, component) = slot.as_mut()?;
+            Some((Entity::with_version(index as u32, *version), component))
+        })
+    }
+
+    /// Returns an iterator over all entities with a component in this slab.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.iter().map(|(entity, _)| entity)
+    }
+
+    /// Removes all components from the slab and returns them together with their entities.
+    pub fn drain(&mut self) -> impl Iterator<Item = (Entity, T)> {
+        self.len = 0;
+        let allocator = *self.slots.allocator();
+        let slots = std::mem::replace(&mut self.slots, AllocVec::new_in(allocator));
+        slots.into_iter().enumerate().filter_map(|(index, slot)| {
+            let (version, component) = slot?;
+            Some((Entity::with_version(index as u32, version), component))
+        })
+    }
+
+    /// Moves every component to the entity returned by `remap` for its current owner.
+    pub fn remap_entities(&mut self, mut remap: impl FnMut(Entity) -> Entity) {
+        let entries = self.drain().collect::<Vec<_>>();
+        for (entity, component) in entries {
+            self.assign(remap(entity), component);
+        }
+    }
+
+    /// Removes all components, keeping the allocated memory for reuse.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// Allocates the slots for all entity indices below `len`.
+    pub fn reserve_indices(&mut self, len: usize) {
+        if len > self.slots.len() {
+            self.slots.resize_with(len, || None);
+        }
+    }
+
+    /// Reports how many of the allocated slots are empty.
+    pub fn fragmentation(&self) -> FragmentationReport {
+        FragmentationReport::from_slots(self.slots.iter().map(Option::is_some), self.reuse)
+    }
+
+    /// Releases unused capacity, including slots past the last entity with a component.
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.slots.iter().rposition(Option::is_some).map_or(0, |index| index + 1);
+        self.slots.truncate(len);
+        self.slots.shrink_to_fit();
+    }
+}
+
+/// Number of components a [`SmallPool`] stores inline before moving them to a sparse pool.
+pub const SMALL_POOL_CAPACITY: usize = 8;
+
+/// Stores up to [`SMALL_POOL_CAPACITY`] components inline and finds them by a linear search,
+/// so component types that only a handful of entities have (cameras, players) need no heap memory
+/// and no sparse indirection. Once more entities get the component, all of them move to a [`Pool`].
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::pool::{SmallPool, SMALL_POOL_CAPACITY};
+///
+/// let mut pool = SmallPool::new();
+/// pool.assign(Entity::new(5), "camera");
+/// assert_eq!(pool.get(Entity::new(5)), Some(&"camera"));
+/// assert!(!pool.is_spilled());
+///
+/// for i in 0..SMALL_POOL_CAPACITY as u32 {
+///     pool.assign(Entity::new(100 + i), "light");
+/// }
+/// assert!(pool.is_spilled());
+/// assert_eq!(pool.get(Entity::new(5)), Some(&"camera"));
+/// assert_eq!(pool.len(), SMALL_POOL_CAPACITY + 1);
+/// ```
+#[derive(Debug)]
+pub struct SmallPool<T> {
+    /// Entities and their components, packed at the front while the pool hasn't spilled
+    inline: [Option<(Entity, T)>; SMALL_POOL_CAPACITY],
+    /// Number of occupied inline slots
+    inline_len: usize,
+    /// Holds all components once more than `SMALL_POOL_CAPACITY` entities had one at the same time
+    spilled: Pool<T>,
+    is_spilled: bool,
+}
+
+impl<T> Default for SmallPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SmallPool<T> {
+    pub fn new() -> Self {
+        Self::new_in(global())
+    }
+
+    /// Creates an empty pool that allocates its memory with the given allocator once it spills.
+    pub fn new_in(allocator: AllocatorRef) -> Self {
+        SmallPool {
+            inline: std::array::from_fn(|_| None),
+            inline_len: 0,
+            spilled: Pool::new_in(allocator),
+            is_spilled: false,
+        }
+    }
+
+    /// Returns whether the components have moved to the heap-allocated sparse pool.
+    pub fn is_spilled(&self) -> bool {
+        self.is_spilled
+    }
+
+    /// Returns the number of components in the pool.
+    pub fn len(&self) -> usize {
+        self.inline_len + self.spilled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        if self.is_spilled {
+            return self.spilled.get(entity);
+        }
+        match &self.inline[self.inline_position(entity)?] {
+            Some((owner, component)) if *owner == entity => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        if self.is_spilled {
+            return self.spilled.get_mut(entity);
+        }
+        match &mut self.inline[self.inline_position(entity)?] {
+            Some((owner, component)) if *owner == entity => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn assign(&mut self, entity: Entity, new_component: T) -> &mut T {
+        self.assign_with(entity, || new_component)
+    }
+
+    /// Returns the entity's component, inserting the result of `f` if it doesn't have one yet.
+    /// A component left behind by an older version of the entity is replaced.
+    pub fn assign_with(&mut self, entity: Entity, f: impl FnOnce() -> T) -> &mut T {
+        if self.is_spilled {
+            return self.spilled.assign_with(entity, f);
+        }
+        let position = match self.inline_position(entity) {
+            Some(position) => position,
+            None if self.inline_len < SMALL_POOL_CAPACITY => {
+                self.inline_len += 1;
+                self.inline_len - 1
+            }
+            None => {
+                self.spill();
+                return self.spilled.assign_with(entity, f);
+            }
+        };
+        let slot = &mut self.inline[position];
+        if !matches!(slot, Some((owner, _)) if *owner == entity) {
+            *slot = Some((entity, f()));
+        }
+        &mut slot.as_mut().unwrap().1
+    }
+
+    /// Removes the entity's component and moves the last inline component into its slot.
+    pub fn free(&mut self, entity: Entity) {
+        if self.is_spilled {
+            self.spilled.free(entity);
+        } else if let Some(position) = self.inline_position(entity).filter(|&i| self.inline_is(i, entity)) {
+            self.inline_len -= 1;
+            self.inline.swap(position, self.inline_len);
+            self.inline[self.inline_len] = None;
+        }
+    }
+
+    /// Returns an iterator over all entities with a component in this pool and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        // Only one of the two is non-empty at any time
+        self.inline
+            .iter()
+            .flatten()
+            .map(|(entity, component)| (*entity, component))
+            .chain(self.spilled.iter())
+    }
+
+    /// Returns an iterator over all entities with a component in this pool and their mutable components.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.inline
+            .iter_mut()
+            .flatten()
+            .map(|(entity, component)| (*entity, component))
+            .chain(self.spilled.iter_mut())
+    }
+
+    /// Returns an iterator over all entities with a component in this pool.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.iter().map(|(entity, _)| entity)
+    }
+
+    /// Removes all components from the pool and returns them together with their entities.
+    /// The pool stores components inline again afterwards.
+    pub fn drain(&mut self) -> impl Iterator<Item = (Entity, T)> {
+        let mut entries = self.spilled.drain().collect::<Vec<_>>();
+        entries.extend(self.inline.iter_mut().filter_map(Option::take));
+        self.inline_len = 0;
+        self.is_spilled = false;
+        entries.into_iter()
+    }
+
+    /// Moves every component to the entity returned by `remap` for its current owner.
+    pub fn remap_entities(&mut self, mut remap: impl FnMut(Entity) -> Entity) {
+        for (entity, _) in self.inline.iter_mut().flatten() {
+            *entity = remap(*entity);
+        }
+        self.spilled.remap_entities(remap);
+    }
+
+    /// Removes all components. A spilled pool keeps its allocated memory and stays spilled.
+    pub fn clear(&mut self) {
+        self.spilled.clear();
+        self.inline.iter_mut().for_each(|slot| *slot = None);
+        self.inline_len = 0;
+    }
+
+    /// Reports how many entries of the sparse pages are unused once the pool has spilled.
+    /// Inline components are packed and never fragment.
+    pub fn fragmentation(&self) -> FragmentationReport {
+        self.spilled.fragmentation()
+    }
+
+    /// Moves the components back inline if they fit and releases the memory of the sparse pool.
+    pub fn shrink_to_fit(&mut self) {
+        if self.is_spilled && self.spilled.len() <= SMALL_POOL_CAPACITY {
+            self.inline_len = self.spilled.len();
+            for (slot, entry) in self.inline.iter_mut().zip(self.spilled.drain()) {
+                *slot = Some(entry);
+            }
+            self.is_spilled = false;
+        }
+        self.spilled.shrink_to_fit();
+    }
+
+    /// Returns the inline slot holding a component of any version of the entity.
+    fn inline_position(&self, entity: Entity) -> Option<usize> {
+        self.inline[..self.inline_len]
+            .iter()
+            .position(|slot| matches!(slot, Some((owner, _)) if owner.index() == entity.index()))
+    }
+
+    fn inline_is(&self, position: usize, entity: Entity) -> bool {
+        matches!(&self.inline[position], Some((owner, _)) if *owner == entity)
+    }
+
+    fn spill(&mut self) {
+        self.spilled.reserve(SMALL_POOL_CAPACITY + 1);
+        for (entity, component) in self.inline.iter_mut().filter_map(Option::take) {
+            self.spilled.assign(entity, component);
+        }
+        self.inline_len = 0;
+        self.is_spilled = true;
+    }
+}
+
+/// Stores each distinct component value once, shared by all entities whose components are equal.
+/// Uniform data like material descriptors then takes memory per distinct value instead of per entity.
+/// Shared values can't be borrowed mutably, assigning a new value to an entity is the only way to change it.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::pool::InternedPool;
+///
+/// let mut pool = InternedPool::new();
+/// for i in 0..1000 {
+///     pool.assign(Entity::new(i), String::from(if i % 2 == 0 { "stone" } else { "grass" }));
+/// }
+/// assert_eq!(pool.len(), 1000);
+/// assert_eq!(pool.distinct_len(), 2);
+/// assert_eq!(pool.get(Entity::new(3)).map(String::as_str), Some("grass"));
+///
+/// pool.assign(Entity::new(3), String::from("water"));
+/// assert_eq!(pool.distinct_len(), 3);
+/// ```
+#[derive(Debug)]
+pub struct InternedPool<T> {
+    /// Index into `values` of each entity's component
+    handles: Pool<u32>,
+    /// Distinct values, `None` for slots whose value is no longer referenced
+    values: AllocVec<Option<InternedValue<T>>>,
+    /// Unused slots in `values`
+    free_values: AllocVec<u32>,
+    /// Indices of the values with each hash
+    by_hash: FxHashMap<u64, Vec<u32>>,
+    /// Gives every entity its own copy when the pool is drained
+    clone: fn(&T) -> T,
+}
+
+#[derive(Debug)]
+struct InternedValue<T> {
+    value: T,
+    hash: u64,
+    /// Number of entities sharing the value
+    count: usize,
+}
+
+impl<T: Eq + Hash + Clone> Default for InternedPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash + Clone> InternedPool<T> {
+    pub fn new() -> Self {
+        Self::new_in(global())
+    }
+
+    /// Creates an empty pool that allocates its memory with the given allocator.
+    pub fn new_in(allocator: AllocatorRef) -> Self {
+        InternedPool {
+            handles: Pool::new_in(allocator),
+            values: AllocVec::new_in(allocator),
+            free_values: AllocVec::new_in(allocator),
+            by_hash: FxHashMap::default(),
+            clone: T::clone,
+        }
+    }
+
+    /// Sets the entity's component, sharing the stored value if an equal one exists.
+    /// Unlike other pools, an existing component of the entity is replaced.
+    pub fn assign(&mut self, entity: Entity, new_component: T) -> &T {
+        let hash = FxBuildHasher.hash_one(&new_component);
+        let existing = self.by_hash.get(&hash).and_then(|indices| {
+            indices.iter().copied().find(|&index| {
+                matches!(&self.values[index as usize], Some(stored) if stored.value == new_component)
+            })
+        });
+        let index = match existing {
+            Some(index) => index,
+            None => self.insert_value(hash, new_component),
+        };
+        // Take the new reference before releasing the old one, so reassigning an equal value keeps it alive.
+        // A handle left behind by an older version of the entity is overwritten as well, so it's released too.
+        self.values[index as usize].as_mut().unwrap().count += 1;
+        if let Some(&previous) = self.handles.get_any_version(entity) {
+            self.release(previous);
+        }
+        self.handles.free(entity);
+        self.handles.assign(entity, index);
+        &self.values[index as usize].as_ref().unwrap().value
+    }
+
+    fn insert_value(&mut self, hash: u64, value: T) -> u32 {
+        let stored = Some(InternedValue { value, hash, count: 0 });
+        let index = match self.free_values.pop() {
+            Some(index) => {
+                self.values[index as usize] = stored;
+                index
+            }
+            None => {
+                self.values.push(stored);
+                self.values.len() as u32 - 1
+            }
+        };
+        self.by_hash.entry(hash).or_default().push(index);
+        index
+    }
+}
+
+impl<T> InternedPool<T> {
+    /// Returns the number of entities with a component in the pool.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Returns the number of distinct values stored.
+    pub fn distinct_len(&self) -> usize {
+        self.values.len() - self.free_values.len()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.handles.contains(entity)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let index = *self.handles.get(entity)?;
+        self.values[index as usize].as_ref().map(|stored| &stored.value)
+    }
+
+    /// Removes the entity's component. The value is dropped once no entity shares it anymore.
+    pub fn free(&mut self, entity: Entity) {
+        if let Some(&index) = self.handles.get(entity) {
+            self.handles.free(entity);
+            self.release(index);
+        }
+    }
+
+    /// Returns an iterator over all entities with a component in this pool and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.handles.iter().filter_map(|(entity, &index)| {
+            Some((entity, &self.values[index as usize].as_ref()?.value))
+        })
+    }
+
+    /// Returns an iterator over all entities with a component in this pool.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.handles.entities()
+    }
+
+    /// Removes all components from the pool and returns a copy of its component for every entity.
+    pub fn drain(&mut self) -> impl Iterator<Item = (Entity, T)> {
+        let entries = self
+            .iter()
+            .map(|(entity, component)| (entity, (self.clone)(component)))
+            .collect::<Vec<_>>();
+        self.clear();
+        entries.into_iter()
+    }
+
+    /// Moves every component to the entity returned by `remap` for its current owner.
+    pub fn remap_entities(&mut self, remap: impl FnMut(Entity) -> Entity) {
+        self.handles.remap_entities(remap);
+    }
+
+    /// Removes all components and values, keeping the allocated memory for reuse.
+    pub fn clear(&mut self) {
+        self.handles.clear();
+        self.values.clear();
+        self.free_values.clear();
+        self.by_hash.clear();
+    }
+
+    /// Reports how many entries of the allocated sparse pages are unused.
+    pub fn fragmentation(&self) -> FragmentationReport {
+        self.handles.fragmentation()
+    }
+
+    /// Releases unused capacity, including value slots past the last value still in use.
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.values.iter().rposition(Option::is_some).map_or(0, |index| index + 1);
+        self.values.truncate(len);
+        self.free_values.retain(|&index| (index as usize) < len);
+        self.values.shrink_to_fit();
+        self.free_values.shrink_to_fit();
+        self.handles.shrink_to_fit();
+        self.by_hash.shrink_to_fit();
+    }
+
+    /// Drops one reference to a value, and the value itself once it was the last one.
+    fn release(&mut self, index: u32) {
+        let slot = &mut self.values[index as usize];
+        let Some(stored) = slot else { return };
+        stored.count -= 1;
+        if stored.count > 0 {
+            return;
+        }
+        let hash = stored.hash;
+        *slot = None;
+        self.free_values.push(index);
+        if let Some(indices) = self.by_hash.get_mut(&hash) {
+            indices.retain(|&other| other != index);
+            if indices.is_empty() {
+                self.by_hash.remove(&hash);
+            }
+        }
+    }
+}
+
+/// Typed handle to the storage of a component type, usually obtained from `Scene::register`.
+/// The handle carries the type ID of the component type, which saves the hash lookup in the thread-local
+/// type ID cache on every access; the pool itself is still found by index and downcast. Hot loops can
+/// resolve a handle once and access components through it instead.
+pub struct PoolHandle<T> {
+    type_id: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> PoolHandle<T> {
+    /// Resolves the handle for components of type `T`.
+    pub fn of() -> Self {
+        PoolHandle {
+            type_id: get_type_id::<T>(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PoolHandle<T> {
+    /// Returns the type ID of the component type.
+    pub fn type_id(&self) -> usize {
+        self.type_id
+    }
+}
+
+impl<T> Clone for PoolHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PoolHandle<T> {}
+
+impl<T> fmt::Debug for PoolHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoolHandle").field("type_id", &self.type_id).finish()
+    }
+}
+
+/// Type-erased interface to a pool, so a scene can manage its pools without knowing their component types.
+pub trait ErasedPool: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Returns the number of components in the pool.
+    fn len(&self) -> usize;
+    /// Removes the entity's component from the pool, if it has one.
+    fn free(&mut self, entity: Entity);
+    /// Moves the components of the entities in `remap` to their new entities.
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>);
+    /// Removes all components, keeping the allocated memory.
+    fn clear(&mut self);
+    /// Releases unused capacity of the pool's buffers.
+    fn shrink_to_fit(&mut self);
+    /// Reports how densely the pool uses its allocated slots.
+    fn fragmentation(&self) -> FragmentationReport;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: 'static> ErasedPool for Pool<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        Pool::len(self)
+    }
+
+    fn free(&mut self, entity: Entity) {
+        Pool::free(self, entity)
+    }
+
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        Pool::remap_entities(self, |entity| remap.get(&entity).copied().unwrap_or(entity))
+    }
+
+    fn clear(&mut self) {
+        Pool::clear(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Pool::shrink_to_fit(self)
+    }
+
+    fn fragmentation(&self) -> FragmentationReport {
+        Pool::fragmentation(self)
+    }
+}
+
+impl<T: 'static> ErasedPool for TagPool<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        TagPool::len(self)
+    }
+
+    fn free(&mut self, entity: Entity) {
+        TagPool::free(self, entity)
+    }
+
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        let indices = remap
+            .iter()
+            .map(|(old, new)| (old.index(), new.index()))
+            .collect::<HashMap<_, _>>();
+        for (index, instance) in self.drain().collect::<Vec<_>>() {
+            let index = indices.get(&index).copied().unwrap_or(index);
+            self.assign(Entity::new(index), instance);
+        }
+    }
+
+    fn clear(&mut self) {
+        TagPool::clear(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        TagPool::shrink_to_fit(self)
+    }
+
+    fn fragmentation(&self) -> FragmentationReport {
+        TagPool::fragmentation(self)
+    }
+}
+
+impl<T: 'static> ErasedPool for SlabPool<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        SlabPool::len(self)
+    }
+
+    fn free(&mut self, entity: Entity) {
+        SlabPool::free(self, entity)
+    }
+
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        SlabPool::remap_entities(self, |entity| remap.get(&entity).copied().unwrap_or(entity))
+    }
+
+    fn clear(&mut self) {
+        SlabPool::clear(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        SlabPool::shrink_to_fit(self)
+    }
+
+    fn fragmentation(&self) -> FragmentationReport {
+        SlabPool::fragmentation(self)
+    }
+}
+
+impl<T: 'static> ErasedPool for SmallPool<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        SmallPool::len(self)
+    }
+
+    fn free(&mut self, entity: Entity) {
+        SmallPool::free(self, entity)
+    }
+
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        SmallPool::remap_entities(self, |entity| remap.get(&entity).copied().unwrap_or(entity))
+    }
+
+    fn clear(&mut self) {
+        SmallPool::clear(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        SmallPool::shrink_to_fit(self)
+    }
+
+    fn fragmentation(&self) -> FragmentationReport {
+        SmallPool::fragmentation(self)
+    }
+}
+
+impl<T: 'static> ErasedPool for InternedPool<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        InternedPool::len(self)
+    }
+
+    fn free(&mut self, entity: Entity) {
+        InternedPool::free(self, entity)
+    }
+
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        InternedPool::remap_entities(self, |entity| remap.get(&entity).copied().unwrap_or(entity))
+    }
+
+    fn clear(&mut self) {
+        InternedPool::clear(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        InternedPool::shrink_to_fit(self)
+    }
+
+    fn fragmentation(&self) -> FragmentationReport {
+        InternedPool::fragmentation(self)
+    }
+}
+
+impl fmt::Debug for dyn ErasedPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ErasedPool").field("len", &self.len()).finish()
+    }
+}
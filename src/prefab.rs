@@ -0,0 +1,824 @@
+use std::{any::Any, collections::BTreeMap, fmt, rc::Rc};
+
+#[cfg(any(feature = "ron", feature = "json"))]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, Visitor},
+    Deserializer,
+};
+
+use crate::{
+    component::{get_type_id, get_type_name, TypeIdSet},
+    entity::Entity,
+    error::RecsError,
+    registry::{clone_fn, insert_fn, CloneFn, InsertFn, RemoveFn},
+    scene::Scene,
+};
+
+#[cfg(feature = "serde")]
+use crate::registry::{TypeRegistry, ValueSeed};
+
+/// A component of a prefab, with the functions to stamp copies of it onto entities
+struct PrefabComponent {
+    type_id: usize,
+    value: Box<dyn Any>,
+    clone: CloneFn,
+    insert: InsertFn,
+    remove: RemoveFn,
+}
+
+impl PrefabComponent {
+    fn duplicate(&self) -> Self {
+        PrefabComponent {
+            type_id: self.type_id,
+            value: (self.clone)(&*self.value),
+            clone: self.clone,
+            insert: self.insert,
+            remove: self.remove,
+        }
+    }
+}
+
+/// A set of components that commonly spawned entities, like enemies, bullets or pickups, are created with.
+/// The prefab is defined once, in code or loaded from data with a [`PrefabDeserializer`],
+/// and every [`Prefab::instantiate`] creates an entity with copies of its components.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::prefab::Prefab;
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone)]
+/// struct Health(u32);
+/// #[derive(Clone)]
+/// struct Speed(f32);
+///
+/// let goblin = Prefab::new().with(Health(30)).with(Speed(1.5));
+///
+/// let mut scene = Scene::new();
+/// let first = goblin.instantiate(&mut scene).unwrap();
+/// let second = goblin.instantiate(&mut scene).unwrap();
+/// scene.get_mut::<Health>(first).unwrap().unwrap().0 -= 10;
+/// assert_eq!(scene.get::<Health>(first).unwrap().unwrap().0, 20);
+/// assert_eq!(scene.get::<Health>(second).unwrap().unwrap().0, 30);
+/// assert_eq!(scene.view::<(Health, (Speed, ()))>().count(), 2);
+/// ```
+#[derive(Default)]
+pub struct Prefab {
+    /// Prefab this one is a variant of
+    base: Option<Rc<Prefab>>,
+    /// Components in the order they are assigned when instantiating, after the ones of the base
+    components: Vec<PrefabComponent>,
+    /// Types of components of the base that this variant leaves out
+    removed: TypeIdSet,
+}
+
+impl Prefab {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a variant of a prefab, which has all components of the base except for the ones it overrides
+    /// or removes. The base is shared, and its components are looked up whenever the variant is instantiated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    /// use yarecs::prefab::Prefab;
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone)]
+    /// struct Health(u32);
+    /// #[derive(Clone)]
+    /// struct Speed(f32);
+    /// #[derive(Clone)]
+    /// struct Crown;
+    ///
+    /// let enemy = Rc::new(Prefab::new().with(Health(30)).with(Speed(1.5)));
+    /// let elite = Prefab::variant_of(enemy.clone()).with(Health(80)).with(Crown);
+    /// let statue = Prefab::variant_of(enemy).without::<Speed>();
+    ///
+    /// let mut scene = Scene::new();
+    /// let boss = elite.instantiate(&mut scene).unwrap();
+    /// assert_eq!(scene.get::<Health>(boss).unwrap().unwrap().0, 80);
+    /// assert_eq!(scene.get::<Speed>(boss).unwrap().unwrap().0, 1.5);
+    /// assert!(scene.get::<Crown>(boss).unwrap().is_some());
+    ///
+    /// let gargoyle = statue.instantiate(&mut scene).unwrap();
+    /// assert!(scene.get::<Speed>(gargoyle).unwrap().is_none());
+    /// assert_eq!(statue.len(), 1);
+    /// ```
+    pub fn variant_of(base: Rc<Prefab>) -> Self {
+        Prefab { base: Some(base), ..Self::default() }
+    }
+
+    /// Returns the prefab this one is a variant of.
+    pub fn base(&self) -> Option<&Rc<Prefab>> {
+        self.base.as_ref()
+    }
+
+    /// Adds a component, replacing a component of the same type, also one of the base.
+    pub fn with<T: Clone + 'static>(mut self, component: T) -> Self {
+        self.insert(component);
+        self
+    }
+
+    /// Adds a component, replacing a component of the same type, also one of the base.
+    pub fn insert<T: Clone + 'static>(&mut self, component: T) {
+        self.insert_boxed(PrefabComponent {
+            type_id: get_type_id::<T>(),
+            value: Box::new(component),
+            clone: clone_fn::<T>(),
+            insert: insert_fn::<T>(),
+            remove: |scene, entity| scene.remove::<T>(entity),
+        });
+    }
+
+    fn insert_boxed(&mut self, component: PrefabComponent) {
+        self.removed.remove(&component.type_id);
+        match self.components.iter_mut().find(|existing| existing.type_id == component.type_id) {
+            Some(existing) => *existing = component,
+            None => self.components.push(component),
+        }
+    }
+
+    /// Removes the component of type `T`, also one of the base.
+    pub fn without<T: 'static>(mut self) -> Self {
+        self.remove::<T>();
+        self
+    }
+
+    /// Removes the component of type `T`, also one of the base.
+    pub fn remove<T: 'static>(&mut self) {
+        self.remove_type(get_type_id::<T>());
+    }
+
+    fn remove_type(&mut self, type_id: usize) {
+        self.components.retain(|component| component.type_id != type_id);
+        if self.base.is_some() {
+            self.removed.insert(type_id);
+        }
+    }
+
+    /// Returns the component of type `T`, which may come from the base.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.find(get_type_id::<T>())?.value.downcast_ref::<T>()
+    }
+
+    /// Returns the component of type `T`, to tweak a variant of a prefab.
+    /// A component of the base is copied into this prefab first, so the base stays as it is.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        let type_id = get_type_id::<T>();
+        if !self.components.iter().any(|component| component.type_id == type_id) {
+            let inherited = self.find(type_id)?.duplicate();
+            self.components.push(inherited);
+        }
+        let component = self.components.iter_mut().find(|component| component.type_id == type_id)?;
+        component.value.downcast_mut::<T>()
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.find(get_type_id::<T>()).is_some()
+    }
+
+    /// Returns the number of component types in the prefab, including the ones of the base.
+    pub fn len(&self) -> usize {
+        self.resolved().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolved().is_empty()
+    }
+
+    /// Creates an entity with copies of the components of the prefab.
+    pub fn instantiate(&self, scene: &mut Scene) -> Result<Entity, RecsError> {
+        let entity = scene.create_entity();
+        self.apply(scene, entity)?;
+        Ok(entity)
+    }
+
+    /// Creates `count` entities with copies of the components of the prefab.
+    pub fn instantiate_many(&self, scene: &mut Scene, count: usize) -> Result<Vec<Entity>, RecsError> {
+        (0..count).map(|_| self.instantiate(scene)).collect()
+    }
+
+    /// Assigns copies of the components of the prefab to an existing entity,
+    /// replacing components of the same types it already has.
+    pub fn apply(&self, scene: &mut Scene, entity: Entity) -> Result<(), RecsError> {
+        self.apply_except(scene, entity, &TypeIdSet::default())
+    }
+
+    /// Assigns copies of the components of the prefab to an existing entity,
+    /// except for the components with the given type IDs.
+    pub fn apply_except(&self, scene: &mut Scene, entity: Entity, except: &TypeIdSet) -> Result<(), RecsError> {
+        for component in self.resolved() {
+            if !except.contains(&component.type_id) {
+                (component.insert)(scene, entity, (component.clone)(&*component.value))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the components this prefab has but `newer` doesn't from an entity,
+    /// except for the components with the given type IDs.
+    pub(crate) fn remove_missing(
+        &self,
+        newer: &Prefab,
+        scene: &mut Scene,
+        entity: Entity,
+        except: &TypeIdSet,
+    ) -> Result<(), RecsError> {
+        for component in self.resolved() {
+            if !except.contains(&component.type_id) && newer.find(component.type_id).is_none() {
+                (component.remove)(scene, entity)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the component of a type, looking into the base if this prefab neither has nor removes it.
+    fn find(&self, type_id: usize) -> Option<&PrefabComponent> {
+        match self.components.iter().find(|component| component.type_id == type_id) {
+            Some(component) => Some(component),
+            None if self.removed.contains(&type_id) => None,
+            None => self.base.as_ref()?.find(type_id),
+        }
+    }
+
+    /// Returns the components of the base that aren't overridden or removed, followed by the own components.
+    fn resolved(&self) -> Vec<&PrefabComponent> {
+        let mut components = match &self.base {
+            Some(base) => base
+                .resolved()
+                .into_iter()
+                .filter(|inherited| {
+                    !self.removed.contains(&inherited.type_id)
+                        && !self.components.iter().any(|component| component.type_id == inherited.type_id)
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        components.extend(&self.components);
+        components
+    }
+}
+
+impl Clone for Prefab {
+    fn clone(&self) -> Self {
+        let components = self.components.iter().map(PrefabComponent::duplicate).collect();
+        Prefab { base: self.base.clone(), components, removed: self.removed.clone() }
+    }
+}
+
+impl fmt::Debug for Prefab {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.resolved().iter().map(|component| get_type_name(component.type_id).unwrap_or("?")))
+            .finish()
+    }
+}
+
+/// Remembers which prefab of a [`PrefabLibrary`] an entity was created from, and which of its components were
+/// changed on purpose, so they are kept when the prefab changes. Entities created with
+/// [`PrefabLibrary::instantiate_tracked`] get it as a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefabInstance {
+    prefab: String,
+    overrides: TypeIdSet,
+}
+
+impl PrefabInstance {
+    pub fn new(prefab: impl Into<String>) -> Self {
+        PrefabInstance { prefab: prefab.into(), overrides: TypeIdSet::default() }
+    }
+
+    /// Returns the name of the prefab the entity was created from.
+    pub fn prefab(&self) -> &str {
+        &self.prefab
+    }
+
+    /// Marks the component of type `T` as changed on purpose, so it isn't updated from the prefab.
+    pub fn mark_overridden<T: 'static>(&mut self) {
+        self.overrides.insert(get_type_id::<T>());
+    }
+
+    /// Lets the component of type `T` be updated from the prefab again.
+    pub fn clear_override<T: 'static>(&mut self) {
+        self.overrides.remove(&get_type_id::<T>());
+    }
+
+    pub fn is_overridden<T: 'static>(&self) -> bool {
+        self.overrides.contains(&get_type_id::<T>())
+    }
+
+    /// Returns the type IDs of the overridden components.
+    pub fn overrides(&self) -> &TypeIdSet {
+        &self.overrides
+    }
+}
+
+/// The prefabs of a [`PrefabLibrary`] that changed, including variants of changed prefabs,
+/// with the versions from before the change. Pass it to [`PrefabLibrary::reapply`] to update their instances.
+#[derive(Debug, Default)]
+pub struct PrefabReload {
+    previous: BTreeMap<String, Rc<Prefab>>,
+}
+
+impl PrefabReload {
+    /// Returns the names of the changed prefabs in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.previous.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty()
+    }
+}
+
+/// Loads a [`Prefab`] saved as a map of type names to components, like `{"Health": 30, "Speed": 1.5}` in JSON.
+/// The types have to be registered in the [`TypeRegistry`] with cloning and serde enabled.
+///
+/// # Example
+///
+/// ```
+/// use serde::{de::DeserializeSeed, Deserialize};
+/// use yarecs::prefab::PrefabDeserializer;
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone, serde::Serialize, Deserialize)]
+/// struct Health(u32);
+/// #[derive(Clone, serde::Serialize, Deserialize)]
+/// struct Loot {
+///     gold: u32,
+/// }
+///
+/// let mut types = TypeRegistry::new();
+/// types.register::<Health>("Health").with_clone().with_serde();
+/// types.register::<Loot>("Loot").with_clone().with_serde();
+///
+/// let json = r#"{"Health": 30, "Loot": {"gold": 5}}"#;
+/// let mut deserializer = serde_json::Deserializer::from_str(json);
+/// let chest = PrefabDeserializer::new(&types).deserialize(&mut deserializer).unwrap();
+///
+/// let mut scene = Scene::new();
+/// let entity = chest.instantiate(&mut scene).unwrap();
+/// assert_eq!(scene.get::<Loot>(entity).unwrap().unwrap().gold, 5);
+/// ```
+#[cfg(feature = "serde")]
+pub struct PrefabDeserializer<'a> {
+    types: &'a TypeRegistry,
+}
+
+#[cfg(feature = "serde")]
+impl<'a> PrefabDeserializer<'a> {
+    pub fn new(types: &'a TypeRegistry) -> Self {
+        PrefabDeserializer { types }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> DeserializeSeed<'de> for PrefabDeserializer<'_> {
+    type Value = Prefab;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Prefab, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for PrefabDeserializer<'_> {
+    type Value = Prefab;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of type names to components")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Prefab, A::Error> {
+        let mut prefab = Prefab::new();
+        while let Some(name) = map.next_key::<String>()? {
+            let info = self
+                .types
+                .get(&name)
+                .ok_or_else(|| de::Error::custom(format!("type {name} is not registered")))?;
+            let clone = info
+                .clone
+                .ok_or_else(|| de::Error::custom(format!("type {name} can't be cloned, so it can't be in a prefab")))?;
+            let value = map.next_value_seed(ValueSeed(info))?;
+            prefab.insert_boxed(PrefabComponent {
+                type_id: info.type_id(),
+                value,
+                clone,
+                insert: info.insert,
+                remove: info.remove,
+            });
+        }
+        Ok(prefab)
+    }
+}
+
+/// A prefab loaded from data, with the name of the prefab it is a variant of
+#[cfg(any(feature = "ron", feature = "json"))]
+struct PrefabDefinition {
+    base: Option<String>,
+    prefab: Prefab,
+}
+
+/// Deserializes a prefab file: the optional name of the `base` prefab, the `components` by type name,
+/// and the type names of components of the base to `remove`.
+#[cfg(any(feature = "ron", feature = "json"))]
+struct DefinitionSeed<'a>(&'a TypeRegistry);
+
+#[cfg(any(feature = "ron", feature = "json"))]
+const DEFINITION_FIELDS: &[&str] = &["base", "components", "remove"];
+
+#[cfg(any(feature = "ron", feature = "json"))]
+impl<'de> DeserializeSeed<'de> for DefinitionSeed<'_> {
+    type Value = PrefabDefinition;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<PrefabDefinition, D::Error> {
+        deserializer.deserialize_struct("Prefab", DEFINITION_FIELDS, self)
+    }
+}
+
+#[cfg(any(feature = "ron", feature = "json"))]
+impl<'de> Visitor<'de> for DefinitionSeed<'_> {
+    type Value = PrefabDefinition;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a prefab with components and an optional base")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<PrefabDefinition, A::Error> {
+        let mut base = None;
+        let mut prefab = None;
+        let mut removed = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "base" if base.is_some() => return Err(de::Error::duplicate_field("base")),
+                "base" => base = Some(map.next_value::<String>()?),
+                "components" if prefab.is_some() => return Err(de::Error::duplicate_field("components")),
+                "components" => prefab = Some(map.next_value_seed(PrefabDeserializer::new(self.0))?),
+                "remove" if removed.is_some() => return Err(de::Error::duplicate_field("remove")),
+                "remove" => removed = Some(map.next_value::<Vec<String>>()?),
+                _ => return Err(de::Error::unknown_field(&key, DEFINITION_FIELDS)),
+            }
+        }
+        let mut prefab = prefab.unwrap_or_default();
+        for name in removed.unwrap_or_default() {
+            if base.is_none() {
+                return Err(de::Error::custom("only variants of other prefabs can remove components"));
+            }
+            let info = self
+                .0
+                .get(&name)
+                .ok_or_else(|| de::Error::custom(format!("type {name} is not registered")))?;
+            prefab.components.retain(|component| component.type_id != info.type_id());
+            prefab.removed.insert(info.type_id());
+        }
+        Ok(PrefabDefinition { base, prefab })
+    }
+}
+
+/// A prefab of a library, with the name of the prefab it is a variant of
+#[derive(Debug)]
+struct LibraryEntry {
+    base: Option<String>,
+    prefab: Rc<Prefab>,
+}
+
+/// Prefabs by name, so content can be spawned by name and defined in data files instead of code.
+///
+/// Prefab files contain the `components` of the prefab keyed by the names their types are registered under
+/// in a [`TypeRegistry`]. A prefab can be a variant of another one in the library, named by `base`,
+/// and `remove` components of it. Files are RON with the `ron` feature, or JSON with the `json` feature.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::prefab::{Prefab, PrefabLibrary};
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone)]
+/// struct Gold(u32);
+///
+/// let mut library = PrefabLibrary::new();
+/// library.insert("coin", Prefab::new().with(Gold(1)));
+///
+/// let mut scene = Scene::new();
+/// let coin = library.instantiate("coin", &mut scene).unwrap();
+/// assert_eq!(scene.get::<Gold>(coin).unwrap().unwrap().0, 1);
+/// assert!(library.instantiate("chest", &mut scene).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct PrefabLibrary {
+    prefabs: BTreeMap<String, LibraryEntry>,
+}
+
+impl PrefabLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a prefab under a name, replacing the prefab with the same name.
+    /// Prefabs loaded as variants of the replaced one become variants of the new one.
+    pub fn insert(&mut self, name: impl Into<String>, prefab: Prefab) -> Rc<Prefab> {
+        let name = name.into();
+        let prefab = Rc::new(prefab);
+        self.prefabs.insert(name.clone(), LibraryEntry { base: None, prefab: prefab.clone() });
+        self.relink_variants(vec![name]);
+        prefab
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rc<Prefab>> {
+        self.prefabs.get(name).map(|entry| &entry.prefab)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    /// Returns the name of the prefab the named prefab was loaded as a variant of.
+    pub fn base_of(&self, name: &str) -> Option<&str> {
+        self.prefabs.get(name)?.base.as_deref()
+    }
+
+    /// Removes a prefab. Variants of it that were loaded before keep their components.
+    pub fn remove(&mut self, name: &str) -> Option<Rc<Prefab>> {
+        self.prefabs.remove(name).map(|entry| entry.prefab)
+    }
+
+    /// Returns the names of the prefabs in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefabs.is_empty()
+    }
+
+    /// Creates an entity from the prefab with the given name.
+    pub fn instantiate(&self, name: &str, scene: &mut Scene) -> Result<Entity, RecsError> {
+        self.get(name)
+            .ok_or_else(|| RecsError::Other(format!("there is no prefab named {name}").into()))?
+            .instantiate(scene)
+    }
+
+    /// Creates an entity from the prefab with the given name and assigns it a [`PrefabInstance`],
+    /// so it can be updated when the prefab changes.
+    pub fn instantiate_tracked(&self, name: &str, scene: &mut Scene) -> Result<Entity, RecsError> {
+        let entity = self.instantiate(name, scene)?;
+        scene.assign(entity, PrefabInstance::new(name))?;
+        Ok(entity)
+    }
+
+    /// Updates the entities created with [`PrefabLibrary::instantiate_tracked`] from the reloaded prefabs:
+    /// components the prefab still has are replaced with copies of the new ones, and components it no longer
+    /// has are removed, unless they are marked as overridden. Returns the number of updated entities.
+    pub fn reapply(&self, scene: &mut Scene, reload: &PrefabReload) -> Result<usize, RecsError> {
+        let instances = scene
+            .view::<(PrefabInstance, ())>()
+            .filter_map(|entity| Some((entity, scene.get::<PrefabInstance>(entity).ok()??.clone())))
+            .collect::<Vec<_>>();
+        let mut updated = 0;
+        for (entity, instance) in instances {
+            let (Some(old), Some(new)) = (reload.previous.get(&instance.prefab), self.get(&instance.prefab)) else {
+                continue;
+            };
+            old.remove_missing(new, scene, entity, &instance.overrides)?;
+            new.apply_except(scene, entity, &instance.overrides)?;
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    /// Loads a prefab from a RON string and adds it under the name.
+    /// A `base` prefab has to be in the library already.
+    #[cfg(feature = "ron")]
+    pub fn load_ron(&mut self, name: &str, ron: &str, types: &TypeRegistry) -> Result<Rc<Prefab>, RecsError> {
+        let definition = parse_definition(PrefabFormat::Ron, ron, types)?;
+        self.link(vec![(name.to_string(), definition)])?;
+        Ok(self.prefabs[name].prefab.clone())
+    }
+
+    /// Loads a prefab from a JSON string and adds it under the name.
+    /// A `base` prefab has to be in the library already.
+    #[cfg(feature = "json")]
+    pub fn load_json(&mut self, name: &str, json: &str, types: &TypeRegistry) -> Result<Rc<Prefab>, RecsError> {
+        let definition = parse_definition(PrefabFormat::Json, json, types)?;
+        self.link(vec![(name.to_string(), definition)])?;
+        Ok(self.prefabs[name].prefab.clone())
+    }
+
+    /// Loads a prefab file and adds it under the name of the file without its extension.
+    /// The extension selects the format, `.ron` or `.json`. A `base` prefab has to be in the library already.
+    #[cfg(any(feature = "ron", feature = "json"))]
+    pub fn load_file(&mut self, path: impl AsRef<Path>, types: &TypeRegistry) -> Result<Rc<Prefab>, RecsError> {
+        let (name, definition) = read_definition(path.as_ref(), types)?;
+        self.link(vec![(name.clone(), definition)])?;
+        Ok(self.prefabs[&name].prefab.clone())
+    }
+
+    /// Loads all prefab files of a directory, named after their files without extension.
+    /// Files of other formats are ignored. Variants may come before their bases, which are loaded first.
+    /// Returns the names of the loaded prefabs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "ron")]
+    /// # {
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::prefab::PrefabLibrary;
+    /// use yarecs::registry::TypeRegistry;
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// struct Health(u32);
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// struct Speed(f32);
+    ///
+    /// let mut types = TypeRegistry::new();
+    /// types.register::<Health>("Health").with_clone().with_serde();
+    /// types.register::<Speed>("Speed").with_clone().with_serde();
+    ///
+    /// let dir = std::env::temp_dir().join("yarecs_prefab_library_example");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("enemy.ron"), r#"(components: { "Health": (30), "Speed": (1.5) })"#).unwrap();
+    /// std::fs::write(dir.join("turret.ron"), r#"(base: "enemy", components: { "Health": (80) }, remove: ["Speed"])"#)
+    ///     .unwrap();
+    ///
+    /// let mut library = PrefabLibrary::new();
+    /// library.load_dir(&dir, &types).unwrap();
+    ///
+    /// let mut scene = Scene::new();
+    /// let turret = library.instantiate("turret", &mut scene).unwrap();
+    /// assert_eq!(scene.get::<Health>(turret).unwrap().unwrap().0, 80);
+    /// assert!(scene.get::<Speed>(turret).unwrap().is_none());
+    /// # std::fs::remove_dir_all(dir).unwrap();
+    /// # }
+    /// ```
+    #[cfg(any(feature = "ron", feature = "json"))]
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>, types: &TypeRegistry) -> Result<Vec<String>, RecsError> {
+        let mut definitions = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(|e| RecsError::Other(Box::new(e)))? {
+            let path = entry.map_err(|e| RecsError::Other(Box::new(e)))?.path();
+            if PrefabFormat::of(&path).is_some() {
+                definitions.push(read_definition(&path, types)?);
+            }
+        }
+        let mut names = definitions.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+        names.sort();
+        self.link(definitions)?;
+        Ok(names)
+    }
+
+    /// Loads prefab files again, replacing the prefabs loaded from them before.
+    /// Variants of the reloaded prefabs are updated, too.
+    #[cfg(feature = "hot-reload")]
+    pub(crate) fn reload_files(
+        &mut self,
+        paths: &[std::path::PathBuf],
+        types: &TypeRegistry,
+    ) -> Result<PrefabReload, RecsError> {
+        let definitions = paths.iter().map(|path| read_definition(path, types)).collect::<Result<Vec<_>, _>>()?;
+        let before = self
+            .prefabs
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.prefab.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let previous = self
+            .link(definitions)?
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), before.get(&name)?.clone())))
+            .collect();
+        Ok(PrefabReload { previous })
+    }
+
+    #[cfg(any(feature = "ron", feature = "json"))]
+    /// Adds loaded prefabs, each after the prefab it is a variant of.
+    /// All bases are checked before anything is added, so on error the library is left unchanged.
+    /// Returns the names of the added prefabs and of the variants of them that were updated.
+    fn link(&mut self, mut definitions: Vec<(String, PrefabDefinition)>) -> Result<Vec<String>, RecsError> {
+        let mut ordered: Vec<(String, PrefabDefinition)> = Vec::with_capacity(definitions.len());
+        while !definitions.is_empty() {
+            let pending = definitions.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+            let ready = definitions.iter().position(|(_, definition)| match &definition.base {
+                Some(base) => {
+                    !pending.contains(base)
+                        && (self.prefabs.contains_key(base) || ordered.iter().any(|(name, _)| name == base))
+                }
+                None => true,
+            });
+            let Some(ready) = ready else {
+                let (name, definition) = &definitions[0];
+                let base = definition.base.as_deref().unwrap_or_default();
+                return Err(RecsError::Other(
+                    format!("prefab {name} is a variant of {base}, which is missing or a variant of itself").into(),
+                ));
+            };
+            ordered.push(definitions.swap_remove(ready));
+        }
+        let mut linked = Vec::with_capacity(ordered.len());
+        for (name, PrefabDefinition { base, mut prefab }) in ordered {
+            if let Some(base) = &base {
+                prefab.base = Some(self.prefabs[base].prefab.clone());
+            }
+            self.prefabs.insert(name.clone(), LibraryEntry { base, prefab: Rc::new(prefab) });
+            linked.push(name);
+        }
+        Ok(self.relink_variants(linked))
+    }
+
+    /// Makes the variants of the replaced prefabs, and variants of those, use the new versions as their bases.
+    /// Returns the names of the replaced prefabs and of the updated variants.
+    fn relink_variants(&mut self, replaced: Vec<String>) -> Vec<String> {
+        let mut updated = replaced;
+        let mut next = 0;
+        while let Some(base) = updated.get(next).cloned() {
+            next += 1;
+            let variants = self
+                .prefabs
+                .iter()
+                .filter(|(name, entry)| entry.base.as_ref() == Some(&base) && !updated.contains(name))
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            for name in variants {
+                let mut prefab = (*self.prefabs[&name].prefab).clone();
+                prefab.base = Some(self.prefabs[&base].prefab.clone());
+                self.prefabs.get_mut(&name).expect("variant exists").prefab = Rc::new(prefab);
+                updated.push(name);
+            }
+        }
+        updated
+    }
+}
+
+/// Formats prefab files can be written in
+#[cfg(any(feature = "ron", feature = "json"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PrefabFormat {
+    #[cfg(feature = "ron")]
+    Ron,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+#[cfg(any(feature = "ron", feature = "json"))]
+impl PrefabFormat {
+    /// Returns the format of a file by its extension, if it is supported.
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            #[cfg(feature = "ron")]
+            "ron" => Some(PrefabFormat::Ron),
+            #[cfg(feature = "json")]
+            "json" => Some(PrefabFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(any(feature = "ron", feature = "json"))]
+fn parse_definition(format: PrefabFormat, text: &str, types: &TypeRegistry) -> Result<PrefabDefinition, RecsError> {
+    match format {
+        #[cfg(feature = "ron")]
+        PrefabFormat::Ron => ron::Options::default()
+            .from_str_seed(text, DefinitionSeed(types))
+            .map_err(|e| RecsError::Other(Box::new(e))),
+        #[cfg(feature = "json")]
+        PrefabFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(text);
+            DefinitionSeed(types)
+                .deserialize(&mut deserializer)
+                .and_then(|definition| deserializer.end().map(|_| definition))
+                .map_err(|e| RecsError::Other(Box::new(e)))
+        }
+    }
+}
+
+/// Reads a prefab file and returns its name, which is the name of the file without extension, and its contents.
+#[cfg(any(feature = "ron", feature = "json"))]
+fn read_definition(path: &Path, types: &TypeRegistry) -> Result<(String, PrefabDefinition), RecsError> {
+    let format = PrefabFormat::of(path)
+        .ok_or_else(|| RecsError::Other(format!("{} is not a prefab file", path.display()).into()))?;
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| RecsError::Other(format!("{} has no valid name", path.display()).into()))?;
+    let text = std::fs::read_to_string(path).map_err(|e| RecsError::Other(Box::new(e)))?;
+    let definition = parse_definition(format, &text, types)
+        .map_err(|e| RecsError::Other(format!("failed to load prefab {}: {e}", path.display()).into()))?;
+    Ok((name.to_string(), definition))
+}
@@ -0,0 +1,83 @@
+use std::{fmt, marker::PhantomData};
+
+use crate::{component::RequireComponents, entity::Entity, scene::Scene};
+
+/// A view whose matching entities are kept between runs.
+/// The entities are only matched again when the structure of the scene changed since the last run,
+/// so queries that run every frame cost almost nothing on frames without spawns, despawns or component changes.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::query::CachedQuery;
+/// use yarecs::scene::Scene;
+///
+/// struct Position(f32);
+/// struct Velocity(f32);
+///
+/// let mut scene = Scene::new();
+/// let entity = scene.create_entity();
+/// scene.assign(entity, Position(0.0)).unwrap();
+/// scene.assign(entity, Velocity(1.0)).unwrap();
+///
+/// let mut moving = CachedQuery::<(Position, (Velocity, ()))>::new();
+/// assert_eq!(moving.entities(&scene), &[entity]);
+///
+/// // Changing component values keeps the cached entities
+/// scene.get_mut::<Position>(entity).unwrap().unwrap().0 += 1.0;
+/// assert!(!moving.is_stale(&scene));
+///
+/// // Structural changes invalidate them
+/// scene.remove::<Velocity>(entity).unwrap();
+/// assert!(moving.is_stale(&scene));
+/// assert!(moving.entities(&scene).is_empty());
+/// ```
+pub struct CachedQuery<T> {
+    /// Entities that matched during the last run
+    entities: Vec<Entity>,
+    /// Structure generation of the scene during the last run
+    generation: Option<u64>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: RequireComponents> Default for CachedQuery<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: RequireComponents> CachedQuery<T> {
+    pub fn new() -> Self {
+        CachedQuery {
+            entities: Vec::new(),
+            generation: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns all entities of the scene that have all the required components,
+    /// matching them again only if the structure of the scene changed since the last call.
+    /// A cached query should only be used with one scene.
+    pub fn entities(&mut self, scene: &Scene) -> &[Entity] {
+        if self.is_stale(scene) {
+            self.entities.clear();
+            self.entities.extend(scene.view::<T>());
+            self.generation = Some(scene.structure_generation());
+        }
+        &self.entities
+    }
+
+    /// Returns true if the next call to [`CachedQuery::entities`] has to match the entities again.
+    pub fn is_stale(&self, scene: &Scene) -> bool {
+        self.generation != Some(scene.structure_generation())
+    }
+}
+
+impl<T> fmt::Debug for CachedQuery<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachedQuery")
+            .field("entities", &self.entities)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
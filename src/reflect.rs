@@ -0,0 +1,88 @@
+use std::{
+    any::{type_name, Any},
+    fmt,
+};
+
+use crate::error::RecsError;
+
+/// Reads a field of a value behind `dyn Any`, or returns `None` if the value has another type
+pub(crate) type FieldGetFn = fn(&dyn Any) -> Option<&dyn Any>;
+/// Borrows a field of a value behind `dyn Any` mutably, or returns `None` if the value has another type
+pub(crate) type FieldGetMutFn = fn(&mut dyn Any) -> Option<&mut dyn Any>;
+/// Replaces a field borrowed as `dyn Any` with a boxed value of the field's type
+type ReplaceFn = fn(&mut dyn Any, Box<dyn Any>) -> Result<(), RecsError>;
+
+/// Describes the fields of a type, so editors and debug UIs can display and edit its values
+/// without knowing the type at compile time. Usually derived with `#[derive(Reflect)]`,
+/// and made available through a [`TypeRegistry`](crate::registry::TypeRegistry) with
+/// [`TypeRegistration::with_reflect`](crate::registry::TypeRegistration::with_reflect).
+pub trait Reflect: 'static {
+    /// Returns the fields of the type in declaration order.
+    /// Fields of tuple structs are named by their position.
+    fn fields() -> Vec<FieldInfo>;
+}
+
+/// Name, type and accessors of a field of a [`Reflect`] type.
+#[derive(Clone)]
+pub struct FieldInfo {
+    name: &'static str,
+    type_name: &'static str,
+    get: FieldGetFn,
+    get_mut: FieldGetMutFn,
+    replace: ReplaceFn,
+}
+
+impl FieldInfo {
+    /// Creates the description of a field of type `F` from functions borrowing it out of the value.
+    pub fn new<F: 'static>(name: &'static str, get: FieldGetFn, get_mut: FieldGetMutFn) -> Self {
+        FieldInfo {
+            name,
+            type_name: type_name::<F>(),
+            get,
+            get_mut,
+            replace: |field, new_value| {
+                let new_value = *new_value
+                    .downcast::<F>()
+                    .map_err(|_| RecsError::Other(format!("value is not a {}", type_name::<F>()).into()))?;
+                *field.downcast_mut::<F>().expect("field type was checked") = new_value;
+                Ok(())
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the rust type path of the field.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the field of `value`, or `None` if `value` isn't of the type the field belongs to.
+    pub fn get<'v>(&self, value: &'v dyn Any) -> Option<&'v dyn Any> {
+        (self.get)(value)
+    }
+
+    /// Returns the field of `value` mutably, or `None` if `value` isn't of the type the field belongs to.
+    pub fn get_mut<'v>(&self, value: &'v mut dyn Any) -> Option<&'v mut dyn Any> {
+        (self.get_mut)(value)
+    }
+
+    /// Replaces the field of `value` with `field_value`.
+    /// Fails if `value` isn't of the type the field belongs to or `field_value` isn't of the field's type.
+    pub fn set(&self, value: &mut dyn Any, field_value: Box<dyn Any>) -> Result<(), RecsError> {
+        let field = (self.get_mut)(value)
+            .ok_or_else(|| RecsError::Other(format!("value has no field {}", self.name).into()))?;
+        (self.replace)(field, field_value)
+    }
+}
+
+impl fmt::Debug for FieldInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FieldInfo")
+            .field("name", &self.name)
+            .field("type_name", &self.type_name)
+            .finish()
+    }
+}
@@ -0,0 +1,535 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::BTreeMap,
+    fmt,
+    marker::PhantomData,
+    mem,
+};
+
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserializer, Serialize, Serializer};
+
+use crate::{
+    component::{get_type_id, Component, TypeIdMap},
+    entity::Entity,
+    error::RecsError,
+    rollback::SnapshotFns,
+    scene::Scene,
+};
+
+#[cfg(feature = "serde")]
+use crate::serialization::ComponentRegistry;
+#[cfg(feature = "reflect")]
+use crate::reflect::{FieldInfo, Reflect};
+
+/// Assigns a boxed value to an entity as a component
+pub(crate) type InsertFn = fn(&mut Scene, Entity, Box<dyn Any>) -> Result<(), RecsError>;
+/// Removes the component of a type from an entity
+pub(crate) type RemoveFn = fn(&mut Scene, Entity) -> Result<(), RecsError>;
+/// Inserts a boxed value as a resource
+pub(crate) type InsertResourceFn = fn(&mut Scene, Box<dyn Any>) -> Result<(), RecsError>;
+/// Clones a value into a box
+pub(crate) type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
+/// Deserializes a value into a box
+#[cfg(feature = "serde")]
+type DeserializeBoxFn =
+    for<'de> fn(&mut dyn erased_serde::Deserializer<'de>) -> Result<Box<dyn Any>, erased_serde::Error>;
+
+/// Everything known about a registered type: its names, its recs type ID, its layout, and the functions to work
+/// with values and components of it without knowing the type at compile time.
+/// Functions that need more than `'static` are only available if they were enabled when registering the type,
+/// see [`TypeRegistration`].
+#[derive(Clone)]
+pub struct TypeInfo {
+    name: String,
+    type_name: &'static str,
+    type_id: usize,
+    rust_type_id: TypeId,
+    size: usize,
+    align: usize,
+    needs_drop: bool,
+    get: for<'a> fn(&'a Scene, Entity) -> Option<&'a dyn Any>,
+    get_mut: for<'a> fn(&'a mut Scene, Entity) -> Option<&'a mut dyn Any>,
+    pub(crate) insert: InsertFn,
+    pub(crate) remove: RemoveFn,
+    pub(crate) insert_resource: InsertResourceFn,
+    default: Option<fn() -> Box<dyn Any>>,
+    pub(crate) clone: Option<CloneFn>,
+    pub(crate) snapshot: Option<SnapshotFns>,
+    debug: Option<fn(&dyn Any, &mut fmt::Formatter) -> fmt::Result>,
+    #[cfg(feature = "serde")]
+    serialize: Option<fn(&dyn Any) -> &dyn erased_serde::Serialize>,
+    #[cfg(feature = "serde")]
+    deserialize: Option<DeserializeBoxFn>,
+    #[cfg(feature = "serde")]
+    pub(crate) register_component: Option<fn(&mut ComponentRegistry, &str)>,
+    #[cfg(feature = "reflect")]
+    fields: Option<Vec<FieldInfo>>,
+}
+
+impl TypeInfo {
+    fn of<T: 'static>(name: String) -> Self {
+        TypeInfo {
+            name,
+            type_name: type_name::<T>(),
+            type_id: get_type_id::<T>(),
+            rust_type_id: TypeId::of::<T>(),
+            size: mem::size_of::<T>(),
+            align: mem::align_of::<T>(),
+            needs_drop: mem::needs_drop::<T>(),
+            get: |scene, entity| Some(scene.get::<T>(entity).ok()?? as &dyn Any),
+            get_mut: |scene, entity| Some(scene.get_mut::<T>(entity).ok()?? as &mut dyn Any),
+            insert: insert_fn::<T>(),
+            remove: |scene, entity| scene.remove::<T>(entity),
+            insert_resource: insert_resource_fn::<T>(),
+            default: None,
+            clone: None,
+            snapshot: None,
+            debug: None,
+            #[cfg(feature = "serde")]
+            serialize: None,
+            #[cfg(feature = "serde")]
+            deserialize: None,
+            #[cfg(feature = "serde")]
+            register_component: None,
+            #[cfg(feature = "reflect")]
+            fields: None,
+        }
+    }
+
+    /// Returns the name the type was registered under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the rust type path of the type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the recs type ID of the type, as handed out by [`get_type_id`].
+    pub fn type_id(&self) -> usize {
+        self.type_id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Returns whether dropping a value of the type runs any code.
+    pub fn needs_drop(&self) -> bool {
+        self.needs_drop
+    }
+
+    /// Returns the component of this type of an entity, if it has one.
+    pub fn get<'a>(&self, scene: &'a Scene, entity: Entity) -> Option<&'a dyn Any> {
+        (self.get)(scene, entity)
+    }
+
+    /// Returns the component of this type of an entity mutably, if it has one that can be borrowed mutably.
+    pub fn get_mut<'a>(&self, scene: &'a mut Scene, entity: Entity) -> Option<&'a mut dyn Any> {
+        (self.get_mut)(scene, entity)
+    }
+
+    /// Assigns a value of this type to an entity as a component, replacing the component it already has.
+    /// Fails if the value has another type.
+    pub fn insert(&self, scene: &mut Scene, entity: Entity, value: Box<dyn Any>) -> Result<(), RecsError> {
+        (self.insert)(scene, entity, value)
+    }
+
+    /// Removes the component of this type from an entity.
+    pub fn remove(&self, scene: &mut Scene, entity: Entity) -> Result<(), RecsError> {
+        (self.remove)(scene, entity)
+    }
+
+    /// Inserts a value of this type as a resource of the scene, replacing the resource it already has.
+    /// Fails if the value has another type.
+    pub fn insert_resource(&self, scene: &mut Scene, value: Box<dyn Any>) -> Result<(), RecsError> {
+        (self.insert_resource)(scene, value)
+    }
+
+    /// Creates the default value of the type, if default construction was enabled.
+    pub fn default_value(&self) -> Option<Box<dyn Any>> {
+        self.default.map(|default| default())
+    }
+
+    /// Clones a value of the type, if cloning was enabled.
+    /// Returns `None` if the value has another type.
+    pub fn clone_value(&self, value: &dyn Any) -> Option<Box<dyn Any>> {
+        let clone = self.clone?;
+        (value.type_id() == self.rust_type_id).then(|| clone(value))
+    }
+
+    /// Formats a value of the type with its `Debug` implementation, if debug formatting was enabled.
+    /// Returns `None` if the value has another type.
+    pub fn debug<'v>(&self, value: &'v dyn Any) -> Option<DebugValue<'v>> {
+        let debug = self.debug?;
+        (value.type_id() == self.rust_type_id).then_some(DebugValue { value, debug })
+    }
+
+    /// Serializes a value of the type, if serialization was enabled.
+    /// Fails if serialization wasn't enabled or the value has another type.
+    #[cfg(feature = "serde")]
+    pub fn serialize<S: Serializer>(&self, value: &dyn Any, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let serialize = self
+            .serialize
+            .ok_or_else(|| S::Error::custom(format!("type {} can't be serialized", self.name)))?;
+        if value.type_id() != self.rust_type_id {
+            return Err(S::Error::custom(format!("value is not a {}", self.name)));
+        }
+        erased_serde::serialize(serialize(value), serializer)
+    }
+
+    /// Deserializes a value of the type, if deserialization was enabled.
+    #[cfg(feature = "serde")]
+    pub fn deserialize<'de, D: Deserializer<'de>>(&self, deserializer: D) -> Result<Box<dyn Any>, D::Error> {
+        use serde::de::Error;
+
+        let deserialize = self
+            .deserialize
+            .ok_or_else(|| D::Error::custom(format!("type {} can't be deserialized", self.name)))?;
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        deserialize(&mut deserializer).map_err(D::Error::custom)
+    }
+
+    /// Returns the fields of the type, if reflection was enabled with [`TypeRegistration::with_reflect`].
+    #[cfg(feature = "reflect")]
+    pub fn fields(&self) -> Option<&[FieldInfo]> {
+        self.fields.as_deref()
+    }
+
+    pub fn can_default(&self) -> bool {
+        self.default.is_some()
+    }
+
+    pub fn can_clone(&self) -> bool {
+        self.clone.is_some()
+    }
+
+    pub fn can_debug(&self) -> bool {
+        self.debug.is_some()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn can_serialize(&self) -> bool {
+        self.serialize.is_some()
+    }
+}
+
+impl fmt::Debug for TypeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypeInfo")
+            .field("name", &self.name)
+            .field("type_name", &self.type_name)
+            .field("type_id", &self.type_id)
+            .finish()
+    }
+}
+
+/// Returns a function assigning a boxed `T` to an entity, replacing the component it already has.
+pub(crate) fn insert_fn<T: 'static>() -> InsertFn {
+    |scene, entity, value| {
+        let value = *value
+            .downcast::<T>()
+            .map_err(|_| RecsError::Other(format!("value is not a {}", type_name::<T>()).into()))?;
+        match scene.get_mut::<T>(entity)? {
+            Some(existing) => *existing = value,
+            None => {
+                scene.assign(entity, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns a function inserting a boxed `T` as a resource.
+pub(crate) fn insert_resource_fn<T: 'static>() -> InsertResourceFn {
+    |scene, value| {
+        let value = *value
+            .downcast::<T>()
+            .map_err(|_| RecsError::Other(format!("value is not a {}", type_name::<T>()).into()))?;
+        scene.insert_resource(value);
+        Ok(())
+    }
+}
+
+/// Returns a function cloning a `T` behind `dyn Any`. The function panics if the value is no `T`.
+pub(crate) fn clone_fn<T: Clone + 'static>() -> CloneFn {
+    |value| Box::new(value.downcast_ref::<T>().cloned().expect("type was checked"))
+}
+
+/// Deserializes a value of a registered type.
+#[cfg(feature = "serde")]
+pub(crate) struct ValueSeed<'a>(pub(crate) &'a TypeInfo);
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Box<dyn Any>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Box<dyn Any>, D::Error> {
+        self.0.deserialize(deserializer)
+    }
+}
+
+/// A value of a registered type, formatted with the `Debug` implementation of that type.
+pub struct DebugValue<'v> {
+    value: &'v dyn Any,
+    debug: fn(&dyn Any, &mut fmt::Formatter) -> fmt::Result,
+}
+
+impl fmt::Debug for DebugValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.debug)(self.value, f)
+    }
+}
+
+/// Names, type IDs and functions of types that code has to handle without knowing them at compile time,
+/// like scene cloning, serialization and inspectors in editors.
+/// Types are registered with [`TypeRegistry::register`], which enables the functions each type supports.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Debug, Clone, Default)]
+/// struct Health(u32);
+/// #[derive(Debug, Clone)]
+/// struct Name(String);
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.register::<Health>("Health").with_default().with_clone().with_debug();
+/// registry.register::<Name>("Name").with_clone().with_debug();
+///
+/// let mut scene = Scene::new();
+/// let entity = scene.create_entity();
+/// scene.assign(entity, Name("goblin".to_string())).unwrap();
+///
+/// // An inspector adding a component picked from a list of names
+/// let health = registry.get("Health").unwrap();
+/// health.insert(&mut scene, entity, health.default_value().unwrap()).unwrap();
+///
+/// let components = registry
+///     .components_of(&scene, entity)
+///     .map(|(info, value)| format!("{}: {:?}", info.name(), info.debug(value).unwrap()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(components, ["Health: Health(0)", "Name: Name(\"goblin\")"]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct TypeRegistry {
+    types: Vec<TypeInfo>,
+    /// Types by name, ordered so that types are listed in a stable order
+    by_name: BTreeMap<String, usize>,
+    by_type_id: TypeIdMap<usize>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a type under a name and returns a builder to enable the functions the type supports.
+    /// Registering a type again renames it and keeps the functions enabled before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another type is already registered under the same name.
+    pub fn register<T: 'static>(&mut self, name: impl Into<String>) -> TypeRegistration<'_, T> {
+        let name = name.into();
+        let type_id = get_type_id::<T>();
+        if let Some(&index) = self.by_name.get(&name) {
+            assert_eq!(self.by_type_id.get(&type_id), Some(&index), "type name {name} is already registered");
+        }
+        let index = match self.by_type_id.get(&type_id) {
+            Some(&index) => {
+                self.by_name.remove(&self.types[index].name);
+                self.types[index].name = name.clone();
+                index
+            }
+            None => {
+                self.by_type_id.insert(type_id, self.types.len());
+                self.types.push(TypeInfo::of::<T>(name.clone()));
+                self.types.len() - 1
+            }
+        };
+        self.by_name.insert(name, index);
+        TypeRegistration { info: &mut self.types[index], _type: PhantomData }
+    }
+
+    /// Registers a type under [`Component::NAME`] and enables the functions chosen by [`Component::register`].
+    pub fn register_component<T: Component>(&mut self) -> TypeRegistration<'_, T> {
+        T::register(self.register::<T>(T::NAME))
+    }
+
+    /// Returns the type registered under the name.
+    pub fn get(&self, name: &str) -> Option<&TypeInfo> {
+        self.by_name.get(name).map(|&index| &self.types[index])
+    }
+
+    /// Returns the type with the recs type ID, if it is registered.
+    pub fn get_by_type_id(&self, type_id: usize) -> Option<&TypeInfo> {
+        self.by_type_id.get(&type_id).map(|&index| &self.types[index])
+    }
+
+    /// Returns the registered type `T`.
+    pub fn get_of<T: 'static>(&self) -> Option<&TypeInfo> {
+        self.get_by_type_id(get_type_id::<T>())
+    }
+
+    /// Returns the name type `T` was registered under.
+    pub fn name_of<T: 'static>(&self) -> Option<&str> {
+        self.get_of::<T>().map(TypeInfo::name)
+    }
+
+    /// Returns the registered types ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &TypeInfo> {
+        self.by_name.values().map(|&index| &self.types[index])
+    }
+
+    /// Returns the components of registered types of an entity, ordered by the names of their types.
+    pub fn components_of<'a>(
+        &'a self,
+        scene: &'a Scene,
+        entity: Entity,
+    ) -> impl Iterator<Item = (&'a TypeInfo, &'a dyn Any)> + 'a {
+        self.iter().filter_map(move |info| Some((info, info.get(scene, entity)?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+/// Enables the functions of a type being registered in a [`TypeRegistry`] that need more than `'static`.
+pub struct TypeRegistration<'r, T> {
+    info: &'r mut TypeInfo,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> TypeRegistration<'_, T> {
+    /// Enables [`TypeInfo::default_value`].
+    pub fn with_default(self) -> Self
+    where
+        T: Default,
+    {
+        self.info.default = Some(|| Box::new(T::default()));
+        self
+    }
+
+    /// Enables [`TypeInfo::clone_value`], and snapshots of components of the type in scenes
+    /// the type is registered in with [`Scene::register_component`].
+    pub fn with_clone(self) -> Self
+    where
+        T: Clone,
+    {
+        self.info.clone = Some(clone_fn::<T>());
+        self.info.snapshot = Some(SnapshotFns::of::<T>());
+        self
+    }
+
+    /// Enables [`TypeInfo::debug`].
+    pub fn with_debug(self) -> Self
+    where
+        T: fmt::Debug,
+    {
+        self.info.debug = Some(|value, f| fmt::Debug::fmt(value.downcast_ref::<T>().expect("type was checked"), f));
+        self
+    }
+
+    /// Enables [`TypeInfo::serialize`] and [`TypeInfo::deserialize`], and saving components of the type
+    /// with a [`ComponentRegistry`] created by [`ComponentRegistry::from_types`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::registry::TypeRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Velocity(f32, f32);
+    ///
+    /// let mut registry = TypeRegistry::new();
+    /// registry.register::<Velocity>("Velocity").with_serde();
+    ///
+    /// let info = registry.get("Velocity").unwrap();
+    /// let value = info.deserialize(&mut serde_json::Deserializer::from_str("[1.5,0.0]")).unwrap();
+    /// let mut json = Vec::new();
+    /// info.serialize(&*value, &mut serde_json::Serializer::new(&mut json)).unwrap();
+    /// assert_eq!(json, b"[1.5,0.0]");
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn with_serde(self) -> Self
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.info.serialize = Some(|value| value.downcast_ref::<T>().expect("type was checked"));
+        self.info.deserialize = Some(|deserializer| Ok(Box::new(erased_serde::deserialize::<T>(deserializer)?)));
+        self.info.register_component = Some(|registry, name| {
+            registry.register_as::<T>(name);
+        });
+        self
+    }
+}
+
+#[cfg(feature = "reflect")]
+impl<T: Reflect> TypeRegistration<'_, T> {
+    /// Enables [`TypeInfo::fields`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::reflect::{FieldInfo, Reflect};
+    /// use yarecs::registry::TypeRegistry;
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Health {
+    ///     current: u32,
+    /// }
+    ///
+    /// impl Reflect for Health {
+    ///     fn fields() -> Vec<FieldInfo> {
+    ///         vec![FieldInfo::new::<u32>(
+    ///             "current",
+    ///             |value| Some(&value.downcast_ref::<Self>()?.current),
+    ///             |value| Some(&mut value.downcast_mut::<Self>()?.current),
+    ///         )]
+    ///     }
+    /// }
+    ///
+    /// let mut registry = TypeRegistry::new();
+    /// registry.register::<Health>("Health").with_reflect();
+    ///
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, Health { current: 10 }).unwrap();
+    ///
+    /// // An inspector editing a field picked from a list
+    /// let info = registry.get("Health").unwrap();
+    /// let field = &info.fields().unwrap()[0];
+    /// assert_eq!((field.name(), field.type_name()), ("current", "u32"));
+    /// field.set(info.get_mut(&mut scene, entity).unwrap(), Box::new(5u32)).unwrap();
+    /// assert!(field.set(info.get_mut(&mut scene, entity).unwrap(), Box::new(5i64)).is_err());
+    /// assert_eq!(scene.get::<Health>(entity).unwrap().unwrap().current, 5);
+    /// ```
+    pub fn with_reflect(self) -> Self {
+        self.info.fields = Some(T::fields());
+        self
+    }
+}
+
+impl<T> fmt::Debug for TypeRegistration<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TypeRegistration").field(&self.info).finish()
+    }
+}
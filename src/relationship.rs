@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use rustc_hash::FxHashMap;
+
+use crate::entity::Entity;
+
+/// Points at another entity, like the enemy a turret aims at. Set with [`Scene::set_target`]; the scene removes
+/// the component once the target is destroyed, so it never refers to a dead or recycled entity.
+///
+/// [`Scene::set_target`]: crate::scene::Scene::set_target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Targets(pub(crate) Entity);
+
+impl Targets {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Makes an entity depend on another one, like a projectile on the weapon that fired it. Set with
+/// [`Scene::set_owner`]; the scene destroys the entity together with its owner.
+///
+/// [`Scene::set_owner`]: crate::scene::Scene::set_owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OwnedBy(pub(crate) Entity);
+
+impl OwnedBy {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// What happens to an entity relating to another one once that one is destroyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Cleanup {
+    /// The relationship component is removed
+    Remove,
+    /// The entity is destroyed
+    Destroy,
+}
+
+/// A component relating its entity to another one, whose reverse links the scene tracks.
+pub(crate) trait Relationship: Copy + 'static {
+    const CLEANUP: Cleanup;
+
+    fn new(target: Entity) -> Self;
+
+    fn target(&self) -> Entity;
+}
+
+impl Relationship for Targets {
+    const CLEANUP: Cleanup = Cleanup::Remove;
+
+    fn new(target: Entity) -> Self {
+        Targets(target)
+    }
+
+    fn target(&self) -> Entity {
+        self.0
+    }
+}
+
+impl Relationship for OwnedBy {
+    const CLEANUP: Cleanup = Cleanup::Destroy;
+
+    fn new(target: Entity) -> Self {
+        OwnedBy(target)
+    }
+
+    fn target(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Reverse links of relationships: the entities relating to each entity.
+/// Links may be stale if relationship components are removed directly, so they have to be verified before use.
+#[derive(Debug, Default)]
+pub(crate) struct Relationships {
+    sources: FxHashMap<Entity, Vec<(Entity, Cleanup)>>,
+}
+
+impl Relationships {
+    pub(crate) fn insert(&mut self, target: Entity, source: Entity, cleanup: Cleanup) {
+        let sources = self.sources.entry(target).or_default();
+        if !sources.contains(&(source, cleanup)) {
+            sources.push((source, cleanup));
+        }
+    }
+
+    pub(crate) fn remove(&mut self, target: Entity, source: Entity, cleanup: Cleanup) {
+        if let Some(sources) = self.sources.get_mut(&target) {
+            sources.retain(|&link| link != (source, cleanup));
+            if sources.is_empty() {
+                self.sources.remove(&target);
+            }
+        }
+    }
+
+    /// Returns the entities that related to the target with the given cleanup.
+    pub(crate) fn sources(&self, target: Entity, cleanup: Cleanup) -> impl Iterator<Item = Entity> + '_ {
+        self.sources
+            .get(&target)
+            .into_iter()
+            .flatten()
+            .filter(move |(_, link_cleanup)| *link_cleanup == cleanup)
+            .map(|(source, _)| *source)
+    }
+
+    /// Replaces the entities of all links after the entities of the scene were compacted.
+    pub(crate) fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        let remapped = |entity: Entity| remap.get(&entity).copied();
+        self.sources = std::mem::take(&mut self.sources)
+            .into_iter()
+            .filter_map(|(target, sources)| {
+                let sources = sources
+                    .into_iter()
+                    .filter_map(|(source, cleanup)| Some((remapped(source)?, cleanup)))
+                    .collect::<Vec<_>>();
+                Some((remapped(target)?, sources)).filter(|(_, sources)| !sources.is_empty())
+            })
+            .collect();
+    }
+
+    /// Returns the entities relating to any entity.
+    pub(crate) fn all_sources(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.sources.values().flatten().map(|(source, _)| *source)
+    }
+
+    /// Removes and returns the links to a destroyed target.
+    pub(crate) fn take(&mut self, target: Entity) -> Vec<(Entity, Cleanup)> {
+        self.sources.remove(&target).unwrap_or_default()
+    }
+}
@@ -0,0 +1,539 @@
+use std::{any::Any, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap},
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    component::{get_type_id, get_type_name},
+    entity::Entity,
+    error::RecsError,
+    registry::{clone_fn, insert_fn, insert_resource_fn, CloneFn, InsertFn, InsertResourceFn, RemoveFn},
+    scene::Scene,
+};
+
+#[cfg(feature = "serde")]
+use crate::registry::{TypeInfo, TypeRegistry, ValueSeed};
+
+/// A recorded component, resource or input
+struct RecordedValue {
+    type_id: usize,
+    value: Box<dyn Any>,
+}
+
+/// A recorded change to a scene, or input to the simulation
+enum Command {
+    Create(Entity),
+    Destroy(Entity),
+    Assign { entity: Entity, component: RecordedValue, clone: CloneFn, insert: InsertFn },
+    Remove { entity: Entity, type_id: usize, remove: RemoveFn },
+    Resource { resource: RecordedValue, clone: CloneFn, insert: InsertResourceFn },
+    Input(RecordedValue),
+    /// End of a frame, after which the simulation ran
+    Step,
+}
+
+/// Records the structural changes of a session, the resources inserted and the inputs to the simulation,
+/// so the session can be played back from a fresh scene, e.g. to reproduce a bug from a replay file.
+/// Changes are made through the recorder, which applies them to the scene and remembers them.
+///
+/// Replays are deterministic as long as the simulation is: it has to depend only on the scene and the inputs,
+/// not on wall-clock time or unseeded randomness.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::replay::Recorder;
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone)]
+/// struct Height(i32);
+/// #[derive(Clone)]
+/// struct Gravity(i32);
+/// struct Jump(i32);
+///
+/// fn simulate(scene: &mut Scene) {
+///     let gravity = scene.resource::<Gravity>().map_or(0, |gravity| gravity.0);
+///     for entity in scene.view::<(Height, ())>().collect::<Vec<_>>() {
+///         let height = &mut scene.get_mut::<Height>(entity).unwrap().unwrap().0;
+///         *height = (*height - gravity).max(0);
+///     }
+/// }
+///
+/// let mut scene = Scene::new();
+/// let mut recorder = Recorder::new();
+/// recorder.insert_resource(&mut scene, Gravity(2));
+/// let player = recorder.create_entity(&mut scene);
+/// recorder.assign(&mut scene, player, Height(0)).unwrap();
+///
+/// let jump = |scene: &mut Scene, input: &Jump| scene.get_mut::<Height>(player).unwrap().unwrap().0 += input.0;
+/// for frame in 0..4 {
+///     if frame == 1 {
+///         let input = Jump(7);
+///         jump(&mut scene, &input);
+///         recorder.input(input);
+///     }
+///     simulate(&mut scene);
+///     recorder.step();
+/// }
+/// let replay = recorder.finish();
+/// assert_eq!(replay.frames(), 4);
+///
+/// let mut replayed = Scene::new();
+/// replay
+///     .play(&mut replayed, |scene, input| jump(scene, input.downcast_ref().unwrap()), simulate)
+///     .unwrap();
+/// assert_eq!(replayed.get::<Height>(player).unwrap().unwrap().0, 1);
+/// assert_eq!(scene.get::<Height>(player).unwrap().unwrap().0, 1);
+/// ```
+#[derive(Default)]
+pub struct Recorder {
+    commands: Vec<Command>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an entity and records it.
+    pub fn create_entity(&mut self, scene: &mut Scene) -> Entity {
+        let entity = scene.create_entity();
+        self.commands.push(Command::Create(entity));
+        entity
+    }
+
+    /// Destroys an entity and records it.
+    pub fn destroy_entity(&mut self, scene: &mut Scene, entity: Entity) {
+        scene.destroy_entity(entity);
+        self.commands.push(Command::Destroy(entity));
+    }
+
+    /// Assigns a component, replacing the component the entity already has, and records a copy of it.
+    pub fn assign<T: Clone + 'static>(
+        &mut self,
+        scene: &mut Scene,
+        entity: Entity,
+        component: T,
+    ) -> Result<(), RecsError> {
+        insert_fn::<T>()(scene, entity, Box::new(component.clone()))?;
+        self.commands.push(Command::Assign {
+            entity,
+            component: RecordedValue { type_id: get_type_id::<T>(), value: Box::new(component) },
+            clone: clone_fn::<T>(),
+            insert: insert_fn::<T>(),
+        });
+        Ok(())
+    }
+
+    /// Removes a component and records it.
+    pub fn remove<T: 'static>(&mut self, scene: &mut Scene, entity: Entity) -> Result<(), RecsError> {
+        scene.remove::<T>(entity)?;
+        self.commands.push(Command::Remove {
+            entity,
+            type_id: get_type_id::<T>(),
+            remove: |scene, entity| scene.remove::<T>(entity),
+        });
+        Ok(())
+    }
+
+    /// Inserts a resource and records a copy of it. Changes made to resources in place aren't recorded,
+    /// so resources that change outside of the simulation, like settings, should be inserted again.
+    pub fn insert_resource<T: Clone + 'static>(&mut self, scene: &mut Scene, resource: T) {
+        scene.insert_resource(resource.clone());
+        self.commands.push(Command::Resource {
+            resource: RecordedValue { type_id: get_type_id::<T>(), value: Box::new(resource) },
+            clone: clone_fn::<T>(),
+            insert: insert_resource_fn::<T>(),
+        });
+    }
+
+    /// Records an input to the simulation, like a key press or a network message.
+    /// The recorder doesn't apply it, it is handed to the input handler when the replay is played.
+    pub fn input<T: 'static>(&mut self, input: T) {
+        self.commands.push(Command::Input(RecordedValue { type_id: get_type_id::<T>(), value: Box::new(input) }));
+    }
+
+    /// Records the end of a frame, after which the simulation ran.
+    pub fn step(&mut self) {
+        self.commands.push(Command::Step);
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Stops recording and returns the replay.
+    pub fn finish(self) -> Replay {
+        Replay { commands: self.commands }
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Recorder").field("commands", &self.commands).finish()
+    }
+}
+
+/// A session recorded with a [`Recorder`]. See there for an example.
+pub struct Replay {
+    commands: Vec<Command>,
+}
+
+impl Replay {
+    /// Plays the session back on a scene, which should be as fresh as the one recorded, so entities are created
+    /// with the same indices and versions. Inputs are passed to `on_input` in the order they were recorded,
+    /// and `step` is called at the end of every frame to run the simulation.
+    /// Fails if the replay diverges from the recording, e.g. because an entity is created with another index.
+    pub fn play(
+        &self,
+        scene: &mut Scene,
+        mut on_input: impl FnMut(&mut Scene, &dyn Any),
+        mut step: impl FnMut(&mut Scene),
+    ) -> Result<(), RecsError> {
+        for command in &self.commands {
+            match command {
+                Command::Create(recorded) => {
+                    let entity = scene.create_entity();
+                    if entity != *recorded {
+                        return Err(RecsError::Other(
+                            format!("replay diverged: created {entity:?} instead of {recorded:?}").into(),
+                        ));
+                    }
+                }
+                Command::Destroy(entity) => scene.destroy_entity(*entity),
+                Command::Assign { entity, component, clone, insert } => {
+                    insert(scene, *entity, clone(&*component.value))?;
+                }
+                Command::Remove { entity, remove, .. } => remove(scene, *entity)?,
+                Command::Resource { resource, clone, insert } => insert(scene, clone(&*resource.value))?,
+                Command::Input(input) => on_input(scene, &*input.value),
+                Command::Step => step(scene),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of recorded frames.
+    pub fn frames(&self) -> usize {
+        self.commands.iter().filter(|command| matches!(command, Command::Step)).count()
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl fmt::Debug for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Replay").field("commands", &self.commands).finish()
+    }
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |type_id| get_type_name(type_id).unwrap_or("?");
+        match self {
+            Command::Create(entity) => f.debug_tuple("Create").field(entity).finish(),
+            Command::Destroy(entity) => f.debug_tuple("Destroy").field(entity).finish(),
+            Command::Assign { entity, component, .. } => {
+                f.debug_tuple("Assign").field(entity).field(&name(component.type_id)).finish()
+            }
+            Command::Remove { entity, type_id, .. } => {
+                f.debug_tuple("Remove").field(entity).field(&name(*type_id)).finish()
+            }
+            Command::Resource { resource, .. } => f.debug_tuple("Resource").field(&name(resource.type_id)).finish(),
+            Command::Input(input) => f.debug_tuple("Input").field(&name(input.type_id)).finish(),
+            Command::Step => f.write_str("Step"),
+        }
+    }
+}
+
+/// Serializes a [`Replay`] into a replay file. Components, resources and inputs are saved under the names
+/// their types are registered under in the [`TypeRegistry`], which need serde enabled.
+///
+/// # Example
+///
+/// ```
+/// use serde::{de::DeserializeSeed, Deserialize, Serialize};
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::replay::{Recorder, ReplayDeserializer, ReplaySerializer};
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Score(u32);
+/// #[derive(Serialize, Deserialize)]
+/// struct Goal;
+///
+/// let mut types = TypeRegistry::new();
+/// types.register::<Score>("Score").with_clone().with_serde();
+/// types.register::<Goal>("Goal").with_serde();
+///
+/// let mut scene = Scene::new();
+/// let mut recorder = Recorder::new();
+/// let team = recorder.create_entity(&mut scene);
+/// recorder.assign(&mut scene, team, Score(0)).unwrap();
+/// recorder.input(Goal);
+/// recorder.step();
+///
+/// let json = serde_json::to_string(&ReplaySerializer::new(&recorder.finish(), &types)).unwrap();
+/// assert_eq!(json, r#"[{"create":0},{"assign":[0,"Score",0]},{"input":["Goal",null]},{"step":null}]"#);
+///
+/// let replay = ReplayDeserializer::new(&types).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+/// let mut replayed = Scene::new();
+/// let on_goal = |scene: &mut Scene, _: &dyn std::any::Any| scene.get_mut::<Score>(team).unwrap().unwrap().0 += 1;
+/// replay.play(&mut replayed, on_goal, |_| {}).unwrap();
+/// assert_eq!(replayed.get::<Score>(team).unwrap().unwrap().0, 1);
+/// ```
+#[cfg(feature = "serde")]
+pub struct ReplaySerializer<'a> {
+    replay: &'a Replay,
+    types: &'a TypeRegistry,
+}
+
+#[cfg(feature = "serde")]
+impl<'a> ReplaySerializer<'a> {
+    pub fn new(replay: &'a Replay, types: &'a TypeRegistry) -> Self {
+        ReplaySerializer { replay, types }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ReplaySerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.replay.commands.iter().map(|command| CommandSerializer(command, self.types)))
+    }
+}
+
+/// Serializes a command as a map with a single entry, keyed by the kind of command.
+#[cfg(feature = "serde")]
+struct CommandSerializer<'a>(&'a Command, &'a TypeRegistry);
+
+#[cfg(feature = "serde")]
+impl Serialize for CommandSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let info = |type_id| {
+            self.1.get_by_type_id(type_id).ok_or_else(|| {
+                ser::Error::custom(format!("type {} is not registered", get_type_name(type_id).unwrap_or("?")))
+            })
+        };
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self.0 {
+            Command::Create(entity) => map.serialize_entry("create", entity)?,
+            Command::Destroy(entity) => map.serialize_entry("destroy", entity)?,
+            Command::Assign { entity, component, .. } => {
+                let info = info(component.type_id)?;
+                map.serialize_entry("assign", &(entity, info.name(), TypedValue(info, &*component.value)))?;
+            }
+            Command::Remove { entity, type_id, .. } => {
+                map.serialize_entry("remove", &(entity, info(*type_id)?.name()))?;
+            }
+            Command::Resource { resource, .. } => {
+                let info = info(resource.type_id)?;
+                map.serialize_entry("resource", &(info.name(), TypedValue(info, &*resource.value)))?;
+            }
+            Command::Input(input) => {
+                let info = info(input.type_id)?;
+                map.serialize_entry("input", &(info.name(), TypedValue(info, &*input.value)))?;
+            }
+            Command::Step => map.serialize_entry("step", &())?,
+        }
+        map.end()
+    }
+}
+
+/// Serializes a value of a registered type.
+#[cfg(feature = "serde")]
+struct TypedValue<'a>(&'a TypeInfo, &'a dyn Any);
+
+#[cfg(feature = "serde")]
+impl Serialize for TypedValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(self.1, serializer)
+    }
+}
+
+/// Loads a replay file written with a [`ReplaySerializer`].
+/// Component and resource types need cloning enabled in the [`TypeRegistry`], so a replay can be played repeatedly.
+#[cfg(feature = "serde")]
+pub struct ReplayDeserializer<'a> {
+    types: &'a TypeRegistry,
+}
+
+#[cfg(feature = "serde")]
+impl<'a> ReplayDeserializer<'a> {
+    pub fn new(types: &'a TypeRegistry) -> Self {
+        ReplayDeserializer { types }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> DeserializeSeed<'de> for ReplayDeserializer<'_> {
+    type Value = Replay;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Replay, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for ReplayDeserializer<'_> {
+    type Value = Replay;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of replay commands")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Replay, A::Error> {
+        let mut commands = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(command) = seq.next_element_seed(CommandSeed(self.types))? {
+            commands.push(command);
+        }
+        Ok(Replay { commands })
+    }
+}
+
+#[cfg(feature = "serde")]
+const COMMANDS: &[&str] = &["create", "destroy", "assign", "remove", "resource", "input", "step"];
+
+#[cfg(feature = "serde")]
+struct CommandSeed<'a>(&'a TypeRegistry);
+
+#[cfg(feature = "serde")]
+impl<'de> DeserializeSeed<'de> for CommandSeed<'_> {
+    type Value = Command;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Command, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for CommandSeed<'_> {
+    type Value = Command;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a replay command")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Command, A::Error> {
+        let kind = map.next_key::<String>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let command = match kind.as_str() {
+            "create" => Command::Create(map.next_value()?),
+            "destroy" => Command::Destroy(map.next_value()?),
+            "assign" => {
+                let (entity, info, value) = map.next_value_seed(AssignSeed(self.0))?;
+                let clone = cloneable(info)?;
+                let component = RecordedValue { type_id: info.type_id(), value };
+                Command::Assign { entity, component, clone, insert: info.insert }
+            }
+            "remove" => {
+                let (entity, name) = map.next_value::<(Entity, String)>()?;
+                let info = registered(self.0, &name)?;
+                Command::Remove { entity, type_id: info.type_id(), remove: info.remove }
+            }
+            "resource" => {
+                let (info, value) = map.next_value_seed(NamedValueSeed(self.0))?;
+                let clone = cloneable(info)?;
+                let resource = RecordedValue { type_id: info.type_id(), value };
+                Command::Resource { resource, clone, insert: info.insert_resource }
+            }
+            "input" => {
+                let (info, value) = map.next_value_seed(NamedValueSeed(self.0))?;
+                Command::Input(RecordedValue { type_id: info.type_id(), value })
+            }
+            "step" => {
+                map.next_value::<()>()?;
+                Command::Step
+            }
+            _ => return Err(de::Error::unknown_variant(&kind, COMMANDS)),
+        };
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::custom("a replay command has exactly one entry"));
+        }
+        Ok(command)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn registered<'a, E: de::Error>(types: &'a TypeRegistry, name: &str) -> Result<&'a TypeInfo, E> {
+    types.get(name).ok_or_else(|| E::custom(format!("type {name} is not registered")))
+}
+
+#[cfg(feature = "serde")]
+fn cloneable<E: de::Error>(info: &TypeInfo) -> Result<CloneFn, E> {
+    info.clone
+        .ok_or_else(|| E::custom(format!("type {} can't be cloned, so it can't be replayed", info.name())))
+}
+
+/// Deserializes the name of a registered type followed by a value of it.
+#[cfg(feature = "serde")]
+struct NamedValueSeed<'a>(&'a TypeRegistry);
+
+#[cfg(feature = "serde")]
+impl<'a, 'de> DeserializeSeed<'de> for NamedValueSeed<'a> {
+    type Value = (&'a TypeInfo, Box<dyn Any>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, 'de> Visitor<'de> for NamedValueSeed<'a> {
+    type Value = (&'a TypeInfo, Box<dyn Any>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a type name and a value")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let name = seq.next_element::<String>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let info = registered(self.0, &name)?;
+        let value = seq.next_element_seed(ValueSeed(info))?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok((info, value))
+    }
+}
+
+/// Deserializes an entity, the name of a registered component type and a component of it.
+#[cfg(feature = "serde")]
+struct AssignSeed<'a>(&'a TypeRegistry);
+
+#[cfg(feature = "serde")]
+impl<'a, 'de> DeserializeSeed<'de> for AssignSeed<'a> {
+    type Value = (Entity, &'a TypeInfo, Box<dyn Any>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(3, self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, 'de> Visitor<'de> for AssignSeed<'a> {
+    type Value = (Entity, &'a TypeInfo, Box<dyn Any>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entity, a type name and a component")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let entity = seq.next_element::<Entity>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let name = seq.next_element::<String>()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let info = registered(self.0, &name)?;
+        let value = seq.next_element_seed(ValueSeed(info))?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok((entity, info, value))
+    }
+}
@@ -0,0 +1,101 @@
+use std::{collections::HashMap, fmt};
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    component::{get_type_id, TypeIdMap},
+    entity::Entity,
+    scene::Scene,
+};
+
+/// A change of a replicated component, ready to be sent to clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationUpdate {
+    pub entity: Entity,
+    /// Name the component type was registered under
+    pub component: &'static str,
+    /// The encoded component, or `None` if it was removed or its entity was destroyed
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// Encodes the component of an entity, if it has one
+type EncodeFn = Box<dyn Fn(&Scene, Entity) -> Option<Vec<u8>>>;
+
+/// A component type registered for replication, with the function to encode its components.
+struct ReplicatedType {
+    name: &'static str,
+    encode: EncodeFn,
+}
+
+/// The replicated component types of a scene and the components that changed since the last update was drained.
+#[derive(Default)]
+pub(crate) struct Replication {
+    types: TypeIdMap<ReplicatedType>,
+    dirty: FxHashSet<(Entity, usize)>,
+}
+
+impl Replication {
+    pub(crate) fn register<T: 'static>(&mut self, name: &'static str, encode: impl Fn(&T) -> Vec<u8> + 'static) {
+        let encode = move |scene: &Scene, entity| scene.get::<T>(entity).ok().flatten().map(&encode);
+        self.types.insert(get_type_id::<T>(), ReplicatedType { name, encode: Box::new(encode) });
+    }
+
+    /// Remembers that the component of the entity changed, if its type is replicated.
+    pub(crate) fn mark_dirty(&mut self, entity: Entity, type_id: usize) {
+        if self.types.contains_key(&type_id) {
+            self.dirty.insert((entity, type_id));
+        }
+    }
+
+    /// Replaces the entities of the changed components with their new handles.
+    /// Changes of destroyed entities, which aren't remapped, are kept so their removal is still reported.
+    pub(crate) fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        self.dirty = self
+            .dirty
+            .iter()
+            .map(|&(entity, type_id)| (remap.get(&entity).copied().unwrap_or(entity), type_id))
+            .collect();
+    }
+
+    /// Forgets all changed components, like when all entities are gone.
+    pub(crate) fn clear_dirty(&mut self) {
+        self.dirty.clear();
+    }
+
+    /// Returns the changed components and forgets them.
+    pub(crate) fn take_dirty(&mut self) -> FxHashSet<(Entity, usize)> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Encodes all components of replicated types.
+    pub(crate) fn encode_all(&self, scene: &Scene) -> Vec<ReplicationUpdate> {
+        let mut updates = Vec::new();
+        for replicated in self.types.values() {
+            for &entity in scene.entities() {
+                if let Some(bytes) = (replicated.encode)(scene, entity) {
+                    updates.push(ReplicationUpdate { entity, component: replicated.name, bytes: Some(bytes) });
+                }
+            }
+        }
+        updates
+    }
+
+    /// Encodes the current state of a component of a replicated type.
+    pub(crate) fn encode(&self, scene: &Scene, entity: Entity, type_id: usize) -> ReplicationUpdate {
+        let replicated = &self.types[&type_id];
+        ReplicationUpdate {
+            entity,
+            component: replicated.name,
+            bytes: (replicated.encode)(scene, entity),
+        }
+    }
+}
+
+impl fmt::Debug for Replication {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Replication")
+            .field("types", &self.types.values().map(|replicated| replicated.name).collect::<Vec<_>>())
+            .field("dirty", &self.dirty.len())
+            .finish()
+    }
+}
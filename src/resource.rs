@@ -0,0 +1,187 @@
+use std::{
+    any::Any,
+    ops::{Deref, DerefMut},
+};
+
+use crate::component::{get_type_id, TypeIdMap};
+
+/// A resource value together with the ticks at which it was added and last changed.
+#[derive(Debug)]
+pub(crate) struct ResourceEntry {
+    value: Box<dyn Any>,
+    added_tick: u32,
+    changed_tick: u32,
+}
+
+/// Type-keyed storage for scene-wide singletons that are not attached to an entity.
+#[derive(Debug, Default)]
+pub struct Resources {
+    entries: TypeIdMap<ResourceEntry>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Resources {
+            entries: TypeIdMap::default(),
+        }
+    }
+
+    /// Inserts a resource, returning the previous value of the same type if there was one.
+    pub fn insert<T: 'static>(&mut self, value: T, tick: u32) -> Option<T> {
+        let previous = self.entries.insert(
+            get_type_id::<T>(),
+            ResourceEntry {
+                value: Box::new(value),
+                added_tick: tick,
+                changed_tick: tick,
+            },
+        );
+        previous.and_then(|entry| entry.value.downcast::<T>().ok().map(|value| *value))
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.entries
+            .remove(&get_type_id::<T>())
+            .and_then(|entry| entry.value.downcast::<T>().ok().map(|value| *value))
+    }
+
+    /// Removes the raw entry for a type id, keeping its ticks so it can be put back later.
+    pub(crate) fn take_entry(&mut self, type_id: usize) -> Option<ResourceEntry> {
+        self.entries.remove(&type_id)
+    }
+
+    /// Puts back an entry previously obtained from `take_entry`, or clears the slot if there was none.
+    pub(crate) fn restore_entry(&mut self, type_id: usize, entry: Option<ResourceEntry>) {
+        match entry {
+            Some(entry) => {
+                self.entries.insert(type_id, entry);
+            }
+            None => {
+                self.entries.remove(&type_id);
+            }
+        }
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.entries.contains_key(&get_type_id::<T>())
+    }
+
+    pub fn get<T: 'static>(&self, tick: u32) -> Option<Res<'_, T>> {
+        let entry = self.entries.get(&get_type_id::<T>())?;
+        Some(Res {
+            value: entry.value.downcast_ref()?,
+            added_tick: entry.added_tick,
+            changed_tick: entry.changed_tick,
+            current_tick: tick,
+        })
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, tick: u32) -> Option<ResMut<'_, T>> {
+        let entry = self.entries.get_mut(&get_type_id::<T>())?;
+        Some(ResMut {
+            value: entry.value.downcast_mut()?,
+            added_tick: entry.added_tick,
+            changed_tick: &mut entry.changed_tick,
+            current_tick: tick,
+        })
+    }
+}
+
+/// Shared access to a resource, with change detection relative to the scene's current tick.
+#[derive(Debug)]
+pub struct Res<'a, T> {
+    value: &'a T,
+    added_tick: u32,
+    changed_tick: u32,
+    current_tick: u32,
+}
+
+impl<'a, T> Res<'a, T> {
+    /// Returns the inner reference, outliving this wrapper.
+    pub fn into_inner(self) -> &'a T {
+        self.value
+    }
+
+    /// Returns true if the resource was inserted during the current tick.
+    pub fn is_added(&self) -> bool {
+        self.added_tick == self.current_tick
+    }
+
+    /// Returns true if the resource was inserted or mutably accessed during the current tick.
+    pub fn is_changed(&self) -> bool {
+        self.changed_tick == self.current_tick
+    }
+
+    /// Returns true if the resource was inserted or mutably accessed after the given tick.
+    pub fn is_changed_since(&self, tick: u32) -> bool {
+        self.changed_tick > tick
+    }
+
+    pub fn last_changed(&self) -> u32 {
+        self.changed_tick
+    }
+}
+
+impl<T> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+/// Mutable access to a resource. Dereferencing it mutably marks the resource as changed.
+#[derive(Debug)]
+pub struct ResMut<'a, T> {
+    value: &'a mut T,
+    added_tick: u32,
+    changed_tick: &'a mut u32,
+    current_tick: u32,
+}
+
+impl<'a, T> ResMut<'a, T> {
+    /// Returns the inner reference, outliving this wrapper. The resource is marked as changed.
+    pub fn into_inner(self) -> &'a mut T {
+        *self.changed_tick = self.current_tick;
+        self.value
+    }
+
+    /// Returns true if the resource was inserted during the current tick.
+    pub fn is_added(&self) -> bool {
+        self.added_tick == self.current_tick
+    }
+
+    /// Returns true if the resource was inserted or mutably accessed during the current tick.
+    pub fn is_changed(&self) -> bool {
+        *self.changed_tick == self.current_tick
+    }
+
+    /// Returns true if the resource was inserted or mutably accessed after the given tick.
+    pub fn is_changed_since(&self, tick: u32) -> bool {
+        *self.changed_tick > tick
+    }
+
+    pub fn last_changed(&self) -> u32 {
+        *self.changed_tick
+    }
+
+    /// Marks the resource as changed without touching its value.
+    pub fn set_changed(&mut self) {
+        *self.changed_tick = self.current_tick;
+    }
+}
+
+impl<T> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        *self.changed_tick = self.current_tick;
+        self.value
+    }
+}
@@ -0,0 +1,407 @@
+use std::{any::Any, collections::VecDeque, fmt, sync::Arc};
+
+use rustc_hash::FxHashSet;
+
+use crate::{component::TypeIdMap, entity::Entity, error::RecsError, scene::Scene};
+
+/// Copies of all components of one type, as `Vec<(Entity, T)>`, or as `SharedPages<T>` for copy-on-write snapshots
+pub(crate) type ComponentCopies = Box<dyn Any>;
+
+/// Number of entity indices covered by one page of a copy-on-write snapshot
+const PAGE_LEN: usize = 256;
+
+/// How [`Scene::snapshot`] copies components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotMode {
+    /// Every snapshot copies all components.
+    #[default]
+    Copy,
+    /// Components are kept in pages of 256 entity indices that are shared between snapshots.
+    /// A snapshot only copies the pages whose components were assigned, removed or mutably accessed since the
+    /// previous one and shares all others, so taking a snapshot every tick costs about as much as the tick changed.
+    /// The entity list itself is still copied.
+    ///
+    /// Writes that hand out whole pools, like [`Scene::column_mut`] or [`Scene::split`], make the next snapshot
+    /// copy all components of the affected types again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::rollback::SnapshotMode;
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone)]
+    /// struct Position(i32);
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.register_snapshot::<Position>();
+    /// scene.set_snapshot_mode(SnapshotMode::CopyOnWrite);
+    /// let units = (0..10_000).map(|i| {
+    ///     let unit = scene.create_entity();
+    ///     scene.assign(unit, Position(i)).unwrap();
+    ///     unit
+    /// }).collect::<Vec<_>>();
+    ///
+    /// // Only the first snapshot copies all positions, later ones only the page of the unit that moved
+    /// let mut snapshots = vec![scene.snapshot().unwrap()];
+    /// for tick in 1..=60 {
+    ///     scene.get_mut::<Position>(units[0]).unwrap().unwrap().0 += 1;
+    ///     snapshots.push(scene.snapshot().unwrap());
+    /// }
+    ///
+    /// scene.restore(&snapshots[30]).unwrap();
+    /// assert_eq!(scene.get::<Position>(units[0]).unwrap().unwrap().0, 30);
+    /// assert_eq!(scene.get::<Position>(units[9_999]).unwrap().unwrap().0, 9_999);
+    /// ```
+    CopyOnWrite,
+}
+
+/// Type-erased functions to copy the components of one type out of a scene and back in.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SnapshotFns {
+    pub(crate) capture: fn(&Scene) -> ComponentCopies,
+    pub(crate) restore: fn(&mut Scene, &dyn Any) -> Result<(), RecsError>,
+    /// Copies all components into shared pages
+    pub(crate) capture_pages: fn(&Scene) -> ComponentCopies,
+    /// Copies the components of the given pages again, sharing the other pages
+    pub(crate) update_pages: fn(&Scene, &dyn Any, &FxHashSet<usize>) -> ComponentCopies,
+    /// Shares the pages with a new snapshot, if the copies are pages
+    pub(crate) share_pages: fn(&dyn Any) -> Option<ComponentCopies>,
+}
+
+impl SnapshotFns {
+    pub(crate) fn of<T: Clone + 'static>() -> Self {
+        SnapshotFns {
+            capture: |scene| {
+                let components = scene
+                    .view::<(T, ())>()
+                    .filter_map(|entity| Some((entity, scene.get::<T>(entity).ok()??.clone())))
+                    .collect::<Vec<_>>();
+                Box::new(components)
+            },
+            restore: |scene, components| {
+                if let Some(pages) = components.downcast_ref::<SharedPages<T>>() {
+                    return pages.pages.iter().flatten().try_for_each(|page| scene.assign_batch(page));
+                }
+                let components = components
+                    .downcast_ref::<Vec<(Entity, T)>>()
+                    .ok_or(RecsError::PoolAccessOrCreationError)?;
+                scene.assign_batch(components)
+            },
+            capture_pages: |scene| {
+                let mut pages = SharedPages::<T> { pages: Vec::new() };
+                for entity in scene.view::<(T, ())>() {
+                    let Some(component) = scene.get::<T>(entity).ok().flatten() else {
+                        continue;
+                    };
+                    let page = entity.index() as usize / PAGE_LEN;
+                    if pages.pages.len() <= page {
+                        pages.pages.resize(page + 1, None);
+                    }
+                    let page = pages.pages[page].get_or_insert_with(|| Arc::new(Vec::new()));
+                    Arc::get_mut(page).expect("new pages are not shared").push((entity, component.clone()));
+                }
+                Box::new(pages)
+            },
+            update_pages: |scene, pages, dirty| {
+                let mut pages = pages.downcast_ref::<SharedPages<T>>().cloned().unwrap_or_default();
+                for &page in dirty {
+                    let components = scene
+                        .entities_in(page * PAGE_LEN..(page + 1) * PAGE_LEN)
+                        .filter_map(|entity| Some((entity, scene.get::<T>(entity).ok()??.clone())))
+                        .collect::<Vec<_>>();
+                    if pages.pages.len() <= page {
+                        pages.pages.resize(page + 1, None);
+                    }
+                    pages.pages[page] = (!components.is_empty()).then(|| Arc::new(components));
+                }
+                Box::new(pages)
+            },
+            share_pages: |pages| {
+                let pages = pages.downcast_ref::<SharedPages<T>>()?.clone();
+                Some(Box::new(pages))
+            },
+        }
+    }
+}
+
+/// Components of the entities of one page, with their entities
+type Page<T> = Arc<Vec<(Entity, T)>>;
+
+/// Copies of the components of one type in pages of entity indices, which snapshots share until they change
+struct SharedPages<T> {
+    pages: Vec<Option<Page<T>>>,
+}
+
+impl<T> Default for SharedPages<T> {
+    fn default() -> Self {
+        SharedPages { pages: Vec::new() }
+    }
+}
+
+impl<T> Clone for SharedPages<T> {
+    fn clone(&self) -> Self {
+        SharedPages { pages: self.pages.clone() }
+    }
+}
+
+/// The pages of the latest copy-on-write snapshot and the pages changed since, for scenes in
+/// [`SnapshotMode::CopyOnWrite`]
+#[derive(Default)]
+pub(crate) struct PageCache {
+    /// Pages of each component type, keyed by type ID. Types without pages are copied in full by the next snapshot.
+    pages: TypeIdMap<ComponentCopies>,
+    /// Indices of the pages changed since the last snapshot, keyed by type ID
+    dirty: TypeIdMap<FxHashSet<usize>>,
+}
+
+impl PageCache {
+    /// Remembers that the component of the entity changed.
+    pub(crate) fn mark_dirty(&mut self, entity: Entity, type_id: usize) {
+        if self.pages.contains_key(&type_id) {
+            self.dirty.entry(type_id).or_default().insert(entity.index() as usize / PAGE_LEN);
+        }
+    }
+
+    /// Makes the next snapshot copy all components of the type.
+    pub(crate) fn invalidate(&mut self, type_id: usize) {
+        self.pages.remove(&type_id);
+        self.dirty.remove(&type_id);
+    }
+
+    /// Makes the next snapshot copy all components.
+    pub(crate) fn invalidate_all(&mut self) {
+        self.pages.clear();
+        self.dirty.clear();
+    }
+
+    /// Brings the pages of the type up to date and returns them for a new snapshot.
+    pub(crate) fn snapshot(&mut self, scene: &Scene, type_id: usize, fns: &SnapshotFns) -> ComponentCopies {
+        let pages = match (self.pages.remove(&type_id), self.dirty.remove(&type_id)) {
+            (Some(pages), Some(dirty)) => (fns.update_pages)(scene, pages.as_ref(), &dirty),
+            (Some(pages), None) => pages,
+            (None, _) => (fns.capture_pages)(scene),
+        };
+        let shared = (fns.share_pages)(pages.as_ref()).unwrap_or_else(|| (fns.capture_pages)(scene));
+        self.pages.insert(type_id, pages);
+        shared
+    }
+
+    /// Shares the pages of a restored snapshot, whose components are now the scene's.
+    pub(crate) fn restored(&mut self, snapshot: &Snapshot, snapshot_types: &TypeIdMap<SnapshotFns>) {
+        self.invalidate_all();
+        for (type_id, components) in &snapshot.components {
+            if let Some(pages) = snapshot_types.get(type_id).and_then(|fns| (fns.share_pages)(components.as_ref())) {
+                self.pages.insert(*type_id, pages);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PageCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PageCache")
+            .field("component_types", &self.pages.len())
+            .field("dirty_pages", &self.dirty.values().map(FxHashSet::len).sum::<usize>())
+            .finish()
+    }
+}
+
+/// The state of all entities of a scene and their components at one point in time, taken with
+/// [`Scene::snapshot`]. Restoring it with [`Scene::restore`] brings back the exact entities, including the
+/// indices and versions that entities created afterwards will get, which makes snapshots suitable for
+/// rollback netcode and savestates.
+///
+/// Resources, change ticks and scene settings like storage types are not part of a snapshot.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+///
+/// #[derive(Clone)]
+/// struct Position(i32);
+///
+/// let mut scene = Scene::new();
+/// scene.register_snapshot::<Position>();
+/// let player = scene.create_entity();
+/// scene.assign(player, Position(0)).unwrap();
+///
+/// let snapshot = scene.snapshot().unwrap();
+///
+/// // Predict a few frames ahead
+/// scene.get_mut::<Position>(player).unwrap().unwrap().0 += 3;
+/// let bullet = scene.create_entity();
+/// scene.assign(bullet, Position(10)).unwrap();
+///
+/// // Roll back once the authoritative input arrives, then simulate again
+/// scene.restore(&snapshot).unwrap();
+/// assert_eq!(scene.get::<Position>(player).unwrap().unwrap().0, 0);
+/// assert!(scene.get::<Position>(bullet).is_err());
+/// assert_eq!(scene.create_entity(), bullet);
+/// ```
+pub struct Snapshot {
+    /// Entity of each slot of the entity list, invalid for free slots
+    pub(crate) entities: Vec<Entity>,
+    pub(crate) free_list: VecDeque<u32>,
+    pub(crate) alive: Vec<Entity>,
+    pub(crate) alive_positions: Vec<u32>,
+    /// Copies of the components of each registered type, keyed by type ID
+    pub(crate) components: Vec<(usize, ComponentCopies)>,
+}
+
+impl Snapshot {
+    /// Returns the number of living entities in the snapshot.
+    pub fn entity_count(&self) -> usize {
+        self.alive.len()
+    }
+}
+
+impl fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("entities", &self.alive)
+            .field("component_types", &self.components.len())
+            .finish()
+    }
+}
+
+/// Keeps snapshots of the most recent simulation ticks, so a scene can be rolled back to any of them and
+/// simulated forward again, as done by rollback netcode once late inputs arrive.
+///
+/// Ticks are counted by the caller and are unrelated to the scene's change tick.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::{rollback::RollbackBuffer, scene::Scene};
+///
+/// #[derive(Clone)]
+/// struct Position(i32);
+///
+/// let mut scene = Scene::new();
+/// scene.register_snapshot::<Position>();
+/// let player = scene.create_entity();
+/// scene.assign(player, Position(0)).unwrap();
+///
+/// let mut rollback = RollbackBuffer::new(8);
+/// rollback.save(0, &scene).unwrap();
+/// let mut velocity = 1;
+/// let mut step = |scene: &mut Scene, _tick: u32, velocity: i32| {
+///     scene.get_mut::<Position>(player).unwrap().unwrap().0 += velocity;
+/// };
+/// for tick in 1..=3 {
+///     step(&mut scene, tick, velocity);
+///     rollback.save(tick, &scene).unwrap();
+/// }
+/// assert_eq!(scene.get::<Position>(player).unwrap().unwrap().0, 3);
+///
+/// // An input from tick 2 arrives late: redo ticks 2 and 3 with it
+/// velocity = 2;
+/// rollback.resimulate(&mut scene, 1, 3, |scene, tick| step(scene, tick, velocity)).unwrap();
+/// assert_eq!(scene.get::<Position>(player).unwrap().unwrap().0, 5);
+/// assert_eq!(rollback.latest_tick(), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct RollbackBuffer {
+    /// Snapshots with their tick, oldest first
+    snapshots: VecDeque<(u32, Snapshot)>,
+    capacity: usize,
+}
+
+impl RollbackBuffer {
+    /// Creates a buffer that keeps snapshots of the last `capacity` saved ticks.
+    pub fn new(capacity: usize) -> Self {
+        RollbackBuffer {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Takes a snapshot of the scene for the given tick.
+    /// Snapshots of the same or later ticks are discarded, since they belong to a timeline that was rolled back.
+    /// Once the capacity is exceeded, the oldest snapshot is discarded.
+    pub fn save(&mut self, tick: u32, scene: &Scene) -> Result<(), RecsError> {
+        let snapshot = scene.snapshot()?;
+        self.discard_from(tick);
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        while self.snapshots.len() >= self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((tick, snapshot));
+        Ok(())
+    }
+
+    /// Restores the scene to the snapshot of the given tick and discards all snapshots of later ticks.
+    /// Fails if no snapshot of that tick is buffered, for example because it is older than the capacity allows.
+    pub fn rollback_to(&mut self, tick: u32, scene: &mut Scene) -> Result<(), RecsError> {
+        let snapshot = self
+            .get(tick)
+            .ok_or_else(|| RecsError::Other(format!("no snapshot of tick {tick} is buffered").into()))?;
+        scene.restore(snapshot)?;
+        if let Some(next_tick) = tick.checked_add(1) {
+            self.discard_from(next_tick);
+        }
+        Ok(())
+    }
+
+    /// Rolls back to tick `from`, then calls `step` for every tick up to and including `to`
+    /// and saves a snapshot after each one. `step` receives the tick it should advance the scene to.
+    pub fn resimulate(
+        &mut self,
+        scene: &mut Scene,
+        from: u32,
+        to: u32,
+        mut step: impl FnMut(&mut Scene, u32),
+    ) -> Result<(), RecsError> {
+        self.rollback_to(from, scene)?;
+        for tick in from.saturating_add(1)..=to {
+            step(scene, tick);
+            self.save(tick, scene)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the snapshot of the given tick, if it is buffered.
+    pub fn get(&self, tick: u32) -> Option<&Snapshot> {
+        self.snapshots
+            .iter()
+            .find(|(snapshot_tick, _)| *snapshot_tick == tick)
+            .map(|(_, snapshot)| snapshot)
+    }
+
+    /// Returns the oldest tick that can be rolled back to.
+    pub fn oldest_tick(&self) -> Option<u32> {
+        self.snapshots.front().map(|(tick, _)| *tick)
+    }
+
+    /// Returns the most recently saved tick.
+    pub fn latest_tick(&self) -> Option<u32> {
+        self.snapshots.back().map(|(tick, _)| *tick)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Discards the snapshots of the given tick and all later ones.
+    fn discard_from(&mut self, tick: u32) {
+        while self.snapshots.back().is_some_and(|(snapshot_tick, _)| *snapshot_tick >= tick) {
+            self.snapshots.pop_back();
+        }
+    }
+}
@@ -1,15 +1,70 @@
 use std::{
-    any::Any,
-    collections::{HashMap, HashSet},
+    any::{type_name, Any},
+    cell::RefCell,
+    cmp::Ordering,
+    fmt,
+    hash::Hash,
+    mem::size_of,
+    collections::{HashMap, VecDeque},
+    ops::Range,
+    panic::Location,
+    rc::Rc,
 };
 
-use crate::{component::{get_type_id, RequireComponents}, entity::Entity, error::RecsError, pool::Pool};
+use crate::{
+    allocator::{global, AllocatorRef, StoredAllocator},
+    archetype::{Archetypes, StorageType, Table},
+    audit::{AuditLog, AuditRecord, StructuralChange},
+    builder::EntityBuilder,
+    diff::{compare_components, DiffFns, SceneDiff},
+    dynamic::{DynamicComponentId, DynamicComponents, DynamicLayout, Value},
+    component::{get_type_id, get_type_name, Component, RequireComponents, TypeIdMap, TypeIdSet},
+    entity::{Entity, WeakEntity},
+    error::RecsError,
+    extensions::Extensions,
+    hierarchy::{Ancestors, Children, Descendants, Parent},
+    mapping::EntityMapper,
+    name::{Name, NameIndex, NamePolicy},
+    persistent::{PersistentId, PersistentIdIndex},
+    registry::{TypeInfo, TypeRegistry},
+    relationship::{Cleanup, OwnedBy, Relationship, Relationships, Targets},
+    hooks::{CompositionChange, Hooks},
+    index::{ErasedIndex, ValueIndex},
+    layer::{Layer, LayerRegistry, Layers},
+    pool::{
+        ErasedPool, FragmentationReport, InternedPool, Pool, PoolHandle, ReuseCounter, SlabPool, SmallPool, TagPool,
+    },
+    replication::{Replication, ReplicationUpdate},
+    resource::{Res, ResMut, ResourceEntry, Resources},
+    rollback::{PageCache, Snapshot, SnapshotFns, SnapshotMode},
+    shared::Shared,
+    soa::{SoAComponent, SoAPool},
+    split::ComponentAccess,
+    tag::{TagIndex, Tags},
+};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::{Metrics, MetricsCounters, PoolAccess};
+#[cfg(feature = "spatial")]
+use crate::spatial::{Aabb, SpatialIndex};
+#[cfg(feature = "bytemuck")]
+use crate::snapshot::LoadedPool;
+#[cfg(feature = "serde")]
+use crate::serialization::{self, ComponentRegistry, SceneSerializer};
+#[cfg(any(feature = "ron", feature = "json"))]
+use crate::serialization::SceneDeserializer;
+#[cfg(feature = "json")]
+use serde::de::DeserializeSeed;
+#[cfg(feature = "ron")]
+use std::path::Path;
 
 /// Holds an entity and a set of associated component types.
 #[derive(Debug)]
 pub struct EntityDescription {
     entity: Entity,
-    components: HashSet<usize>,
+    components: TypeIdSet,
+    /// Change tick of the last structural change or mutable access of the entity
+    changed_tick: u32,
 }
 
 impl EntityDescription {
@@ -30,7 +85,8 @@ impl EntityDescription {
     pub fn new(entity: Entity) -> Self {
         EntityDescription {
             entity,
-            components: HashSet::new(),
+            components: TypeIdSet::default(),
+            changed_tick: 0,
         }
     }
 
@@ -38,7 +94,7 @@ impl EntityDescription {
         self.entity
     }
 
-    pub fn components(&self) -> &HashSet<usize> {
+    pub fn components(&self) -> &TypeIdSet {
         &self.components
     }
 
@@ -82,6 +138,11 @@ impl EntityDescription {
         self.entity == entity && self.entity.is_valid()
     }
 
+    /// Returns the change tick at which components of the entity were last assigned, removed or mutably accessed.
+    pub fn changed_tick(&self) -> u32 {
+        self.changed_tick
+    }
+
     fn has_component_with_type_id(&self, type_id: usize) -> bool {
         self.components.contains(&type_id)
     }
@@ -90,12 +151,96 @@ impl EntityDescription {
 /// A scene is a container for entities and their associated components.
 #[derive(Debug)]
 pub struct Scene {
-    /// List of free entity indices
-    free_list: Vec<u32>,
+    /// Queue of free entity indices, oldest first
+    free_list: VecDeque<u32>,
+    /// Number of free indices that have to be queued before the oldest one is reused
+    min_free_indices: usize,
+    /// How often created entities reused a free index
+    entity_reuse: ReuseCounter,
     /// List of entities in the scene
     entities: Vec<EntityDescription>,
-    /// Map of component pools keyed by type ID
-    pools: HashMap<usize, Box<dyn Any>>,
+    /// Packed list of living entities, so iterating over all entities never visits dead slots
+    alive: Vec<Entity>,
+    /// Position of each living entity in `alive`, indexed by entity index
+    alive_positions: Vec<u32>,
+    /// Component pools indexed by type ID
+    pools: Vec<Option<Box<dyn ErasedPool>>>,
+    /// Last frame's pools of double-buffered component types, keyed by type ID
+    previous_pools: TypeIdMap<Box<dyn ErasedPool>>,
+    /// Archetype tables for components with table storage
+    archetypes: Archetypes,
+    /// Storage used for component types without an explicit storage type
+    storage_type: StorageType,
+    /// Storage types chosen for individual component types, keyed by type ID
+    component_storage_types: TypeIdMap<StorageType>,
+    /// Scene-wide resources keyed by type ID
+    resources: Resources,
+    /// Engine-specific data attached to the scene, kept apart from resources
+    extensions: Extensions,
+    /// Opt-in log of structural changes
+    audit_log: Option<AuditLog>,
+    /// User callbacks for scene events
+    hooks: Hooks,
+    /// Current change tick, used for change detection
+    change_tick: u32,
+    /// Counter bumped by every structural change, used to invalidate cached queries
+    structure_generation: u64,
+    /// Allocator for component storage
+    allocator: StoredAllocator,
+    /// Functions to copy the components of the types registered for snapshots, keyed by type ID
+    snapshot_types: TypeIdMap<SnapshotFns>,
+    /// Component types registered with `Scene::register_component`
+    types: TypeRegistry,
+    /// Pages shared between snapshots, if snapshots are copy-on-write
+    page_cache: Option<RefCell<PageCache>>,
+    /// Functions to compare the components of the types registered for diffs, keyed by type ID
+    diff_types: TypeIdMap<DiffFns>,
+    /// Replicated component types and the components that changed since updates were last drained
+    replication: Replication,
+    /// Index of the persistent IDs given to new entities, if persistent IDs are enabled
+    persistent_ids: Option<PersistentIdIndex>,
+    /// Entities relating to each entity through [`Targets`] or [`OwnedBy`]
+    relationships: Relationships,
+    /// Index of the names given with [`Scene::set_name`]
+    names: NameIndex,
+    /// Whether entities may share a name
+    name_policy: NamePolicy,
+    /// Index of the tags added with [`Scene::add_tag`]
+    tags: TagIndex,
+    /// Components of the types registered with [`Scene::register_dynamic_component`]
+    dynamic: DynamicComponents,
+    /// Entity holding the singleton components, created by the first [`Scene::insert_singleton`]
+    singleton_entity: Option<Entity>,
+    /// Component types inserted with [`Scene::insert_singleton`], which no other entity may have
+    singleton_types: TypeIdSet,
+    /// Indexes of component values added with [`Scene::add_index`], keyed by type ID
+    indexes: TypeIdMap<RefCell<Box<dyn ErasedIndex>>>,
+    /// Names of the layers registered with [`Scene::register_layer`]
+    layers: LayerRegistry,
+    /// The members of the exclusive group of each component type that is in one, keyed by type ID
+    exclusive_groups: TypeIdMap<Rc<[usize]>>,
+    /// Component types inserted with their default values along with components of each type, keyed by type ID
+    required_components: TypeIdMap<Rc<[(usize, InsertDefaultFn)]>>,
+    /// Component types whose required components are being inserted, to stop at cyclic requirements
+    inserting_required: Vec<usize>,
+    /// Component types an entity needs before components of each type can be assigned to it, keyed by type ID
+    dependencies: TypeIdMap<Rc<[usize]>>,
+    /// Grid of the positions of entities, if a spatial index is enabled
+    #[cfg(feature = "spatial")]
+    spatial: Option<RefCell<SpatialIndex>>,
+    /// Counter bumped every time entities are moved to other indices, to detect outdated weak entities
+    entity_layout: u32,
+    /// Version of entities created at unused indices, past the versions of slots dropped by [`Scene::compact`]
+    fresh_version: u32,
+    /// Running counters for the metrics snapshot
+    #[cfg(feature = "metrics")]
+    metrics: MetricsCounters,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scene {
@@ -109,10 +254,83 @@ impl Scene {
     /// let scene = Scene::new();
     /// ```
     pub fn new() -> Self {
+        Self::with_storage_type(StorageType::Sparse)
+    }
+
+    /// Creates a new empty scene that stores components using the given storage type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::archetype::StorageType;
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::with_storage_type(StorageType::Table);
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 1u32).unwrap();
+    /// scene.assign(entity, 2.0f32).unwrap();
+    /// scene.remove::<u32>(entity).unwrap();
+    ///
+    /// assert_eq!(scene.storage_type(), StorageType::Table);
+    /// assert!(scene.get::<u32>(entity).unwrap().is_none());
+    /// assert_eq!(*scene.get::<f32>(entity).unwrap().unwrap(), 2.0);
+    /// ```
+    pub fn with_storage_type(storage_type: StorageType) -> Self {
+        Self::with_storage_type_in(storage_type, global())
+    }
+
+    /// Creates a new empty scene whose pools and archetype tables allocate their memory with the given allocator.
+    /// Bookkeeping like the entity list stays on the global heap. See [`AllocatorRef`] for an example.
+    pub fn with_allocator(allocator: AllocatorRef) -> Self {
+        Self::with_storage_type_in(StorageType::Sparse, allocator)
+    }
+
+    /// Creates a new empty scene with the given storage type whose component storage uses the given allocator.
+    pub fn with_storage_type_in(storage_type: StorageType, allocator: AllocatorRef) -> Self {
         Scene {
-            free_list: Vec::new(),
+            free_list: VecDeque::new(),
+            min_free_indices: 0,
+            entity_reuse: ReuseCounter::default(),
             entities: Vec::new(),
-            pools: HashMap::new(),
+            alive: Vec::new(),
+            alive_positions: Vec::new(),
+            pools: Vec::new(),
+            previous_pools: TypeIdMap::default(),
+            archetypes: Archetypes::new_in(allocator),
+            storage_type,
+            component_storage_types: TypeIdMap::default(),
+            resources: Resources::new(),
+            extensions: Extensions::new(),
+            audit_log: None,
+            hooks: Hooks::default(),
+            change_tick: 1,
+            structure_generation: 0,
+            allocator: StoredAllocator(allocator),
+            snapshot_types: TypeIdMap::default(),
+            types: TypeRegistry::new(),
+            page_cache: None,
+            diff_types: TypeIdMap::default(),
+            replication: Replication::default(),
+            persistent_ids: None,
+            relationships: Relationships::default(),
+            names: NameIndex::default(),
+            name_policy: NamePolicy::default(),
+            tags: TagIndex::default(),
+            dynamic: DynamicComponents::default(),
+            singleton_entity: None,
+            singleton_types: TypeIdSet::default(),
+            indexes: TypeIdMap::default(),
+            layers: LayerRegistry::default(),
+            exclusive_groups: TypeIdMap::default(),
+            required_components: TypeIdMap::default(),
+            inserting_required: Vec::new(),
+            dependencies: TypeIdMap::default(),
+            #[cfg(feature = "spatial")]
+            spatial: None,
+            entity_layout: 0,
+            fresh_version: 0,
+            #[cfg(feature = "metrics")]
+            metrics: MetricsCounters::default(),
         }
     }
 
@@ -130,12 +348,20 @@ impl Scene {
     /// assert_eq!(all_entities.len(), 1);
     /// assert_eq!(all_entities[0], entity);
     /// ```
+    #[track_caller]
     pub fn create_entity(&mut self) -> Entity {
-        let free_index = self.free_list.pop();
+        let free_index = if self.min_free_indices == 0 {
+            self.free_list.pop_back()
+        } else if self.free_list.len() > self.min_free_indices {
+            self.free_list.pop_front()
+        } else {
+            None
+        };
         let index = free_index.unwrap_or(self.entities.len() as u32);
+        self.entity_reuse.record(free_index.is_some());
 
-        if free_index.is_none() {
-            let entity = Entity::new(index);
+        let entity = if free_index.is_none() {
+            let entity = Entity::with_version(index, self.fresh_version);
             self.entities.push(EntityDescription::new(entity));
             entity
         } else {
@@ -143,253 +369,4388 @@ impl Scene {
             let entity = Entity::with_version(index, version);
             self.entities[index as usize] = EntityDescription::new(entity);
             entity
+        };
+        self.mark_alive(entity);
+        self.record_change(StructuralChange::CreateEntity, entity, None);
+        #[cfg(feature = "metrics")]
+        self.metrics.entity_created();
+        if self.persistent_ids.is_some() {
+            self.assign_persistent_id(entity, PersistentId::generate());
         }
+        entity
     }
 
-    /// Destroys an entity and removes it from the scene.
-    /// 
+    /// Destroys an entity and removes it and its components from the scene.
+    /// Destroying an entity that isn't alive, like a stale handle or one destroyed before, does nothing.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
     /// let entity = scene.create_entity();
+    /// scene.assign(entity, 42).unwrap();
     /// scene.destroy_entity(entity);
-    /// 
+    ///
     /// let all_entities = scene.view::<()>().collect::<Vec<_>>();
     /// assert!(all_entities.is_empty());
+    ///
+    /// // The recycled entity does not inherit the old components
+    /// let recycled = scene.create_entity();
+    /// assert_eq!(recycled.index(), entity.index());
+    /// assert!(scene.get::<i32>(recycled).unwrap().is_none());
+    ///
+    /// // Destroying the stale handle again leaves the recycled entity alone
+    /// scene.assign(recycled, 5u32).unwrap();
+    /// scene.destroy_entity(entity);
+    /// assert!(scene.is_alive(recycled));
+    /// assert_eq!(scene.get::<u32>(recycled).unwrap(), Some(&5));
     /// ```
+    #[track_caller]
     pub fn destroy_entity(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        let persistent_id = match self.persistent_ids {
+            Some(_) => self.get::<PersistentId>(entity).ok().flatten().copied(),
+            None => None,
+        };
+        if let (Some(persistent_ids), Some(id)) = (&mut self.persistent_ids, persistent_id) {
+            persistent_ids.remove(id);
+        }
+        self.detach_from_hierarchy(entity);
+        self.detach_relationships(entity);
+        // Fails only for entities that aren't alive, which have no name
+        self.remove_name(entity).ok();
+        if let Ok(Some(tags)) = self.get::<Tags>(entity) {
+            for tag in tags.0.clone() {
+                self.tags.remove(&tag, entity);
+            }
+        }
+        self.dynamic.remove_entity(entity);
         let index = entity.index();
+        let components = std::mem::take(&mut self.entities[index as usize].components);
+        for &type_id in &components {
+            if let Some(pool) = self.pool_by_id_mut(type_id) {
+                pool.free(entity);
+            }
+            self.component_changed(entity, type_id);
+            if self.audit_log.is_some() {
+                self.record_change(StructuralChange::Remove, entity, get_type_name(type_id));
+            }
+        }
+        if !components.is_empty() && self.hooks.observes_composition() {
+            let mut removed = components.iter().copied().collect::<Vec<_>>();
+            removed.sort_unstable();
+            self.hooks.composition_changed(entity, &[], &removed);
+        }
+        self.archetypes.remove_entity(entity);
+        self.record_change(StructuralChange::DestroyEntity, entity, None);
+        #[cfg(feature = "metrics")]
+        self.metrics.entity_destroyed(components.len());
+        self.mark_dead(index);
         self.entities[index as usize].invalidate_entity();
-        self.free_list.push(index);
+        self.free_list.push_back(index);
     }
 
-    /// Assigns a component to an entity.
-    /// 
+    /// Creates an entity and returns a builder that adds components and children to it.
+    /// See [`EntityBuilder`] for an example.
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        let entity = self.create_entity();
+        EntityBuilder::new(self, entity)
+    }
+
+    /// Makes `parent` the parent of `child`, replacing its previous parent, and adds `child` to the [`Children`]
+    /// of `parent`. Fails if either entity isn't alive or if `child` is `parent` or one of its ancestors,
+    /// which would create a cycle. Destroying an entity removes it from its parent and orphans its children;
+    /// use [`Scene::despawn_recursive`] to destroy them as well.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
+    /// use yarecs::hierarchy::{Children, Parent};
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
-    /// let entity = scene.create_entity();
-    /// scene.assign(entity, 42).unwrap();
-    /// 
-    /// let value = scene.get::<i32>(entity).unwrap().unwrap();
-    /// assert_eq!(*value, 42);
+    /// let car = scene.create_entity();
+    /// let trailer = scene.create_entity();
+    /// let wheel = scene.create_entity();
+    /// scene.set_parent(wheel, car).unwrap();
+    /// assert_eq!(scene.get::<Parent>(wheel).unwrap().unwrap().get(), car);
+    ///
+    /// // Moving the wheel to the trailer removes it from the car's children
+    /// scene.set_parent(wheel, trailer).unwrap();
+    /// assert!(scene.get::<Children>(car).unwrap().is_none());
+    /// assert_eq!(scene.get::<Children>(trailer).unwrap().unwrap().as_slice(), &[wheel]);
+    /// assert!(scene.set_parent(trailer, wheel).is_err());
+    ///
+    /// scene.destroy_entity(trailer);
+    /// assert!(scene.get::<Parent>(wheel).unwrap().is_none());
     /// ```
-    pub fn assign<T: 'static>(
-        &mut self,
-        entity: Entity,
-        new_component: T,
-    ) -> Result<&mut T, RecsError> {
-        let entity_description = self.get_valid_entity_description_mut(entity)?;
-        entity_description.components.insert(get_type_id::<T>());
-        let pool = self.get_or_create_pool::<T>()?;
-        Ok(pool.assign(entity, new_component))
+    #[track_caller]
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), RecsError> {
+        self.assert_entity_valid(child)?;
+        self.assert_entity_valid(parent)?;
+        if parent == child || self.ancestors(parent).any(|ancestor| ancestor == child) {
+            return Err(RecsError::Other("an entity can't be the parent of itself or its ancestors".into()));
+        }
+        self.remove_parent(child)?;
+        self.assign(child, Parent(parent))?;
+        match self.get_mut::<Children>(parent)? {
+            Some(children) => children.0.push(child),
+            None => {
+                self.assign(parent, Children(vec![child]))?;
+            }
+        }
+        Ok(())
     }
 
-    /// Assigns the default value of a component to an entity.
-    /// 
+    /// Removes the parent of an entity, taking the entity out of the parent's [`Children`], and returns the parent.
+    #[track_caller]
+    pub fn remove_parent(&mut self, child: Entity) -> Result<Option<Entity>, RecsError> {
+        let Some(parent) = self.get::<Parent>(child)?.map(Parent::get) else {
+            return Ok(None);
+        };
+        self.remove::<Parent>(child)?;
+        self.remove_child(parent, child)?;
+        Ok(Some(parent))
+    }
+
+    /// Destroys an entity together with its children, their children and so on.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
+    /// use yarecs::hierarchy::Children;
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
-    /// let entity = scene.create_entity();
-    /// scene.assign_default::<i32>(entity).unwrap();
-    /// 
-    /// let value = scene.get::<i32>(entity).unwrap().unwrap();
-    /// assert_eq!(*value, 0);
+    /// let garage = scene.create_entity();
+    /// let car = scene.create_entity();
+    /// let wheels = (0..4).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// scene.set_parent(car, garage).unwrap();
+    /// for &wheel in &wheels {
+    ///     scene.set_parent(wheel, car).unwrap();
+    ///     scene.assign(wheel, 0.3f32).unwrap();
+    /// }
+    ///
+    /// scene.despawn_recursive(car).unwrap();
+    /// assert_eq!(scene.entities(), &[garage]);
+    /// assert!(scene.get::<Children>(garage).unwrap().is_none());
+    /// assert_eq!(scene.view::<(f32, ())>().count(), 0);
     /// ```
-    pub fn assign_default<T: Default + 'static>(
-        &mut self,
-        entity: Entity,
-    ) -> Result<&mut T, RecsError> {
-        let entity_description = self.get_valid_entity_description_mut(entity)?;
-        entity_description.components.insert(get_type_id::<T>());
-        println!("{:?}", entity_description.components);
-        let pool = self.get_or_create_pool::<T>()?;
-        Ok(pool.assign_default(entity))
+    #[track_caller]
+    pub fn despawn_recursive(&mut self, entity: Entity) -> Result<(), RecsError> {
+        self.assert_entity_valid(entity)?;
+        let subtree = std::iter::once(entity).chain(self.descendants(entity)).collect::<Vec<_>>();
+        for entity in subtree {
+            self.destroy_entity(entity);
+        }
+        Ok(())
     }
 
-    /// Removes a component from an entity.
-    /// 
+    /// Returns the parent of an entity, the parent's parent and so on up to the root of its hierarchy.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
-    /// let entity = scene.create_entity();
-    /// scene.assign(entity, 42).unwrap();
-    /// scene.remove::<i32>(entity).unwrap();
-    /// 
-    /// let value = scene.get::<i32>(entity).unwrap();
-    /// assert!(value.is_none());
+    /// let window = scene.create_entity();
+    /// let panel = scene.create_entity();
+    /// let label = scene.create_entity();
+    /// let button = scene.create_entity();
+    /// let icon = scene.create_entity();
+    /// scene.set_parent(panel, window).unwrap();
+    /// scene.set_parent(label, panel).unwrap();
+    /// scene.set_parent(button, panel).unwrap();
+    /// scene.set_parent(icon, button).unwrap();
+    ///
+    /// assert_eq!(scene.ancestors(icon).collect::<Vec<_>>(), vec![button, panel, window]);
+    /// assert_eq!(scene.descendants(window).collect::<Vec<_>>(), vec![panel, label, button, icon]);
+    /// assert_eq!(scene.root_of(icon), window);
+    /// assert_eq!(scene.root_of(window), window);
     /// ```
-    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Result<(), RecsError> {
-        self.get_valid_entity_description_mut(entity)
-            .map(|ed| ed.components.remove(&get_type_id::<T>()))?;
-        self.get_pool_if_exists_mut::<T>().map(|p| p.free(entity));
-        Ok(())
+    pub fn ancestors(&self, entity: Entity) -> Ancestors<'_> {
+        Ancestors::new(self, entity)
     }
 
-    /// Gets a component from an entity.
-    /// 
+    /// Returns the children of an entity, their children and so on, depth first.
+    /// See [`Scene::ancestors`] for an example.
+    pub fn descendants(&self, entity: Entity) -> Descendants<'_> {
+        Descendants::new(self, entity)
+    }
+
+    /// Returns the root of the hierarchy an entity belongs to, which is the entity itself if it has no parent.
+    /// See [`Scene::ancestors`] for an example.
+    pub fn root_of(&self, entity: Entity) -> Entity {
+        self.ancestors(entity).last().unwrap_or(entity)
+    }
+
+    /// Makes `entity` target `target` through a [`Targets`] component, replacing its previous target.
+    /// The component is removed once the target is destroyed. Fails if either entity isn't alive.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
+    /// use yarecs::relationship::Targets;
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
-    /// let entity = scene.create_entity();
-    /// scene.assign(entity, 42).unwrap();
-    /// 
-    /// let value = scene.get::<i32>(entity).unwrap().unwrap();
-    /// assert_eq!(*value, 42);
+    /// let turret = scene.create_entity();
+    /// let enemy = scene.create_entity();
+    /// scene.set_target(turret, enemy).unwrap();
+    /// assert_eq!(scene.get::<Targets>(turret).unwrap().unwrap().get(), enemy);
+    /// assert_eq!(scene.targeting(enemy).collect::<Vec<_>>(), vec![turret]);
+    ///
+    /// scene.destroy_entity(enemy);
+    /// assert!(scene.get::<Targets>(turret).unwrap().is_none());
     /// ```
-    pub fn get<T: 'static>(&self, entity: Entity) -> Result<Option<&T>, RecsError> {
-        self.assert_entity_valid(entity)?;
-        Ok(self
-            .get_pool_if_exists::<T>()
-            .map_or(None, |p| p.get(entity)))
+    #[track_caller]
+    pub fn set_target(&mut self, entity: Entity, target: Entity) -> Result<(), RecsError> {
+        self.relate::<Targets>(entity, target)
     }
 
-    /// Gets a mutable component from an entity.
-    /// 
+    /// Removes the [`Targets`] component of an entity and returns its target.
+    #[track_caller]
+    pub fn remove_target(&mut self, entity: Entity) -> Result<Option<Entity>, RecsError> {
+        self.unrelate::<Targets>(entity)
+    }
+
+    /// Returns the entities targeting an entity.
+    pub fn targeting(&self, target: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.related::<Targets>(target)
+    }
+
+    /// Makes `owner` the owner of `entity` through an [`OwnedBy`] component, replacing its previous owner.
+    /// Destroying the owner destroys the entity as well, and whatever the entity owns in turn.
+    /// Fails if either entity isn't alive or if `entity` is `owner` or owns it.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
-    /// let entity = scene.create_entity();
-    /// scene.assign(entity, 42).unwrap();
-    /// 
-    /// let value = scene.get_mut::<i32>(entity).unwrap().unwrap();
-    /// *value = 43;
-    /// 
-    /// let new_value = scene.get::<i32>(entity).unwrap().unwrap();
-    /// assert_eq!(*new_value, 43);
+    /// let player = scene.create_entity();
+    /// let weapon = scene.create_entity();
+    /// let bullet = scene.create_entity();
+    /// scene.set_owner(weapon, player).unwrap();
+    /// scene.set_owner(bullet, weapon).unwrap();
+    /// assert!(scene.set_owner(player, bullet).is_err());
+    /// assert_eq!(scene.owned_by(player).collect::<Vec<_>>(), vec![weapon]);
+    ///
+    /// scene.destroy_entity(player);
+    /// assert_eq!(scene.entity_count(), 0);
     /// ```
-    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Result<Option<&mut T>, RecsError> {
-        self.assert_entity_valid(entity)?;
-        Ok(self
-            .get_pool_if_exists_mut::<T>()
-            .map_or(None, |p| p.get_mut(entity)))
+    #[track_caller]
+    pub fn set_owner(&mut self, entity: Entity, owner: Entity) -> Result<(), RecsError> {
+        let mut current = Some(owner);
+        while let Some(ancestor) = current {
+            if ancestor == entity {
+                return Err(RecsError::Other("an entity can't be owned by itself or what it owns".into()));
+            }
+            current = self.get::<OwnedBy>(ancestor).ok().flatten().map(OwnedBy::get);
+        }
+        self.relate::<OwnedBy>(entity, owner)
     }
 
-    /// Returns an iterator over all entities that have all the required components.
-    /// 
+    /// Removes the [`OwnedBy`] component of an entity, so it outlives its owner, and returns the owner.
+    #[track_caller]
+    pub fn remove_owner(&mut self, entity: Entity) -> Result<Option<Entity>, RecsError> {
+        self.unrelate::<OwnedBy>(entity)
+    }
+
+    /// Returns the entities owned by an entity.
+    pub fn owned_by(&self, owner: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.related::<OwnedBy>(owner)
+    }
+
+    /// Returns all living entities, in no particular order.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```
     /// use yarecs::scene::Scene;
-    /// 
+    ///
     /// let mut scene = Scene::new();
-    /// let entity = scene.create_entity();
-    /// scene.assign_default::<i32>(entity).unwrap();
-    /// scene.assign_default::<f32>(entity).unwrap();
-    /// 
-    /// let all_entities = scene.view::<(i32, (f32,()))>().collect::<Vec<_>>();
-    /// assert_eq!(all_entities.len(), 1);
-    /// 
-    /// let entities_with_i32 = scene.view::<(i32,())>().collect::<Vec<_>>();
-    /// assert_eq!(entities_with_i32.len(), 1);
-    /// 
-    /// let entities_with_String = scene.view::<(String,())>().collect::<Vec<_>>();
-    /// assert!(entities_with_String.is_empty());
-    /// 
-    /// let entities_with_i32_and_f32 = scene.view::<(i32, (f32,()))>().collect::<Vec<_>>();
-    /// assert_eq!(entities_with_i32_and_f32.len(), 1);
-    /// 
-    /// let entities_with_String_and_f32 = scene.view::<(String, (f32,()))>().collect::<Vec<_>>();
-    /// assert!(entities_with_String_and_f32.is_empty());
+    /// let entities = (0..3).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// scene.destroy_entity(entities[0]);
+    ///
+    /// assert_eq!(scene.entity_count(), 2);
+    /// assert!(scene.entities().contains(&entities[2]));
+    /// assert!(!scene.entities().contains(&entities[0]));
     /// ```
-    pub fn view<'a, T: RequireComponents>(&'a self) -> SceneView<'a> {
-        SceneView::new(self, T::required_component_ids())
+    pub fn entities(&self) -> &[Entity] {
+        &self.alive
     }
 
-    fn assert_entity_valid(&self, entity: Entity) -> Result<(), RecsError> {
-        self.entities
-            .get(entity.index() as usize)
-            .filter(|ed| ed.check_validity(entity))
-            .map_or(Err(RecsError::InvalidEntityError), |_| Ok(()))
+    /// Lists every living entity with its components, formatted with their `Debug` implementations, for quick
+    /// inspection in tests and bug reports. Components of types registered with debug formatting show their value,
+    /// other components only their type name. See [`Scene::display`] for an example.
+    pub fn dump(&self, types: &TypeRegistry) -> String {
+        self.display(types).to_string()
     }
 
-    fn get_valid_entity_description_mut(
-        &mut self,
-        entity: Entity,
-    ) -> Result<&mut EntityDescription, RecsError> {
-        self.entities
-            .get_mut(entity.index() as usize)
-            .filter(|ed| ed.check_validity(entity))
-            .map_or(Err(RecsError::InvalidEntityError), Ok)
+    /// Returns a formatter for the contents of the scene, like [`Scene::dump`].
+    /// `Display` prints one line per component, `Debug` prints the entities as a map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::registry::TypeRegistry;
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Debug)]
+    /// struct Health(u32);
+    /// #[derive(Debug)]
+    /// struct Name(&'static str);
+    ///
+    /// let mut types = TypeRegistry::new();
+    /// types.register::<Health>("Health").with_debug();
+    /// types.register::<Name>("Name").with_debug();
+    ///
+    /// let mut scene = Scene::new();
+    /// let orc = scene.create_entity();
+    /// scene.assign(orc, Name("orc")).unwrap();
+    /// scene.assign(orc, Health(20)).unwrap();
+    /// scene.assign(orc, 7u8).unwrap();
+    ///
+    /// assert_eq!(
+    ///     scene.dump(&types),
+    ///     "entity 0v0\n  Health: Health(20)\n  Name: Name(\"orc\")\n  u8\n",
+    /// );
+    /// assert_eq!(
+    ///     format!("{:?}", scene.display(&types)),
+    ///     "{0v0: {\"Health\": Health(20), \"Name\": Name(\"orc\"), \"u8\": ..}}",
+    /// );
+    /// ```
+    pub fn display<'a>(&'a self, types: &'a TypeRegistry) -> SceneDump<'a> {
+        SceneDump { scene: self, types }
     }
 
-    fn get_or_create_pool<T: 'static>(&mut self) -> Result<&mut Pool<T>, RecsError> {
-        let type_id = get_type_id::<T>();
-        self.pools
-            .entry(type_id)
-            .or_insert_with(|| Box::new(Pool::<T>::new()))
-            .downcast_mut()
-            .ok_or(RecsError::PoolAccessOrCreationError)
+    /// Returns the living entities, ordered by index, whose components were assigned, removed or mutably accessed
+    /// after the given change tick, including entities created since. Mutations through column access,
+    /// splits and unchecked accessors aren't tracked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let (a, b) = (scene.create_entity(), scene.create_entity());
+    /// scene.assign(a, 1u32).unwrap();
+    /// scene.assign(b, 2u32).unwrap();
+    ///
+    /// let saved = scene.change_tick();
+    /// scene.increment_change_tick();
+    /// *scene.get_mut::<u32>(b).unwrap().unwrap() += 1;
+    /// let c = scene.create_entity();
+    /// scene.destroy_entity(a);
+    ///
+    /// assert_eq!(scene.entities_changed_since(saved), vec![b, c]);
+    /// assert_eq!(scene.entities_destroyed_since(saved), vec![a]);
+    /// ```
+    pub fn entities_changed_since(&self, tick: u32) -> Vec<Entity> {
+        let mut entities = self
+            .alive
+            .iter()
+            .copied()
+            .filter(|entity| self.entities[entity.index() as usize].changed_tick > tick)
+            .collect::<Vec<_>>();
+        entities.sort_by_key(Entity::index);
+        entities
     }
 
-    fn get_pool_if_exists<T: 'static>(&self) -> Option<&Pool<T>> {
-        self.pools
-            .get(&get_type_id::<T>())
-            .and_then(|p| p.downcast_ref::<Pool<T>>())
+    /// Returns the entities, ordered by index, that were destroyed after the given change tick.
+    /// Entities whose index has been reused since are left out; their successors are reported as changed instead.
+    pub fn entities_destroyed_since(&self, tick: u32) -> Vec<Entity> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, entity_description)| {
+                !entity_description.entity.is_valid() && entity_description.changed_tick > tick
+            })
+            .map(|(index, entity_description)| {
+                // Destroying an entity bumped the version of its slot
+                let version = entity_description.entity.version().checked_sub(1).unwrap_or(u32::MAX - 1);
+                Entity::with_version(index as u32, version)
+            })
+            .collect()
     }
 
-    fn get_pool_if_exists_mut<T: 'static>(&mut self) -> Option<&mut Pool<T>> {
-        self.pools
-            .get_mut(&get_type_id::<T>())
-            .and_then(|p| p.downcast_mut::<Pool<T>>())
+    /// Gives every entity created from now on a random [`PersistentId`] component, and gives one to all living
+    /// entities that don't have one yet. Persistent IDs survive saving and loading the scene, so save files and
+    /// references between scenes can use them instead of entities, whose indices and versions are recycled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::persistent::PersistentId;
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.enable_persistent_ids();
+    /// let door = scene.create_entity();
+    /// let id = *scene.get::<PersistentId>(door).unwrap().unwrap();
+    /// assert_eq!(scene.find_by_persistent_id(id), Some(door));
+    ///
+    /// // The index of the door is recycled, but its persistent ID isn't
+    /// scene.destroy_entity(door);
+    /// let key = scene.create_entity();
+    /// assert_eq!(key.index(), door.index());
+    /// assert_eq!(scene.find_by_persistent_id(id), None);
+    /// ```
+    pub fn enable_persistent_ids(&mut self) {
+        if self.persistent_ids.is_some() {
+            return;
+        }
+        self.persistent_ids = Some(PersistentIdIndex::default());
+        let mut entities = self.alive.clone();
+        entities.sort_by_key(Entity::index);
+        for entity in entities {
+            let id = self.get::<PersistentId>(entity).ok().flatten().copied().unwrap_or_else(PersistentId::generate);
+            self.assign_persistent_id(entity, id);
+        }
     }
-}
 
-/// An iterator over a subset of the entities in a scene.
-pub struct SceneView<'a> {
-    scene: &'a Scene,
-    required_components: Vec<usize>,
-    index: usize,
-}
+    /// Stops giving new entities persistent IDs. Entities keep the IDs they already have.
+    pub fn disable_persistent_ids(&mut self) {
+        self.persistent_ids = None;
+    }
 
-impl<'a> SceneView<'a> {
-    fn new(scene: &'a Scene, required_components: Vec<usize>) -> Self {
-        SceneView {
-            scene,
-            required_components,
-            index: 0,
+    /// Returns the living entity with the given persistent ID. Entities given their ID by the scene are found
+    /// through an index, others, like entities of a loaded scene, by searching all persistent IDs.
+    pub fn find_by_persistent_id(&self, id: PersistentId) -> Option<Entity> {
+        let has_id = |entity: Entity| self.get::<PersistentId>(entity).ok().flatten() == Some(&id);
+        if let Some(entity) = self.persistent_ids.as_ref().and_then(|persistent_ids| persistent_ids.get(id)) {
+            if has_id(entity) {
+                return Some(entity);
+            }
         }
+        self.view::<(PersistentId, ())>().find(|&entity| has_id(entity))
     }
 
-    fn entity_has_required_components(&self, entity_description: &EntityDescription) -> bool {
-        self.required_components
-            .iter()
-            .all(|&id| entity_description.has_component_with_type_id(id))
+    #[track_caller]
+    fn assign_persistent_id(&mut self, entity: Entity, id: PersistentId) {
+        if self.assign(entity, id).is_ok() {
+            if let Some(persistent_ids) = &mut self.persistent_ids {
+                persistent_ids.insert(id, entity);
+            }
+        }
+    }
+
+    /// Decides whether entities named from now on may share a name. Existing names aren't changed.
+    /// See [`Scene::set_name`] for an example.
+    pub fn set_name_policy(&mut self, policy: NamePolicy) {
+        self.name_policy = policy;
+    }
+
+    pub fn name_policy(&self) -> NamePolicy {
+        self.name_policy
+    }
+
+    /// Gives an entity a [`Name`], replacing its previous one, and indexes it for [`Scene::find_by_name`].
+    /// Depending on the [`NamePolicy`], a name another living entity has fails or gets a numbered suffix;
+    /// returns the name the entity was given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::name::{Name, NamePolicy};
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let player = scene.create_entity();
+    /// scene.set_name(player, "Player").unwrap();
+    /// assert_eq!(scene.find_by_name("Player"), Some(player));
+    ///
+    /// scene.set_name_policy(NamePolicy::Unique);
+    /// let impostor = scene.create_entity();
+    /// assert!(scene.set_name(impostor, "Player").is_err());
+    ///
+    /// scene.set_name_policy(NamePolicy::MakeUnique);
+    /// let enemies = (0..3).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &enemy in &enemies {
+    ///     scene.set_name(enemy, "Enemy").unwrap();
+    /// }
+    /// assert_eq!(scene.get::<Name>(enemies[2]).unwrap().unwrap(), &"Enemy (2)");
+    /// assert_eq!(scene.set_name(impostor, "Player").unwrap(), "Player (1)");
+    /// assert_eq!(scene.find_by_name("Enemy (1)"), Some(enemies[1]));
+    ///
+    /// scene.destroy_entity(player);
+    /// assert_eq!(scene.find_by_name("Player"), None);
+    /// ```
+    #[track_caller]
+    pub fn set_name(&mut self, entity: Entity, name: impl Into<String>) -> Result<&str, RecsError> {
+        self.assert_entity_valid(entity)?;
+        let mut name = name.into();
+        let taken = |scene: &Self, name: &str| scene.find_all_by_name(name).any(|named| named != entity);
+        if self.name_policy != NamePolicy::AllowDuplicates && taken(self, &name) {
+            if self.name_policy == NamePolicy::Unique {
+                let message = format!("the name {name:?} is already taken");
+                return Err(RecsError::Other(message.into()));
+            }
+            name = (1..)
+                .map(|number| format!("{name} ({number})"))
+                .find(|candidate| !taken(self, candidate))
+                .expect("there are fewer entities than numbers");
+        }
+        self.remove_name(entity)?;
+        self.names.insert(&name, entity);
+        self.assign(entity, Name(name)).map(|name| name.as_str())
+    }
+
+    /// Removes the [`Name`] of an entity and returns it.
+    #[track_caller]
+    pub fn remove_name(&mut self, entity: Entity) -> Result<Option<Name>, RecsError> {
+        let Some(name) = self.get::<Name>(entity)?.cloned() else {
+            return Ok(None);
+        };
+        self.remove::<Name>(entity)?;
+        self.names.remove(name.as_str(), entity);
+        Ok(Some(name))
+    }
+
+    /// Returns a living entity with the given name, looked up through the index kept by [`Scene::set_name`].
+    /// If several entities share the name, the one named first is returned.
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.find_all_by_name(name).next()
+    }
+
+    /// Returns all living entities with the given name, in the order they were named.
+    pub fn find_all_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Entity> + 'a {
+        self.names
+            .get(name)
+            .iter()
+            .copied()
+            .filter(move |&entity| matches!(self.get::<Name>(entity), Ok(Some(named)) if named == name))
+    }
+
+    /// Rebuilds the name index from the [`Name`] components of all living entities.
+    /// Call it after loading or restoring a scene, whose names weren't given with [`Scene::set_name`].
+    pub fn reindex_names(&mut self) {
+        let mut names = NameIndex::default();
+        for entity in self.view::<(Name, ())>() {
+            if let Ok(Some(name)) = self.get::<Name>(entity) {
+                names.insert(name.as_str(), entity);
+            }
+        }
+        self.names = names;
+    }
+
+    /// Indexes the components of type `T` by value, so [`Scene::find_by`] finds the entities with a value without
+    /// scanning a view. Assigning, removing and mutably accessing components marks them in the index,
+    /// which is brought up to date by the next lookup.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone, PartialEq, Eq, Hash)]
+    /// struct TeamId(u32);
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.add_index::<TeamId>();
+    /// let red = (0..3).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// let blue = scene.create_entity();
+    /// for &entity in &red {
+    ///     scene.assign(entity, TeamId(1)).unwrap();
+    /// }
+    /// scene.assign(blue, TeamId(2)).unwrap();
+    /// assert_eq!(scene.find_by(&TeamId(2)), Some(blue));
+    /// assert_eq!(scene.find_all_by(&TeamId(1)).len(), 3);
+    ///
+    /// // The blue player switches teams
+    /// scene.get_mut::<TeamId>(blue).unwrap().unwrap().0 = 1;
+    /// assert_eq!(scene.find_by(&TeamId(2)), None);
+    /// assert_eq!(scene.find_all_by(&TeamId(1)).len(), 4);
+    /// ```
+    pub fn add_index<T: Eq + Hash + Clone + 'static>(&mut self) {
+        let type_id = get_type_id::<T>();
+        self.indexes.entry(type_id).or_insert_with(|| RefCell::new(Box::new(ValueIndex::<T>::new())));
+    }
+
+    /// Tracks the positions of the entities with a component of type `T` in a [`SpatialGrid`] with the given cell
+    /// size, for [`Scene::query_aabb`] and [`Scene::query_radius`]. Like indexes of component values, the grid
+    /// learns of assigned, removed and mutably accessed components and catches up on the next query.
+    /// Replaces the spatial index of another component type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell_size` isn't positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// use yarecs::spatial::Aabb;
+    ///
+    /// struct Position { x: f32, y: f32 }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.enable_spatial_index::<Position>(16.0, |position| [position.x, position.y]);
+    /// let tree = scene.create_entity();
+    /// let rock = scene.create_entity();
+    /// scene.assign(tree, Position { x: 4.0, y: 4.0 }).unwrap();
+    /// scene.assign(rock, Position { x: 100.0, y: 40.0 }).unwrap();
+    /// assert_eq!(scene.query_aabb(Aabb::new([0.0, 0.0], [10.0, 10.0])), vec![tree]);
+    ///
+    /// scene.get_mut::<Position>(rock).unwrap().unwrap().x = 6.0;
+    /// assert_eq!(scene.query_radius([0.0, 40.0], 10.0), vec![rock]);
+    /// ```
+    ///
+    /// [`SpatialGrid`]: crate::spatial::SpatialGrid
+    #[cfg(feature = "spatial")]
+    pub fn enable_spatial_index<T: 'static>(&mut self, cell_size: f32, position: impl Fn(&T) -> [f32; 2] + 'static) {
+        let spatial = SpatialIndex::new(get_type_id::<T>(), cell_size, position);
+        self.spatial = Some(RefCell::new(spatial));
+    }
+
+    /// Stops tracking the positions of entities.
+    #[cfg(feature = "spatial")]
+    pub fn disable_spatial_index(&mut self) {
+        self.spatial = None;
+    }
+
+    /// Returns the entities whose position is inside the rectangle, in no particular order,
+    /// or none if no spatial index is enabled. See [`Scene::enable_spatial_index`] for an example.
+    #[cfg(feature = "spatial")]
+    pub fn query_aabb(&self, aabb: Aabb) -> Vec<Entity> {
+        match &self.spatial {
+            Some(spatial) => spatial.borrow_mut().refresh(self).query_aabb(aabb),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the entities whose position is inside the circle, in no particular order,
+    /// or none if no spatial index is enabled. See [`Scene::enable_spatial_index`] for an example.
+    #[cfg(feature = "spatial")]
+    pub fn query_radius(&self, center: [f32; 2], radius: f32) -> Vec<Entity> {
+        match &self.spatial {
+            Some(spatial) => spatial.borrow_mut().refresh(self).query_radius(center, radius),
+            None => Vec::new(),
+        }
+    }
+
+    /// Drops the index of the components of type `T`. Returns false if there was none.
+    pub fn remove_index<T: 'static>(&mut self) -> bool {
+        self.indexes.remove(&get_type_id::<T>()).is_some()
+    }
+
+    /// Returns an entity whose component of type `T` equals `value`, looked up through the index added with
+    /// [`Scene::add_index`]. Without an index, all components of the type are searched.
+    pub fn find_by<T: Eq + Hash + Clone + 'static>(&self, value: &T) -> Option<Entity> {
+        self.find_all_by(value).first().copied()
+    }
+
+    /// Returns all entities whose component of type `T` equals `value`, in no particular order.
+    /// See [`Scene::add_index`] for an example.
+    pub fn find_all_by<T: Eq + Hash + Clone + 'static>(&self, value: &T) -> Vec<Entity> {
+        let Some(index) = self.indexes.get(&get_type_id::<T>()) else {
+            let has_value = |&entity: &Entity| self.get::<T>(entity).ok().flatten() == Some(value);
+            return self.view::<(T, ())>().filter(has_value).collect();
+        };
+        let mut index = index.borrow_mut();
+        let index = index.as_any_mut().downcast_mut::<ValueIndex<T>>().expect("indexes are keyed by their type");
+        index.refresh(
+            |entity| self.get::<T>(entity).ok().flatten().cloned(),
+            || self.view::<(T, ())>().collect(),
+        );
+        index.get(value).collect()
+    }
+
+    /// Adds a string tag to an entity, which is stored in its [`Tags`] component.
+    /// Returns false if the entity already had the tag.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// use yarecs::tag::Tags;
+    ///
+    /// let mut scene = Scene::new();
+    /// let goblin = scene.create_entity();
+    /// let dragon = scene.create_entity();
+    /// let villager = scene.create_entity();
+    /// scene.add_tag(goblin, "enemy").unwrap();
+    /// scene.add_tag(dragon, "enemy").unwrap();
+    /// scene.add_tag(dragon, "boss").unwrap();
+    /// scene.add_tag(villager, "friendly").unwrap();
+    ///
+    /// let mut enemies = scene.view_tagged("enemy").collect::<Vec<_>>();
+    /// enemies.sort_by_key(|entity| entity.index());
+    /// assert_eq!(enemies, vec![goblin, dragon]);
+    /// assert_eq!(scene.get::<Tags>(dragon).unwrap().unwrap().iter().collect::<Vec<_>>(), vec!["enemy", "boss"]);
+    ///
+    /// scene.remove_tag(dragon, "enemy").unwrap();
+    /// scene.destroy_entity(goblin);
+    /// assert_eq!(scene.view_tagged("enemy").count(), 0);
+    /// assert!(scene.has_tag(dragon, "boss"));
+    /// ```
+    #[track_caller]
+    pub fn add_tag(&mut self, entity: Entity, tag: &str) -> Result<bool, RecsError> {
+        if self.get::<Tags>(entity)?.is_some_and(|tags| tags.contains(tag)) {
+            return Ok(false);
+        }
+        let tag = self.tags.insert(tag, entity);
+        match self.get_mut::<Tags>(entity)? {
+            Some(tags) => tags.0.push(tag),
+            None => {
+                self.assign(entity, Tags(vec![tag]))?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Removes a string tag from an entity, removing its [`Tags`] once it's empty.
+    /// Returns false if the entity didn't have the tag.
+    #[track_caller]
+    pub fn remove_tag(&mut self, entity: Entity, tag: &str) -> Result<bool, RecsError> {
+        let Some(tags) = self.get_mut::<Tags>(entity)? else {
+            return Ok(false);
+        };
+        let Some(position) = tags.0.iter().position(|own| &**own == tag) else {
+            return Ok(false);
+        };
+        tags.0.remove(position);
+        if tags.is_empty() {
+            self.remove::<Tags>(entity)?;
+        }
+        self.tags.remove(tag, entity);
+        Ok(true)
+    }
+
+    /// Returns true if the entity is alive and has the string tag.
+    pub fn has_tag(&self, entity: Entity, tag: &str) -> bool {
+        matches!(self.get::<Tags>(entity), Ok(Some(tags)) if tags.contains(tag))
+    }
+
+    /// Returns the living entities with the string tag, in no particular order.
+    /// See [`Scene::add_tag`] for an example.
+    pub fn view_tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = Entity> + 'a {
+        self.tags.get(tag).filter(move |&entity| self.has_tag(entity, tag))
+    }
+
+    /// Registers a component type whose layout is only known at runtime, like one defined by a script or a mod.
+    /// Its components are stored as rows of bytes and accessed field by field with
+    /// [`Scene::get_dynamic_field`] and [`Scene::set_dynamic_field`].
+    /// Fails if a dynamic component with the same name is already registered.
+    ///
+    /// Dynamic components are removed when their entity is destroyed, but they aren't part of
+    /// snapshots, diffs or saved scenes, and views over rust component types don't see them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::dynamic::{DynamicLayout, FieldType, Value};
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let layout = DynamicLayout::new("Mana")
+    ///     .with_field("current", FieldType::F32)
+    ///     .with_field("regenerates", FieldType::Bool);
+    /// let mana = scene.register_dynamic_component(layout).unwrap();
+    /// assert_eq!(scene.dynamic_component("Mana"), Some(mana));
+    ///
+    /// let wizard = scene.create_entity();
+    /// assert!(scene.assign_dynamic(wizard, mana).unwrap());
+    /// assert_eq!(scene.get_dynamic_field(wizard, mana, "current").unwrap(), Some(Value::F32(0.0)));
+    ///
+    /// scene.set_dynamic_field(wizard, mana, "current", Value::F32(12.5)).unwrap();
+    /// assert_eq!(scene.get_dynamic_field(wizard, mana, "current").unwrap(), Some(Value::F32(12.5)));
+    /// assert!(scene.set_dynamic_field(wizard, mana, "current", Value::I32(1)).is_err());
+    /// assert_eq!(scene.view_dynamic(mana).unwrap().collect::<Vec<_>>(), vec![wizard]);
+    ///
+    /// assert!(scene.remove_dynamic(wizard, mana).unwrap());
+    /// assert!(!scene.has_dynamic(wizard, mana));
+    /// ```
+    pub fn register_dynamic_component(&mut self, layout: DynamicLayout) -> Result<DynamicComponentId, RecsError> {
+        self.dynamic.register(layout)
+    }
+
+    /// Returns the dynamic component registered under the name.
+    pub fn dynamic_component(&self, name: &str) -> Option<DynamicComponentId> {
+        self.dynamic.id(name)
+    }
+
+    /// Returns the layout of a dynamic component, or `None` if it isn't registered in this scene.
+    pub fn dynamic_layout(&self, id: DynamicComponentId) -> Option<&DynamicLayout> {
+        self.dynamic.pool(id).ok().map(|pool| pool.layout())
+    }
+
+    /// Assigns a dynamic component with all fields set to zero or false to an entity.
+    /// Returns false if the entity already has one, which is left as it is.
+    pub fn assign_dynamic(&mut self, entity: Entity, id: DynamicComponentId) -> Result<bool, RecsError> {
+        self.assert_entity_valid(entity)?;
+        let added = self.dynamic.pool_mut(id)?.insert(entity);
+        if added {
+            self.structure_generation += 1;
+        }
+        Ok(added)
+    }
+
+    /// Removes a dynamic component from an entity. Returns false if the entity didn't have one.
+    pub fn remove_dynamic(&mut self, entity: Entity, id: DynamicComponentId) -> Result<bool, RecsError> {
+        self.assert_entity_valid(entity)?;
+        let removed = self.dynamic.pool_mut(id)?.remove(entity);
+        if removed {
+            self.structure_generation += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Checks if an entity has a dynamic component.
+    pub fn has_dynamic(&self, entity: Entity, id: DynamicComponentId) -> bool {
+        self.dynamic.pool(id).is_ok_and(|pool| pool.contains(entity))
+    }
+
+    /// Returns the value of a field of an entity's dynamic component, or `None` if the entity doesn't have one.
+    /// Fails if the entity isn't alive or the layout has no such field.
+    pub fn get_dynamic_field(
+        &self,
+        entity: Entity,
+        id: DynamicComponentId,
+        field: &str,
+    ) -> Result<Option<Value>, RecsError> {
+        self.assert_entity_valid(entity)?;
+        let pool = self.dynamic.pool(id)?;
+        let field = pool.layout().field(field).ok_or_else(|| {
+            RecsError::Other(format!("dynamic component {} has no field {field}", pool.layout().name()).into())
+        })?;
+        Ok(pool.get(entity, field))
+    }
+
+    /// Overwrites a field of an entity's dynamic component.
+    /// Fails if the entity doesn't have the component, the layout has no such field or the value has another type.
+    pub fn set_dynamic_field(
+        &mut self,
+        entity: Entity,
+        id: DynamicComponentId,
+        field: &str,
+        value: Value,
+    ) -> Result<(), RecsError> {
+        self.assert_entity_valid(entity)?;
+        let pool = self.dynamic.pool_mut(id)?;
+        let name = pool.layout().name();
+        let field = pool
+            .layout()
+            .field(field)
+            .ok_or_else(|| RecsError::Other(format!("dynamic component {name} has no field {field}").into()))?
+            .clone();
+        if field.field_type() != value.field_type() {
+            let message = format!(
+                "field {} of dynamic component {name} is a {:?}, not a {:?}",
+                field.name(),
+                field.field_type(),
+                value.field_type()
+            );
+            return Err(RecsError::Other(message.into()));
+        }
+        if !pool.set(entity, &field, value) {
+            let message = format!("entity has no dynamic component {}", pool.layout().name());
+            return Err(RecsError::Other(message.into()));
+        }
+        Ok(())
+    }
+
+    /// Returns the entities with a dynamic component, in no particular order.
+    pub fn view_dynamic(&self, id: DynamicComponentId) -> Result<impl Iterator<Item = Entity> + '_, RecsError> {
+        Ok(self.dynamic.pool(id)?.entities().iter().copied())
+    }
+
+    /// Declares that components of type `A` require a component of type `B`: assigning an `A` to an entity
+    /// without a `B` assigns the default value of `B` first, so entities can't be left partially configured.
+    /// Requirements are transitive, and cycles are fine. Removing the `B` later is still possible.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Default)]
+    /// struct Position(f32, f32);
+    /// #[derive(Default)]
+    /// struct Velocity(f32, f32);
+    /// struct RigidBody { mass: f32 }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.add_required_component::<RigidBody, Velocity>();
+    /// scene.add_required_component::<Velocity, Position>();
+    ///
+    /// let crate_ = scene.create_entity();
+    /// scene.assign(crate_, Velocity(0.0, -1.0)).unwrap();
+    /// scene.assign(crate_, RigidBody { mass: 20.0 }).unwrap();
+    /// assert_eq!(scene.get::<Velocity>(crate_).unwrap().unwrap().1, -1.0);
+    ///
+    /// let ball = scene.create_entity();
+    /// scene.assign(ball, RigidBody { mass: 0.5 }).unwrap();
+    /// assert_eq!(scene.view::<(RigidBody, (Velocity, (Position, ())))>().count(), 2);
+    /// ```
+    pub fn add_required_component<A: 'static, B: Default + 'static>(&mut self) {
+        let required_id = get_type_id::<B>();
+        let insert_default: InsertDefaultFn = |scene, entity| {
+            let required_id = get_type_id::<B>();
+            match scene.entities[entity.index() as usize].has_component_with_type_id(required_id) {
+                true => Ok(()),
+                false => scene.assign_default::<B>(entity).map(|_| ()),
+            }
+        };
+        let required = self.required_components.entry(get_type_id::<A>()).or_insert_with(|| Rc::new([]));
+        if !required.iter().any(|&(type_id, _)| type_id == required_id) {
+            *required = required.iter().copied().chain([(required_id, insert_default)]).collect();
+        }
+    }
+
+    /// Declares that components of type `A` depend on a component of type `B`: assigning an `A` to an entity
+    /// without a `B` fails with [`RecsError::MissingDependency`], catching content bugs when components are
+    /// assigned instead of when systems iterate over incomplete entities. Required components declared with
+    /// [`Scene::add_required_component`] count as present, and dependencies are checked before any of them
+    /// is inserted, so a failed assignment leaves the entity unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::error::RecsError;
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Mesh(&'static str);
+    /// struct Material(&'static str);
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.add_component_dependency::<Material, Mesh>();
+    /// let statue = scene.create_entity();
+    /// assert!(matches!(
+    ///     scene.assign(statue, Material("marble")),
+    ///     Err(RecsError::MissingDependency { .. })
+    /// ));
+    ///
+    /// scene.assign(statue, Mesh("statue.obj")).unwrap();
+    /// scene.assign(statue, Material("marble")).unwrap();
+    /// ```
+    pub fn add_component_dependency<A: 'static, B: 'static>(&mut self) {
+        let dependency = get_type_id::<B>();
+        let dependencies = self.dependencies.entry(get_type_id::<A>()).or_insert_with(|| Rc::new([]));
+        if !dependencies.contains(&dependency) {
+            *dependencies = dependencies.iter().copied().chain([dependency]).collect();
+        }
+    }
+
+    /// Declares that an entity can have at most one component of the types in `G`, a list like the one of
+    /// [`Scene::view`]: assigning a component of one of the types removes the components of the others,
+    /// so states like idle, walking and attacking can't be combined by mistake.
+    /// Entities that already have several of the components keep them until one of the types is assigned.
+    /// Fails if one of the types is already in another group.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Idle;
+    /// struct Walking { speed: f32 }
+    /// struct Attacking { target: u32 }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.add_exclusive_group::<(Idle, (Walking, (Attacking, ())))>().unwrap();
+    /// let knight = scene.create_entity();
+    /// scene.assign(knight, Idle).unwrap();
+    /// scene.assign(knight, Walking { speed: 2.0 }).unwrap();
+    /// assert!(scene.get::<Idle>(knight).unwrap().is_none());
+    ///
+    /// scene.assign(knight, Attacking { target: 7 }).unwrap();
+    /// assert!(scene.get::<Walking>(knight).unwrap().is_none());
+    /// assert_eq!(scene.view::<(Attacking, ())>().count(), 1);
+    /// assert!(scene.add_exclusive_group::<(Idle, (u8, ()))>().is_err());
+    /// ```
+    pub fn add_exclusive_group<G: RequireComponents>(&mut self) -> Result<(), RecsError> {
+        let group = Rc::<[usize]>::from(G::required_component_ids());
+        if let Some(&grouped) = group.iter().find(|type_id| self.exclusive_groups.contains_key(type_id)) {
+            let type_name = get_type_name(grouped).unwrap_or("unknown");
+            let message = format!("component type {type_name} is already in an exclusive group");
+            return Err(RecsError::Other(message.into()));
+        }
+        for &type_id in group.iter() {
+            self.exclusive_groups.insert(type_id, group.clone());
+        }
+        Ok(())
+    }
+
+    /// Returns the layer with the given name, registering it if it's new. Fails once all 64 layers are taken.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Collider;
+    ///
+    /// let mut scene = Scene::new();
+    /// let players = scene.register_layer("players").unwrap();
+    /// let walls = scene.register_layer("walls").unwrap();
+    /// let ghost = scene.create_entity();
+    /// let wall = scene.create_entity();
+    /// scene.assign(ghost, Collider).unwrap();
+    /// scene.assign(wall, Collider).unwrap();
+    /// scene.add_to_layer(ghost, players).unwrap();
+    /// scene.add_to_layer(wall, walls).unwrap();
+    ///
+    /// let solid = scene.view::<(Collider, ())>().in_layers(walls).collect::<Vec<_>>();
+    /// assert_eq!(solid, vec![wall]);
+    /// assert_eq!(scene.layer("players"), Some(players));
+    /// assert_eq!(scene.layer_name(walls), Some("walls"));
+    /// ```
+    pub fn register_layer(&mut self, name: &str) -> Result<Layer, RecsError> {
+        self.layers
+            .register(name)
+            .ok_or_else(|| RecsError::Other(format!("no layer left for {name:?}, all 64 are taken").into()))
+    }
+
+    /// Returns the registered layer with the given name.
+    pub fn layer(&self, name: &str) -> Option<Layer> {
+        self.layers.get(name)
+    }
+
+    /// Returns the name a layer was registered with.
+    pub fn layer_name(&self, layer: Layer) -> Option<&str> {
+        self.layers.name(layer)
+    }
+
+    /// Adds an entity to a layer, recorded in its [`Layers`] component. See [`Scene::register_layer`] for an example.
+    #[track_caller]
+    pub fn add_to_layer(&mut self, entity: Entity, layer: Layer) -> Result<(), RecsError> {
+        match self.get_mut::<Layers>(entity)? {
+            Some(layers) => *layers = layers.with(layer),
+            None => {
+                self.assign(entity, Layers::from(layer))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes an entity from a layer, removing its [`Layers`] component once it's in none.
+    #[track_caller]
+    pub fn remove_from_layer(&mut self, entity: Entity, layer: Layer) -> Result<(), RecsError> {
+        let Some(layers) = self.get_mut::<Layers>(entity)? else {
+            return Ok(());
+        };
+        *layers = layers.without(layer);
+        if layers.is_empty() {
+            self.remove::<Layers>(entity)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the entity hasn't been destroyed.
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.assert_entity_valid(entity).is_ok()
+    }
+
+    /// Creates a checked weak reference to an entity. See [`WeakEntity`] for an example.
+    pub fn weak(&self, entity: Entity) -> WeakEntity {
+        WeakEntity::new(entity, self.entity_layout)
+    }
+
+    /// Returns the entity a weak reference refers to, or `None` if that entity isn't alive anymore.
+    /// See [`WeakEntity`] for an example.
+    pub fn resolve(&self, handle: WeakEntity) -> Option<Entity> {
+        let entity = handle.entity_unchecked();
+        (handle.layout() == self.entity_layout && self.is_alive(entity)).then_some(entity)
+    }
+
+    /// Returns the number of living entities.
+    pub fn entity_count(&self) -> usize {
+        self.alive.len()
+    }
+
+    /// Reports how many slots of the entity list belong to destroyed entities.
+    /// A high fragmentation with few reused indices means [`Scene::compact_entities`] would free a lot of memory.
+    /// See [`FragmentationReport`] for an example.
+    pub fn entity_fragmentation(&self) -> FragmentationReport {
+        let slots = self.entities.iter().map(|entity_description| entity_description.entity.is_valid());
+        FragmentationReport::from_slots(slots, self.entity_reuse)
+    }
+
+    /// Reports how densely each pool uses its allocated slots, keyed by component type name.
+    /// Pools with a high fragmentation shrink well with [`Scene::shrink_to_fit`].
+    /// Components with table storage have no holes and aren't reported.
+    pub fn pool_fragmentation(&self) -> Vec<(&'static str, FragmentationReport)> {
+        let mut reports = self
+            .pools
+            .iter()
+            .enumerate()
+            .filter_map(|(type_id, pool)| {
+                let report = pool.as_ref()?.fragmentation();
+                Some((get_type_name(type_id).unwrap_or("<unknown>"), report))
+            })
+            .collect::<Vec<_>>();
+        reports.sort_by_key(|(name, _)| *name);
+        reports
+    }
+
+    /// Returns the number of free entity indices that are held back before indices are reused.
+    pub fn min_free_indices(&self) -> usize {
+        self.min_free_indices
+    }
+
+    /// Holds back the given number of destroyed entity indices before reusing any of them.
+    /// Once more indices are free, the one that was freed first is reused, so indices rest as long as possible
+    /// and stale handles are less likely to collide with new entities. The default of 0 reuses the most
+    /// recently freed index right away, which keeps the entity list dense.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.set_min_free_indices(2);
+    /// let entities = (0..3).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &entity in &entities {
+    ///     scene.destroy_entity(entity);
+    /// }
+    ///
+    /// // The two most recently freed indices rest, the oldest one is reused
+    /// assert_eq!(scene.create_entity().index(), 0);
+    /// assert_eq!(scene.create_entity().index(), 3);
+    /// ```
+    pub fn set_min_free_indices(&mut self, min_free_indices: usize) {
+        self.min_free_indices = min_free_indices;
+    }
+
+    /// Assigns a component to an entity.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// 
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 42).unwrap();
+    /// 
+    /// let value = scene.get::<i32>(entity).unwrap().unwrap();
+    /// assert_eq!(*value, 42);
+    /// ```
+    #[track_caller]
+    pub fn assign<T: 'static>(
+        &mut self,
+        entity: Entity,
+        new_component: T,
+    ) -> Result<&mut T, RecsError> {
+        self.assign_with_handle(PoolHandle::of(), entity, new_component)
+    }
+
+    /// Assigns a component to an entity, using a handle resolved earlier instead of looking up the component type.
+    #[track_caller]
+    pub fn assign_with_handle<T: 'static>(
+        &mut self,
+        handle: PoolHandle<T>,
+        entity: Entity,
+        new_component: T,
+    ) -> Result<&mut T, RecsError> {
+        self.add_to_entity_description::<T>(handle.type_id(), entity)?;
+        self.insert_component(handle.type_id(), entity, || new_component)
+    }
+
+    /// Assigns the default value of a component to an entity.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// 
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign_default::<i32>(entity).unwrap();
+    /// 
+    /// let value = scene.get::<i32>(entity).unwrap().unwrap();
+    /// assert_eq!(*value, 0);
+    /// ```
+    #[track_caller]
+    pub fn assign_default<T: Default + 'static>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<&mut T, RecsError> {
+        let type_id = get_type_id::<T>();
+        self.add_to_entity_description::<T>(type_id, entity)?;
+        self.insert_component(type_id, entity, T::default)
+    }
+
+    /// Removes a component from an entity.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// 
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 42).unwrap();
+    /// scene.remove::<i32>(entity).unwrap();
+    /// 
+    /// let value = scene.get::<i32>(entity).unwrap();
+    /// assert!(value.is_none());
+    /// ```
+    #[track_caller]
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Result<(), RecsError> {
+        self.remove_by_type_id(entity, get_type_id::<T>(), Some(type_name::<T>()))
+    }
+
+    /// Gets a component from an entity.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// 
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 42).unwrap();
+    /// 
+    /// let value = scene.get::<i32>(entity).unwrap().unwrap();
+    /// assert_eq!(*value, 42);
+    /// ```
+    pub fn get<T: 'static>(&self, entity: Entity) -> Result<Option<&T>, RecsError> {
+        self.get_with_handle(PoolHandle::of(), entity)
+    }
+
+    /// Gets a component from an entity, using a handle resolved earlier instead of looking up the component type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let velocities = scene.register::<f32>();
+    /// let entities = (0..10).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &entity in &entities {
+    ///     scene.assign_with_handle(velocities, entity, 1.5).unwrap();
+    /// }
+    ///
+    /// let mut sum = 0.0;
+    /// for &entity in &entities {
+    ///     sum += scene.get_with_handle(velocities, entity).unwrap().unwrap();
+    /// }
+    /// assert_eq!(sum, 15.0);
+    /// ```
+    pub fn get_with_handle<T: 'static>(
+        &self,
+        handle: PoolHandle<T>,
+        entity: Entity,
+    ) -> Result<Option<&T>, RecsError> {
+        self.assert_entity_valid(entity)?;
+        let type_id = handle.type_id();
+        #[cfg(feature = "metrics")]
+        self.record_read(type_id, entity);
+        Ok(match self.storage_type_of(type_id) {
+            StorageType::Sparse if is_tag::<T>() => {
+                self.get_pool_if_exists::<TagPool<T>>(type_id).and_then(|p| p.get(entity))
+            }
+            StorageType::Sparse => self.get_pool_if_exists::<Pool<T>>(type_id).and_then(|p| p.get(entity)),
+            StorageType::Table => self.archetypes.get(entity),
+            StorageType::Slab => self.get_pool_if_exists::<SlabPool<T>>(type_id).and_then(|p| p.get(entity)),
+            StorageType::Small => self.get_pool_if_exists::<SmallPool<T>>(type_id).and_then(|p| p.get(entity)),
+            StorageType::Interned => {
+                self.get_pool_if_exists::<InternedPool<T>>(type_id).and_then(|p| p.get(entity))
+            }
+        })
+    }
+
+    /// Gets a mutable component from an entity.
+    /// The entity's change tick is only updated, and the component only marked as changed, if it has one.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// 
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 42).unwrap();
+    /// 
+    /// let value = scene.get_mut::<i32>(entity).unwrap().unwrap();
+    /// *value = 43;
+    /// 
+    /// let new_value = scene.get::<i32>(entity).unwrap().unwrap();
+    /// assert_eq!(*new_value, 43);
+    ///
+    /// // Looking for a component the entity doesn't have changes nothing
+    /// let saved = scene.change_tick();
+    /// scene.increment_change_tick();
+    /// assert!(scene.get_mut::<u8>(entity).unwrap().is_none());
+    /// assert!(scene.entities_changed_since(saved).is_empty());
+    /// ```
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Result<Option<&mut T>, RecsError> {
+        self.get_mut_with_handle(PoolHandle::of(), entity)
+    }
+
+    /// Gets a mutable component from an entity, using a handle resolved earlier.
+    pub fn get_mut_with_handle<T: 'static>(
+        &mut self,
+        handle: PoolHandle<T>,
+        entity: Entity,
+    ) -> Result<Option<&mut T>, RecsError> {
+        let type_id = handle.type_id();
+        let change_tick = self.change_tick;
+        let storage_type = self.storage_type_of(type_id);
+        let entity_description = self.get_valid_entity_description_mut(entity)?;
+        // Only a component that is actually handed out can be changed
+        if entity_description.has_component_with_type_id(type_id) && !matches!(storage_type, StorageType::Interned) {
+            entity_description.changed_tick = change_tick;
+            self.component_changed(entity, type_id);
+        }
+        #[cfg(feature = "metrics")]
+        self.record_read(type_id, entity);
+        Ok(match storage_type {
+            StorageType::Sparse if is_tag::<T>() => {
+                self.get_pool_if_exists_mut::<TagPool<T>>(type_id).and_then(|p| p.get_mut(entity))
+            }
+            StorageType::Sparse => {
+                self.get_pool_if_exists_mut::<Pool<T>>(type_id).and_then(|p| p.get_mut(entity))
+            }
+            StorageType::Table => self.archetypes.get_mut(entity),
+            StorageType::Slab => {
+                self.get_pool_if_exists_mut::<SlabPool<T>>(type_id).and_then(|p| p.get_mut(entity))
+            }
+            StorageType::Small => {
+                self.get_pool_if_exists_mut::<SmallPool<T>>(type_id).and_then(|p| p.get_mut(entity))
+            }
+            StorageType::Interned => return Err(interned_mutation()),
+        })
+    }
+
+    /// Gets a component from an entity without checking that the entity is alive and has the component.
+    /// Components in sparse pools are read without any bounds or version checks, which helps hot inner loops
+    /// over entities that were validated once in an outer loop.
+    ///
+    /// # Safety
+    ///
+    /// `entity` must be alive and have a component of type `T`,
+    /// which must not be stored as a struct of arrays.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let entities = (0..10).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &entity in &entities {
+    ///     scene.assign(entity, 2u32).unwrap();
+    /// }
+    ///
+    /// // Every entity was just given a component
+    /// let total = entities.iter().map(|&entity| unsafe { *scene.get_unchecked::<u32>(entity) }).sum::<u32>();
+    /// assert_eq!(total, 20);
+    /// ```
+    #[cfg(feature = "unchecked")]
+    pub unsafe fn get_unchecked<T: 'static>(&self, entity: Entity) -> &T {
+        let type_id = get_type_id::<T>();
+        // The pool slot of a type only holds a `Pool<T>` while the type has sparse storage
+        match self.get_pool_if_exists::<Pool<T>>(type_id) {
+            // SAFETY: the caller guarantees that the entity has the component
+            Some(pool) => unsafe { pool.get_unchecked(entity) },
+            // SAFETY: as above, so the checked lookup finds the component
+            None => unsafe { self.get::<T>(entity).ok().flatten().unwrap_unchecked() },
+        }
+    }
+
+    /// Gets a mutable component from an entity without checking that the entity is alive and has the component.
+    /// Like [`Scene::get_mut`], this updates the change tick of the entity and marks the component for replication.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`Scene::get_unchecked`]. Additionally, the component must not be interned.
+    #[cfg(feature = "unchecked")]
+    pub unsafe fn get_unchecked_mut<T: 'static>(&mut self, entity: Entity) -> &mut T {
+        let type_id = get_type_id::<T>();
+        if self.get_pool_if_exists::<Pool<T>>(type_id).is_some() {
+            self.entities[entity.index() as usize].changed_tick = self.change_tick;
+            self.component_changed(entity, type_id);
+            let pool = self.get_pool_if_exists_mut::<Pool<T>>(type_id);
+            // SAFETY: the pool exists, and the caller guarantees that the entity has the component
+            return unsafe { pool.unwrap_unchecked().get_unchecked_mut(entity) };
+        }
+        // SAFETY: the caller guarantees that the entity has the component
+        unsafe { self.get_mut::<T>(entity).ok().flatten().unwrap_unchecked() }
+    }
+
+    /// Copies the components of type `T` of `sources` to the entities at the same positions in `targets`,
+    /// overwriting components the targets already have. Sources without the component are skipped.
+    /// Components in sparse pools are copied in bulk, which makes duplicating large populations cheap.
+    /// All entities are validated before anything is copied, so an invalid entity leaves the scene unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let particles = (0..1000).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for (i, &particle) in particles.iter().enumerate() {
+    ///     scene.assign(particle, [i as f32, 0.0]).unwrap();
+    /// }
+    ///
+    /// let copies = (0..1000).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// scene.clone_components_to::<[f32; 2]>(&particles, &copies).unwrap();
+    /// assert_eq!(*scene.get::<[f32; 2]>(copies[500]).unwrap().unwrap(), [500.0, 0.0]);
+    /// assert_eq!(scene.view::<([f32; 2], ())>().count(), 2000);
+    /// ```
+    #[track_caller]
+    pub fn clone_components_to<T: Copy + 'static>(
+        &mut self,
+        sources: &[Entity],
+        targets: &[Entity],
+    ) -> Result<(), RecsError> {
+        if sources.len() != targets.len() {
+            return Err(RecsError::Other("every source entity needs exactly one target entity".into()));
+        }
+        for &target in targets {
+            self.assert_entity_valid(target)?;
+        }
+        let mut copies = Vec::with_capacity(sources.len());
+        for (&source, &target) in sources.iter().zip(targets) {
+            if let Some(&component) = self.get::<T>(source)? {
+                copies.push((target, component));
+            }
+        }
+        let type_id = get_type_id::<T>();
+        for &(target, _) in &copies {
+            self.add_to_entity_description::<T>(type_id, target)?;
+        }
+
+        if self.storage_type_of(type_id) == StorageType::Sparse && !is_tag::<T>() {
+            let (targets, components): (Vec<_>, Vec<_>) = copies.into_iter().unzip();
+            self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?.assign_slice(&targets, &components);
+        } else {
+            for (target, component) in copies {
+                *self.insert_component(type_id, target, || component)? = component;
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns every component to its entity, overwriting components the entities already have.
+    /// All entities are validated before anything is assigned, so an invalid entity leaves the scene unchanged.
+    /// Sparse pools reserve memory for the whole batch at once instead of growing with every component.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone)]
+    /// struct Name(String);
+    ///
+    /// let mut scene = Scene::new();
+    /// let pairs = (0..100)
+    ///     .map(|i| (scene.create_entity(), Name(format!("unit {i}"))))
+    ///     .collect::<Vec<_>>();
+    /// scene.assign_batch(&pairs).unwrap();
+    ///
+    /// assert_eq!(scene.get::<Name>(pairs[42].0).unwrap().unwrap().0, "unit 42");
+    /// assert_eq!(scene.view::<(Name, ())>().count(), 100);
+    /// ```
+    #[track_caller]
+    pub fn assign_batch<T: Clone + 'static>(&mut self, pairs: &[(Entity, T)]) -> Result<(), RecsError> {
+        for (entity, _) in pairs {
+            self.assert_entity_valid(*entity)?;
+        }
+        let type_id = get_type_id::<T>();
+        for (entity, _) in pairs {
+            self.add_to_entity_description::<T>(type_id, *entity)?;
+        }
+
+        if self.storage_type_of(type_id) == StorageType::Sparse && !is_tag::<T>() {
+            self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?.assign_batch(pairs);
+        } else {
+            for (entity, component) in pairs {
+                self.insert_component(type_id, *entity, || component.clone())?.clone_from(component);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps a second copy of the components of type `T` holding their values as of the last call to
+    /// [`Scene::swap_buffers`]. Systems can read last frame's values with [`Scene::get_previous`] while
+    /// writing this frame's values, so the order in which they run doesn't change the results.
+    /// Only components stored in a sparse pool can be double-buffered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone)]
+    /// struct Heat(f32);
+    ///
+    /// let mut scene = Scene::new();
+    /// let cells = (0..3).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for (i, &cell) in cells.iter().enumerate() {
+    ///     scene.assign(cell, Heat(i as f32 * 10.0)).unwrap();
+    /// }
+    /// scene.set_double_buffered::<Heat>().unwrap();
+    ///
+    /// // Each cell takes the heat of its right neighbor, regardless of update order
+    /// for window in cells.windows(2) {
+    ///     let neighbor = scene.get_previous::<Heat>(window[1]).unwrap().unwrap().0;
+    ///     scene.get_mut::<Heat>(window[0]).unwrap().unwrap().0 = neighbor;
+    /// }
+    /// scene.swap_buffers::<Heat>().unwrap();
+    ///
+    /// assert_eq!(scene.get::<Heat>(cells[0]).unwrap().unwrap().0, 10.0);
+    /// assert_eq!(scene.get_previous::<Heat>(cells[1]).unwrap().unwrap().0, 20.0);
+    /// ```
+    pub fn set_double_buffered<T: Clone + 'static>(&mut self) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        if self.storage_type_of(type_id) != StorageType::Sparse || is_tag::<T>() {
+            return Err(RecsError::Other("component type has no pool to double-buffer".into()));
+        }
+        let previous = self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?.clone();
+        self.previous_pools.insert(type_id, Box::new(previous));
+        Ok(())
+    }
+
+    /// Gets the value a double-buffered component of an entity had at the last call to [`Scene::swap_buffers`].
+    /// Returns `None` if the entity had no such component back then or the type isn't double-buffered.
+    pub fn get_previous<T: 'static>(&self, entity: Entity) -> Result<Option<&T>, RecsError> {
+        self.assert_entity_valid(entity)?;
+        Ok(self
+            .previous_pools
+            .get(&get_type_id::<T>())
+            .and_then(|pool| pool.as_any().downcast_ref::<Pool<T>>())
+            .and_then(|pool| pool.get(entity)))
+    }
+
+    /// Copies the current components of a double-buffered type into the buffer read by [`Scene::get_previous`],
+    /// usually at the end of a frame.
+    pub fn swap_buffers<T: Clone + 'static>(&mut self) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        let current = self
+            .pools
+            .get(type_id)
+            .and_then(Option::as_ref)
+            .and_then(|pool| pool.as_any().downcast_ref::<Pool<T>>());
+        let previous = self
+            .previous_pools
+            .get_mut(&type_id)
+            .and_then(|pool| pool.as_any_mut().downcast_mut::<Pool<T>>());
+        match (current, previous) {
+            (Some(current), Some(previous)) => {
+                previous.clone_from(current);
+                Ok(())
+            }
+            _ => Err(RecsError::Other("component type is not double-buffered".into())),
+        }
+    }
+
+    /// Keeps removed components of type `T` for reuse by [`Scene::assign_recycled`], which calls `reset` on them
+    /// before handing them out again. This avoids dropping and rebuilding components that own expensive allocations.
+    /// Only components stored in a sparse pool can be recycled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Default)]
+    /// struct Path {
+    ///     waypoints: Vec<(f32, f32)>,
+    /// }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.set_component_reset::<Path>(|path| path.waypoints.clear()).unwrap();
+    ///
+    /// let first = scene.create_entity();
+    /// scene.assign_recycled::<Path>(first).unwrap().waypoints.extend([(0.0, 0.0); 256]);
+    /// scene.destroy_entity(first);
+    ///
+    /// let second = scene.create_entity();
+    /// let path = scene.assign_recycled::<Path>(second).unwrap();
+    /// assert!(path.waypoints.is_empty());
+    /// assert!(path.waypoints.capacity() >= 256);
+    /// ```
+    pub fn set_component_reset<T: 'static>(&mut self, reset: fn(&mut T)) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        if self.storage_type_of(type_id) != StorageType::Sparse || is_tag::<T>() {
+            return Err(RecsError::Other("component type has no pool to recycle into".into()));
+        }
+        self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?.set_reset(reset);
+        Ok(())
+    }
+
+    /// Assigns a component to an entity, reusing a recycled component if one is available
+    /// and falling back to `T::default()` otherwise. See [`Scene::set_component_reset`].
+    #[track_caller]
+    pub fn assign_recycled<T: Default + 'static>(&mut self, entity: Entity) -> Result<&mut T, RecsError> {
+        let type_id = get_type_id::<T>();
+        self.add_to_entity_description::<T>(type_id, entity)?;
+        if self.storage_type_of(type_id) == StorageType::Sparse && !is_tag::<T>() {
+            Ok(self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?.assign_recycled(entity, T::default))
+        } else {
+            self.insert_component(type_id, entity, T::default)
+        }
+    }
+
+    /// Creates the storage for components of type `T` if needed and reserves room for `additional` more components,
+    /// including the sparse lookup structures for the next `additional` entities, so hot pools can be
+    /// pre-sized before a burst of assignments.
+    /// Components with table storage live in archetype tables, which grow on demand, so nothing is reserved for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.reserve::<f32>(10_000).unwrap();
+    /// assert!(scene.pool::<f32>().unwrap().is_empty());
+    ///
+    /// for _ in 0..10_000 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, 1.0f32).unwrap();
+    /// }
+    /// assert_eq!(scene.pool::<f32>().unwrap().len(), 10_000);
+    /// ```
+    pub fn reserve<T: 'static>(&mut self, additional: usize) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        let indices = self.entities.len() + additional;
+        match self.storage_type_of(type_id) {
+            StorageType::Sparse if is_tag::<T>() => {
+                self.get_or_create_pool::<T, _>(type_id, |_| TagPool::<T>::new())?.reserve_indices(indices);
+            }
+            StorageType::Sparse => {
+                let pool = self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?;
+                pool.reserve(additional);
+                pool.reserve_indices(indices);
+            }
+            StorageType::Table => {}
+            StorageType::Slab => {
+                self.get_or_create_pool::<T, _>(type_id, SlabPool::<T>::new_in)?.reserve_indices(indices);
+            }
+            StorageType::Small => {
+                self.get_or_create_pool::<T, _>(type_id, SmallPool::<T>::new_in)?;
+            }
+            StorageType::Interned => {}
+        }
+        Ok(())
+    }
+
+    /// Resolves the handle for components of type `T` and creates their storage, if it doesn't exist yet.
+    /// Keep the handle around to access components in hot loops without looking up the component type each time.
+    pub fn register<T: 'static>(&mut self) -> PoolHandle<T> {
+        let handle = PoolHandle::of();
+        let type_id = handle.type_id();
+        match self.storage_type_of(type_id) {
+            StorageType::Sparse if is_tag::<T>() => {
+                let _ = self.get_or_create_pool::<T, _>(type_id, |_| TagPool::<T>::new());
+            }
+            StorageType::Sparse => {
+                let _ = self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in);
+            }
+            StorageType::Table => {}
+            StorageType::Slab => {
+                let _ = self.get_or_create_pool::<T, _>(type_id, SlabPool::<T>::new_in);
+            }
+            StorageType::Small => {
+                let _ = self.get_or_create_pool::<T, _>(type_id, SmallPool::<T>::new_in);
+            }
+            StorageType::Interned => {}
+        }
+        handle
+    }
+
+    /// Returns the current change tick of the scene.
+    /// Ticks start at 1, so a tick of 0 can be used to mean "before anything happened".
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// assert_eq!(scene.change_tick(), 1);
+    ///
+    /// scene.increment_change_tick();
+    /// assert_eq!(scene.change_tick(), 2);
+    /// ```
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick
+    }
+
+    /// Advances the change tick, usually once per frame.
+    /// Changes made before this call are no longer reported by `is_changed`.
+    pub fn increment_change_tick(&mut self) -> u32 {
+        self.change_tick = self.change_tick.wrapping_add(1);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(tick = self.change_tick, "change tick incremented");
+        #[cfg(feature = "metrics")]
+        self.metrics.tick_advanced();
+        self.change_tick
+    }
+
+    /// Inserts a resource into the scene, returning the previous value of the same type if there was one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Gravity(f32);
+    ///
+    /// let mut scene = Scene::new();
+    /// assert!(scene.insert_resource(Gravity(9.81)).is_none());
+    ///
+    /// let previous = scene.insert_resource(Gravity(1.62)).unwrap();
+    /// assert_eq!(previous.0, 9.81);
+    /// assert_eq!(scene.resource::<Gravity>().unwrap().0, 1.62);
+    /// ```
+    pub fn insert_resource<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.resources.insert(value, self.change_tick)
+    }
+
+    /// Removes a resource from the scene and returns it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.insert_resource(42u64);
+    ///
+    /// assert_eq!(scene.remove_resource::<u64>(), Some(42));
+    /// assert!(scene.resource::<u64>().is_none());
+    /// ```
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.resources.remove::<T>()
+    }
+
+    /// Returns true if a resource of the given type exists.
+    pub fn contains_resource<T: 'static>(&self) -> bool {
+        self.resources.contains::<T>()
+    }
+
+    /// Gets a resource from the scene.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.insert_resource(String::from("settings"));
+    ///
+    /// let settings = scene.resource::<String>().unwrap();
+    /// assert_eq!(*settings, "settings");
+    /// assert!(settings.is_added());
+    /// ```
+    pub fn resource<T: 'static>(&self) -> Option<Res<'_, T>> {
+        self.resources.get::<T>(self.change_tick)
+    }
+
+    /// Gets a mutable resource from the scene.
+    /// The resource is marked as changed as soon as it is mutably dereferenced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.insert_resource(1.0f32);
+    /// scene.increment_change_tick();
+    ///
+    /// *scene.resource_mut::<f32>().unwrap() = 2.0;
+    /// assert!(scene.resource::<f32>().unwrap().is_changed());
+    /// ```
+    pub fn resource_mut<T: 'static>(&mut self) -> Option<ResMut<'_, T>> {
+        self.resources.get_mut::<T>(self.change_tick)
+    }
+
+    /// Inserts the only component of type `T` in the scene, returning the previous one if there was one.
+    /// Unlike resources, singletons are components of an entity created for them, so views, change detection,
+    /// snapshots and replication see them like any other component. Assigning `T` to another entity fails from
+    /// now on, and so does inserting a singleton of a type other entities already have.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct ActiveCamera { zoom: f32 }
+    ///
+    /// let mut scene = Scene::new();
+    /// assert!(scene.insert_singleton(ActiveCamera { zoom: 1.0 }).unwrap().is_none());
+    /// scene.get_singleton_mut::<ActiveCamera>().unwrap().zoom = 2.0;
+    /// assert_eq!(scene.get_singleton::<ActiveCamera>().unwrap().zoom, 2.0);
+    ///
+    /// let camera = scene.singleton_entity().unwrap();
+    /// assert_eq!(scene.view::<(ActiveCamera, ())>().collect::<Vec<_>>(), vec![camera]);
+    ///
+    /// let other = scene.create_entity();
+    /// assert!(scene.assign(other, ActiveCamera { zoom: 3.0 }).is_err());
+    /// ```
+    #[track_caller]
+    pub fn insert_singleton<T: 'static>(&mut self, value: T) -> Result<Option<T>, RecsError> {
+        let existing = self.singleton_entity.filter(|&entity| self.is_alive(entity));
+        let type_id = get_type_id::<T>();
+        if !self.singleton_types.contains(&type_id) && self.view::<(T, ())>().any(|owner| Some(owner) != existing) {
+            let message = format!("entities already have components of type {}", type_name::<T>());
+            return Err(RecsError::Other(message.into()));
+        }
+        let entity = match existing {
+            Some(entity) => entity,
+            None => {
+                let entity = self.create_entity();
+                self.singleton_entity = Some(entity);
+                entity
+            }
+        };
+        self.singleton_types.insert(type_id);
+        if let Some(previous) = self.get_mut::<T>(entity)? {
+            return Ok(Some(std::mem::replace(previous, value)));
+        }
+        self.assign(entity, value)?;
+        Ok(None)
+    }
+
+    /// Returns the singleton component of type `T`. See [`Scene::insert_singleton`] for an example.
+    pub fn get_singleton<T: 'static>(&self) -> Option<&T> {
+        self.get::<T>(self.singleton_entity?).ok().flatten()
+    }
+
+    /// Returns the singleton component of type `T` mutably. See [`Scene::insert_singleton`] for an example.
+    pub fn get_singleton_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.get_mut::<T>(self.singleton_entity?).ok().flatten()
+    }
+
+    /// Removes the singleton component of type `T`, which other entities may have again afterwards.
+    /// Returns false if there was none.
+    #[track_caller]
+    pub fn remove_singleton<T: 'static>(&mut self) -> bool {
+        let owner = self.singleton_entity.filter(|&entity| matches!(self.get::<T>(entity), Ok(Some(_))));
+        if let Some(entity) = owner {
+            if self.remove::<T>(entity).is_err() {
+                return false;
+            }
+        }
+        self.singleton_types.remove(&get_type_id::<T>());
+        owner.is_some()
+    }
+
+    /// Returns the entity holding the singleton components, if a singleton was inserted.
+    pub fn singleton_entity(&self) -> Option<Entity> {
+        self.singleton_entity.filter(|&entity| self.is_alive(entity))
+    }
+
+    /// Returns true if the resource exists and was inserted or changed during the current tick.
+    /// Useful as a run criterion, e.g. to rebuild derived data only when settings were modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Settings { volume: f32 }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.insert_resource(Settings { volume: 0.5 });
+    /// assert!(scene.resource_changed::<Settings>());
+    ///
+    /// scene.increment_change_tick();
+    /// assert!(!scene.resource_changed::<Settings>());
+    ///
+    /// // Only reading does not count as a change
+    /// assert_eq!(scene.resource_mut::<Settings>().unwrap().volume, 0.5);
+    /// assert!(!scene.resource_changed::<Settings>());
+    ///
+    /// scene.resource_mut::<Settings>().unwrap().volume = 1.0;
+    /// assert!(scene.resource_changed::<Settings>());
+    /// ```
+    pub fn resource_changed<T: 'static>(&self) -> bool {
+        self.resource::<T>().is_some_and(|res| res.is_changed())
+    }
+
+    /// Temporarily overrides a resource for the duration of the closure.
+    /// Afterwards the previous value is restored, or the resource is removed if there was none.
+    /// This also happens if the closure panics, so a caught panic doesn't leave the override in place.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct TimeScale(f32);
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.insert_resource(TimeScale(1.0));
+    ///
+    /// let scale_inside = scene.with_resource(TimeScale(0.5), |scene| {
+    ///     scene.resource::<TimeScale>().unwrap().0
+    /// });
+    /// assert_eq!(scale_inside, 0.5);
+    /// assert_eq!(scene.resource::<TimeScale>().unwrap().0, 1.0);
+    ///
+    /// // Overrides also work for resources that didn't exist before
+    /// scene.with_resource(42u8, |scene| assert!(scene.contains_resource::<u8>()));
+    /// assert!(!scene.contains_resource::<u8>());
+    ///
+    /// // The previous value is restored even if the closure panics
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     scene.with_resource(TimeScale(0.0), |_| panic!("paused"));
+    /// }));
+    /// assert!(result.is_err());
+    /// assert_eq!(scene.resource::<TimeScale>().unwrap().0, 1.0);
+    /// ```
+    pub fn with_resource<T: 'static, R>(
+        &mut self,
+        value: T,
+        f: impl FnOnce(&mut Scene) -> R,
+    ) -> R {
+        let type_id = get_type_id::<T>();
+        let previous = self.resources.take_entry(type_id);
+        let guard = RestoreResource { scene: self, type_id, previous };
+        guard.scene.insert_resource(value);
+        f(guard.scene)
+    }
+
+    /// Returns the extensions attached to this scene.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns the extensions attached to this scene mutably.
+    /// Extensions are meant for engine integrations and never interfere with user resources.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct RendererHandle(u32);
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.extensions_mut().insert(RendererHandle(7));
+    ///
+    /// assert_eq!(scene.extensions().get::<RendererHandle>().unwrap().0, 7);
+    /// assert!(!scene.contains_resource::<RendererHandle>());
+    /// ```
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// Starts recording structural changes into an unbounded audit log.
+    /// Does nothing if recording is already enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::audit::StructuralChange;
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.enable_audit_log();
+    ///
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 42u32).unwrap();
+    /// scene.remove::<u32>(entity).unwrap();
+    /// scene.destroy_entity(entity);
+    ///
+    /// let changes = scene
+    ///     .audit_log()
+    ///     .unwrap()
+    ///     .records_for(entity)
+    ///     .map(|record| (record.change, record.component))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(changes, vec![
+    ///     (StructuralChange::CreateEntity, None),
+    ///     (StructuralChange::Assign, Some("u32")),
+    ///     (StructuralChange::Remove, Some("u32")),
+    ///     (StructuralChange::DestroyEntity, None),
+    /// ]);
+    /// ```
+    pub fn enable_audit_log(&mut self) {
+        if self.audit_log.is_none() {
+            self.audit_log = Some(AuditLog::new());
+        }
+    }
+
+    /// Starts recording structural changes into the given audit log, e.g. one created with `AuditLog::with_capacity`.
+    pub fn set_audit_log(&mut self, audit_log: AuditLog) {
+        self.audit_log = Some(audit_log);
+    }
+
+    /// Stops recording structural changes and returns the recorded log.
+    pub fn disable_audit_log(&mut self) -> Option<AuditLog> {
+        self.audit_log.take()
+    }
+
+    /// Returns the audit log, if recording is enabled.
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_ref()
+    }
+
+    /// Returns the audit log mutably, if recording is enabled.
+    pub fn audit_log_mut(&mut self) -> Option<&mut AuditLog> {
+        self.audit_log.as_mut()
+    }
+
+    /// Registers a callback that is invoked the first time a pool for a component type is created,
+    /// e.g. to lazily set up GPU buffers or serialization entries for that type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use yarecs::scene::Scene;
+    ///
+    /// let created = Rc::new(RefCell::new(Vec::new()));
+    /// let created_in_hook = created.clone();
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.on_pool_created(move |_type_id, name| created_in_hook.borrow_mut().push(name));
+    ///
+    /// let first = scene.create_entity();
+    /// let second = scene.create_entity();
+    /// scene.assign(first, 1.0f64).unwrap();
+    /// scene.assign(second, 2.0f64).unwrap();
+    ///
+    /// assert_eq!(*created.borrow(), vec!["f64"]);
+    /// ```
+    pub fn on_pool_created(&mut self, hook: impl FnMut(usize, &'static str) + 'static) {
+        self.hooks.add_pool_created(Box::new(hook));
+    }
+
+    /// Registers a callback that is invoked whenever the set of component types of an entity changes,
+    /// with the type IDs it gained and lost. Overwriting an existing component doesn't change the set
+    /// and invokes nothing. Destroying an entity reports all of its components as removed at once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use yarecs::{component::get_type_id, scene::Scene};
+    ///
+    /// let changes = Rc::new(RefCell::new(Vec::new()));
+    /// let changes_in_hook = changes.clone();
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.on_composition_changed(move |change| {
+    ///     changes_in_hook.borrow_mut().push((change.entity, change.added.to_vec(), change.removed.to_vec()));
+    /// });
+    ///
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, 1u32).unwrap();
+    /// scene.assign(entity, 2u32).unwrap();
+    /// scene.assign(entity, 1.0f32).unwrap();
+    /// scene.remove::<u32>(entity).unwrap();
+    /// scene.destroy_entity(entity);
+    ///
+    /// let (u32_id, f32_id) = (get_type_id::<u32>(), get_type_id::<f32>());
+    /// assert_eq!(*changes.borrow(), vec![
+    ///     (entity, vec![u32_id], vec![]),
+    ///     (entity, vec![f32_id], vec![]),
+    ///     (entity, vec![], vec![u32_id]),
+    ///     (entity, vec![], vec![f32_id]),
+    /// ]);
+    /// ```
+    pub fn on_composition_changed(&mut self, hook: impl FnMut(&CompositionChange) + 'static) {
+        self.hooks.add_composition_changed(Box::new(hook));
+    }
+
+    /// Returns a snapshot of the scene's counters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let first = scene.create_entity();
+    /// let second = scene.create_entity();
+    /// scene.assign(first, 1u8).unwrap();
+    /// scene.assign(second, 2u8).unwrap();
+    /// scene.destroy_entity(second);
+    ///
+    /// let metrics = scene.metrics();
+    /// assert_eq!(metrics.entities_alive, 1);
+    /// assert_eq!(metrics.spawns_this_tick, 2);
+    /// assert_eq!(metrics.components_added, 2);
+    /// assert_eq!(metrics.components_removed, 1);
+    ///
+    /// scene.increment_change_tick();
+    /// assert_eq!(scene.metrics().spawns_this_tick, 0);
+    ///
+    /// // Lookups are counted per component type
+    /// scene.get::<u8>(first).unwrap();
+    /// scene.get::<u8>(first).unwrap();
+    /// scene.get::<u16>(first).unwrap();
+    /// let (name, access) = scene.metrics().pool_accesses[0];
+    /// assert_eq!(name, "u8");
+    /// assert_eq!((access.gets, access.misses, access.sets), (2, 0, 2));
+    /// assert_eq!(scene.metrics().pool_accesses[1].1.misses, 1);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        let mut pool_sizes = self
+            .pools
+            .iter()
+            .enumerate()
+            .filter_map(|(type_id, pool)| Some((type_id, pool.as_ref()?.len())))
+            .chain(self.archetypes.component_counts())
+            .map(|(type_id, len)| (get_type_name(type_id).unwrap_or("<unknown>"), len))
+            .collect::<Vec<_>>();
+        pool_sizes.sort();
+        let mut pool_accesses = self
+            .metrics
+            .pool_accesses
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, access)| **access != PoolAccess::default())
+            .map(|(type_id, &access)| (get_type_name(type_id).unwrap_or("<unknown>"), access))
+            .collect::<Vec<_>>();
+        pool_accesses.sort_by_key(|(name, access)| (std::cmp::Reverse(access.gets), *name));
+        Metrics {
+            tick: self.change_tick,
+            entities_alive: self.alive.len(),
+            entities_created: self.metrics.entities_created,
+            entities_destroyed: self.metrics.entities_destroyed,
+            spawns_this_tick: self.metrics.spawns_this_tick,
+            despawns_this_tick: self.metrics.despawns_this_tick,
+            components_added: self.metrics.components_added,
+            components_removed: self.metrics.components_removed,
+            pool_sizes,
+            pool_accesses,
+        }
+    }
+
+    /// Returns an iterator over all entities that have all the required components.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// 
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign_default::<i32>(entity).unwrap();
+    /// scene.assign_default::<f32>(entity).unwrap();
+    /// 
+    /// let all_entities = scene.view::<(i32, (f32,()))>().collect::<Vec<_>>();
+    /// assert_eq!(all_entities.len(), 1);
+    /// 
+    /// let entities_with_i32 = scene.view::<(i32,())>().collect::<Vec<_>>();
+    /// assert_eq!(entities_with_i32.len(), 1);
+    /// 
+    /// let entities_with_String = scene.view::<(String,())>().collect::<Vec<_>>();
+    /// assert!(entities_with_String.is_empty());
+    /// 
+    /// let entities_with_i32_and_f32 = scene.view::<(i32, (f32,()))>().collect::<Vec<_>>();
+    /// assert_eq!(entities_with_i32_and_f32.len(), 1);
+    /// 
+    /// let entities_with_String_and_f32 = scene.view::<(String, (f32,()))>().collect::<Vec<_>>();
+    /// assert!(entities_with_String_and_f32.is_empty());
+    /// ```
+    pub fn view<'a, T: RequireComponents>(&'a self) -> SceneView<'a> {
+        SceneView::new(self, T::required_component_ids())
+    }
+
+    /// Splits the scene into mutable accesses to two disjoint sets of component types,
+    /// so two subsystems can modify different components at the same time.
+    /// Fails if the sets overlap or contain components with table storage, which share their archetype tables.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Position(f32);
+    /// struct Velocity(f32);
+    /// struct Health(u32);
+    ///
+    /// let mut scene = Scene::new();
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, Position(0.0)).unwrap();
+    /// scene.assign(entity, Velocity(2.0)).unwrap();
+    /// scene.assign(entity, Health(10)).unwrap();
+    ///
+    /// let (mut physics, mut combat) = scene.split::<(Position, (Velocity, ())), (Health, ())>().unwrap();
+    /// let velocity = physics.get::<Velocity>(entity).unwrap().unwrap().0;
+    /// physics.get_mut::<Position>(entity).unwrap().unwrap().0 += velocity;
+    /// combat.get_mut::<Health>(entity).unwrap().unwrap().0 -= 1;
+    /// assert!(combat.get::<Position>(entity).is_err());
+    ///
+    /// assert_eq!(scene.get::<Position>(entity).unwrap().unwrap().0, 2.0);
+    /// assert!(scene.split::<(Position, ()), (Position, ())>().is_err());
+    /// ```
+    pub fn split<A: RequireComponents, B: RequireComponents>(
+        &mut self,
+    ) -> Result<(ComponentAccess<'_>, ComponentAccess<'_>), RecsError> {
+        let first_ids = A::required_component_ids();
+        let second_ids = B::required_component_ids();
+        if first_ids.iter().any(|id| second_ids.contains(id)) {
+            return Err(RecsError::Other("split component sets overlap".into()));
+        }
+        if first_ids.iter().chain(&second_ids).any(|&id| self.storage_type_of(id) == StorageType::Table) {
+            return Err(RecsError::Other("components with table storage can't be split".into()));
+        }
+        for &type_id in first_ids.iter().chain(&second_ids) {
+            self.changed_in_bulk(type_id);
+        }
+
+        let mut first = ComponentAccess::new(&self.entities, first_ids);
+        let mut second = ComponentAccess::new(&self.entities, second_ids);
+        for (type_id, pool) in self.pools.iter_mut().enumerate() {
+            let Some(pool) = pool else {
+                continue;
+            };
+            if first.covers(type_id) {
+                first.add_pool(type_id, pool.as_mut());
+            } else if second.covers(type_id) {
+                second.add_pool(type_id, pool.as_mut());
+            }
+        }
+        Ok((first, second))
+    }
+
+    /// Returns a counter that changes whenever entities are created or destroyed or components are added or removed.
+    /// Comparing it with an earlier value tells whether the results of views may have changed,
+    /// see [`CachedQuery`](crate::query::CachedQuery).
+    pub fn structure_generation(&self) -> u64 {
+        self.structure_generation
+    }
+
+    /// Returns the storage type used for component types without an explicit storage type.
+    pub fn storage_type(&self) -> StorageType {
+        self.storage_type
+    }
+
+    /// Returns the storage type used for components of type `T`.
+    pub fn component_storage_type<T: 'static>(&self) -> StorageType {
+        self.storage_type_of(get_type_id::<T>())
+    }
+
+    /// Sets up component type `T` as described by its [`Component`] implementation,
+    /// moving existing components of the type to its preferred storage.
+    /// The type is also registered in the scene's own [`TypeRegistry`], which keeps the type-erased functions
+    /// enabled by [`Component::register`], so the scene can inspect ([`Scene::inspect`]), snapshot
+    /// ([`Scene::snapshot`]) and clone ([`Scene::try_clone`]) components of the type without knowing it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::archetype::StorageType;
+    /// use yarecs::component::Component;
+    /// use yarecs::registry::TypeRegistration;
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// struct Position(f32, f32);
+    ///
+    /// impl Component for Position {
+    ///     const NAME: &'static str = "Position";
+    ///     const STORAGE: Option<StorageType> = Some(StorageType::Table);
+    ///
+    ///     fn register(registration: TypeRegistration<'_, Self>) -> TypeRegistration<'_, Self> {
+    ///         registration.with_clone().with_debug()
+    ///     }
+    /// }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.register_component::<Position>().unwrap();
+    /// assert_eq!(scene.component_storage_type::<Position>(), StorageType::Table);
+    ///
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, Position(1.0, 2.0)).unwrap();
+    /// let components = scene
+    ///     .inspect(entity)
+    ///     .map(|(info, value)| format!("{}: {:?}", info.name(), info.debug(value).unwrap()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(components, ["Position: Position(1.0, 2.0)"]);
+    ///
+    /// let copy = scene.try_clone().unwrap();
+    /// assert_eq!(copy.get::<Position>(entity).unwrap().unwrap().1, 2.0);
+    /// ```
+    pub fn register_component<T: Component>(&mut self) -> Result<(), RecsError> {
+        if let Some(storage_type) = T::STORAGE {
+            self.set_component_storage_type::<T>(storage_type)?;
+        }
+        T::register(self.types.register::<T>(T::NAME));
+        if let Some(snapshot) = self.types.get_of::<T>().and_then(|info| info.snapshot) {
+            self.snapshot_types.insert(get_type_id::<T>(), snapshot);
+        }
+        Ok(())
+    }
+
+    /// Returns the component types registered with [`Scene::register_component`].
+    pub fn types(&self) -> &TypeRegistry {
+        &self.types
+    }
+
+    /// Returns the components of an entity whose types are registered with [`Scene::register_component`],
+    /// ordered by the names of their types. See [`Scene::register_component`] for an example.
+    pub fn inspect(&self, entity: Entity) -> impl Iterator<Item = (&TypeInfo, &dyn Any)> + '_ {
+        self.types.components_of(self, entity)
+    }
+
+    /// Chooses the storage type for components of type `T`, overriding the scene's default.
+    /// Use table storage for types that are iterated a lot and sparse storage for types that are added and removed frequently.
+    /// Components of this type that are already stored are moved to the new storage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::archetype::StorageType;
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Position(f32, f32);
+    /// struct Selected;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.set_component_storage_type::<Position>(StorageType::Table).unwrap();
+    ///
+    /// let entity = scene.create_entity();
+    /// scene.assign(entity, Position(1.0, 2.0)).unwrap();
+    /// scene.assign(entity, Selected).unwrap();
+    ///
+    /// assert_eq!(scene.component_storage_type::<Position>(), StorageType::Table);
+    /// assert_eq!(scene.component_storage_type::<Selected>(), StorageType::Sparse);
+    /// assert_eq!(scene.view::<(Position, (Selected, ()))>().count(), 1);
+    ///
+    /// // Existing components move along when the storage type changes
+    /// scene.set_component_storage_type::<Position>(StorageType::Sparse).unwrap();
+    /// assert_eq!(scene.get::<Position>(entity).unwrap().unwrap().0, 1.0);
+    /// ```
+    pub fn set_component_storage_type<T: 'static>(
+        &mut self,
+        storage_type: StorageType,
+    ) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        let previous_storage_type = self.storage_type_of(type_id);
+        if previous_storage_type == storage_type {
+            return Ok(());
+        }
+        if storage_type == StorageType::Interned {
+            return Err(RecsError::Other("interned storage is selected with Scene::set_interned".into()));
+        }
+        self.component_storage_types.insert(type_id, storage_type);
+
+        let components = self.take_components::<T>(type_id, previous_storage_type);
+        // Sparse pools and the other pools of a type share one pool slot, so the drained one has to make room
+        if previous_storage_type != StorageType::Sparse || storage_type != StorageType::Sparse {
+            if let Some(pool) = self.pools.get_mut(type_id) {
+                *pool = None;
+            }
+        }
+        for (entity, component) in components {
+            self.insert_component(type_id, entity, || component)?;
+        }
+        Ok(())
+    }
+
+    /// Stores components of type `T` once per distinct value, shared by all entities with an equal component.
+    /// Components of this type that are already stored are moved to the interned storage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::archetype::StorageType;
+    /// use yarecs::scene::Scene;
+    ///
+    /// #[derive(Clone, PartialEq, Eq, Hash)]
+    /// struct Material {
+    ///     texture: String,
+    ///     roughness: u8,
+    /// }
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.set_interned::<Material>().unwrap();
+    /// assert_eq!(scene.component_storage_type::<Material>(), StorageType::Interned);
+    ///
+    /// let stone = Material { texture: String::from("stone.png"), roughness: 200 };
+    /// let entities = (0..100).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &entity in &entities {
+    ///     scene.assign_interned(entity, stone.clone()).unwrap();
+    /// }
+    ///
+    /// // Every entity still gets its own reference, but they all point to the same value
+    /// let first = scene.get::<Material>(entities[0]).unwrap().unwrap();
+    /// let last = scene.get::<Material>(entities[99]).unwrap().unwrap();
+    /// assert!(std::ptr::eq(first, last));
+    /// assert_eq!(scene.view::<(Material, ())>().count(), 100);
+    /// assert!(scene.get_mut::<Material>(entities[0]).is_err());
+    /// ```
+    pub fn set_interned<T: Eq + Hash + Clone + 'static>(&mut self) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        let previous_storage_type = self.storage_type_of(type_id);
+        if previous_storage_type == StorageType::Interned {
+            return Ok(());
+        }
+        let components = self.take_components::<T>(type_id, previous_storage_type);
+        if let Some(pool) = self.pools.get_mut(type_id) {
+            *pool = None;
+        }
+        self.component_storage_types.insert(type_id, StorageType::Interned);
+        let pool = self.get_or_create_pool::<T, _>(type_id, InternedPool::new_in)?;
+        for (entity, component) in components {
+            pool.assign(entity, component);
+        }
+        Ok(())
+    }
+
+    /// Assigns an interned component to an entity, replacing the component it already has.
+    /// Switches components of type `T` to interned storage first if they don't use it yet.
+    #[track_caller]
+    pub fn assign_interned<T: Eq + Hash + Clone + 'static>(
+        &mut self,
+        entity: Entity,
+        new_component: T,
+    ) -> Result<&T, RecsError> {
+        self.set_interned::<T>()?;
+        let type_id = get_type_id::<T>();
+        self.add_to_entity_description_unchecked::<T>(type_id, entity)?;
+        Ok(self.get_or_create_pool::<T, _>(type_id, InternedPool::new_in)?.assign(entity, new_component))
+    }
+
+    /// Removes all components of type `T` from their storage and returns them with their entities.
+    fn take_components<T: 'static>(&mut self, type_id: usize, storage_type: StorageType) -> Vec<(Entity, T)> {
+        match storage_type {
+            StorageType::Sparse if is_tag::<T>() => self
+                .get_pool_if_exists_mut::<TagPool<T>>(type_id)
+                .map(|pool| pool.drain().collect::<Vec<_>>())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(index, component)| (self.entities[index as usize].entity, component))
+                .collect(),
+            StorageType::Sparse => self
+                .get_pool_if_exists_mut::<Pool<T>>(type_id)
+                .map(|pool| pool.drain().collect::<Vec<_>>())
+                .unwrap_or_default(),
+            StorageType::Table => self
+                .archetypes
+                .entities_with(type_id)
+                .into_iter()
+                .filter_map(|entity| self.archetypes.take::<T>(entity).map(|component| (entity, component)))
+                .collect(),
+            StorageType::Slab => self
+                .get_pool_if_exists_mut::<SlabPool<T>>(type_id)
+                .map(|pool| pool.drain().collect::<Vec<_>>())
+                .unwrap_or_default(),
+            StorageType::Small => self
+                .get_pool_if_exists_mut::<SmallPool<T>>(type_id)
+                .map(|pool| pool.drain().collect::<Vec<_>>())
+                .unwrap_or_default(),
+            StorageType::Interned => self
+                .get_pool_if_exists_mut::<InternedPool<T>>(type_id)
+                .map(|pool| pool.drain().collect::<Vec<_>>())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns all non-empty archetype tables containing all the required components.
+    /// Iterating over the columns of these tables is the fastest way to process components with table storage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::archetype::StorageType;
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::with_storage_type(StorageType::Table);
+    /// for i in 0..3 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, i as f32).unwrap();
+    ///     if i > 0 {
+    ///         scene.assign(entity, i as u8).unwrap();
+    ///     }
+    /// }
+    ///
+    /// let mut sum = 0.0;
+    /// for table in scene.tables::<(f32, (u8, ()))>() {
+    ///     sum += table.column::<f32>().unwrap().iter().sum::<f32>();
+    /// }
+    /// assert_eq!(sum, 3.0);
+    /// ```
+    pub fn tables<T: RequireComponents>(&self) -> impl Iterator<Item = &Table> {
+        let tables = self.archetypes.tables();
+        self.archetypes
+            .matching_tables(&T::required_component_ids())
+            .into_iter()
+            .map(move |table| &tables[table])
+    }
+
+    /// Returns the pool holding the components of type `T`, if components of this type have sparse storage and one was ever assigned.
+    /// Zero-sized components are stored as tags and have no such pool.
+    pub fn pool<T: 'static>(&self) -> Option<&Pool<T>> {
+        if self.component_storage_type::<T>() != StorageType::Sparse {
+            return None;
+        }
+        self.get_pool_if_exists::<Pool<T>>(get_type_id::<T>())
+    }
+
+    /// Returns all components of type `T` as one contiguous slice, for kernels over raw component arrays.
+    /// Returns `None` if the type has no pool, e.g. because it has table storage; use [`Scene::tables`] for those.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// for i in 0..3 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, i as f32).unwrap();
+    /// }
+    /// let (_, values) = scene.column_mut::<f32>().unwrap();
+    /// values.iter_mut().for_each(|value| *value += 1.0);
+    ///
+    /// assert_eq!(scene.column::<f32>().unwrap().iter().sum::<f32>(), 6.0);
+    /// ```
+    pub fn column<T: 'static>(&self) -> Option<&[T]> {
+        self.pool::<T>().map(Pool::as_slice)
+    }
+
+    /// Returns the entities and mutable components of type `T` as parallel slices.
+    pub fn column_mut<T: 'static>(&mut self) -> Option<(&[Entity], &mut [T])> {
+        if self.component_storage_type::<T>() != StorageType::Sparse {
+            return None;
+        }
+        self.changed_in_bulk(get_type_id::<T>());
+        self.get_pool_if_exists_mut::<Pool<T>>(get_type_id::<T>()).map(Pool::as_mut_slices)
+    }
+
+    /// Returns the raw bytes of all components of a plain-old-data type together with the entities owning them,
+    /// in the same order, so renderers can copy instance data straight into GPU buffers each frame.
+    /// Returns `None` if the type has no pool; use [`Table::column_bytes`](crate::archetype::Table::column_bytes)
+    /// for components with table storage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// for i in 0..4 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, [i as f32, 0.0, 0.0, 1.0]).unwrap();
+    /// }
+    ///
+    /// let (entities, bytes) = scene.column_bytes::<[f32; 4]>().unwrap();
+    /// assert_eq!(entities.len(), 4);
+    /// assert_eq!(bytes.len(), 4 * 16);
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    pub fn column_bytes<T: bytemuck::Pod>(&self) -> Option<(&[Entity], &[u8])> {
+        self.pool::<T>().map(|pool| (pool.entity_slice(), pool.component_bytes()))
+    }
+
+    /// Assigns zeroed components of a plain-old-data type to all given entities that don't have one yet.
+    /// With sparse storage, the components are initialized in one batch.
+    #[cfg(feature = "bytemuck")]
+    #[track_caller]
+    pub fn assign_zeroed<T: bytemuck::Pod>(&mut self, entities: &[Entity]) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        for &entity in entities {
+            self.add_to_entity_description::<T>(type_id, entity)?;
+        }
+        if self.storage_type_of(type_id) == StorageType::Sparse && !is_tag::<T>() {
+            self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)?.assign_zeroed(entities);
+        } else {
+            for &entity in entities {
+                self.insert_component(type_id, entity, T::zeroed)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the pool of a plain-old-data component type with plain memory copies,
+    /// e.g. to save the state of a frame for rollback. See [`Scene::column`] for which types have a pool.
+    #[cfg(feature = "bytemuck")]
+    pub fn snapshot_pod<T: bytemuck::Pod>(&self) -> Option<Pool<T>> {
+        self.pool::<T>().map(Pool::clone_pod)
+    }
+
+    /// Replaces the pool of a plain-old-data component type, e.g. with a snapshot taken earlier.
+    /// Every entity in the pool has to be alive, and the component type has to have a pool.
+    /// Entities not in the pool lose their component of this type.
+    /// No audit records are written for the replaced components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let first = scene.create_entity();
+    /// let second = scene.create_entity();
+    /// scene.assign(first, 1u64).unwrap();
+    ///
+    /// let snapshot = scene.snapshot_pod::<u64>().unwrap();
+    /// *scene.get_mut::<u64>(first).unwrap().unwrap() = 2;
+    /// scene.assign(second, 3u64).unwrap();
+    /// scene.restore_pod(snapshot).unwrap();
+    ///
+    /// assert_eq!(*scene.get::<u64>(first).unwrap().unwrap(), 1);
+    /// assert!(scene.get::<u64>(second).unwrap().is_none());
+    /// assert_eq!(scene.view::<(u64, ())>().count(), 1);
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    pub fn restore_pod<T: bytemuck::Pod>(&mut self, pool: Pool<T>) -> Result<(), RecsError> {
+        if self.component_storage_type::<T>() != StorageType::Sparse || is_tag::<T>() {
+            return Err(RecsError::Other("component type has no pool to restore".into()));
+        }
+        for entity in pool.entities() {
+            self.assert_entity_valid(entity)?;
+        }
+
+        self.structure_generation += 1;
+        let type_id = get_type_id::<T>();
+        self.changed_in_bulk(type_id);
+        for entity_description in &mut self.entities {
+            let had = entity_description.components.remove(&type_id);
+            let entity = entity_description.entity;
+            if entity.is_valid() && pool.contains(entity) {
+                entity_description.components.insert(type_id);
+                if !had {
+                    self.hooks.composition_changed(entity, &[type_id], &[]);
+                }
+            } else if had {
+                self.hooks.composition_changed(entity, &[], &[type_id]);
+            }
+        }
+        *self.get_or_create_pool::<T, _>(type_id, Pool::<T>::new_in)? = pool;
+        Ok(())
+    }
+
+    /// Saves all living entities and their registered components to a human-readable RON file,
+    /// listing components by the names they are registered under. Entities are written in order of their indices
+    /// and components in order of their names, so saving an unchanged scene produces the same file again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Door {
+    ///     locked: bool,
+    /// }
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Door>("Door");
+    ///
+    /// let mut scene = Scene::new();
+    /// let door = scene.create_entity();
+    /// scene.assign(door, Door { locked: true }).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("yarecs_save_ron_example.ron");
+    /// scene.save_ron(&path, &registry).unwrap();
+    /// let file = std::fs::read_to_string(&path).unwrap();
+    /// assert!(file.contains(r#""Door": ("#) && file.contains("locked: true"));
+    ///
+    /// let loaded = Scene::load_ron(&path, &registry).unwrap();
+    /// assert!(loaded.get::<Door>(door).unwrap().unwrap().locked);
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    #[cfg(feature = "ron")]
+    pub fn save_ron(&self, path: impl AsRef<Path>, registry: &ComponentRegistry) -> Result<(), RecsError> {
+        let serializer = SceneSerializer::new(self, registry);
+        let ron = ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+            .map_err(|e| RecsError::Other(Box::new(e)))?;
+        std::fs::write(path, ron).map_err(|e| RecsError::Other(Box::new(e)))
+    }
+
+    /// Loads a scene saved with [`Scene::save_ron`] into a new scene.
+    /// Entities keep their saved indices and versions. Components of types missing from the registry are an error.
+    #[cfg(feature = "ron")]
+    pub fn load_ron(path: impl AsRef<Path>, registry: &ComponentRegistry) -> Result<Scene, RecsError> {
+        let ron = std::fs::read_to_string(path).map_err(|e| RecsError::Other(Box::new(e)))?;
+        ron::Options::default()
+            .from_str_seed(&ron, SceneDeserializer::new(registry))
+            .map_err(|e| RecsError::Other(Box::new(e)))
+    }
+
+    /// Exports all living entities and their registered components as JSON, so tools without a Rust dependency
+    /// like web editors and CI validators can read scenes. The layout is the same as for [`Scene::save_ron`]:
+    /// the schema `version` of the registry, and an `entities` array of objects with the entity `id`
+    /// and a `components` object keyed by registered name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Health(u32);
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Health>("Health");
+    ///
+    /// // Scenes can be produced by external tools, too
+    /// let json = r#"{ "entities": [{ "id": 3, "components": { "Health": 80 } }] }"#;
+    /// let scene = Scene::from_json(json, &registry).unwrap();
+    /// let entity = scene.entities()[0];
+    /// assert_eq!(entity.index(), 3);
+    /// assert_eq!(scene.get::<Health>(entity).unwrap().unwrap().0, 80);
+    ///
+    /// let exported = scene.to_json(&registry).unwrap();
+    /// assert_eq!(Scene::from_json(&exported, &registry).unwrap().to_json(&registry).unwrap(), exported);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self, registry: &ComponentRegistry) -> Result<String, RecsError> {
+        serde_json::to_string_pretty(&SceneSerializer::new(self, registry)).map_err(|e| RecsError::Other(Box::new(e)))
+    }
+
+    /// Imports a scene exported with [`Scene::to_json`] into a new scene.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str, registry: &ComponentRegistry) -> Result<Scene, RecsError> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let scene = SceneDeserializer::new(registry)
+            .deserialize(&mut deserializer)
+            .and_then(|scene| deserializer.end().map(|_| scene))
+            .map_err(|e| RecsError::Other(Box::new(e)))?;
+        Ok(scene)
+    }
+
+    /// Serializes only the entities that changed after the given change tick, in the same layout as
+    /// [`SceneSerializer`], plus a `destroyed` list of entities destroyed since. Large persistent worlds can
+    /// autosave and replicate these deltas instead of the whole scene. See [`Scene::entities_changed_since`]
+    /// for which changes are tracked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Gold(u32);
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Gold>("Gold");
+    ///
+    /// let mut scene = Scene::new();
+    /// let chests = (0..1000).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &chest in &chests {
+    ///     scene.assign(chest, Gold(10)).unwrap();
+    /// }
+    ///
+    /// let last_autosave = scene.change_tick();
+    /// scene.increment_change_tick();
+    /// scene.get_mut::<Gold>(chests[1]).unwrap().unwrap().0 = 0;
+    /// scene.destroy_entity(chests[2]);
+    ///
+    /// let delta = serde_json::to_string(&scene.serialize_changes_since(last_autosave, &registry)).unwrap();
+    /// assert_eq!(delta, r#"{"version":0,"entities":[{"id":1,"components":{"Gold":0}}],"destroyed":[2]}"#);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn serialize_changes_since<'a>(&'a self, tick: u32, registry: &'a ComponentRegistry) -> SceneSerializer<'a> {
+        SceneSerializer::changes_since(self, registry, tick)
+    }
+
+    /// Serializes only the entities that have all components of `F`, in the same layout as [`SceneSerializer`].
+    /// Servers can tag entities with the regions they are in and send each client just the part of the world
+    /// it should see.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Name(String);
+    /// struct InForest;
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Name>("Name");
+    ///
+    /// let mut scene = Scene::new();
+    /// for (name, in_forest) in [("wolf", true), ("shark", false), ("bear", true)] {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, Name(name.to_string())).unwrap();
+    ///     if in_forest {
+    ///         scene.assign(entity, InForest).unwrap();
+    ///     }
+    /// }
+    ///
+    /// let visible = serde_json::to_string(&scene.serialize_matching::<(InForest, ())>(&registry)).unwrap();
+    /// assert!(visible.contains("wolf") && visible.contains("bear"));
+    /// assert!(!visible.contains("shark"));
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn serialize_matching<'a, F: RequireComponents>(
+        &'a self,
+        registry: &'a ComponentRegistry,
+    ) -> SceneSerializer<'a> {
+        SceneSerializer::matching::<F>(self, registry)
+    }
+
+    /// Hashes all living entities and their registered components, in a stable order and with a hash function
+    /// that gives the same result on every platform. Lockstep multiplayer games and replay tests can compare
+    /// the hashes of each tick to detect desyncs. Components of types that aren't registered are left out.
+    ///
+    /// Maps are hashed independently of the order of their entries, but sequences are hashed in order, since
+    /// the order of a `Vec` is part of its value. Components containing sets with an unstable iteration order,
+    /// like `HashSet`, don't hash the same in different processes; use ordered collections like `BTreeSet` instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Position(i32, i32);
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Position>("Position");
+    ///
+    /// let mut host = Scene::new();
+    /// let mut peer = Scene::new();
+    /// for scene in [&mut host, &mut peer] {
+    ///     let unit = scene.create_entity();
+    ///     scene.assign(unit, Position(0, 0)).unwrap();
+    /// }
+    /// assert_eq!(host.state_hash(&registry), peer.state_hash(&registry));
+    ///
+    /// // The peer missed an input
+    /// let unit = host.entities()[0];
+    /// host.get_mut::<Position>(unit).unwrap().unwrap().0 += 1;
+    /// assert_ne!(host.state_hash(&registry), peer.state_hash(&registry));
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn state_hash(&self, registry: &ComponentRegistry) -> u64 {
+        serialization::state_hash(self, registry)
+    }
+
+    /// Reorders the pool of `T` so that iterating over it follows the order given by `compare`, e.g. z-depth for rendering.
+    /// Only components kept in a plain [`Pool`] are reordered. Components with table, slab, small or interned
+    /// storage, tags and components stored as a struct of arrays are left as they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Depth(f32);
+    ///
+    /// let mut scene = Scene::new();
+    /// for depth in [0.5, -1.0, 2.0] {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, Depth(depth)).unwrap();
+    /// }
+    /// scene.sort_by::<Depth>(|a, b| a.0.total_cmp(&b.0));
+    ///
+    /// let depths = scene.pool::<Depth>().unwrap().iter().map(|(_, depth)| depth.0).collect::<Vec<_>>();
+    /// assert_eq!(depths, vec![-1.0, 0.5, 2.0]);
+    /// ```
+    pub fn sort_by<T: 'static>(&mut self, compare: impl FnMut(&T, &T) -> Ordering) {
+        if let Some(pool) = self.get_pool_if_exists_mut::<Pool<T>>(get_type_id::<T>()) {
+            pool.sort_by(compare);
+        }
+    }
+
+    /// Reorders the pool of `T` so that iterating over it follows the natural order of the components.
+    pub fn sort<T: Ord + 'static>(&mut self) {
+        self.sort_by(T::cmp)
+    }
+
+    /// Assigns a component stored as a struct of arrays, replacing an existing one.
+    /// Components assigned this way are not available through [`Scene::get`];
+    /// read them through the columns of [`Scene::soa_pool`] instead. They always have sparse storage.
+    #[track_caller]
+    pub fn assign_soa<T: SoAComponent>(&mut self, entity: Entity, value: T) -> Result<(), RecsError> {
+        let type_id = get_type_id::<T>();
+        self.add_to_entity_description::<T>(type_id, entity)?;
+        self.component_storage_types.insert(type_id, StorageType::Sparse);
+        self.get_or_create_pool::<T, _>(type_id, |_| SoAPool::new())?.insert(entity, value);
+        Ok(())
+    }
+
+    /// Returns the pool of a component type stored as a struct of arrays.
+    pub fn soa_pool<T: SoAComponent>(&self) -> Option<&SoAPool<T>> {
+        self.get_pool_if_exists::<SoAPool<T>>(get_type_id::<T>())
+    }
+
+    /// Returns the entities and the mutable columns of a component type stored as a struct of arrays.
+    pub fn soa_columns_mut<T: SoAComponent>(&mut self) -> Option<(&[Entity], &mut T::Columns)> {
+        self.changed_in_bulk(get_type_id::<T>());
+        self.get_pool_if_exists_mut::<SoAPool<T>>(get_type_id::<T>()).map(SoAPool::columns_mut)
+    }
+
+    /// Returns all entities whose shared component of type `T` refers to the same value as `shared`.
+    pub fn entities_sharing<T: 'static>(&self, shared: &Shared<T>) -> Vec<Entity> {
+        self.view::<(Shared<T>, ())>()
+            .filter(|&entity| {
+                self.get::<Shared<T>>(entity)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|other| Shared::ptr_eq(shared, other))
+            })
+            .collect()
+    }
+
+    /// Groups all entities with a shared component of type `T` by the value they refer to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    /// use yarecs::shared::Shared;
+    ///
+    /// struct ChunkId(u32);
+    ///
+    /// let mut scene = Scene::new();
+    /// let chunks = [Shared::new(ChunkId(0)), Shared::new(ChunkId(1))];
+    /// for i in 0..6 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, chunks[i % 2].clone()).unwrap();
+    /// }
+    ///
+    /// let groups = scene.group_by_shared::<ChunkId>();
+    /// assert_eq!(groups.len(), 2);
+    /// assert!(groups.iter().all(|(_, entities)| entities.len() == 3));
+    /// ```
+    pub fn group_by_shared<T: 'static>(&self) -> Vec<(Shared<T>, Vec<Entity>)> {
+        let mut groups: Vec<(Shared<T>, Vec<Entity>)> = Vec::new();
+        let mut group_indices = HashMap::new();
+        for entity in self.view::<(Shared<T>, ())>() {
+            let Some(shared) = self.get::<Shared<T>>(entity).ok().flatten() else {
+                continue;
+            };
+            let group = *group_indices.entry(shared.key()).or_insert_with(|| {
+                groups.push((shared.clone(), Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].1.push(entity);
+        }
+        groups
+    }
+
+    /// Drops destroyed entities from the end of the entity list and from the free list.
+    /// Call this after large despawn waves to reclaim memory. Entity handles stay the same.
+    /// Pools and tables need no compaction, since they are always densely packed.
+    ///
+    /// Entities created later at the dropped indices get versions past those of the dropped slots,
+    /// so handles and [`WeakEntity`] handles to destroyed entities stay outdated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let entities = (0..4).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// scene.assign(entities[0], 0u8).unwrap();
+    /// for &entity in &entities[1..] {
+    ///     scene.destroy_entity(entity);
+    /// }
+    /// scene.compact();
+    ///
+    /// assert_eq!(*scene.get::<u8>(entities[0]).unwrap().unwrap(), 0);
+    /// assert_eq!(scene.create_entity().index(), 1);
+    ///
+    /// // A handle to a destroyed entity doesn't refer to the entity created in its dropped slot
+    /// let doomed = scene.create_entity();
+    /// let weak = scene.weak(doomed);
+    /// scene.destroy_entity(doomed);
+    /// scene.compact();
+    /// let created = scene.create_entity();
+    /// assert_eq!(created.index(), doomed.index());
+    /// assert_ne!(created, doomed);
+    /// assert_eq!(scene.resolve(weak), None);
+    /// ```
+    pub fn compact(&mut self) {
+        let len = self
+            .entities
+            .iter()
+            .rposition(|entity_description| entity_description.entity.is_valid())
+            .map_or(0, |index| index + 1);
+        for entity_description in &self.entities[len..] {
+            self.fresh_version = self.fresh_version.max(entity_description.entity.version());
+        }
+        self.entities.truncate(len);
+        self.alive_positions.truncate(len);
+        self.free_list.retain(|&index| (index as usize) < len);
+        self.archetypes.remap_entities(&HashMap::new());
+    }
+
+    /// Compacts the scene like [`Scene::compact`] and additionally moves all living entities to the lowest indices.
+    /// Returns a table mapping the old handle of each living entity to its new handle,
+    /// which has to be used to update entity handles stored outside the scene or inside components.
+    ///
+    /// All version history of destroyed entities is lost, so old handles must not be used after the remap.
+    /// [`WeakEntity`] handles created before compacting resolve to `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let first = scene.create_entity();
+    /// let second = scene.create_entity();
+    /// scene.assign(second, "second").unwrap();
+    /// scene.destroy_entity(first);
+    ///
+    /// let remap = scene.compact_entities();
+    /// let second = remap[&second];
+    /// assert_eq!(second.index(), 0);
+    /// assert_eq!(*scene.get::<&str>(second).unwrap().unwrap(), "second");
+    /// ```
+    ///
+    /// Entity handles in the hierarchy are rewritten as well:
+    ///
+    /// ```
+    /// use yarecs::hierarchy::{Children, Parent};
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let doomed = scene.create_entity();
+    /// let parent = scene.create_entity();
+    /// let child = scene.create_entity();
+    /// scene.set_parent(child, parent).unwrap();
+    /// scene.destroy_entity(doomed);
+    ///
+    /// let remap = scene.compact_entities();
+    /// let (parent, child) = (remap[&parent], remap[&child]);
+    /// assert_eq!(scene.get::<Parent>(child).unwrap().unwrap().get(), parent);
+    /// assert_eq!(scene.get::<Children>(parent).unwrap().unwrap().as_slice(), &[child]);
+    /// assert_eq!(scene.ancestors(child).collect::<Vec<_>>(), vec![parent]);
+    ///
+    /// scene.despawn_recursive(parent).unwrap();
+    /// assert!(!scene.is_alive(child));
+    /// ```
+    pub fn compact_entities(&mut self) -> HashMap<Entity, Entity> {
+        self.structure_generation += 1;
+        self.entity_layout = self.entity_layout.wrapping_add(1);
+        if let Some(page_cache) = &mut self.page_cache {
+            page_cache.get_mut().invalidate_all();
+        }
+        let mut remap = HashMap::new();
+        for mut entity_description in std::mem::take(&mut self.entities) {
+            if !entity_description.entity.is_valid() {
+                continue;
+            }
+            let old_entity = entity_description.entity;
+            let new_entity = Entity::with_version(self.entities.len() as u32, old_entity.version());
+            entity_description.entity = new_entity;
+            self.entities.push(entity_description);
+            remap.insert(old_entity, new_entity);
+        }
+        self.alive = self.entities.iter().map(|entity_description| entity_description.entity).collect();
+        self.alive_positions = (0..self.alive.len() as u32).collect();
+        self.free_list.clear();
+        for pool in self.pools.iter_mut().flatten().chain(self.previous_pools.values_mut()) {
+            pool.remap_entities(&remap);
+        }
+        self.archetypes.remap_entities(&remap);
+        self.relationships.remap_entities(&remap);
+        self.names.remap_entities(&remap);
+        self.tags.remap_entities(&remap);
+        self.dynamic.remap_entities(&remap);
+        if let Some(persistent_ids) = &mut self.persistent_ids {
+            persistent_ids.remap_entities(&remap);
+        }
+        self.replication.remap_entities(&remap);
+        self.singleton_entity = self.singleton_entity.and_then(|entity| remap.get(&entity).copied());
+        self.invalidate_indexes();
+        for source in self.relationships.all_sources().collect::<Vec<_>>() {
+            if let Ok(Some(Targets(target))) = self.get_mut::<Targets>(source) {
+                *target = remap.get(target).copied().unwrap_or(*target);
+            }
+            if let Ok(Some(OwnedBy(owner))) = self.get_mut::<OwnedBy>(source) {
+                *owner = remap.get(owner).copied().unwrap_or(*owner);
+            }
+        }
+        for child in self.view::<(Parent, ())>().collect::<Vec<_>>() {
+            if let Ok(Some(Parent(parent))) = self.get_mut::<Parent>(child) {
+                *parent = remap.get(parent).copied().unwrap_or(*parent);
+            }
+        }
+        for parent in self.view::<(Children, ())>().collect::<Vec<_>>() {
+            if let Ok(Some(Children(children))) = self.get_mut::<Children>(parent) {
+                children.retain_mut(|child| match remap.get(child) {
+                    Some(&remapped) => {
+                        *child = remapped;
+                        true
+                    }
+                    None => false,
+                });
+            }
+        }
+        remap
+    }
+
+    /// Releases unused capacity of the entity list, the free list, every pool and every archetype table.
+    /// Long-running applications can call this after peak loads to return memory; combine it with [`Scene::compact`]
+    /// to also release the slots of destroyed entities and components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// let entities = (0..1000).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for &entity in &entities {
+    ///     scene.assign(entity, entity.index()).unwrap();
+    /// }
+    /// for &entity in &entities[1..] {
+    ///     scene.destroy_entity(entity);
+    /// }
+    /// scene.compact();
+    /// scene.shrink_to_fit();
+    ///
+    /// assert_eq!(*scene.get::<u32>(entities[0]).unwrap().unwrap(), 0);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.entities.shrink_to_fit();
+        for entity_description in &mut self.entities {
+            entity_description.components.shrink_to_fit();
+        }
+        self.free_list.shrink_to_fit();
+        for pool in self.pools.iter_mut().flatten().chain(self.previous_pools.values_mut()) {
+            pool.shrink_to_fit();
+        }
+        self.pools.shrink_to_fit();
+        self.archetypes.shrink_to_fit();
+    }
+
+    /// Destroys all entities and drops all components at once, keeping pools, tables and their memory for reuse.
+    /// Meant for transient scenes, like per-frame scratch worlds or speculative simulations:
+    /// after warming up, clearing and refilling such a scene allocates nothing,
+    /// and components without drop glue are released without touching them one by one.
+    /// Pair it with an arena via [`Scene::with_allocator`] to also serve the first fill from the arena.
+    ///
+    /// All existing entity handles become invalid. No audit records are written for the destroyed entities,
+    /// and no replication updates are produced for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scratch = Scene::new();
+    /// for frame in 0..3 {
+    ///     let entities = (0..100).map(|_| scratch.create_entity()).collect::<Vec<_>>();
+    ///     for &entity in &entities {
+    ///         scratch.assign(entity, frame).unwrap();
+    ///     }
+    ///     assert_eq!(scratch.view::<(i32, ())>().count(), 100);
+    ///
+    ///     scratch.clear();
+    ///     assert!(scratch.get::<i32>(entities[0]).is_err());
+    /// }
+    /// ```
+    pub fn clear(&mut self) {
+        self.structure_generation += 1;
+        self.alive.clear();
+        self.free_list.clear();
+        for (index, entity_description) in self.entities.iter_mut().enumerate().rev() {
+            if entity_description.entity.is_valid() {
+                #[cfg(feature = "metrics")]
+                self.metrics.entity_destroyed(entity_description.components.len());
+                if !entity_description.components.is_empty() && self.hooks.observes_composition() {
+                    let mut removed = entity_description.components.iter().copied().collect::<Vec<_>>();
+                    removed.sort_unstable();
+                    self.hooks.composition_changed(entity_description.entity, &[], &removed);
+                }
+                entity_description.invalidate_entity();
+            }
+            self.free_list.push_back(index as u32);
+        }
+        for pool in self.pools.iter_mut().flatten().chain(self.previous_pools.values_mut()) {
+            pool.clear();
+        }
+        self.archetypes.clear();
+        self.relationships = Relationships::default();
+        self.names = NameIndex::default();
+        self.tags = TagIndex::default();
+        self.dynamic.clear();
+        if let Some(persistent_ids) = &mut self.persistent_ids {
+            *persistent_ids = PersistentIdIndex::default();
+        }
+        self.replication.clear_dirty();
+        self.singleton_entity = None;
+        self.invalidate_indexes();
+        if let Some(page_cache) = &mut self.page_cache {
+            page_cache.get_mut().invalidate_all();
+        }
+    }
+
+    /// Includes components of type `T` in snapshots taken with [`Scene::snapshot`].
+    /// See [`Snapshot`] for an example.
+    pub fn register_snapshot<T: Clone + 'static>(&mut self) {
+        self.snapshot_types.insert(get_type_id::<T>(), SnapshotFns::of::<T>());
+    }
+
+    /// Chooses how [`Scene::snapshot`] copies components. See [`SnapshotMode::CopyOnWrite`] for an example.
+    pub fn set_snapshot_mode(&mut self, mode: SnapshotMode) {
+        if mode != self.snapshot_mode() {
+            self.page_cache = match mode {
+                SnapshotMode::Copy => None,
+                SnapshotMode::CopyOnWrite => Some(RefCell::default()),
+            };
+        }
+    }
+
+    pub fn snapshot_mode(&self) -> SnapshotMode {
+        match self.page_cache {
+            Some(_) => SnapshotMode::CopyOnWrite,
+            None => SnapshotMode::Copy,
+        }
+    }
+
+    /// Copies all entities and their components, so the scene can be rolled back with [`Scene::restore`].
+    /// In [`SnapshotMode::CopyOnWrite`], components that didn't change since the previous snapshot are shared with it.
+    /// Fails if a living entity has a component of a type that isn't registered with [`Scene::register_snapshot`],
+    /// since the snapshot couldn't restore it.
+    pub fn snapshot(&self) -> Result<Snapshot, RecsError> {
+        for entity in &self.alive {
+            let entity_description = &self.entities[entity.index() as usize];
+            if let Some(&type_id) =
+                entity_description.components.iter().find(|type_id| !self.snapshot_types.contains_key(type_id))
+            {
+                let type_name = get_type_name(type_id).unwrap_or("<unknown>");
+                let message = format!("component type {type_name} is not registered for snapshots");
+                return Err(RecsError::Other(message.into()));
+            }
+        }
+        Ok(Snapshot {
+            entities: self.entities.iter().map(|entity_description| entity_description.entity).collect(),
+            free_list: self.free_list.clone(),
+            alive: self.alive.clone(),
+            alive_positions: self.alive_positions.clone(),
+            components: self
+                .snapshot_types
+                .iter()
+                .map(|(&type_id, fns)| match &self.page_cache {
+                    Some(page_cache) => (type_id, page_cache.borrow_mut().snapshot(self, type_id, fns)),
+                    None => (type_id, (fns.capture)(self)),
+                })
+                .collect(),
+        })
+    }
+
+    /// Copies all entities and their components into a new scene, keeping the entity handles valid in the copy.
+    /// The copy uses the same storage types and registrations. Like [`Scene::snapshot`], this fails if a living
+    /// entity has a component of a type that isn't registered for snapshots, either with [`Scene::register_snapshot`]
+    /// or with clone enabled by [`Scene::register_component`].
+    /// See [`Scene::register_component`] for an example.
+    pub fn try_clone(&self) -> Result<Scene, RecsError> {
+        let snapshot = self.snapshot()?;
+        let mut scene = Scene::with_storage_type(self.storage_type);
+        scene.component_storage_types.clone_from(&self.component_storage_types);
+        scene.snapshot_types.clone_from(&self.snapshot_types);
+        scene.types.clone_from(&self.types);
+        scene.restore(&snapshot)?;
+        Ok(scene)
+    }
+
+    /// Replaces all entities and components with the ones captured in the snapshot.
+    /// Entities created afterwards get the same indices and versions as they did after the snapshot was taken.
+    /// Relationships, names, tags, persistent IDs and the singleton entity are looked up again
+    /// from the restored components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::name::Name;
+    /// use yarecs::relationship::Targets;
+    /// use yarecs::scene::Scene;
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.register_snapshot::<Name>();
+    /// scene.register_snapshot::<Targets>();
+    /// let turret = scene.create_entity();
+    /// let enemy = scene.create_entity();
+    /// scene.set_name(turret, "Turret").unwrap();
+    /// scene.set_target(turret, enemy).unwrap();
+    ///
+    /// let mut copy = Scene::new();
+    /// copy.register_snapshot::<Name>();
+    /// copy.register_snapshot::<Targets>();
+    /// copy.restore(&scene.snapshot().unwrap()).unwrap();
+    /// assert_eq!(copy.find_by_name("Turret"), Some(turret));
+    /// assert_eq!(copy.targeting(enemy).collect::<Vec<_>>(), vec![turret]);
+    ///
+    /// copy.destroy_entity(enemy);
+    /// assert!(copy.get::<Targets>(turret).unwrap().is_none());
+    /// ```
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), RecsError> {
+        self.structure_generation += 1;
+        for pool in self.pools.iter_mut().flatten().chain(self.previous_pools.values_mut()) {
+            pool.clear();
+        }
+        self.archetypes.clear();
+        self.entities = snapshot
+            .entities
+            .iter()
+            .map(|&entity| EntityDescription {
+                changed_tick: self.change_tick,
+                ..EntityDescription::new(entity)
+            })
+            .collect();
+        self.free_list.clone_from(&snapshot.free_list);
+        self.alive.clone_from(&snapshot.alive);
+        self.alive_positions.clone_from(&snapshot.alive_positions);
+        for (type_id, components) in &snapshot.components {
+            let restore = self.snapshot_types.get(type_id).ok_or(RecsError::PoolAccessOrCreationError)?.restore;
+            restore(self, components.as_ref())?;
+        }
+        if let Some(page_cache) = &mut self.page_cache {
+            page_cache.get_mut().restored(snapshot, &self.snapshot_types);
+        }
+        self.invalidate_indexes();
+        self.reindex_components();
+        Ok(())
+    }
+
+    /// Registers `T` as replicated under the given name: assigning, removing and mutably accessing components of
+    /// type `T` marks them as dirty, and [`Scene::drain_replication_updates`] encodes the dirty ones with `encode`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Health(u32);
+    /// struct Hunger(u32);
+    ///
+    /// let mut scene = Scene::new();
+    /// scene.register_replicated::<Health>("health", |health| health.0.to_le_bytes().to_vec());
+    /// let player = scene.create_entity();
+    /// scene.assign(player, Health(100)).unwrap();
+    /// scene.assign(player, Hunger(0)).unwrap();
+    /// assert_eq!(scene.drain_replication_updates().len(), 1);
+    ///
+    /// // Only replicated components that changed since the last drain are sent
+    /// scene.get_mut::<Health>(player).unwrap().unwrap().0 -= 10;
+    /// scene.get_mut::<Hunger>(player).unwrap().unwrap().0 += 1;
+    /// let updates = scene.drain_replication_updates();
+    /// assert_eq!(updates.len(), 1);
+    /// assert_eq!(updates[0].entity, player);
+    /// assert_eq!(updates[0].component, "health");
+    /// assert_eq!(updates[0].bytes, Some(90u32.to_le_bytes().to_vec()));
+    ///
+    /// scene.destroy_entity(player);
+    /// assert_eq!(scene.drain_replication_updates()[0].bytes, None);
+    /// assert!(scene.drain_replication_updates().is_empty());
+    /// ```
+    pub fn register_replicated<T: 'static>(&mut self, name: &'static str, encode: impl Fn(&T) -> Vec<u8> + 'static) {
+        self.replication.register::<T>(name, encode);
+    }
+
+    /// Returns the replicated components that were assigned, removed or mutably accessed since the last call,
+    /// encoded for sending over the network and ordered by entity index and component name.
+    /// Removed components and components of destroyed entities have no bytes.
+    /// Mutations through column access, splits and unchecked accessors aren't tracked.
+    pub fn drain_replication_updates(&mut self) -> Vec<ReplicationUpdate> {
+        let mut updates = self
+            .replication
+            .take_dirty()
+            .into_iter()
+            .map(|(entity, type_id)| self.replication.encode(self, entity, type_id))
+            .collect::<Vec<_>>();
+        updates.sort_by(|a, b| (a.entity.index(), a.component).cmp(&(b.entity.index(), b.component)));
+        updates
+    }
+
+    /// Encodes all replicated components of all living entities, ordered like [`Scene::drain_replication_updates`],
+    /// e.g. to send the whole state to a client that just connected. Doesn't change which components are dirty.
+    pub fn encode_replicated(&self) -> Vec<ReplicationUpdate> {
+        let mut updates = self.replication.encode_all(self);
+        updates.sort_by(|a, b| (a.entity.index(), a.component).cmp(&(b.entity.index(), b.component)));
+        updates
+    }
+
+    /// Includes components of type `T` in diffs computed with [`Scene::diff`] on this scene as the new state.
+    /// See [`SceneDiff`] for an example.
+    pub fn register_diff<T: Clone + PartialEq + 'static>(&mut self) {
+        self.diff_types.insert(get_type_id::<T>(), DiffFns::of::<T>());
+    }
+
+    /// Computes the changes that turn `old` into `new`. Components are compared for the types registered
+    /// with [`Scene::register_diff`] on `new`; fails if a living entity of either scene has a component
+    /// of another type, since the diff couldn't describe its changes.
+    pub fn diff(old: &Scene, new: &Scene) -> Result<SceneDiff, RecsError> {
+        for scene in [old, new] {
+            for entity in &scene.alive {
+                let entity_description = &scene.entities[entity.index() as usize];
+                if let Some(&type_id) =
+                    entity_description.components.iter().find(|type_id| !new.diff_types.contains_key(type_id))
+                {
+                    let type_name = get_type_name(type_id).unwrap_or("<unknown>");
+                    let message = format!("component type {type_name} is not registered for diffs");
+                    return Err(RecsError::Other(message.into()));
+                }
+            }
+        }
+        Ok(SceneDiff {
+            created: new.alive.iter().copied().filter(|&entity| old.assert_entity_valid(entity).is_err()).collect(),
+            destroyed: old.alive.iter().copied().filter(|&entity| new.assert_entity_valid(entity).is_err()).collect(),
+            components: compare_components(new.diff_types.iter(), old, new),
+        })
+    }
+
+    /// Applies a diff computed with [`Scene::diff`], turning a scene in the diff's old state into its new state.
+    /// Created entities keep their indices and versions, but freed indices may be reused in a different order
+    /// than in the new state.
+    #[track_caller]
+    pub fn apply_diff(&mut self, diff: &SceneDiff) -> Result<(), RecsError> {
+        for &entity in &diff.destroyed {
+            self.assert_entity_valid(entity)?;
+        }
+        for &entity in &diff.destroyed {
+            self.destroy_entity(entity);
+        }
+        for &entity in &diff.created {
+            self.create_entity_at(entity)?;
+        }
+        diff.apply_components(self, None)
+    }
+
+    /// Applies a diff of a remote scene, translating its entities with the mapper. Local entities are created
+    /// for created remote entities, and the local entities of destroyed remote entities are destroyed.
+    /// Mappings of local entities that died since the last update are dropped first,
+    /// and changes of remote entities without a local entity are skipped.
+    /// See [`EntityMapper`] for an example.
+    #[track_caller]
+    pub fn apply_diff_mapped(&mut self, diff: &SceneDiff, mapper: &mut EntityMapper) -> Result<(), RecsError> {
+        mapper.retain_alive(self);
+        for &remote in &diff.destroyed {
+            mapper.destroy_remote(remote, self);
+        }
+        for &remote in &diff.created {
+            mapper.local_or_create(remote, self);
+        }
+        diff.apply_components(self, Some(mapper))
+    }
+
+    /// Creates the given entity, keeping its index and version. Fails if its index is in use.
+    #[track_caller]
+    pub(crate) fn create_entity_at(&mut self, entity: Entity) -> Result<(), RecsError> {
+        let index = entity.index();
+        while self.entities.len() <= index as usize {
+            let free_index = self.entities.len() as u32;
+            let mut free = Entity::new(free_index);
+            free.invalidate();
+            self.free_list.push_back(free_index);
+            self.entities.push(EntityDescription::new(free));
+        }
+        let position = self
+            .free_list
+            .iter()
+            .position(|&free_index| free_index == index)
+            .ok_or(RecsError::Other("the index of a created entity is already in use".into()))?;
+        self.free_list.remove(position);
+        self.entity_reuse.record(true);
+        self.entities[index as usize] = EntityDescription::new(entity);
+        self.mark_alive(entity);
+        self.record_change(StructuralChange::CreateEntity, entity, None);
+        #[cfg(feature = "metrics")]
+        self.metrics.entity_created();
+        Ok(())
+    }
+
+    /// Takes an entity out of the children of its parent, removing the parent's [`Children`] once it's empty.
+    fn remove_child(&mut self, parent: Entity, child: Entity) -> Result<(), RecsError> {
+        let Some(children) = self.get_mut::<Children>(parent)? else {
+            return Ok(());
+        };
+        children.0.retain(|&entity| entity != child);
+        if children.is_empty() {
+            self.remove::<Children>(parent)?;
+        }
+        Ok(())
+    }
+
+    /// Removes a destroyed entity from the children of its parent and removes the parent of its children.
+    fn detach_from_hierarchy(&mut self, entity: Entity) {
+        // Fails only for entities that aren't alive, which have no hierarchy
+        if self.remove_parent(entity).is_err() || !matches!(self.get::<Children>(entity), Ok(Some(_))) {
+            return;
+        }
+        if let Ok(Some(children)) = self.get_mut::<Children>(entity) {
+            for child in std::mem::take(&mut children.0) {
+                self.remove::<Parent>(child).ok();
+            }
+        }
+    }
+
+    /// Relates an entity to a target through the relationship component `R`, replacing its previous target.
+    #[track_caller]
+    fn relate<R: Relationship>(&mut self, entity: Entity, target: Entity) -> Result<(), RecsError> {
+        self.assert_entity_valid(target)?;
+        self.unrelate::<R>(entity)?;
+        self.assign(entity, R::new(target))?;
+        self.relationships.insert(target, entity, R::CLEANUP);
+        Ok(())
+    }
+
+    /// Removes the relationship component `R` of an entity and returns its target.
+    #[track_caller]
+    fn unrelate<R: Relationship>(&mut self, entity: Entity) -> Result<Option<Entity>, RecsError> {
+        let Some(target) = self.get::<R>(entity)?.map(R::target) else {
+            return Ok(None);
+        };
+        self.remove::<R>(entity)?;
+        self.relationships.remove(target, entity, R::CLEANUP);
+        Ok(Some(target))
+    }
+
+    /// Returns the entities relating to a target through the relationship component `R`.
+    fn related<R: Relationship>(&self, target: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.relationships.sources(target, R::CLEANUP).filter(move |&source| {
+            matches!(self.get::<R>(source), Ok(Some(relationship)) if relationship.target() == target)
+        })
+    }
+
+    /// Drops the relationships of a destroyed entity, and removes the relationships to it or destroys its dependents.
+    fn detach_relationships(&mut self, entity: Entity) {
+        // Fails only for entities that aren't alive, which have no relationships
+        if self.unrelate::<Targets>(entity).is_err() {
+            return;
+        }
+        self.unrelate::<OwnedBy>(entity).ok();
+        for (source, cleanup) in self.relationships.take(entity) {
+            match cleanup {
+                Cleanup::Remove => {
+                    if matches!(self.get::<Targets>(source), Ok(Some(Targets(target))) if *target == entity) {
+                        self.remove::<Targets>(source).ok();
+                    }
+                }
+                Cleanup::Destroy => {
+                    if matches!(self.get::<OwnedBy>(source), Ok(Some(OwnedBy(owner))) if *owner == entity) {
+                        self.destroy_entity(source);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the living entities whose indices are in the range.
+    pub(crate) fn entities_in(&self, indices: Range<usize>) -> impl Iterator<Item = Entity> + '_ {
+        let indices = indices.start.min(self.entities.len())..indices.end.min(self.entities.len());
+        self.entities[indices]
+            .iter()
+            .map(|entity_description| entity_description.entity)
+            .filter(|entity| entity.is_valid())
+    }
+
+    /// Remembers that the component of the entity was assigned, removed or mutably accessed.
+    fn component_changed(&mut self, entity: Entity, type_id: usize) {
+        self.replication.mark_dirty(entity, type_id);
+        if let Some(page_cache) = &mut self.page_cache {
+            page_cache.get_mut().mark_dirty(entity, type_id);
+        }
+        if let Some(index) = self.indexes.get_mut(&type_id) {
+            index.get_mut().mark_dirty(entity);
+        }
+        #[cfg(feature = "spatial")]
+        self.spatial_changed(entity, type_id);
+    }
+
+    /// Remembers that the position of the entity may have changed if the component type is the spatially indexed one.
+    #[cfg(feature = "spatial")]
+    fn spatial_changed(&mut self, entity: Entity, type_id: usize) {
+        if let Some(spatial) = &mut self.spatial {
+            let spatial = spatial.get_mut();
+            if spatial.type_id == type_id {
+                spatial.mark_dirty(entity);
+            }
+        }
+    }
+
+    /// Makes the next copy-on-write snapshot copy all components of the type and rebuilds its index,
+    /// after the components were accessed in bulk.
+    fn changed_in_bulk(&mut self, type_id: usize) {
+        if let Some(page_cache) = &mut self.page_cache {
+            page_cache.get_mut().invalidate(type_id);
+        }
+        if let Some(index) = self.indexes.get_mut(&type_id) {
+            index.get_mut().invalidate();
+        }
+        #[cfg(feature = "spatial")]
+        if let Some(spatial) = &mut self.spatial {
+            if spatial.get_mut().type_id == type_id {
+                spatial.get_mut().invalidate();
+            }
+        }
+    }
+
+    /// Rebuilds the lookups kept beside the components, like the relationship, name and tag indexes,
+    /// from the components after they were replaced wholesale.
+    fn reindex_components(&mut self) {
+        let mut relationships = Relationships::default();
+        for source in self.view::<(Targets, ())>() {
+            if let Ok(Some(target)) = self.get::<Targets>(source) {
+                relationships.insert(target.get(), source, Targets::CLEANUP);
+            }
+        }
+        for source in self.view::<(OwnedBy, ())>() {
+            if let Ok(Some(owner)) = self.get::<OwnedBy>(source) {
+                relationships.insert(owner.get(), source, OwnedBy::CLEANUP);
+            }
+        }
+        self.relationships = relationships;
+        self.reindex_names();
+
+        let mut tags = TagIndex::default();
+        for entity in self.view::<(Tags, ())>() {
+            for tag in self.get::<Tags>(entity).ok().flatten().into_iter().flat_map(Tags::iter) {
+                tags.insert(tag, entity);
+            }
+        }
+        self.tags = tags;
+
+        if self.persistent_ids.is_some() {
+            let mut persistent_ids = PersistentIdIndex::default();
+            for entity in self.view::<(PersistentId, ())>() {
+                if let Ok(Some(&id)) = self.get::<PersistentId>(entity) {
+                    persistent_ids.insert(id, entity);
+                }
+            }
+            self.persistent_ids = Some(persistent_ids);
+        }
+
+        self.singleton_entity = self.alive.iter().copied().find(|&entity| {
+            let components = &self.entities[entity.index() as usize].components;
+            self.singleton_types.iter().any(|type_id| components.contains(type_id))
+        });
+    }
+
+    /// Rebuilds all indexes of component values, after entities were moved or replaced.
+    fn invalidate_indexes(&mut self) {
+        for index in self.indexes.values_mut() {
+            index.get_mut().invalidate();
+        }
+        #[cfg(feature = "spatial")]
+        if let Some(spatial) = &mut self.spatial {
+            spatial.get_mut().invalidate();
+        }
+    }
+
+    /// Returns the storage type of a component type.
+    fn storage_type_of(&self, type_id: usize) -> StorageType {
+        self.component_storage_types
+            .get(&type_id)
+            .copied()
+            .unwrap_or(self.storage_type)
+    }
+
+    /// Removes the component with the type ID from an entity.
+    #[track_caller]
+    fn remove_by_type_id(
+        &mut self,
+        entity: Entity,
+        type_id: usize,
+        type_name: Option<&'static str>,
+    ) -> Result<(), RecsError> {
+        let removed = self
+            .get_valid_entity_description_mut(entity)
+            .map(|ed| ed.components.remove(&type_id))?;
+        if removed {
+            self.component_changed(entity, type_id);
+            self.record_change(StructuralChange::Remove, entity, type_name);
+            self.hooks.composition_changed(entity, &[], &[type_id]);
+            #[cfg(feature = "metrics")]
+            self.metrics.component_removed();
+        }
+        match self.storage_type_of(type_id) {
+            StorageType::Sparse | StorageType::Slab | StorageType::Small | StorageType::Interned => {
+                if let Some(pool) = self.pool_by_id_mut(type_id) {
+                    pool.free(entity);
+                }
+            }
+            StorageType::Table => self.archetypes.remove(entity, type_id),
+        }
+        Ok(())
+    }
+
+    /// Marks the component type as present in the entity description.
+    #[track_caller]
+    fn add_to_entity_description<T: 'static>(&mut self, type_id: usize, entity: Entity) -> Result<(), RecsError> {
+        // Interned components are only added through `assign_interned`
+        if self.storage_type_of(type_id) == StorageType::Interned {
+            return Err(interned_mutation());
+        }
+        self.add_to_entity_description_unchecked::<T>(type_id, entity)
+    }
+
+    fn add_to_entity_description_unchecked<T: 'static>(
+        &mut self,
+        type_id: usize,
+        entity: Entity,
+    ) -> Result<(), RecsError> {
+        if self.singleton_types.contains(&type_id) && self.singleton_entity != Some(entity) {
+            let message = format!("component type {} is a singleton", type_name::<T>());
+            return Err(RecsError::Other(message.into()));
+        }
+        if !self.dependencies.is_empty() {
+            self.assert_entity_valid(entity)?;
+            self.check_dependencies(entity, type_id)?;
+        }
+        if let Some(required) = self.required_components.get(&type_id).cloned() {
+            self.assert_entity_valid(entity)?;
+            self.inserting_required.push(type_id);
+            let mut inserted = Ok(());
+            for &(required_id, insert_default) in required.iter() {
+                if !self.inserting_required.contains(&required_id) {
+                    inserted = inserted.and_then(|_| insert_default(self, entity));
+                }
+            }
+            self.inserting_required.pop();
+            inserted?;
+        }
+        if let Some(group) = self.exclusive_groups.get(&type_id).cloned() {
+            self.assert_entity_valid(entity)?;
+            for &other in group.iter().filter(|&&other| other != type_id) {
+                if self.entities[entity.index() as usize].has_component_with_type_id(other) {
+                    self.remove_by_type_id(entity, other, get_type_name(other))?;
+                }
+            }
+        }
+        let entity_description = self.get_valid_entity_description_mut(entity)?;
+        let added = entity_description.components.insert(type_id);
+        self.component_changed(entity, type_id);
+        self.record_change(StructuralChange::Assign, entity, Some(type_name::<T>()));
+        #[cfg(feature = "metrics")]
+        self.metrics.component_written(type_id);
+        if added {
+            self.hooks.composition_changed(entity, &[type_id], &[]);
+            #[cfg(feature = "metrics")]
+            self.metrics.component_added();
+        }
+        Ok(())
+    }
+
+    /// Checks that adding the component type to an alive entity leaves no declared dependency missing,
+    /// counting the required components that would be inserted along with it, before anything is inserted.
+    fn check_dependencies(&self, entity: Entity, type_id: usize) -> Result<(), RecsError> {
+        let mut added = vec![type_id];
+        let mut next = 0;
+        while let Some(&added_id) = added.get(next) {
+            let required = self.required_components.get(&added_id).map_or(&[][..], |required| &required[..]);
+            for &(required_id, _) in required {
+                if !added.contains(&required_id) {
+                    added.push(required_id);
+                }
+            }
+            next += 1;
+        }
+        let entity_description = &self.entities[entity.index() as usize];
+        for &added_id in &added {
+            let dependencies = self.dependencies.get(&added_id).map_or(&[][..], |dependencies| &dependencies[..]);
+            let mut missing = dependencies
+                .iter()
+                .filter(|&&id| !entity_description.has_component_with_type_id(id) && !added.contains(&id));
+            if let Some(&missing) = missing.next() {
+                return Err(RecsError::MissingDependency {
+                    component: get_type_name(added_id).unwrap_or("unknown"),
+                    dependency: get_type_name(missing).unwrap_or("unknown"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores a component in the storage of its type, unless the entity already has one.
+    fn insert_component<T: 'static>(
+        &mut self,
+        type_id: usize,
+        entity: Entity,
+        f: impl FnOnce() -> T,
+    ) -> Result<&mut T, RecsError> {
+        match self.storage_type_of(type_id) {
+            StorageType::Sparse if is_tag::<T>() => {
+                Ok(self.get_or_create_pool::<T, _>(type_id, |_| TagPool::new())?.assign_with(entity, f))
+            }
+            StorageType::Sparse => {
+                Ok(self.get_or_create_pool::<T, _>(type_id, Pool::new_in)?.assign_with(entity, f))
+            }
+            StorageType::Table => {
+                if !self.archetypes.knows_type_id(type_id) {
+                    self.hooks.pool_created(type_id, type_name::<T>());
+                }
+                Ok(self.archetypes.insert_with(entity, f))
+            }
+            StorageType::Slab => {
+                Ok(self.get_or_create_pool::<T, _>(type_id, SlabPool::new_in)?.assign_with(entity, f))
+            }
+            StorageType::Small => {
+                Ok(self.get_or_create_pool::<T, _>(type_id, SmallPool::new_in)?.assign_with(entity, f))
+            }
+            StorageType::Interned => Err(interned_mutation()),
+        }
+    }
+
+    #[track_caller]
+    fn record_change(
+        &mut self,
+        change: StructuralChange,
+        entity: Entity,
+        component: Option<&'static str>,
+    ) {
+        self.structure_generation += 1;
+        if let Some(entity_description) = self.entities.get_mut(entity.index() as usize) {
+            entity_description.changed_tick = self.change_tick;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(tick = self.change_tick, ?entity, ?change, component, "structural change");
+        if let Some(audit_log) = &mut self.audit_log {
+            audit_log.push(AuditRecord {
+                tick: self.change_tick,
+                entity,
+                change,
+                component,
+                location: Location::caller(),
+            });
+        }
+    }
+
+    /// Puts pools loaded by a `PodSnapshotLoader` into this empty scene
+    /// and recreates the entities owning their components.
+    #[cfg(feature = "bytemuck")]
+    pub(crate) fn install_loaded_pools(&mut self, pools: Vec<LoadedPool>) -> Result<(), RecsError> {
+        if !self.entities.is_empty() {
+            return Err(RecsError::Other("pool snapshots can only be loaded into an empty scene".into()));
+        }
+        if pools.iter().any(|loaded| self.storage_type_of(loaded.type_id) != StorageType::Sparse) {
+            return Err(RecsError::Other("component type has no pool to restore".into()));
+        }
+        self.recreate_entities(pools.iter().flat_map(|loaded| loaded.entities.iter().copied()))?;
+        for loaded in &pools {
+            self.changed_in_bulk(loaded.type_id);
+        }
+
+        for loaded in pools {
+            for entity in loaded.entities {
+                self.entities[entity.index() as usize].components.insert(loaded.type_id);
+                self.hooks.composition_changed(entity, &[loaded.type_id], &[]);
+                #[cfg(feature = "metrics")]
+                self.metrics.component_added();
+            }
+            if loaded.type_id >= self.pools.len() {
+                self.pools.resize_with(loaded.type_id + 1, || None);
+            }
+            self.hooks.pool_created(loaded.type_id, loaded.type_name);
+            self.pools[loaded.type_id] = Some(loaded.pool);
+        }
+        Ok(())
+    }
+
+    /// Fills the entity table of an empty scene so that exactly the given entities are alive,
+    /// with their saved indices and versions. Entities may be listed more than once.
+    #[cfg(any(feature = "bytemuck", feature = "serde"))]
+    pub(crate) fn recreate_entities(&mut self, entities: impl IntoIterator<Item = Entity>) -> Result<(), RecsError> {
+        if !self.entities.is_empty() {
+            return Err(RecsError::Other("entities can only be recreated in an empty scene".into()));
+        }
+        let mut versions = HashMap::new();
+        for entity in entities {
+            if *versions.entry(entity.index()).or_insert(entity.version()) != entity.version() {
+                return Err(RecsError::Other("saved entities disagree on an entity version".into()));
+            }
+        }
+
+        self.structure_generation += 1;
+        let len = versions.keys().max().map_or(0, |&index| index as usize + 1);
+        for index in 0..len as u32 {
+            let entity = match versions.get(&index) {
+                Some(&version) => Entity::with_version(index, version),
+                None => {
+                    let mut entity = Entity::new(index);
+                    entity.invalidate();
+                    entity
+                }
+            };
+            self.entities.push(EntityDescription::new(entity));
+            if entity.is_valid() {
+                self.mark_alive(entity);
+                #[cfg(feature = "metrics")]
+                self.metrics.entity_created();
+            }
+        }
+        self.free_list = (0..len as u32).rev().filter(|index| !versions.contains_key(index)).collect();
+        Ok(())
+    }
+
+    fn mark_alive(&mut self, entity: Entity) {
+        let index = entity.index() as usize;
+        if index >= self.alive_positions.len() {
+            self.alive_positions.resize(index + 1, u32::MAX);
+        }
+        self.alive_positions[index] = self.alive.len() as u32;
+        self.alive.push(entity);
+    }
+
+    fn mark_dead(&mut self, index: u32) {
+        let position = self.alive_positions[index as usize] as usize;
+        self.alive.swap_remove(position);
+        if let Some(&moved) = self.alive.get(position) {
+            self.alive_positions[moved.index() as usize] = position as u32;
+        }
+    }
+
+    /// Counts a lookup of a component of a valid entity for the metrics.
+    #[cfg(feature = "metrics")]
+    fn record_read(&self, type_id: usize, entity: Entity) {
+        let hit = self.entities[entity.index() as usize].has_component_with_type_id(type_id);
+        self.metrics.component_read(type_id, hit);
+    }
+
+    fn assert_entity_valid(&self, entity: Entity) -> Result<(), RecsError> {
+        self.entities
+            .get(entity.index() as usize)
+            .filter(|ed| ed.check_validity(entity))
+            .map_or(Err(RecsError::InvalidEntityError), |_| Ok(()))
+    }
+
+    fn get_valid_entity_description_mut(
+        &mut self,
+        entity: Entity,
+    ) -> Result<&mut EntityDescription, RecsError> {
+        self.entities
+            .get_mut(entity.index() as usize)
+            .filter(|ed| ed.check_validity(entity))
+            .ok_or(RecsError::InvalidEntityError)
+    }
+
+    /// Returns the pool of a component type, creating it with `create` if it doesn't exist yet.
+    fn get_or_create_pool<T: 'static, P: ErasedPool>(
+        &mut self,
+        type_id: usize,
+        create: impl FnOnce(AllocatorRef) -> P,
+    ) -> Result<&mut P, RecsError> {
+        if type_id >= self.pools.len() {
+            self.pools.resize_with(type_id + 1, || None);
+        }
+        if self.pools[type_id].is_none() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(type_id, type_name = type_name::<T>(), "pool created");
+            self.hooks.pool_created(type_id, type_name::<T>());
+            self.pools[type_id] = Some(Box::new(create(self.allocator.0)));
+        }
+        self.pool_by_id_mut(type_id)
+            .and_then(|pool| pool.as_any_mut().downcast_mut())
+            .ok_or(RecsError::PoolAccessOrCreationError)
+    }
+
+    fn get_pool_if_exists<P: 'static>(&self, type_id: usize) -> Option<&P> {
+        self.pool_by_id(type_id).and_then(|p| p.as_any().downcast_ref::<P>())
+    }
+
+    fn get_pool_if_exists_mut<P: 'static>(&mut self, type_id: usize) -> Option<&mut P> {
+        self.pool_by_id_mut(type_id).and_then(|p| p.as_any_mut().downcast_mut::<P>())
+    }
+
+    fn pool_by_id(&self, type_id: usize) -> Option<&dyn ErasedPool> {
+        self.pools.get(type_id)?.as_deref()
+    }
+
+    fn pool_by_id_mut(&mut self, type_id: usize) -> Option<&mut (dyn ErasedPool + 'static)> {
+        self.pools.get_mut(type_id)?.as_deref_mut()
     }
 }
 
-impl<'a> Iterator for SceneView<'a> {
-    type Item = Entity;
+pub(crate) fn interned_mutation() -> RecsError {
+    RecsError::Other("interned components can't be borrowed mutably, use Scene::assign_interned".into())
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        while self.index < self.scene.entities.len() {
-            let entity_description = &self.scene.entities[self.index];
+/// Assigns the default value of a component type to an entity that doesn't have one
+type InsertDefaultFn = fn(&mut Scene, Entity) -> Result<(), RecsError>;
+
+/// Returns true if components of type `T` are stored as tags when they have sparse storage.
+fn is_tag<T>() -> bool {
+    size_of::<T>() == 0
+}
+
+/// An iterator over a subset of the entities in a scene.
+pub struct SceneView<'a> {
+    scene: &'a Scene,
+    required_components: Vec<usize>,
+    /// Layers of which the entities have to be in at least one, if any
+    layers: Option<Layers>,
+    /// Archetype tables to walk instead of all entities, if any required component has table storage
+    tables: Option<Vec<usize>>,
+    /// Children of a parent to walk instead of all entities or tables, if the view is restricted to them
+    children: Option<&'a [Entity]>,
+    /// Index into the list of living entities, into `tables` when walking tables or into `children`
+    index: usize,
+    /// Row within the current table when walking tables
+    row: usize,
+    /// Number of entities checked so far, reported when the view is dropped
+    #[cfg(feature = "tracing")]
+    scanned: usize,
+    /// Number of entities yielded so far, reported when the view is dropped
+    #[cfg(feature = "tracing")]
+    matched: usize,
+}
+
+impl<'a> SceneView<'a> {
+    fn new(scene: &'a Scene, required_components: Vec<usize>) -> Self {
+        let table_components = required_components
+            .iter()
+            .copied()
+            .filter(|&id| scene.storage_type_of(id) == StorageType::Table)
+            .collect::<Vec<_>>();
+        let tables = (!table_components.is_empty())
+            .then(|| scene.archetypes.matching_tables(&table_components));
+        SceneView {
+            scene,
+            required_components,
+            layers: None,
+            tables,
+            children: None,
+            index: 0,
+            row: 0,
+            #[cfg(feature = "tracing")]
+            scanned: 0,
+            #[cfg(feature = "tracing")]
+            matched: 0,
+        }
+    }
+
+    fn next_from_entities(&mut self) -> Option<Entity> {
+        while let Some(&entity) = self.scene.alive.get(self.index) {
+            self.index += 1;
+            #[cfg(feature = "tracing")]
+            {
+                self.scanned += 1;
+            }
+
+            if self.entity_has_required_components(&self.scene.entities[entity.index() as usize]) {
+                return Some(entity);
+            }
+        }
+        None
+    }
+
+    fn next_from_children(&mut self) -> Option<Entity> {
+        while let Some(&entity) = self.children.and_then(|children| children.get(self.index)) {
             self.index += 1;
+            #[cfg(feature = "tracing")]
+            {
+                self.scanned += 1;
+            }
+
+            if self.entity_has_required_components(&self.scene.entities[entity.index() as usize]) {
+                return Some(entity);
+            }
+        }
+        None
+    }
 
-            if self.entity_has_required_components(entity_description)
-                && entity_description.entity.is_valid()
+    fn next_from_tables(&mut self) -> Option<Entity> {
+        let tables = self.scene.archetypes.tables();
+        while let Some(&table) = self.tables.as_ref().and_then(|tables| tables.get(self.index)) {
+            let Some(&entity) = tables[table].entities().get(self.row) else {
+                self.index += 1;
+                self.row = 0;
+                continue;
+            };
+            self.row += 1;
+            #[cfg(feature = "tracing")]
             {
-                return Some(entity_description.entity);
+                self.scanned += 1;
+            }
+
+            if self.entity_has_required_components(&self.scene.entities[entity.index() as usize]) {
+                return Some(entity);
             }
         }
         None
     }
+
+    fn entity_has_required_components(&self, entity_description: &EntityDescription) -> bool {
+        self.required_components
+            .iter()
+            .all(|&id| entity_description.has_component_with_type_id(id))
+            && self.layers.is_none_or(|layers| {
+                matches!(self.scene.get::<Layers>(entity_description.entity), Ok(Some(own)) if own.intersects(layers))
+            })
+    }
+}
+
+impl<'a> Iterator for SceneView<'a> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = if self.children.is_some() {
+            self.next_from_children()
+        } else if self.tables.is_some() {
+            self.next_from_tables()
+        } else {
+            self.next_from_entities()
+        };
+        #[cfg(feature = "tracing")]
+        if next.is_some() {
+            self.matched += 1;
+        }
+        next
+    }
+}
+
+impl<'a> SceneView<'a> {
+    /// Restricts the view to the children of `parent`, see [`Parent`]. Only the [`Children`] of the parent are
+    /// visited, so processing a subtree, like the parts of a vehicle or the widgets of a panel, doesn't scan
+    /// the whole scene. Call it before iterating.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Wheel;
+    ///
+    /// let mut scene = Scene::new();
+    /// let car = scene.create_entity();
+    /// let bike = scene.create_entity();
+    /// let wheels = (0..6).map(|_| scene.create_entity()).collect::<Vec<_>>();
+    /// for (i, &wheel) in wheels.iter().enumerate() {
+    ///     scene.assign(wheel, Wheel).unwrap();
+    ///     scene.set_parent(wheel, if i < 4 { car } else { bike }).unwrap();
+    /// }
+    /// let seat = scene.create_entity();
+    /// scene.set_parent(seat, bike).unwrap();
+    ///
+    /// assert_eq!(scene.view::<(Wheel, ())>().child_of(bike).collect::<Vec<_>>(), wheels[4..]);
+    /// assert_eq!(scene.view::<()>().child_of(bike).count(), 3);
+    /// assert_eq!(scene.view::<(Wheel, ())>().child_of(seat).count(), 0);
+    /// ```
+    pub fn child_of(mut self, parent: Entity) -> Self {
+        let children = self.scene.get::<Children>(parent).ok().flatten();
+        self.children = Some(children.map_or(&[], Children::as_slice));
+        self.tables = None;
+        self.index = 0;
+        self.row = 0;
+        self
+    }
+}
+
+impl SceneView<'_> {
+    /// Restricts the view to entities whose [`Layers`] component shares at least one layer with `layers`.
+    /// See [`Scene::register_layer`] for an example.
+    pub fn in_layers(mut self, layers: impl Into<Layers>) -> Self {
+        self.layers = Some(layers.into());
+        self
+    }
+
+    /// Yields the entities of the view ordered so that every entity comes after its ancestors, see [`Parent`].
+    /// A single pass over the view is then enough to propagate transforms or visibility down hierarchies.
+    /// Entities at the same depth keep the order of the view.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::hierarchy::Parent;
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Offset(f32);
+    /// struct Position(f32);
+    ///
+    /// let mut scene = Scene::new();
+    /// let wheel = scene.create_entity();
+    /// let car = scene.create_entity();
+    /// let ferry = scene.create_entity();
+    /// scene.set_parent(wheel, car).unwrap();
+    /// scene.set_parent(car, ferry).unwrap();
+    /// for (entity, offset) in [(wheel, 1.0), (car, 10.0), (ferry, 100.0)] {
+    ///     scene.assign(entity, Offset(offset)).unwrap();
+    ///     scene.assign(entity, Position(0.0)).unwrap();
+    /// }
+    ///
+    /// let ordered = scene.view::<(Offset, (Position, ()))>().hierarchy_ordered().collect::<Vec<_>>();
+    /// assert_eq!(ordered, vec![ferry, car, wheel]);
+    /// for entity in ordered {
+    ///     let parent_position = match scene.get::<Parent>(entity).unwrap() {
+    ///         Some(parent) => scene.get::<Position>(parent.get()).unwrap().unwrap().0,
+    ///         None => 0.0,
+    ///     };
+    ///     let offset = scene.get::<Offset>(entity).unwrap().unwrap().0;
+    ///     scene.get_mut::<Position>(entity).unwrap().unwrap().0 = parent_position + offset;
+    /// }
+    /// assert_eq!(scene.get::<Position>(wheel).unwrap().unwrap().0, 111.0);
+    /// ```
+    pub fn hierarchy_ordered(mut self) -> impl Iterator<Item = Entity> {
+        let scene = self.scene;
+        let mut entities = self.by_ref().map(|entity| (scene.ancestors(entity).count(), entity)).collect::<Vec<_>>();
+        entities.sort_by_key(|&(depth, _)| depth);
+        entities.into_iter().map(|(_, entity)| entity)
+    }
+}
+
+#[cfg(feature = "unchecked")]
+impl<'a> SceneView<'a> {
+    /// Pairs every entity in the view with its component of type `T`, skipping the checks of [`Scene::get`].
+    ///
+    /// # Safety
+    ///
+    /// `T` must be one of the components required by the view and must not be stored as a struct of arrays.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::scene::Scene;
+    ///
+    /// struct Mass(f32);
+    ///
+    /// let mut scene = Scene::new();
+    /// for _ in 0..4 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, Mass(1.5)).unwrap();
+    /// }
+    ///
+    /// // The view only yields entities with a `Mass`
+    /// let total = unsafe { scene.view::<(Mass, ())>().iter_unchecked::<Mass>() }
+    ///     .map(|(_, mass)| mass.0)
+    ///     .sum::<f32>();
+    /// assert_eq!(total, 6.0);
+    /// ```
+    pub unsafe fn iter_unchecked<T: 'static>(self) -> impl Iterator<Item = (Entity, &'a T)> {
+        let type_id = get_type_id::<T>();
+        debug_assert!(self.required_components.contains(&type_id), "component type is not required by the view");
+        let scene = self.scene;
+        let pool = scene.get_pool_if_exists::<Pool<T>>(type_id);
+        self.map(move |entity| {
+            // SAFETY: the view only yields alive entities with all required components, which include `T`
+            let component = unsafe {
+                match pool {
+                    Some(pool) => pool.get_unchecked(entity),
+                    None => scene.get_unchecked(entity),
+                }
+            };
+            (entity, component)
+        })
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for SceneView<'_> {
+    fn drop(&mut self) {
+        tracing::trace!(
+            required_components = ?self.required_components,
+            scanned = self.scanned,
+            matched = self.matched,
+            "scene view dropped"
+        );
+    }
+}
+
+/// Puts back the resource overridden by [`Scene::with_resource`] when dropped, even during a panic.
+struct RestoreResource<'a> {
+    scene: &'a mut Scene,
+    type_id: usize,
+    previous: Option<ResourceEntry>,
+}
+
+impl Drop for RestoreResource<'_> {
+    fn drop(&mut self) {
+        self.scene.resources.restore_entry(self.type_id, self.previous.take());
+    }
+}
+
+/// Formats the entities of a scene with their components, returned by [`Scene::display`].
+pub struct SceneDump<'a> {
+    scene: &'a Scene,
+    types: &'a TypeRegistry,
+}
+
+impl<'a> SceneDump<'a> {
+    /// Returns the living entities ordered by index.
+    fn entities(&self) -> Vec<&'a EntityDescription> {
+        let mut entities =
+            self.scene.alive.iter().map(|entity| &self.scene.entities[entity.index() as usize]).collect::<Vec<_>>();
+        entities.sort_by_key(|entity_description| entity_description.entity.index());
+        entities
+    }
+
+    /// Calls `f` with the name and the debug-formatted value, if available, of every component of the entity.
+    /// Components of registered types come first, ordered by name, then the others ordered by their Rust type names.
+    fn for_each_component(
+        &self,
+        entity_description: &EntityDescription,
+        mut f: impl FnMut(&str, Option<&dyn fmt::Debug>) -> fmt::Result,
+    ) -> fmt::Result {
+        let entity = entity_description.entity;
+        for (info, value) in self.types.components_of(self.scene, entity) {
+            match info.debug(value) {
+                Some(value) => f(info.name(), Some(&value))?,
+                None => f(info.name(), None)?,
+            }
+        }
+        let mut unregistered = entity_description
+            .components
+            .iter()
+            .filter(|&&type_id| self.types.get_by_type_id(type_id).is_none())
+            .map(|&type_id| get_type_name(type_id).unwrap_or("<unknown>"))
+            .collect::<Vec<_>>();
+        unregistered.sort_unstable();
+        unregistered.into_iter().try_for_each(|name| f(name, None))
+    }
+}
+
+impl fmt::Display for SceneDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entity_description in self.entities() {
+            let entity = entity_description.entity;
+            writeln!(f, "entity {}v{}", entity.index(), entity.version())?;
+            self.for_each_component(entity_description, |name, value| match value {
+                Some(value) => writeln!(f, "  {name}: {value:?}"),
+                None => writeln!(f, "  {name}"),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SceneDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        /// Formats an entity like `3v0`
+        struct EntityId(Entity);
+        impl fmt::Debug for EntityId {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}v{}", self.0.index(), self.0.version())
+            }
+        }
+        /// Formats the components of an entity as a map from their names to their values
+        struct Components<'d, 'a>(&'d SceneDump<'a>, &'a EntityDescription);
+        impl fmt::Debug for Components<'_, '_> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut map = f.debug_map();
+                self.0.for_each_component(self.1, |name, value| {
+                    match value {
+                        Some(value) => map.entry(&name, value),
+                        None => map.entry(&name, &format_args!("..")),
+                    };
+                    Ok(())
+                })?;
+                map.finish()
+            }
+        }
+
+        f.debug_map()
+            .entries(self.entities().into_iter().map(|entity_description| {
+                (EntityId(entity_description.entity), Components(self, entity_description))
+            }))
+            .finish()
+    }
 }
@@ -0,0 +1,1551 @@
+use std::{any::type_name, collections::BTreeMap, fmt};
+
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeStruct},
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    component::{get_type_id, RequireComponents, TypeIdMap, TypeIdSet},
+    entity::Entity,
+    error::RecsError,
+    mapping::EntityMapper,
+    registry::TypeRegistry,
+    scene::Scene,
+};
+
+/// A deserialized component, waiting for its entity to be recreated
+pub(crate) type DeferredAssign = Box<dyn FnOnce(&mut Scene, Entity) -> Result<(), RecsError>>;
+
+/// Deserializes a component and defers assigning it
+pub(crate) type DeserializeFn =
+    dyn for<'de> Fn(&mut dyn erased_serde::Deserializer<'de>) -> Result<DeferredAssign, erased_serde::Error>;
+
+/// Assigns a loaded component, replacing the value of a component the entity already has.
+fn assign_or_replace<T: 'static>(scene: &mut Scene, entity: Entity, component: T) -> Result<(), RecsError> {
+    match scene.get_mut::<T>(entity)? {
+        Some(existing) => *existing = component,
+        None => {
+            scene.assign(entity, component)?;
+        }
+    }
+    Ok(())
+}
+
+/// Type-erased functions to save and load the components of one type.
+pub(crate) struct Registration {
+    pub(crate) name: String,
+    type_id: usize,
+    pub(crate) serialize: for<'a> fn(&'a Scene, Entity) -> Option<&'a dyn erased_serde::Serialize>,
+    deserialize: for<'de> fn(&mut dyn erased_serde::Deserializer<'de>) -> Result<DeferredAssign, erased_serde::Error>,
+    remove: fn(&mut Scene, Entity),
+    /// Migrations from older formats, ordered by the schema version they lead up to
+    migrations: Vec<Migration>,
+}
+
+impl Registration {
+    /// Returns the function loading components saved with the given schema version.
+    pub(crate) fn deserializer_for(&self, version: u32) -> &DeserializeFn {
+        match self.migrations.iter().find(|migration| migration.since > version) {
+            Some(migration) => &*migration.deserialize,
+            None => &self.deserialize,
+        }
+    }
+}
+
+/// Loads components saved before the schema version `since` in their old format and converts them.
+struct Migration {
+    since: u32,
+    deserialize: Box<DeserializeFn>,
+}
+
+/// The component types that are saved and loaded with a scene, and the names they are saved under.
+/// Scenes store components behind type-erased pools, so the registry supplies the serialization functions
+/// that user code can't reach otherwise.
+///
+/// # Example
+///
+/// ```
+/// use serde::{de::DeserializeSeed, Deserialize, Serialize};
+/// use yarecs::scene::Scene;
+/// use yarecs::serialization::{ComponentRegistry, SceneDeserializer, SceneSerializer};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Position {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_as::<Position>("Position");
+///
+/// let mut scene = Scene::new();
+/// let entity = scene.create_entity();
+/// scene.assign(entity, Position { x: 1.0, y: 2.0 }).unwrap();
+///
+/// let json = serde_json::to_string(&SceneSerializer::new(&scene, &registry)).unwrap();
+/// assert_eq!(json, r#"{"version":0,"entities":[{"id":0,"components":{"Position":{"x":1.0,"y":2.0}}}]}"#);
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(&json);
+/// let loaded = SceneDeserializer::new(&registry).deserialize(&mut deserializer).unwrap();
+/// assert_eq!(loaded.get::<Position>(entity).unwrap().unwrap().y, 2.0);
+/// ```
+#[derive(Default)]
+pub struct ComponentRegistry {
+    registrations: Vec<Registration>,
+    /// Registrations by saved name, ordered so that components are saved in a stable order
+    by_name: BTreeMap<String, usize>,
+    by_type_id: TypeIdMap<usize>,
+    /// Version of the serialized format of the registered components, saved with every scene
+    schema_version: u32,
+    /// Functions loading components saved under names that are no longer registered
+    legacy: BTreeMap<String, Box<DeserializeFn>>,
+    unknown_components: UnknownComponents,
+}
+
+/// What happens to saved components whose names are neither registered nor legacy names,
+/// e.g. components of a plugin that isn't loaded, or of a newer version of the game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownComponents {
+    /// Loading fails
+    #[default]
+    Fail,
+    /// The components are dropped
+    Skip,
+    /// The components are kept in an [`OpaqueComponents`] component of their entity and saved again with it,
+    /// so tools can edit scenes without losing data they don't understand.
+    /// Requires a self-describing format, like JSON or RON.
+    Preserve,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry of all types of a [`TypeRegistry`] that have serialization enabled,
+    /// saved under the names they were registered under.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::registry::TypeRegistry;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Position(f32, f32);
+    /// struct Sprite;
+    ///
+    /// let mut types = TypeRegistry::new();
+    /// types.register::<Position>("Position").with_serde();
+    /// types.register::<Sprite>("Sprite");
+    ///
+    /// let registry = ComponentRegistry::from_types(&types);
+    /// assert_eq!(registry.len(), 1);
+    /// assert_eq!(registry.name_of::<Position>(), Some("Position"));
+    /// ```
+    pub fn from_types(types: &TypeRegistry) -> Self {
+        let mut registry = ComponentRegistry::new();
+        for info in types.iter() {
+            if let Some(register) = info.register_component {
+                register(&mut registry, info.name());
+            }
+        }
+        registry
+    }
+
+    /// Registers a component type under its full type path.
+    pub fn register<T: Serialize + DeserializeOwned + 'static>(&mut self) -> &mut Self {
+        self.register_as::<T>(type_name::<T>())
+    }
+
+    /// Registers a component type under a custom name, e.g. a short one for hand-edited files,
+    /// or the old path of a type that moved to another module. Registering a type again replaces its name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another component type is already registered under the same name.
+    pub fn register_as<T: Serialize + DeserializeOwned + 'static>(&mut self, name: impl Into<String>) -> &mut Self {
+        let name = name.into();
+        let type_id = get_type_id::<T>();
+        if let Some(&index) = self.by_name.get(&name) {
+            assert_eq!(self.by_type_id.get(&type_id), Some(&index), "component name {name} is already registered");
+        }
+        assert!(!self.legacy.contains_key(&name), "component name {name} is already registered as a legacy name");
+        let registration = Registration {
+            name: name.clone(),
+            type_id,
+            serialize: |scene, entity| {
+                let component = scene.get::<T>(entity).ok().flatten()?;
+                Some(component as &dyn erased_serde::Serialize)
+            },
+            deserialize: |deserializer| {
+                let component = erased_serde::deserialize::<T>(deserializer)?;
+                Ok(Box::new(move |scene: &mut Scene, entity| assign_or_replace(scene, entity, component)))
+            },
+            remove: |scene, entity| {
+                let _ = scene.remove::<T>(entity);
+            },
+            migrations: Vec::new(),
+        };
+        match self.by_type_id.get(&type_id) {
+            Some(&index) => {
+                let mut registration = registration;
+                registration.migrations = std::mem::take(&mut self.registrations[index].migrations);
+                self.by_name.remove(&self.registrations[index].name);
+                self.registrations[index] = registration;
+                self.by_name.insert(name, index);
+            }
+            None => {
+                self.by_type_id.insert(type_id, self.registrations.len());
+                self.by_name.insert(name, self.registrations.len());
+                self.registrations.push(registration);
+            }
+        }
+        self
+    }
+
+    /// Sets the schema version saved with every scene. Increase it whenever a registered component type
+    /// changes its serialized format, and register a migration for the old format with
+    /// [`ComponentRegistry::register_migration`].
+    /// Scenes saved without a version, or before the version was set, have version 0.
+    pub fn set_schema_version(&mut self, version: u32) -> &mut Self {
+        self.schema_version = version;
+        self
+    }
+
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    /// Sets what happens to unknown components when loading. By default, loading fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{de::DeserializeSeed, Deserialize, Serialize};
+    /// use yarecs::serialization::{
+    ///     ComponentRegistry, OpaqueComponents, SceneDeserializer, SceneSerializer, UnknownComponents,
+    /// };
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Position(f32, f32);
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Position>("Position");
+    ///
+    /// // Written by a version of the editor that knows about health
+    /// let json = r#"{"version":0,"entities":[{"id":0,"components":{"Position":[1.0,2.0],"Health":{"max":30}}}]}"#;
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// assert!(SceneDeserializer::new(&registry).deserialize(&mut deserializer).is_err());
+    ///
+    /// registry.set_unknown_components(UnknownComponents::Preserve);
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let scene = SceneDeserializer::new(&registry).deserialize(&mut deserializer).unwrap();
+    /// let entity = scene.entities()[0];
+    /// assert!(scene.get::<OpaqueComponents>(entity).unwrap().unwrap().get("Health").is_some());
+    /// assert_eq!(serde_json::to_string(&SceneSerializer::new(&scene, &registry)).unwrap(), json);
+    ///
+    /// registry.set_unknown_components(UnknownComponents::Skip);
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let scene = SceneDeserializer::new(&registry).deserialize(&mut deserializer).unwrap();
+    /// assert!(scene.get::<OpaqueComponents>(entity).unwrap().is_none());
+    /// assert_eq!(scene.get::<Position>(entity).unwrap().unwrap().1, 2.0);
+    /// ```
+    pub fn set_unknown_components(&mut self, unknown_components: UnknownComponents) -> &mut Self {
+        self.unknown_components = unknown_components;
+        self
+    }
+
+    pub fn unknown_components(&self) -> UnknownComponents {
+        self.unknown_components
+    }
+
+    /// Lets components of type `T` that were saved with a schema version older than `since` load: they are
+    /// deserialized as `Old` and converted with `migrate`. If several migrations apply to a scene, the one with
+    /// the lowest `since` is used, so every migration converts the format that was current right before `since`.
+    /// Scenes saved with a newer schema version than the registry's fail to load.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// // Version 1 stored the health as a single number
+    /// #[derive(Deserialize)]
+    /// struct HealthV1(u32);
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Health {
+    ///     current: u32,
+    ///     max: u32,
+    /// }
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Health>("Health").set_schema_version(2);
+    /// registry.register_migration::<Health, HealthV1>(2, |old| Health { current: old.0, max: 100 });
+    ///
+    /// let old_save = r#"{ "version": 1, "entities": [{ "id": 0, "components": { "Health": 80 } }] }"#;
+    /// let scene = Scene::from_json(old_save, &registry).unwrap();
+    /// let health = scene.get::<Health>(scene.entities()[0]).unwrap().unwrap();
+    /// assert_eq!((health.current, health.max), (80, 100));
+    ///
+    /// let new_save = scene.to_json(&registry).unwrap();
+    /// assert!(new_save.contains(r#""version": 2"#));
+    /// assert!(Scene::from_json(&new_save, &registry).is_ok());
+    /// ```
+    pub fn register_migration<T: 'static, Old: DeserializeOwned + 'static>(
+        &mut self,
+        since: u32,
+        migrate: impl Fn(Old) -> T + 'static,
+    ) -> &mut Self {
+        let Some(&index) = self.by_type_id.get(&get_type_id::<T>()) else {
+            panic!("component type {} is not registered", type_name::<T>());
+        };
+        let deserialize = move |deserializer: &mut dyn erased_serde::Deserializer<'_>| {
+            let component = migrate(erased_serde::deserialize::<Old>(deserializer)?);
+            let assign: DeferredAssign = Box::new(move |scene, entity| assign_or_replace(scene, entity, component));
+            Ok(assign)
+        };
+        let migrations = &mut self.registrations[index].migrations;
+        migrations.retain(|migration| migration.since != since);
+        migrations.push(Migration { since, deserialize: Box::new(deserialize) });
+        migrations.sort_by_key(|migration| migration.since);
+        self
+    }
+
+    /// Lets components saved under a name that is no longer registered, e.g. of a type that was renamed or
+    /// split up, load as components of type `T`. The saved data is deserialized as `Old`, which can be the
+    /// old type itself or a self-describing value like `serde_json::Value`, and `migrate` is called with the
+    /// old name and the data. If it returns `None`, the component is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a component type is registered under `old_name`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use yarecs::scene::Scene;
+    /// use yarecs::serialization::ComponentRegistry;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Velocity(f32, f32);
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_as::<Velocity>("Velocity");
+    /// // Older saves had separate speed components per axis, and speeds above 100 were invalid
+    /// for old_name in ["SpeedX", "SpeedY"] {
+    ///     registry.register_legacy::<Velocity, f32>(old_name, |old_name, speed| {
+    ///         (speed <= 100.0).then(|| match old_name {
+    ///             "SpeedX" => Velocity(speed, 0.0),
+    ///             _ => Velocity(0.0, speed),
+    ///         })
+    ///     });
+    /// }
+    ///
+    /// let old_save = r#"{ "entities": [
+    ///     { "id": 0, "components": { "SpeedY": 2.5 } },
+    ///     { "id": 1, "components": { "SpeedX": 900.0 } }
+    /// ] }"#;
+    /// let scene = Scene::from_json(old_save, &registry).unwrap();
+    /// let mut entities = scene.entities().to_vec();
+    /// entities.sort_by_key(|entity| entity.index());
+    /// assert_eq!(scene.get::<Velocity>(entities[0]).unwrap().unwrap().1, 2.5);
+    /// assert!(scene.get::<Velocity>(entities[1]).unwrap().is_none());
+    /// ```
+    pub fn register_legacy<T: 'static, Old: DeserializeOwned + 'static>(
+        &mut self,
+        old_name: impl Into<String>,
+        migrate: impl Fn(&str, Old) -> Option<T> + 'static,
+    ) -> &mut Self {
+        let old_name = old_name.into();
+        assert!(!self.by_name.contains_key(&old_name), "component name {old_name} is already registered");
+        let name = old_name.clone();
+        let deserialize = move |deserializer: &mut dyn erased_serde::Deserializer<'_>| {
+            let component = migrate(&name, erased_serde::deserialize::<Old>(deserializer)?);
+            let assign: DeferredAssign = Box::new(move |scene, entity| match component {
+                Some(component) => assign_or_replace(scene, entity, component),
+                None => Ok(()),
+            });
+            Ok(assign)
+        };
+        self.legacy.insert(old_name, Box::new(deserialize));
+        self
+    }
+
+    /// Returns the name a component type is saved under, if it is registered.
+    pub fn name_of<T: 'static>(&self) -> Option<&str> {
+        let index = *self.by_type_id.get(&get_type_id::<T>())?;
+        Some(&self.registrations[index].name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn get_by_type_id(&self, type_id: usize) -> Option<&Registration> {
+        self.by_type_id.get(&type_id).map(|&index| &self.registrations[index])
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Registration> {
+        self.by_name.get(name).map(|&index| &self.registrations[index])
+    }
+
+    /// Returns the registrations ordered by name.
+    fn iter(&self) -> impl Iterator<Item = &Registration> {
+        self.by_name.values().map(|&index| &self.registrations[index])
+    }
+}
+
+impl fmt::Debug for ComponentRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.by_name.keys()).finish()
+    }
+}
+
+/// Selects which of the registered component types are saved, e.g. to leave out caches that are rebuilt after
+/// loading, or to save only a few types and merge them into an existing scene with a [`SceneMergeDeserializer`].
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use yarecs::scene::Scene;
+/// use yarecs::serialization::{ComponentFilter, ComponentRegistry, SceneSerializer};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Name(String);
+/// #[derive(Serialize, Deserialize)]
+/// struct MeshCache(Vec<u8>);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_as::<Name>("Name").register_as::<MeshCache>("MeshCache");
+///
+/// let mut scene = Scene::new();
+/// let tree = scene.create_entity();
+/// scene.assign(tree, Name("oak".to_string())).unwrap();
+/// scene.assign(tree, MeshCache(vec![0; 1024])).unwrap();
+///
+/// let filter = ComponentFilter::all().exclude::<MeshCache>();
+/// let json = serde_json::to_string(&SceneSerializer::new(&scene, &registry).with_filter(filter)).unwrap();
+/// assert!(json.contains("oak"));
+/// assert!(!json.contains("MeshCache"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ComponentFilter {
+    /// Types to save, or `None` to save all types that aren't excluded
+    include: Option<TypeIdSet>,
+    exclude: TypeIdSet,
+}
+
+impl ComponentFilter {
+    /// Creates a filter that lets all component types through.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Creates a filter that lets no component types through, so that only types added with
+    /// [`ComponentFilter::include`] are saved.
+    pub fn none() -> Self {
+        ComponentFilter { include: Some(TypeIdSet::default()), exclude: TypeIdSet::default() }
+    }
+
+    /// Lets components of type `T` through, unless they are excluded.
+    pub fn include<T: 'static>(mut self) -> Self {
+        if let Some(include) = &mut self.include {
+            include.insert(get_type_id::<T>());
+        }
+        self
+    }
+
+    /// Keeps components of type `T` from being saved.
+    pub fn exclude<T: 'static>(mut self) -> Self {
+        self.exclude.insert(get_type_id::<T>());
+        self
+    }
+
+    fn allows(&self, type_id: usize) -> bool {
+        !self.exclude.contains(&type_id) && self.include.as_ref().is_none_or(|include| include.contains(&type_id))
+    }
+}
+
+/// Serializes all living entities of a scene, in order of their indices, together with their registered components.
+/// Components of types that aren't registered are left out.
+pub struct SceneSerializer<'a> {
+    scene: &'a Scene,
+    registry: &'a ComponentRegistry,
+    selection: Selection,
+    filter: ComponentFilter,
+}
+
+/// Which entities a [`SceneSerializer`] writes.
+enum Selection {
+    All,
+    /// Entities that changed after the change tick, followed by the entities destroyed since
+    ChangedSince(u32),
+    /// The given entities, ordered by index
+    Entities(Vec<Entity>),
+}
+
+impl<'a> SceneSerializer<'a> {
+    pub fn new(scene: &'a Scene, registry: &'a ComponentRegistry) -> Self {
+        SceneSerializer { scene, registry, selection: Selection::All, filter: ComponentFilter::all() }
+    }
+
+    /// Creates a serializer for the entities that changed after the given change tick,
+    /// followed by the entities destroyed since. See [`Scene::serialize_changes_since`].
+    pub fn changes_since(scene: &'a Scene, registry: &'a ComponentRegistry, tick: u32) -> Self {
+        SceneSerializer { scene, registry, selection: Selection::ChangedSince(tick), filter: ComponentFilter::all() }
+    }
+
+    /// Creates a serializer for the entities that have all components of `F`.
+    /// See [`Scene::serialize_matching`].
+    pub fn matching<F: RequireComponents>(scene: &'a Scene, registry: &'a ComponentRegistry) -> Self {
+        let mut entities = scene.view::<F>().collect::<Vec<_>>();
+        entities.sort_by_key(Entity::index);
+        SceneSerializer { scene, registry, selection: Selection::Entities(entities), filter: ComponentFilter::all() }
+    }
+
+    /// Saves only the component types the filter lets through.
+    pub fn with_filter(mut self, filter: ComponentFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+impl Serialize for SceneSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entities = match &self.selection {
+            Selection::All => {
+                let mut entities = self.scene.entities().to_vec();
+                entities.sort_by_key(Entity::index);
+                entities
+            }
+            Selection::ChangedSince(tick) => self.scene.entities_changed_since(*tick),
+            Selection::Entities(entities) => entities.clone(),
+        };
+        let entities = entities
+            .into_iter()
+            .map(|entity| EntitySerializer {
+                scene: self.scene,
+                registry: self.registry,
+                filter: &self.filter,
+                entity,
+            })
+            .collect::<Vec<_>>();
+        match self.selection {
+            Selection::ChangedSince(tick) => {
+                let mut state = serializer.serialize_struct("SceneChanges", 3)?;
+                state.serialize_field("version", &self.registry.schema_version)?;
+                state.serialize_field("entities", &entities)?;
+                state.serialize_field("destroyed", &self.scene.entities_destroyed_since(tick))?;
+                state.end()
+            }
+            Selection::All | Selection::Entities(_) => {
+                let mut state = serializer.serialize_struct("Scene", 2)?;
+                state.serialize_field("version", &self.registry.schema_version)?;
+                state.serialize_field("entities", &entities)?;
+                state.end()
+            }
+        }
+    }
+}
+
+pub(crate) struct EntitySerializer<'a> {
+    pub(crate) scene: &'a Scene,
+    pub(crate) registry: &'a ComponentRegistry,
+    pub(crate) filter: &'a ComponentFilter,
+    pub(crate) entity: Entity,
+}
+
+impl Serialize for EntitySerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut components = self
+            .registry
+            .iter()
+            .filter(|registration| self.filter.allows(registration.type_id))
+            .filter_map(|registration| Some((&registration.name, (registration.serialize)(self.scene, self.entity)?)))
+            .collect::<Vec<_>>();
+        if self.filter.allows(get_type_id::<OpaqueComponents>()) {
+            if let Ok(Some(opaque)) = self.scene.get::<OpaqueComponents>(self.entity) {
+                components.extend(
+                    opaque
+                        .components
+                        .iter()
+                        .map(|(name, value)| (name, value as &dyn erased_serde::Serialize)),
+                );
+            }
+        }
+        let mut state = serializer.serialize_struct("Entity", 2)?;
+        state.serialize_field("id", &self.entity)?;
+        state.serialize_field("components", &ComponentsSerializer(&components))?;
+        state.end()
+    }
+}
+
+struct ComponentsSerializer<'a>(&'a [(&'a String, &'a dyn erased_serde::Serialize)]);
+
+impl Serialize for ComponentsSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().copied())
+    }
+}
+
+/// Deserializes a scene written by a [`SceneSerializer`] into a new scene.
+/// Entities keep their saved indices and versions, so entities stored inside components stay valid.
+/// See [`ComponentRegistry`] for an example.
+pub struct SceneDeserializer<'a> {
+    registry: &'a ComponentRegistry,
+}
+
+impl<'a> SceneDeserializer<'a> {
+    pub fn new(registry: &'a ComponentRegistry) -> Self {
+        SceneDeserializer { registry }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for SceneDeserializer<'_> {
+    type Value = Scene;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Scene, D::Error> {
+        deserializer.deserialize_struct("Scene", SCENE_FIELDS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SceneDeserializer<'_> {
+    type Value = Scene;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scene")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Scene, A::Error> {
+        let saved = read_elements(self.registry, seq, false, &self)?;
+        build_scene(saved.entities).map_err(de::Error::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Scene, A::Error> {
+        let saved = read_fields(self.registry, map, SCENE_FIELDS)?;
+        build_scene(saved.entities).map_err(de::Error::custom)
+    }
+}
+
+const SCENE_FIELDS: &[&str] = &["version", "entities"];
+const CHANGES_FIELDS: &[&str] = &["version", "entities", "destroyed"];
+
+/// The contents of a saved scene or of saved changes.
+struct Saved {
+    entities: Vec<(Entity, Vec<DeferredAssign>)>,
+    destroyed: Vec<Entity>,
+}
+
+/// Checks that a scene wasn't saved with a newer schema version than the registry knows.
+pub(crate) fn checked_version<E: de::Error>(registry: &ComponentRegistry, version: u32) -> Result<u32, E> {
+    if version > registry.schema_version {
+        return Err(E::custom(format!(
+            "scene was saved with schema version {version}, but the newest known version is {}",
+            registry.schema_version
+        )));
+    }
+    Ok(version)
+}
+
+/// Reads a saved scene from a format that stores structs as sequences.
+fn read_elements<'de, A: SeqAccess<'de>>(
+    registry: &ComponentRegistry,
+    mut seq: A,
+    with_destroyed: bool,
+    expected: &dyn de::Expected,
+) -> Result<Saved, A::Error> {
+    let version = seq.next_element::<u32>()?.ok_or_else(|| de::Error::invalid_length(0, expected))?;
+    let version = checked_version(registry, version)?;
+    let entities = seq
+        .next_element_seed(EntitiesSeed(registry, version))?
+        .ok_or_else(|| de::Error::invalid_length(1, expected))?;
+    let destroyed = match with_destroyed {
+        true => seq.next_element::<Vec<Entity>>()?.unwrap_or_default(),
+        false => Vec::new(),
+    };
+    Ok(Saved { entities, destroyed })
+}
+
+/// Reads a saved scene from a format that stores structs as maps.
+/// The schema version has to come before the entities, which are loaded according to it.
+fn read_fields<'de, A: MapAccess<'de>>(
+    registry: &ComponentRegistry,
+    mut map: A,
+    fields: &'static [&'static str],
+) -> Result<Saved, A::Error> {
+    let mut version = None;
+    let mut entities = None;
+    let mut destroyed = None;
+    while let Some(key) = map.next_key::<String>()? {
+        match key.as_str() {
+            "version" if version.is_some() => return Err(de::Error::duplicate_field("version")),
+            "version" if entities.is_some() => {
+                return Err(de::Error::custom("the schema version has to come before the entities"));
+            }
+            "version" => version = Some(checked_version(registry, map.next_value()?)?),
+            "entities" if entities.is_some() => return Err(de::Error::duplicate_field("entities")),
+            "entities" => {
+                entities = Some(map.next_value_seed(EntitiesSeed(registry, version.unwrap_or(0)))?);
+            }
+            "destroyed" if fields.contains(&"destroyed") && destroyed.is_some() => {
+                return Err(de::Error::duplicate_field("destroyed"));
+            }
+            "destroyed" if fields.contains(&"destroyed") => destroyed = Some(map.next_value::<Vec<Entity>>()?),
+            _ => return Err(de::Error::unknown_field(&key, fields)),
+        }
+    }
+    let entities = entities.ok_or_else(|| de::Error::missing_field("entities"))?;
+    Ok(Saved { entities, destroyed: destroyed.unwrap_or_default() })
+}
+
+/// Recreates the saved entities in a new scene and assigns their components.
+fn build_scene(entities: Vec<(Entity, Vec<DeferredAssign>)>) -> Result<Scene, RecsError> {
+    let mut scene = Scene::new();
+    scene.recreate_entities(entities.iter().map(|(entity, _)| *entity))?;
+    for (entity, components) in entities {
+        for assign in components {
+            assign(&mut scene, entity)?;
+        }
+    }
+    Ok(scene)
+}
+
+/// Applies a scene or changes written by a [`SceneSerializer`] of a remote scene, e.g. of a server, to an existing
+/// scene, translating entities with an [`EntityMapper`]. Remote entities get a local entity the first time
+/// they appear, and their registered components are replaced with the received ones. The local entities of
+/// entities listed as destroyed are destroyed, and mappings of local entities that died are dropped.
+///
+/// # Example
+///
+/// ```
+/// use serde::{de::DeserializeSeed, Deserialize, Serialize};
+/// use yarecs::{mapping::EntityMapper, scene::Scene};
+/// use yarecs::serialization::{ComponentRegistry, SceneUpdateDeserializer};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Health(u32);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_as::<Health>("Health");
+///
+/// let mut server = Scene::new();
+/// let monster = server.create_entity();
+/// server.assign(monster, Health(30)).unwrap();
+///
+/// let mut client = Scene::new();
+/// let mut mapper = EntityMapper::new();
+/// let mut receive = |client: &mut Scene, message: String| {
+///     let mut deserializer = serde_json::Deserializer::from_str(&message);
+///     SceneUpdateDeserializer::new(&registry, client, &mut mapper).deserialize(&mut deserializer).unwrap();
+/// };
+///
+/// receive(&mut client, serde_json::to_string(&server.serialize_changes_since(0, &registry)).unwrap());
+/// assert_eq!(client.entity_count(), 1);
+///
+/// let tick = server.change_tick();
+/// server.increment_change_tick();
+/// server.destroy_entity(monster);
+/// receive(&mut client, serde_json::to_string(&server.serialize_changes_since(tick, &registry)).unwrap());
+/// assert_eq!(client.entity_count(), 0);
+/// ```
+pub struct SceneUpdateDeserializer<'a> {
+    registry: &'a ComponentRegistry,
+    scene: &'a mut Scene,
+    mapper: &'a mut EntityMapper,
+}
+
+impl<'a> SceneUpdateDeserializer<'a> {
+    pub fn new(registry: &'a ComponentRegistry, scene: &'a mut Scene, mapper: &'a mut EntityMapper) -> Self {
+        SceneUpdateDeserializer { registry, scene, mapper }
+    }
+
+    fn apply(self, entities: Vec<(Entity, Vec<DeferredAssign>)>, destroyed: Vec<Entity>) -> Result<(), RecsError> {
+        self.mapper.retain_alive(self.scene);
+        for remote in destroyed {
+            self.mapper.destroy_remote(remote, self.scene);
+        }
+        for (remote, components) in entities {
+            let local = self.mapper.local_or_create(remote, self.scene);
+            for registration in &self.registry.registrations {
+                (registration.remove)(self.scene, local);
+            }
+            for assign in components {
+                assign(self.scene, local)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for SceneUpdateDeserializer<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_struct("SceneChanges", CHANGES_FIELDS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SceneUpdateDeserializer<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scene or scene changes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        let saved = read_elements(self.registry, seq, true, &self)?;
+        self.apply(saved.entities, saved.destroyed).map_err(de::Error::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        let saved = read_fields(self.registry, map, CHANGES_FIELDS)?;
+        self.apply(saved.entities, saved.destroyed).map_err(de::Error::custom)
+    }
+}
+
+/// Merges the components of a scene written by a [`SceneSerializer`] into the entities of an existing scene,
+/// instead of loading a new scene. Saved entities that are alive in the scene get the saved components,
+/// replacing components of the same types; other components are kept. Saved entities that aren't alive are
+/// recreated with their saved indices and versions, which fails if their indices are taken by other entities.
+///
+/// # Example
+///
+/// ```
+/// use serde::{de::DeserializeSeed, Deserialize, Serialize};
+/// use yarecs::scene::Scene;
+/// use yarecs::serialization::{ComponentFilter, ComponentRegistry, SceneMergeDeserializer, SceneSerializer};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Position(i32, i32);
+/// #[derive(Serialize, Deserialize)]
+/// struct Settings(String);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_as::<Position>("Position").register_as::<Settings>("Settings");
+///
+/// let mut scene = Scene::new();
+/// let player = scene.create_entity();
+/// scene.assign(player, Position(0, 0)).unwrap();
+/// scene.assign(player, Settings("vsync".to_string())).unwrap();
+///
+/// // Checkpoints only store positions
+/// let filter = ComponentFilter::none().include::<Position>();
+/// let checkpoint = serde_json::to_string(&SceneSerializer::new(&scene, &registry).with_filter(filter)).unwrap();
+///
+/// scene.get_mut::<Position>(player).unwrap().unwrap().0 = 50;
+/// scene.get_mut::<Settings>(player).unwrap().unwrap().0 = "no vsync".to_string();
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(&checkpoint);
+/// SceneMergeDeserializer::new(&registry, &mut scene).deserialize(&mut deserializer).unwrap();
+/// assert_eq!(scene.get::<Position>(player).unwrap().unwrap().0, 0);
+/// assert_eq!(scene.get::<Settings>(player).unwrap().unwrap().0, "no vsync");
+/// ```
+pub struct SceneMergeDeserializer<'a> {
+    registry: &'a ComponentRegistry,
+    scene: &'a mut Scene,
+}
+
+impl<'a> SceneMergeDeserializer<'a> {
+    pub fn new(registry: &'a ComponentRegistry, scene: &'a mut Scene) -> Self {
+        SceneMergeDeserializer { registry, scene }
+    }
+
+    fn merge(self, entities: Vec<(Entity, Vec<DeferredAssign>)>) -> Result<(), RecsError> {
+        merge_entities(self.scene, entities)
+    }
+}
+
+/// Assigns loaded components to their entities, recreating entities that aren't alive.
+pub(crate) fn merge_entities(
+    scene: &mut Scene,
+    entities: Vec<(Entity, Vec<DeferredAssign>)>,
+) -> Result<(), RecsError> {
+    for (entity, components) in entities {
+        if !scene.is_alive(entity) {
+            scene.create_entity_at(entity)?;
+        }
+        for assign in components {
+            assign(scene, entity)?;
+        }
+    }
+    Ok(())
+}
+
+impl<'de> DeserializeSeed<'de> for SceneMergeDeserializer<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_struct("Scene", SCENE_FIELDS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SceneMergeDeserializer<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scene")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        let saved = read_elements(self.registry, seq, false, &self)?;
+        self.merge(saved.entities).map_err(de::Error::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        let saved = read_fields(self.registry, map, SCENE_FIELDS)?;
+        self.merge(saved.entities).map_err(de::Error::custom)
+    }
+}
+
+/// Seeds carry the registry and the schema version the scene was saved with.
+pub(crate) struct EntitiesSeed<'a>(pub(crate) &'a ComponentRegistry, pub(crate) u32);
+
+impl<'de> DeserializeSeed<'de> for EntitiesSeed<'_> {
+    type Value = Vec<(Entity, Vec<DeferredAssign>)>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntitiesSeed<'_> {
+    type Value = Vec<(Entity, Vec<DeferredAssign>)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of entities")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut entities = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(entity) = seq.next_element_seed(EntitySeed(self.0, self.1))? {
+            entities.push(entity);
+        }
+        Ok(entities)
+    }
+}
+
+struct EntitySeed<'a>(&'a ComponentRegistry, u32);
+
+impl<'de> DeserializeSeed<'de> for EntitySeed<'_> {
+    type Value = (Entity, Vec<DeferredAssign>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("Entity", &["id", "components"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntitySeed<'_> {
+    type Value = (Entity, Vec<DeferredAssign>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entity with its components")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let entity = seq.next_element::<Entity>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let components = seq.next_element_seed(ComponentsSeed(self.0, self.1))?.unwrap_or_default();
+        Ok((entity, components))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut id = None;
+        let mut components = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" if id.is_none() => id = Some(map.next_value::<Entity>()?),
+                "components" if components.is_none() => {
+                    components = Some(map.next_value_seed(ComponentsSeed(self.0, self.1))?);
+                }
+                "id" => return Err(de::Error::duplicate_field("id")),
+                "components" => return Err(de::Error::duplicate_field("components")),
+                _ => return Err(de::Error::unknown_field(&key, &["id", "components"])),
+            }
+        }
+        let entity = id.ok_or_else(|| de::Error::missing_field("id"))?;
+        Ok((entity, components.unwrap_or_default()))
+    }
+}
+
+struct ComponentsSeed<'a>(&'a ComponentRegistry, u32);
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_> {
+    type Value = Vec<DeferredAssign>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_> {
+    type Value = Vec<DeferredAssign>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of component names to components")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::<DeferredAssign>::new();
+        while let Some(name) = map.next_key::<String>()? {
+            let deserialize = match self.0.get(&name) {
+                Some(registration) => Some(registration.deserializer_for(self.1)),
+                None => self.0.legacy.get(&name).map(|deserialize| &**deserialize),
+            };
+            match (deserialize, self.0.unknown_components) {
+                (Some(deserialize), _) => components.push(map.next_value_seed(ComponentSeed(deserialize))?),
+                (None, UnknownComponents::Fail) => {
+                    return Err(de::Error::custom(format!("component type {name} is not registered")));
+                }
+                (None, UnknownComponents::Skip) => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+                (None, UnknownComponents::Preserve) => {
+                    let value = map.next_value::<OpaqueValue>()?;
+                    components.push(Box::new(move |scene, entity| preserve_unknown(scene, entity, name, value)));
+                }
+            }
+        }
+        Ok(components)
+    }
+}
+
+pub(crate) struct ComponentSeed<'a>(pub(crate) &'a DeserializeFn);
+
+impl<'de> DeserializeSeed<'de> for ComponentSeed<'_> {
+    type Value = DeferredAssign;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<DeferredAssign, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.0)(&mut deserializer).map_err(de::Error::custom)
+    }
+}
+
+/// Adds a loaded unknown component to the opaque components of its entity.
+fn preserve_unknown(scene: &mut Scene, entity: Entity, name: String, value: OpaqueValue) -> Result<(), RecsError> {
+    match scene.get_mut::<OpaqueComponents>(entity)? {
+        Some(opaque) => opaque.insert(name, value),
+        None => {
+            scene.assign(entity, OpaqueComponents { components: vec![(name, value)] })?;
+        }
+    }
+    Ok(())
+}
+
+/// Components of an entity that were loaded under unknown names and kept as they were saved,
+/// see [`UnknownComponents::Preserve`]. They are saved again under their names along with the registered
+/// components, unless a [`ComponentFilter`] excludes this type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpaqueComponents {
+    /// Names and values in the order they were loaded
+    components: Vec<(String, OpaqueValue)>,
+}
+
+impl OpaqueComponents {
+    /// Returns the value of the component saved under the name.
+    pub fn get(&self, name: &str) -> Option<&OpaqueValue> {
+        self.components.iter().find(|(component, _)| component == name).map(|(_, value)| value)
+    }
+
+    /// Adds a component, replacing the value of a component with the same name.
+    pub fn insert(&mut self, name: String, value: OpaqueValue) {
+        match self.components.iter_mut().find(|(component, _)| *component == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.components.push((name, value)),
+        }
+    }
+
+    /// Removes the component saved under the name and returns its value.
+    pub fn remove(&mut self, name: &str) -> Option<OpaqueValue> {
+        let index = self.components.iter().position(|(component, _)| component == name)?;
+        Some(self.components.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &OpaqueValue)> {
+        self.components.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+/// A value of any self-describing format, loaded without knowing its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpaqueValue {
+    Unit,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Option(Option<Box<OpaqueValue>>),
+    Seq(Vec<OpaqueValue>),
+    /// Entries in the order they were loaded
+    Map(Vec<(OpaqueValue, OpaqueValue)>),
+}
+
+impl Serialize for OpaqueValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OpaqueValue::Unit => serializer.serialize_unit(),
+            OpaqueValue::Bool(value) => serializer.serialize_bool(*value),
+            OpaqueValue::I64(value) => serializer.serialize_i64(*value),
+            OpaqueValue::U64(value) => serializer.serialize_u64(*value),
+            OpaqueValue::F64(value) => serializer.serialize_f64(*value),
+            OpaqueValue::String(value) => serializer.serialize_str(value),
+            OpaqueValue::Bytes(value) => serializer.serialize_bytes(value),
+            OpaqueValue::Option(None) => serializer.serialize_none(),
+            OpaqueValue::Option(Some(value)) => serializer.serialize_some(value),
+            OpaqueValue::Seq(values) => serializer.collect_seq(values),
+            OpaqueValue::Map(entries) => serializer.collect_map(entries.iter().map(|(key, value)| (key, value))),
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for OpaqueValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(OpaqueValueVisitor)
+    }
+}
+
+struct OpaqueValueVisitor;
+
+impl<'de> Visitor<'de> for OpaqueValueVisitor {
+    type Value = OpaqueValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::I64(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::U64(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::F64(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Bytes(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Option(None))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<OpaqueValue, D::Error> {
+        let value = deserializer.deserialize_any(OpaqueValueVisitor)?;
+        Ok(OpaqueValue::Option(Some(Box::new(value))))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Unit)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<OpaqueValue, D::Error> {
+        deserializer.deserialize_any(OpaqueValueVisitor)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<OpaqueValue, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(OpaqueValue::Seq(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OpaqueValue, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(OpaqueValue::Map(entries))
+    }
+}
+
+/// Hashes all living entities, in order of their indices, together with their registered components.
+pub(crate) fn state_hash(scene: &Scene, registry: &ComponentRegistry) -> u64 {
+    let mut entities = scene.entities().to_vec();
+    entities.sort_by_key(Entity::index);
+    let mut hasher = StateHasher::new();
+    for entity in entities {
+        hasher.write(&entity.to_bits().to_le_bytes());
+        for registration in registry.iter() {
+            if let Some(component) = (registration.serialize)(scene, entity) {
+                hasher.write_str(&registration.name);
+                // A failing component still marks where it was, so both sides of a comparison hash the same
+                if component.serialize(&mut hasher).is_err() {
+                    hasher.write(&[0xff]);
+                }
+            }
+        }
+    }
+    hasher.hash
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A serializer that feeds values into a 64 bit FNV-1a hash instead of writing them out.
+/// Unlike the standard library's hashers, the result is the same on every platform and in every run.
+/// Map entries are combined independently of their order, so hash maps hash the same regardless of iteration order.
+/// Sequences are hashed in order, so sets with an unstable iteration order aren't supported.
+/// Variant indices are always hashed as 4 bytes, whether or not the variant has fields.
+struct StateHasher {
+    hash: u64,
+}
+
+impl StateHasher {
+    fn new() -> Self {
+        StateHasher { hash: FNV_OFFSET_BASIS }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash = (self.hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_len(value.len());
+        self.write(value.as_bytes());
+    }
+}
+
+impl<'a> Serializer for &'a mut StateHasher {
+    type Ok = ();
+    type Error = fmt::Error;
+    type SerializeSeq = SeqHasher<'a>;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = MapHasher<'a>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), fmt::Error> {
+        self.write(&[v as u8]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), fmt::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), fmt::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), fmt::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), fmt::Error> {
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), fmt::Error> {
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), fmt::Error> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), fmt::Error> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), fmt::Error> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), fmt::Error> {
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), fmt::Error> {
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), fmt::Error> {
+        self.write(&v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), fmt::Error> {
+        self.write(&v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), fmt::Error> {
+        self.serialize_u32(v as u32)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), fmt::Error> {
+        self.write_str(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), fmt::Error> {
+        self.write_len(v.len());
+        self.write(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), fmt::Error> {
+        self.write(&[0]);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), fmt::Error> {
+        self.write(&[1]);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        index: u32,
+        _variant: &'static str,
+    ) -> Result<(), fmt::Error> {
+        self.write(&index.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), fmt::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), fmt::Error> {
+        self.write(&index.to_le_bytes());
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqHasher<'a>, fmt::Error> {
+        Ok(SeqHasher { hasher: self, len: 0 })
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, fmt::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, fmt::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, fmt::Error> {
+        self.write(&index.to_le_bytes());
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapHasher<'a>, fmt::Error> {
+        Ok(MapHasher { hasher: self, entry: StateHasher::new(), sum: 0, len: 0 })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, fmt::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, fmt::Error> {
+        self.write(&index.to_le_bytes());
+        Ok(self)
+    }
+}
+
+impl ser::SerializeTuple for &mut StateHasher {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), fmt::Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut StateHasher {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), fmt::Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut StateHasher {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), fmt::Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+impl SerializeStruct for &mut StateHasher {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), fmt::Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut StateHasher {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), fmt::Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+/// Hashes the elements of a sequence followed by their number, so nested sequences can't be confused.
+struct SeqHasher<'a> {
+    hasher: &'a mut StateHasher,
+    len: usize,
+}
+
+impl ser::SerializeSeq for SeqHasher<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), fmt::Error> {
+        self.len += 1;
+        value.serialize(&mut *self.hasher)
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        self.hasher.write_len(self.len);
+        Ok(())
+    }
+}
+
+/// Hashes every entry of a map on its own and sums the hashes, which doesn't depend on the order of the entries.
+struct MapHasher<'a> {
+    hasher: &'a mut StateHasher,
+    entry: StateHasher,
+    sum: u64,
+    len: usize,
+}
+
+impl ser::SerializeMap for MapHasher<'_> {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), fmt::Error> {
+        self.entry = StateHasher::new();
+        key.serialize(&mut self.entry)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), fmt::Error> {
+        value.serialize(&mut self.entry)?;
+        self.sum = self.sum.wrapping_add(self.entry.hash);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        self.hasher.write(&self.sum.to_le_bytes());
+        self.hasher.write_len(self.len);
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fmt,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use crate::scene::Scene;
+
+/// Work queued for the thread owning a shard.
+enum Command {
+    Run(Box<dyn FnOnce(&mut Shard) + Send>),
+    Stop,
+}
+
+/// One partition of a [`ShardedScene`], owned by its own thread.
+pub struct Shard {
+    index: usize,
+    scene: Scene,
+    /// Queues of all shards, including this one
+    peers: Vec<Sender<Command>>,
+}
+
+impl Shard {
+    /// Returns the position of this shard in the sharded scene.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// Sends a message to another shard, e.g. to hand over an entity that crossed a partition border.
+    /// The message is applied to the scene of the receiving shard once that shard finished the work it is
+    /// currently doing, and before any work queued later.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no shard with the given index.
+    pub fn send(&self, shard: usize, message: impl FnOnce(&mut Scene) + Send + 'static) {
+        let command = Command::Run(Box::new(move |shard: &mut Shard| message(&mut shard.scene)));
+        // A shard that already stopped has no scene left to apply the message to
+        let _ = self.peers[shard].send(command);
+    }
+}
+
+impl fmt::Debug for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Shard")
+            .field("index", &self.index)
+            .field("scene", &self.scene)
+            .finish()
+    }
+}
+
+/// Partitions entities into independent shards, each with its own scene living on its own thread,
+/// so massive simulations can update all shards in parallel.
+/// Shards talk to each other through messages (see [`Shard::send`]), and [`ShardedScene::update`]
+/// collects one result per shard to merge queries over the whole simulation.
+///
+/// Entities are only unique within their shard, so handles should be kept together with the shard index.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::sharded::ShardedScene;
+///
+/// struct Position(f32);
+///
+/// let sharded = ShardedScene::new(4);
+/// sharded.update(|shard| {
+///     for i in 0..1000 {
+///         let entity = shard.scene_mut().create_entity();
+///         shard.scene_mut().assign(entity, Position(i as f32)).unwrap();
+///     }
+/// });
+///
+/// // Shard 0 hands one entity over to shard 1
+/// sharded.run_on(0, |shard| {
+///     shard.send(1, |scene| {
+///         let entity = scene.create_entity();
+///         scene.assign(entity, Position(-1.0)).unwrap();
+///     });
+/// });
+///
+/// let counts = sharded.update(|shard| shard.scene().view::<(Position, ())>().count());
+/// assert_eq!(counts, vec![1000, 1001, 1000, 1000]);
+/// ```
+pub struct ShardedScene {
+    shards: Vec<Sender<Command>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl ShardedScene {
+    /// Creates the given number of empty shards, each on a new thread.
+    pub fn new(shard_count: usize) -> Self {
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..shard_count).map(|_| mpsc::channel()).unzip();
+        let threads = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(index, receiver)| {
+                let peers = senders.clone();
+                thread::spawn(move || run_shard(index, peers, receiver))
+            })
+            .collect();
+        ShardedScene {
+            shards: senders,
+            threads,
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Runs `f` on the thread of one shard and returns its result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no shard with the given index or if `f` panics.
+    pub fn run_on<R: Send + 'static>(&self, shard: usize, f: impl FnOnce(&mut Shard) -> R + Send + 'static) -> R {
+        let (result_sender, result_receiver) = mpsc::channel();
+        let command = Command::Run(Box::new(move |shard: &mut Shard| {
+            let _ = result_sender.send(f(shard));
+        }));
+        self.shards[shard].send(command).expect("shard thread stopped");
+        result_receiver.recv().expect("shard thread panicked")
+    }
+
+    /// Runs `f` on all shards in parallel and returns the results, ordered by shard index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` panics on any shard.
+    pub fn update<R: Send + 'static>(&self, f: impl Fn(&mut Shard) -> R + Send + Sync + 'static) -> Vec<R> {
+        let f = std::sync::Arc::new(f);
+        let receivers = self
+            .shards
+            .iter()
+            .map(|shard| {
+                let f = f.clone();
+                let (result_sender, result_receiver) = mpsc::channel();
+                let command = Command::Run(Box::new(move |shard: &mut Shard| {
+                    let _ = result_sender.send(f(shard));
+                }));
+                shard.send(command).expect("shard thread stopped");
+                result_receiver
+            })
+            .collect::<Vec<_>>();
+        receivers
+            .into_iter()
+            .map(|receiver| receiver.recv().expect("shard thread panicked"))
+            .collect()
+    }
+}
+
+impl Drop for ShardedScene {
+    fn drop(&mut self) {
+        for shard in &self.shards {
+            let _ = shard.send(Command::Stop);
+        }
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for ShardedScene {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShardedScene")
+            .field("shards", &self.shards.len())
+            .finish()
+    }
+}
+
+/// Owns the scene of one shard and applies queued work to it until the sharded scene is dropped.
+/// The scene is created on this thread and never leaves it, so components don't need to be `Send`.
+fn run_shard(index: usize, peers: Vec<Sender<Command>>, receiver: Receiver<Command>) {
+    let mut shard = Shard {
+        index,
+        scene: Scene::new(),
+        peers,
+    };
+    while let Ok(Command::Run(work)) = receiver.recv() {
+        work(&mut shard);
+    }
+}
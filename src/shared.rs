@@ -0,0 +1,70 @@
+use std::{fmt, ops::Deref, rc::Rc};
+
+/// A component value shared by many entities, e.g. a material or a tilemap chunk.
+/// Each entity only stores a pointer to the value, so large populations using the same value stay small.
+/// Clones refer to the same value; use [`Shared::ptr_eq`] to check whether two shared components are the same value.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+/// use yarecs::shared::Shared;
+///
+/// struct Material {
+///     color: [f32; 3],
+/// }
+///
+/// let mut scene = Scene::new();
+/// let red = Shared::new(Material { color: [1.0, 0.0, 0.0] });
+/// let blue = Shared::new(Material { color: [0.0, 0.0, 1.0] });
+/// for i in 0..10 {
+///     let entity = scene.create_entity();
+///     let material = if i < 7 { red.clone() } else { blue.clone() };
+///     scene.assign(entity, material).unwrap();
+/// }
+///
+/// assert_eq!(scene.entities_sharing(&red).len(), 7);
+/// assert_eq!(red.color, [1.0, 0.0, 0.0]);
+/// ```
+pub struct Shared<T>(Rc<T>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Rc::new(value))
+    }
+
+    /// Returns true if both refer to the same value.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(&this.0, &other.0)
+    }
+
+    /// Returns the number of references to the value, including ones held outside of scenes.
+    pub fn reference_count(this: &Self) -> usize {
+        Rc::strong_count(&this.0)
+    }
+
+    /// Returns an address identifying the shared value, usable as a key for grouping.
+    pub(crate) fn key(&self) -> usize {
+        Rc::as_ptr(&self.0) as *const () as usize
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(Rc::clone(&self.0))
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Shared").field(&self.0).finish()
+    }
+}
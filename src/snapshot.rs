@@ -0,0 +1,459 @@
+use std::{any::type_name, mem::size_of, thread};
+
+use crate::{
+    component::{get_stable_type_id, get_type_id, get_type_id_from_stable},
+    entity::Entity,
+    error::RecsError,
+    pool::{ErasedPool, PodPoolView, Pool},
+    scene::Scene,
+};
+
+/// A pool deserialized by a [`PodSnapshotLoader`], waiting to be stitched into a scene.
+pub(crate) struct LoadedPool {
+    pub(crate) type_id: usize,
+    pub(crate) type_name: &'static str,
+    pub(crate) pool: Box<dyn ErasedPool>,
+    /// Entities owning the components of the pool
+    pub(crate) entities: Vec<Entity>,
+}
+
+/// Marks the start of a snapshot written by [`PodSnapshotWriter`]
+const MAGIC: [u8; 4] = *b"yrps";
+const FORMAT_VERSION: u8 = 2;
+/// Length of the part of the header before the pool descriptions: magic, format version and pool count
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+/// Length of the description of one pool in the header: stable type ID, component size, entity count,
+/// lengths of the entity and component bytes, and checksum
+const POOL_HEADER_LEN: usize = 8 + 4 + 4 + 8 + 8 + 8;
+/// Offset from the start of the snapshot that every entity and component section is padded to,
+/// so sections of a snapshot mapped at a page boundary are aligned for components with up to this alignment
+const SECTION_ALIGN: usize = 64;
+
+/// The bytes of one pool in a snapshot, with the counts and checksum written into the header.
+#[derive(Debug, Clone, Copy)]
+struct PoolBytes<'a> {
+    stable_type_id: u64,
+    component_size: u32,
+    entity_count: u32,
+    checksum: u64,
+    entity_bytes: &'a [u8],
+    component_bytes: &'a [u8],
+}
+
+impl PoolBytes<'_> {
+    /// Fails if the bytes don't match the checksum of the header.
+    fn verify(&self, type_name: &str) -> Result<(), RecsError> {
+        let checksum = checksum(self.entity_bytes, self.component_bytes);
+        if checksum != self.checksum {
+            return Err(RecsError::CorruptSnapshot(format!(
+                "checksum of the {type_name} pool is {checksum:#018x}, but the header says {:#018x}",
+                self.checksum
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a hash of the entity and component bytes of a pool
+fn checksum(entity_bytes: &[u8], component_bytes: &[u8]) -> u64 {
+    entity_bytes.iter().chain(component_bytes).fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Writes the pools of plain-old-data component types into one buffer, behind a header with the entity count and
+/// a checksum of every pool, so [`PodSnapshot::parse`] and [`PodSnapshotLoader::add_from`] can detect truncated or
+/// corrupted files instead of loading garbage into a scene.
+///
+/// Pools are identified by the stable IDs of their component types, see
+/// [`get_stable_type_id`](crate::component::get_stable_type_id). Every section of entities or components
+/// is padded to start at a multiple of 64 bytes, so [`PodSnapshot::view`] can borrow the components
+/// of a memory-mapped snapshot without copying them.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::error::RecsError;
+/// use yarecs::scene::Scene;
+/// use yarecs::snapshot::{PodSnapshot, PodSnapshotLoader, PodSnapshotWriter};
+///
+/// let mut scene = Scene::new();
+/// for i in 0..100u32 {
+///     let entity = scene.create_entity();
+///     scene.assign(entity, i).unwrap();
+///     scene.assign(entity, [i as f32; 3]).unwrap();
+/// }
+///
+/// let mut writer = PodSnapshotWriter::new();
+/// writer.add(scene.pool::<u32>().unwrap()).add(scene.pool::<[f32; 3]>().unwrap());
+/// let mut bytes = writer.finish();
+///
+/// let snapshot = PodSnapshot::parse(&bytes).unwrap();
+/// assert_eq!(snapshot.entity_count::<u32>(), Some(100));
+/// let mut loaded = Scene::new();
+/// let mut loader = PodSnapshotLoader::new();
+/// loader.add_from::<u32>(&snapshot).unwrap().add_from::<[f32; 3]>(&snapshot).unwrap();
+/// loader.load_into(&mut loaded).unwrap();
+/// assert_eq!(loaded.view::<(u32, ([f32; 3], ()))>().count(), 100);
+///
+/// // A flipped bit is caught before anything is loaded
+/// let last = bytes.len() - 1;
+/// bytes[last] ^= 1;
+/// let snapshot = PodSnapshot::parse(&bytes).unwrap();
+/// let mut loader = PodSnapshotLoader::new();
+/// loader.add_from::<u32>(&snapshot).unwrap().add_from::<[f32; 3]>(&snapshot).unwrap();
+/// assert!(matches!(loader.load_into(&mut Scene::new()), Err(RecsError::CorruptSnapshot(_))));
+/// assert!(matches!(PodSnapshot::parse(&bytes[..last]), Err(RecsError::CorruptSnapshot(_))));
+/// ```
+#[derive(Debug, Default)]
+pub struct PodSnapshotWriter<'a> {
+    pools: Vec<PoolBytes<'a>>,
+}
+
+impl<'a> PodSnapshotWriter<'a> {
+    pub fn new() -> Self {
+        PodSnapshotWriter { pools: Vec::new() }
+    }
+
+    /// Adds a pool to the snapshot.
+    pub fn add<T: bytemuck::Pod>(&mut self, pool: &'a Pool<T>) -> &mut Self {
+        let (entity_bytes, component_bytes) = (pool.entity_bytes(), pool.component_bytes());
+        self.pools.push(PoolBytes {
+            stable_type_id: get_stable_type_id::<T>(),
+            component_size: size_of::<T>() as u32,
+            entity_count: pool.len() as u32,
+            checksum: checksum(entity_bytes, component_bytes),
+            entity_bytes,
+            component_bytes,
+        });
+        self
+    }
+
+    /// Returns the header followed by the bytes of all pools.
+    pub fn finish(&self) -> Vec<u8> {
+        let data_len =
+            self.pools.iter().map(|pool| pool.entity_bytes.len() + pool.component_bytes.len()).sum::<usize>();
+        let padding = self.pools.len() * 2 * SECTION_ALIGN;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.pools.len() * POOL_HEADER_LEN + data_len + padding);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&(self.pools.len() as u32).to_le_bytes());
+        for pool in &self.pools {
+            bytes.extend_from_slice(&pool.stable_type_id.to_le_bytes());
+            bytes.extend_from_slice(&pool.component_size.to_le_bytes());
+            bytes.extend_from_slice(&pool.entity_count.to_le_bytes());
+            bytes.extend_from_slice(&(pool.entity_bytes.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&(pool.component_bytes.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&pool.checksum.to_le_bytes());
+        }
+        for pool in &self.pools {
+            bytes.resize(bytes.len().next_multiple_of(SECTION_ALIGN), 0);
+            bytes.extend_from_slice(pool.entity_bytes);
+            bytes.resize(bytes.len().next_multiple_of(SECTION_ALIGN), 0);
+            bytes.extend_from_slice(pool.component_bytes);
+        }
+        bytes
+    }
+}
+
+/// The pools of a snapshot written by [`PodSnapshotWriter`], borrowed from its bytes.
+/// Parsing checks that the header is intact and that the lengths of all pools add up; the checksums are verified
+/// when the pools are loaded, on the loading threads, or up front with [`PodSnapshot::verify`].
+/// See [`PodSnapshotWriter`] for an example.
+#[derive(Debug)]
+pub struct PodSnapshot<'a> {
+    pools: Vec<PoolBytes<'a>>,
+}
+
+impl<'a> PodSnapshot<'a> {
+    /// Reads the header of a snapshot. Fails with [`RecsError::CorruptSnapshot`] if it is malformed or the bytes
+    /// don't have the lengths it announces.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, RecsError> {
+        let corrupt = |details: &str| RecsError::CorruptSnapshot(details.to_string());
+        let mut reader = ByteReader { bytes, offset: 0 };
+        if reader.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(corrupt("the bytes don't start with the snapshot magic"));
+        }
+        let version = reader.take(1)?[0];
+        if version != FORMAT_VERSION {
+            return Err(RecsError::CorruptSnapshot(format!("unknown snapshot format version {version}")));
+        }
+        let pool_count = reader.u32()? as usize;
+        if pool_count.saturating_mul(POOL_HEADER_LEN) > reader.remaining() {
+            return Err(RecsError::CorruptSnapshot(format!("the header announces {pool_count} pools, but is cut off")));
+        }
+        let mut headers = Vec::with_capacity(pool_count);
+        for _ in 0..pool_count {
+            headers.push((reader.u64()?, reader.u32()?, reader.u32()?, reader.u64()?, reader.u64()?, reader.u64()?));
+        }
+        let mut pools = Vec::with_capacity(pool_count);
+        for (stable_type_id, component_size, entity_count, entity_len, component_len, checksum) in headers {
+            let expected_entity_len = entity_count as u64 * size_of::<Entity>() as u64;
+            let expected_component_len = entity_count as u64 * component_size as u64;
+            if entity_len != expected_entity_len || component_len != expected_component_len {
+                return Err(RecsError::CorruptSnapshot(format!(
+                    "pool {stable_type_id:#018x} has {entity_len} entity and {component_len} component bytes, \
+                     which doesn't match its {entity_count} entities of {component_size} bytes"
+                )));
+            }
+            reader.skip_padding()?;
+            let entity_bytes = reader.take(entity_len as usize)?;
+            reader.skip_padding()?;
+            let component_bytes = reader.take(component_len as usize)?;
+            pools.push(PoolBytes {
+                stable_type_id,
+                component_size,
+                entity_count,
+                checksum,
+                entity_bytes,
+                component_bytes,
+            });
+        }
+        if reader.remaining() > 0 {
+            return Err(RecsError::CorruptSnapshot(format!("{} bytes follow the last pool", reader.remaining())));
+        }
+        Ok(PodSnapshot { pools })
+    }
+
+    /// Returns the number of entities in the pool of components of type `T`, as written into the header.
+    pub fn entity_count<T: 'static>(&self) -> Option<usize> {
+        self.pool_of::<T>().map(|pool| pool.entity_count as usize)
+    }
+
+    /// Returns the stable type IDs of the component types whose pools are in the snapshot.
+    pub fn stable_type_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.pools.iter().map(|pool| pool.stable_type_id)
+    }
+
+    /// Verifies the checksums of all pools.
+    pub fn verify(&self) -> Result<(), RecsError> {
+        self.pools.iter().try_for_each(|pool| pool.verify(&format!("{:#018x}", pool.stable_type_id)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// Borrows the components of type `T` from the snapshot without copying them, after verifying the checksum
+    /// of their pool. Fails like [`PodSnapshotLoader::add_from`], and if the snapshot doesn't start at an address
+    /// aligned for `T`. Memory mappings start at page boundaries, so their sections are always aligned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use yarecs::entity::Entity;
+    /// use yarecs::pool::PodCowPool;
+    /// use yarecs::scene::Scene;
+    /// use yarecs::snapshot::{PodSnapshot, PodSnapshotWriter};
+    ///
+    /// let mut scene = Scene::new();
+    /// for i in 0..100u64 {
+    ///     let entity = scene.create_entity();
+    ///     scene.assign(entity, i).unwrap();
+    /// }
+    /// let mut writer = PodSnapshotWriter::new();
+    /// writer.add(scene.pool::<u64>().unwrap());
+    /// let bytes = writer.finish();
+    ///
+    /// // Stands in for a memory-mapped file, which is aligned to a page boundary
+    /// let mut mapping = vec![0u64; bytes.len().div_ceil(8)];
+    /// bytemuck::cast_slice_mut::<u64, u8>(&mut mapping)[..bytes.len()].copy_from_slice(&bytes);
+    /// let mapped = &bytemuck::cast_slice::<u64, u8>(&mapping)[..bytes.len()];
+    ///
+    /// let snapshot = PodSnapshot::parse(mapped).unwrap();
+    /// let view = snapshot.view::<u64>().unwrap();
+    /// assert_eq!(view.get(Entity::new(42)), Some(&42));
+    ///
+    /// // The components are copied on the first write only
+    /// let mut pool = PodCowPool::new(view);
+    /// assert!(pool.is_borrowed());
+    /// *pool.get_mut(Entity::new(42)).unwrap() += 1;
+    /// assert!(!pool.is_borrowed());
+    /// assert_eq!(snapshot.view::<u64>().unwrap().get(Entity::new(42)), Some(&42));
+    ///
+    /// scene.restore_pod(pool.into_pool()).unwrap();
+    /// assert_eq!(*scene.get::<u64>(Entity::new(42)).unwrap().unwrap(), 43);
+    /// ```
+    pub fn view<T: bytemuck::Pod>(&self) -> Result<PodPoolView<'a, T>, RecsError> {
+        let pool = self.pool_for::<T>()?;
+        pool.verify(type_name::<T>())?;
+        PodPoolView::from_bytes(pool.entity_bytes, pool.component_bytes)
+    }
+
+    /// Returns the pool of `T`, unless another type used in this process has the same stable ID,
+    /// in which case the pool can't be told apart from the pool of that type.
+    fn pool_of<T: 'static>(&self) -> Option<&PoolBytes<'a>> {
+        let stable_type_id = get_stable_type_id::<T>();
+        if get_type_id_from_stable(stable_type_id) != Some(get_type_id::<T>()) {
+            return None;
+        }
+        self.pools.iter().find(|pool| pool.stable_type_id == stable_type_id)
+    }
+
+    /// Returns the pool of `T`, failing if there is none, it's ambiguous or its components have a different size.
+    fn pool_for<T: 'static>(&self) -> Result<PoolBytes<'a>, RecsError> {
+        if get_type_id_from_stable(get_stable_type_id::<T>()) != Some(get_type_id::<T>()) {
+            return Err(RecsError::Other(
+                format!("another type has the same stable type ID as {}", type_name::<T>()).into(),
+            ));
+        }
+        let pool = *self
+            .pool_of::<T>()
+            .ok_or_else(|| RecsError::Other(format!("the snapshot has no pool of {}", type_name::<T>()).into()))?;
+        if pool.component_size as usize != size_of::<T>() {
+            return Err(RecsError::CorruptSnapshot(format!(
+                "components of {} have {} bytes, but the snapshot stores {} bytes per component",
+                type_name::<T>(),
+                size_of::<T>(),
+                pool.component_size
+            )));
+        }
+        Ok(pool)
+    }
+}
+
+/// Reads little-endian values from the front of a snapshot
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    /// Number of bytes read so far
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RecsError> {
+        if self.remaining() < len {
+            return Err(RecsError::CorruptSnapshot(format!(
+                "the snapshot is cut off, {len} more bytes were expected but only {} are left",
+                self.remaining()
+            )));
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    /// Skips the padding in front of a section of entities or components.
+    fn skip_padding(&mut self) -> Result<(), RecsError> {
+        self.take(self.offset.next_multiple_of(SECTION_ALIGN) - self.offset)?;
+        Ok(())
+    }
+
+    fn u32(&mut self) -> Result<u32, RecsError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, RecsError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+type LoadedBytes = Result<(Box<dyn ErasedPool + Send>, Vec<Entity>), RecsError>;
+type LoadJob<'a> = Box<dyn FnOnce() -> LoadedBytes + Send + 'a>;
+
+/// Loads the pools of many plain-old-data component types into an empty scene, deserializing each pool
+/// on its own thread. The entity table is stitched together from the loaded pools at the end, so every entity
+/// owning a component is recreated with its saved index and version.
+///
+/// The bytes of each pool are the ones written by [`Pool::entity_bytes`] and [`Pool::component_bytes`].
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+/// use yarecs::snapshot::PodSnapshotLoader;
+///
+/// let mut scene = Scene::new();
+/// let first = scene.create_entity();
+/// let second = scene.create_entity();
+/// scene.assign(first, 1u32).unwrap();
+/// scene.assign(second, 2u32).unwrap();
+/// scene.assign(second, 0.5f64).unwrap();
+///
+/// let ints = scene.pool::<u32>().unwrap();
+/// let floats = scene.pool::<f64>().unwrap();
+/// let mut loader = PodSnapshotLoader::new();
+/// loader.add::<u32>(ints.entity_bytes(), ints.component_bytes());
+/// loader.add::<f64>(floats.entity_bytes(), floats.component_bytes());
+///
+/// let mut loaded = Scene::new();
+/// loader.load_into(&mut loaded).unwrap();
+/// assert_eq!(*loaded.get::<u32>(second).unwrap().unwrap(), 2);
+/// assert_eq!(*loaded.get::<f64>(second).unwrap().unwrap(), 0.5);
+/// assert_eq!(loaded.view::<(u32, ())>().count(), 2);
+/// ```
+#[derive(Default)]
+pub struct PodSnapshotLoader<'a> {
+    jobs: Vec<(usize, &'static str, LoadJob<'a>)>,
+}
+
+impl<'a> PodSnapshotLoader<'a> {
+    pub fn new() -> Self {
+        PodSnapshotLoader { jobs: Vec::new() }
+    }
+
+    /// Adds the pool of components of type `T` to the snapshot.
+    pub fn add<T: bytemuck::Pod + Send>(&mut self, entity_bytes: &'a [u8], component_bytes: &'a [u8]) -> &mut Self {
+        let job: LoadJob<'a> = Box::new(move || load_pool::<T>(entity_bytes, component_bytes));
+        self.jobs.push((get_type_id::<T>(), type_name::<T>(), job));
+        self
+    }
+
+    /// Adds the pool of components of type `T` from a snapshot. Fails if the snapshot has no pool of `T` or its
+    /// components have a different size. The checksum of the pool is verified while it is loaded, and loading fails
+    /// with [`RecsError::CorruptSnapshot`] if it doesn't match.
+    pub fn add_from<T: bytemuck::Pod + Send>(&mut self, snapshot: &PodSnapshot<'a>) -> Result<&mut Self, RecsError> {
+        let pool = snapshot.pool_for::<T>()?;
+        let job: LoadJob<'a> = Box::new(move || {
+            pool.verify(type_name::<T>())?;
+            load_pool::<T>(pool.entity_bytes, pool.component_bytes)
+        });
+        self.jobs.push((get_type_id::<T>(), type_name::<T>(), job));
+        Ok(self)
+    }
+
+    /// Deserializes all pools in parallel and puts them into the scene, which has to be empty.
+    pub fn load_into(self, scene: &mut Scene) -> Result<(), RecsError> {
+        let results = thread::scope(|scope| {
+            let handles = self
+                .jobs
+                .into_iter()
+                .map(|(type_id, type_name, job)| (type_id, type_name, scope.spawn(job)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|(type_id, type_name, handle)| {
+                    let (pool, entities) = handle.join().expect("pool deserialization panicked")?;
+                    Ok(LoadedPool {
+                        type_id,
+                        type_name,
+                        pool,
+                        entities,
+                    })
+                })
+                .collect::<Result<Vec<_>, RecsError>>()
+        })?;
+        scene.install_loaded_pools(results)
+    }
+}
+
+/// Rebuilds a pool from its bytes, returning it with the entities owning its components.
+fn load_pool<T: bytemuck::Pod + Send>(entity_bytes: &[u8], component_bytes: &[u8]) -> LoadedBytes {
+    if size_of::<T>() == 0 {
+        return Err(RecsError::Other("zero-sized components are stored as tags and have no pool to load".into()));
+    }
+    let pool = Pool::<T>::from_bytes(entity_bytes, component_bytes)?;
+    let entities = pool.entities().collect();
+    Ok((Box::new(pool), entities))
+}
@@ -0,0 +1,210 @@
+use std::{any::Any, collections::HashMap, fmt};
+
+use crate::{
+    entity::Entity,
+    pool::{ErasedPool, FragmentationReport, SparseArray},
+};
+
+/// A component stored as a struct of arrays: every field lives in its own column,
+/// so systems touching only some fields don't pull the whole struct through the cache.
+/// Usually derived with `#[derive(SoAComponent)]` (requires the `derive` feature).
+///
+/// # Example
+///
+/// Implementing the traits by hand, the way the derive does it:
+///
+/// ```
+/// use yarecs::scene::Scene;
+/// use yarecs::soa::{SoAColumns, SoAComponent};
+///
+/// struct Position {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// #[derive(Default)]
+/// struct PositionColumns {
+///     x: Vec<f32>,
+///     y: Vec<f32>,
+/// }
+///
+/// impl SoAColumns for PositionColumns {
+///     type Item = Position;
+///
+///     fn push(&mut self, value: Position) {
+///         self.x.push(value.x);
+///         self.y.push(value.y);
+///     }
+///
+///     fn swap_remove(&mut self, row: usize) -> Position {
+///         Position { x: self.x.swap_remove(row), y: self.y.swap_remove(row) }
+///     }
+///
+///     fn clear(&mut self) {
+///         self.x.clear();
+///         self.y.clear();
+///     }
+///
+///     fn shrink_to_fit(&mut self) {
+///         self.x.shrink_to_fit();
+///         self.y.shrink_to_fit();
+///     }
+/// }
+///
+/// impl SoAComponent for Position {
+///     type Columns = PositionColumns;
+/// }
+///
+/// let mut scene = Scene::new();
+/// for i in 0..3 {
+///     let entity = scene.create_entity();
+///     scene.assign_soa(entity, Position { x: i as f32, y: 0.0 }).unwrap();
+/// }
+/// let (_, columns) = scene.soa_columns_mut::<Position>().unwrap();
+/// columns.y.iter_mut().zip(&columns.x).for_each(|(y, x)| *y = x * 2.0);
+///
+/// assert_eq!(scene.soa_pool::<Position>().unwrap().columns().y, vec![0.0, 2.0, 4.0]);
+/// ```
+pub trait SoAComponent: Sized + 'static {
+    type Columns: SoAColumns<Item = Self>;
+}
+
+/// The columns of an [`SoAComponent`], all of the same length.
+pub trait SoAColumns: Default + 'static {
+    type Item;
+
+    /// Appends the fields of a value to the ends of the columns.
+    fn push(&mut self, value: Self::Item);
+    /// Removes the row and moves the last row into its place.
+    fn swap_remove(&mut self, row: usize) -> Self::Item;
+    fn clear(&mut self);
+    fn shrink_to_fit(&mut self);
+}
+
+/// Stores all components of one [`SoAComponent`] type as a sparse set whose dense part is split into columns.
+pub struct SoAPool<T: SoAComponent> {
+    /// Map of entity index to row
+    sparse: SparseArray,
+    /// Entity owning each row
+    dense: Vec<Entity>,
+    columns: T::Columns,
+}
+
+impl<T: SoAComponent> Default for SoAPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: SoAComponent> SoAPool<T> {
+    pub fn new() -> Self {
+        SoAPool {
+            sparse: SparseArray::default(),
+            dense: Vec::new(),
+            columns: T::Columns::default(),
+        }
+    }
+
+    /// Returns the number of components in the pool.
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.row(entity).is_some()
+    }
+
+    /// Returns the row of the entity's component in the columns.
+    pub fn row(&self, entity: Entity) -> Option<usize> {
+        let row = self.sparse.get(entity.index() as usize)? as usize;
+        (self.dense[row] == entity).then_some(row)
+    }
+
+    /// Inserts the entity's component, returning the previous one if there was one.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        let previous = self.remove(entity);
+        self.sparse.insert(entity.index() as usize, self.dense.len() as u32);
+        self.dense.push(entity);
+        self.columns.push(value);
+        previous
+    }
+
+    /// Removes the entity's component and returns it, moving the last row into its place.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let row = self.row(entity)?;
+        self.sparse.remove(entity.index() as usize);
+        self.dense.swap_remove(row);
+        if let Some(&moved) = self.dense.get(row) {
+            self.sparse.insert(moved.index() as usize, row as u32);
+        }
+        Some(self.columns.swap_remove(row))
+    }
+
+    /// Returns the entities owning the rows of the columns.
+    pub fn entities(&self) -> &[Entity] {
+        &self.dense
+    }
+
+    pub fn columns(&self) -> &T::Columns {
+        &self.columns
+    }
+
+    /// Returns the entities and the mutable columns, which are parallel to each other.
+    pub fn columns_mut(&mut self) -> (&[Entity], &mut T::Columns) {
+        (&self.dense, &mut self.columns)
+    }
+}
+
+impl<T: SoAComponent> ErasedPool for SoAPool<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        SoAPool::len(self)
+    }
+
+    fn free(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        self.sparse.clear();
+        for (row, entity) in self.dense.iter_mut().enumerate() {
+            *entity = remap.get(entity).copied().unwrap_or(*entity);
+            self.sparse.insert(entity.index() as usize, row as u32);
+        }
+    }
+
+    fn clear(&mut self) {
+        for entity in &self.dense {
+            self.sparse.remove(entity.index() as usize);
+        }
+        self.dense.clear();
+        self.columns.clear();
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.sparse.shrink_to_fit();
+        self.dense.shrink_to_fit();
+        self.columns.shrink_to_fit();
+    }
+
+    fn fragmentation(&self) -> FragmentationReport {
+        self.sparse.fragmentation()
+    }
+}
+
+impl<T: SoAComponent> fmt::Debug for SoAPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SoAPool").field("len", &self.len()).finish()
+    }
+}
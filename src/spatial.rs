@@ -0,0 +1,227 @@
+use std::fmt;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{entity::Entity, scene::Scene};
+
+/// An axis-aligned rectangle, given by its lowest and highest corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Aabb {
+    pub fn new(min: [f32; 2], max: [f32; 2]) -> Self {
+        Aabb { min, max }
+    }
+
+    /// Returns the square around a circle.
+    pub fn around(center: [f32; 2], radius: f32) -> Self {
+        Aabb {
+            min: [center[0] - radius, center[1] - radius],
+            max: [center[0] + radius, center[1] + radius],
+        }
+    }
+
+    /// Returns true if the point is inside the rectangle or on its border.
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        (self.min[0]..=self.max[0]).contains(&point[0]) && (self.min[1]..=self.max[1]).contains(&point[1])
+    }
+}
+
+/// A uniform grid of square cells that finds the entities at positions inside a rectangle or circle by only
+/// looking at the cells overlapping it. Scenes keep one up to date with [`Scene::enable_spatial_index`],
+/// but the grid can also be filled by hand, like for positions that aren't stored in components.
+///
+/// Pick a cell size around the typical query radius: much smaller cells make queries visit many cells,
+/// much larger ones make them test many entities.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::entity::Entity;
+/// use yarecs::spatial::{Aabb, SpatialGrid};
+///
+/// let mut grid = SpatialGrid::new(10.0);
+/// grid.insert(Entity::new(0), [1.0, 1.0]);
+/// grid.insert(Entity::new(1), [25.0, 3.0]);
+/// grid.insert(Entity::new(2), [-40.0, 8.0]);
+///
+/// assert_eq!(grid.query_aabb(Aabb::new([0.0, 0.0], [30.0, 5.0])).len(), 2);
+/// assert_eq!(grid.query_radius([-38.0, 8.0], 5.0), vec![Entity::new(2)]);
+///
+/// grid.insert(Entity::new(2), [3.0, 3.0]);
+/// assert_eq!(grid.query_radius([-38.0, 8.0], 5.0), vec![]);
+/// assert_eq!(grid.len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: FxHashMap<(i32, i32), Vec<Entity>>,
+    positions: FxHashMap<Entity, [f32; 2]>,
+}
+
+impl SpatialGrid {
+    /// Creates an empty grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell_size` isn't positive.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "the cell size of a spatial grid must be positive");
+        SpatialGrid {
+            cell_size,
+            cells: FxHashMap::default(),
+            positions: FxHashMap::default(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Puts an entity at a position, moving it if it was in the grid already.
+    pub fn insert(&mut self, entity: Entity, position: [f32; 2]) {
+        self.remove(entity);
+        self.cells.entry(self.cell_of(position)).or_default().push(entity);
+        self.positions.insert(entity, position);
+    }
+
+    /// Takes an entity out of the grid and returns its position.
+    pub fn remove(&mut self, entity: Entity) -> Option<[f32; 2]> {
+        let position = self.positions.remove(&entity)?;
+        let cell = self.cell_of(position);
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            if let Some(slot) = entities.iter().position(|&other| other == entity) {
+                entities.swap_remove(slot);
+            }
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+        Some(position)
+    }
+
+    pub fn position(&self, entity: Entity) -> Option<[f32; 2]> {
+        self.positions.get(&entity).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.positions.clear();
+    }
+
+    /// Returns the entities inside the rectangle, in no particular order.
+    pub fn query_aabb(&self, aabb: Aabb) -> Vec<Entity> {
+        self.query(aabb, |position| aabb.contains(position))
+    }
+
+    /// Returns the entities inside the circle, in no particular order.
+    pub fn query_radius(&self, center: [f32; 2], radius: f32) -> Vec<Entity> {
+        self.query(Aabb::around(center, radius), |position| {
+            let (dx, dy) = (position[0] - center[0], position[1] - center[1]);
+            dx * dx + dy * dy <= radius * radius
+        })
+    }
+
+    /// Collects the entities of the cells overlapping the rectangle whose positions pass the test.
+    fn query(&self, aabb: Aabb, test: impl Fn([f32; 2]) -> bool) -> Vec<Entity> {
+        let (min_x, min_y) = self.cell_of(aabb.min);
+        let (max_x, max_y) = self.cell_of(aabb.max);
+        let in_range = |&(x, y): &(i32, i32)| (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y);
+        let cell_count = (max_x as i64 - min_x as i64 + 1) * (max_y as i64 - min_y as i64 + 1);
+        let entities = |cell: &(i32, i32)| self.cells.get(cell).into_iter().flatten();
+        let candidates: Box<dyn Iterator<Item = &Entity>> = if cell_count > self.cells.len() as i64 {
+            // Visiting the occupied cells is cheaper than visiting every cell the rectangle overlaps
+            Box::new(self.cells.keys().filter(|cell| in_range(cell)).flat_map(entities))
+        } else {
+            Box::new((min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y))).flat_map(move |cell| {
+                self.cells.get(&cell).into_iter().flatten()
+            }))
+        };
+        candidates.copied().filter(|entity| test(self.positions[entity])).collect()
+    }
+
+    fn cell_of(&self, position: [f32; 2]) -> (i32, i32) {
+        ((position[0] / self.cell_size).floor() as i32, (position[1] / self.cell_size).floor() as i32)
+    }
+}
+
+type PositionFn = Box<dyn Fn(&Scene, Entity) -> Option<[f32; 2]>>;
+type EntitiesFn = Box<dyn Fn(&Scene) -> Vec<Entity>>;
+
+/// The grid a scene keeps of the positions of one component type, with the entities whose component changed
+/// since the last query.
+pub(crate) struct SpatialIndex {
+    pub(crate) type_id: usize,
+    grid: SpatialGrid,
+    dirty: FxHashSet<Entity>,
+    rebuild: bool,
+    position: PositionFn,
+    entities: EntitiesFn,
+}
+
+impl SpatialIndex {
+    pub(crate) fn new<T: 'static>(type_id: usize, cell_size: f32, position: impl Fn(&T) -> [f32; 2] + 'static) -> Self {
+        SpatialIndex {
+            type_id,
+            grid: SpatialGrid::new(cell_size),
+            dirty: FxHashSet::default(),
+            rebuild: true,
+            position: Box::new(move |scene, entity| scene.get::<T>(entity).ok().flatten().map(&position)),
+            entities: Box::new(|scene| scene.view::<(T, ())>().collect()),
+        }
+    }
+
+    pub(crate) fn mark_dirty(&mut self, entity: Entity) {
+        if !self.rebuild {
+            self.dirty.insert(entity);
+        }
+    }
+
+    pub(crate) fn invalidate(&mut self) {
+        self.rebuild = true;
+        self.dirty.clear();
+    }
+
+    /// Brings the grid up to date with the components of the scene and returns it.
+    pub(crate) fn refresh(&mut self, scene: &Scene) -> &SpatialGrid {
+        let entities = match self.rebuild {
+            true => {
+                self.rebuild = false;
+                self.dirty.clear();
+                self.grid.clear();
+                (self.entities)(scene)
+            }
+            false => self.dirty.drain().collect(),
+        };
+        for entity in entities {
+            match (self.position)(scene, entity) {
+                Some(position) => self.grid.insert(entity, position),
+                None => {
+                    self.grid.remove(entity);
+                }
+            }
+        }
+        &self.grid
+    }
+}
+
+impl fmt::Debug for SpatialIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SpatialIndex")
+            .field("grid", &self.grid)
+            .field("dirty", &self.dirty.len())
+            .field("rebuild", &self.rebuild)
+            .finish()
+    }
+}
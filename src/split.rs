@@ -0,0 +1,127 @@
+use std::fmt;
+
+use crate::{
+    component::get_type_id,
+    entity::Entity,
+    error::RecsError,
+    pool::{ErasedPool, InternedPool, Pool, SlabPool, SmallPool, TagPool},
+    scene::{interned_mutation, EntityDescription},
+};
+
+/// Mutable access to a set of component types of a scene, created by `Scene::split`.
+/// Accesses to component types outside the set fail, which guarantees that two accesses from
+/// the same split never touch the same components.
+pub struct ComponentAccess<'a> {
+    entities: &'a [EntityDescription],
+    /// Type IDs of the component types this access covers
+    granted: Vec<usize>,
+    /// Pools of the covered component types that exist
+    pools: Vec<(usize, &'a mut dyn ErasedPool)>,
+}
+
+impl<'a> ComponentAccess<'a> {
+    pub(crate) fn new(entities: &'a [EntityDescription], granted: Vec<usize>) -> Self {
+        ComponentAccess {
+            entities,
+            granted,
+            pools: Vec::new(),
+        }
+    }
+
+    pub(crate) fn covers(&self, type_id: usize) -> bool {
+        self.granted.contains(&type_id)
+    }
+
+    pub(crate) fn add_pool(&mut self, type_id: usize, pool: &'a mut dyn ErasedPool) {
+        self.pools.push((type_id, pool));
+    }
+
+    /// Gets a component from an entity.
+    pub fn get<T: 'static>(&self, entity: Entity) -> Result<Option<&T>, RecsError> {
+        let Some(pool) = self.erased_pool(get_type_id::<T>(), entity)? else {
+            return Ok(None);
+        };
+        let pool = pool.as_any();
+        Ok(if let Some(pool) = pool.downcast_ref::<Pool<T>>() {
+            pool.get(entity)
+        } else if let Some(pool) = pool.downcast_ref::<TagPool<T>>() {
+            pool.get(entity)
+        } else if let Some(pool) = pool.downcast_ref::<SlabPool<T>>() {
+            pool.get(entity)
+        } else if let Some(pool) = pool.downcast_ref::<SmallPool<T>>() {
+            pool.get(entity)
+        } else {
+            pool.downcast_ref::<InternedPool<T>>().and_then(|pool| pool.get(entity))
+        })
+    }
+
+    /// Gets a mutable component from an entity. Fails for interned components, like [`Scene::get_mut`].
+    ///
+    /// [`Scene::get_mut`]: crate::scene::Scene::get_mut
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Result<Option<&mut T>, RecsError> {
+        let Some(pool) = self.erased_pool_mut(get_type_id::<T>(), entity)? else {
+            return Ok(None);
+        };
+        let pool = pool.as_any_mut();
+        Ok(if pool.is::<Pool<T>>() {
+            pool.downcast_mut::<Pool<T>>().and_then(|pool| pool.get_mut(entity))
+        } else if pool.is::<TagPool<T>>() {
+            pool.downcast_mut::<TagPool<T>>().and_then(|pool| pool.get_mut(entity))
+        } else if pool.is::<SlabPool<T>>() {
+            pool.downcast_mut::<SlabPool<T>>().and_then(|pool| pool.get_mut(entity))
+        } else if pool.is::<InternedPool<T>>() {
+            return Err(interned_mutation());
+        } else {
+            pool.downcast_mut::<SmallPool<T>>().and_then(|pool| pool.get_mut(entity))
+        })
+    }
+
+    /// Returns the sparse pool of `T`, e.g. to iterate over all its components.
+    pub fn pool_mut<T: 'static>(&mut self) -> Result<Option<&mut Pool<T>>, RecsError> {
+        let type_id = get_type_id::<T>();
+        if !self.covers(type_id) {
+            return Err(not_covered());
+        }
+        Ok(self
+            .pools
+            .iter_mut()
+            .find(|(id, _)| *id == type_id)
+            .and_then(|(_, pool)| pool.as_any_mut().downcast_mut()))
+    }
+
+    fn erased_pool(&self, type_id: usize, entity: Entity) -> Result<Option<&dyn ErasedPool>, RecsError> {
+        self.check_access(type_id, entity)?;
+        Ok(self.pools.iter().find(|(id, _)| *id == type_id).map(|(_, pool)| &**pool))
+    }
+
+    fn erased_pool_mut(&mut self, type_id: usize, entity: Entity) -> Result<Option<&mut dyn ErasedPool>, RecsError> {
+        self.check_access(type_id, entity)?;
+        Ok(self
+            .pools
+            .iter_mut()
+            .find(|(id, _)| *id == type_id)
+            .map(|(_, pool)| &mut **pool as &mut dyn ErasedPool))
+    }
+
+    fn check_access(&self, type_id: usize, entity: Entity) -> Result<(), RecsError> {
+        if !self.covers(type_id) {
+            return Err(not_covered());
+        }
+        self.entities
+            .get(entity.index() as usize)
+            .filter(|entity_description| entity_description.check_validity(entity))
+            .map_or(Err(RecsError::InvalidEntityError), |_| Ok(()))
+    }
+}
+
+fn not_covered() -> RecsError {
+    RecsError::Other("component type is not covered by this access".into())
+}
+
+impl fmt::Debug for ComponentAccess<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ComponentAccess")
+            .field("granted", &self.granted)
+            .finish()
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    any::type_name,
+    fmt,
+    io::{BufRead, Write},
+};
+
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct},
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    component::get_type_id,
+    entity::Entity,
+    error::RecsError,
+    scene::Scene,
+    serialization::{
+        checked_version, merge_entities, ComponentFilter, ComponentRegistry, ComponentSeed, DeferredAssign,
+        EntitiesSeed, EntitySerializer, Registration,
+    },
+};
+
+/// Number of entities or components written per chunk by default
+const DEFAULT_BATCH_SIZE: usize = 1024;
+
+/// Writes a scene as a stream of chunks, one JSON document per line, so that scenes far larger than memory allows
+/// to serialize at once can be saved. Each chunk holds a batch of entities with their registered components,
+/// or a batch of components of a single type. Read the stream back with a [`SceneReader`].
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use yarecs::scene::Scene;
+/// use yarecs::serialization::ComponentRegistry;
+/// use yarecs::streaming::{SceneReader, SceneWriter};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Height(f32);
+/// #[derive(Serialize, Deserialize)]
+/// struct Biome(String);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_as::<Height>("Height").register_as::<Biome>("Biome");
+///
+/// let mut world = Scene::new();
+/// for i in 0..10_000 {
+///     let tile = world.create_entity();
+///     world.assign(tile, Height(i as f32)).unwrap();
+///     world.assign(tile, Biome("plains".to_string())).unwrap();
+/// }
+///
+/// let mut file = Vec::new();
+/// let mut writer = SceneWriter::new(&mut file, &registry).unwrap().with_batch_size(500);
+/// writer.write_pool::<Height>(&world).unwrap();
+/// writer.write_pool::<Biome>(&world).unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(file.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).count(), 41);
+///
+/// let mut loaded = Scene::new();
+/// SceneReader::new(file.as_slice(), &registry).unwrap().read_into(&mut loaded).unwrap();
+/// assert_eq!(loaded.entity_count(), 10_000);
+/// let tile = world.entities()[1234];
+/// assert_eq!(loaded.get::<Height>(tile).unwrap().unwrap().0, world.get::<Height>(tile).unwrap().unwrap().0);
+/// ```
+pub struct SceneWriter<'a, W: Write> {
+    writer: W,
+    registry: &'a ComponentRegistry,
+    batch_size: usize,
+}
+
+impl<'a, W: Write> SceneWriter<'a, W> {
+    /// Creates a writer and writes the header with the schema version of the registry.
+    pub fn new(mut writer: W, registry: &'a ComponentRegistry) -> Result<Self, RecsError> {
+        write_line(&mut writer, &Header { version: registry.schema_version() })?;
+        Ok(SceneWriter { writer, registry, batch_size: DEFAULT_BATCH_SIZE })
+    }
+
+    /// Sets how many entities or components are written per chunk.
+    /// Larger chunks need more memory while writing and reading, but less space.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Writes all living entities of the scene in order of their indices, together with their registered components.
+    pub fn write_scene(&mut self, scene: &Scene) -> Result<(), RecsError> {
+        let mut entities = scene.entities().to_vec();
+        entities.sort_by_key(Entity::index);
+        self.write_entities(scene, &entities)
+    }
+
+    /// Writes the given entities together with their registered components.
+    pub fn write_entities(&mut self, scene: &Scene, entities: &[Entity]) -> Result<(), RecsError> {
+        let filter = ComponentFilter::all();
+        for batch in entities.chunks(self.batch_size) {
+            let entities = batch
+                .iter()
+                .map(|&entity| EntitySerializer { scene, registry: self.registry, filter: &filter, entity })
+                .collect::<Vec<_>>();
+            write_line(&mut self.writer, &EntitiesChunk { entities })?;
+        }
+        Ok(())
+    }
+
+    /// Writes all components of type `T`, which has to be registered, together with the entities owning them.
+    pub fn write_pool<T: 'static>(&mut self, scene: &Scene) -> Result<(), RecsError> {
+        let registration = self.registry.get_by_type_id(get_type_id::<T>()).ok_or_else(|| {
+            RecsError::Other(format!("component type {} is not registered", type_name::<T>()).into())
+        })?;
+        let mut entities = scene.view::<(T, ())>().collect::<Vec<_>>();
+        entities.sort_by_key(Entity::index);
+        for batch in entities.chunks(self.batch_size) {
+            write_line(&mut self.writer, &PoolChunk { scene, registration, entities: batch })?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the stream and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, RecsError> {
+        self.writer.flush().map_err(|e| RecsError::Other(Box::new(e)))?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> fmt::Debug for SceneWriter<'_, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SceneWriter").field("batch_size", &self.batch_size).finish()
+    }
+}
+
+fn write_line<W: Write>(writer: &mut W, chunk: &impl Serialize) -> Result<(), RecsError> {
+    serde_json::to_writer(&mut *writer, chunk).map_err(|e| RecsError::Other(Box::new(e)))?;
+    writer.write_all(b"\n").map_err(|e| RecsError::Other(Box::new(e)))
+}
+
+struct Header {
+    version: u32,
+}
+
+impl Serialize for Header {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Header", 1)?;
+        state.serialize_field("version", &self.version)?;
+        state.end()
+    }
+}
+
+struct EntitiesChunk<'a> {
+    entities: Vec<EntitySerializer<'a>>,
+}
+
+impl Serialize for EntitiesChunk<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("EntitiesChunk", 1)?;
+        state.serialize_field("entities", &self.entities)?;
+        state.end()
+    }
+}
+
+/// A batch of components of one type, written as the registered name and a list of entity and component pairs.
+struct PoolChunk<'a> {
+    scene: &'a Scene,
+    registration: &'a Registration,
+    entities: &'a [Entity],
+}
+
+impl Serialize for PoolChunk<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("PoolChunk", 2)?;
+        state.serialize_field("pool", &self.registration.name)?;
+        state.serialize_field("components", &PoolComponents(self))?;
+        state.end()
+    }
+}
+
+struct PoolComponents<'a>(&'a PoolChunk<'a>);
+
+impl Serialize for PoolComponents<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let chunk = self.0;
+        let mut seq = serializer.serialize_seq(Some(chunk.entities.len()))?;
+        for &entity in chunk.entities {
+            if let Some(component) = (chunk.registration.serialize)(chunk.scene, entity) {
+                seq.serialize_element(&(entity, component))?;
+            }
+        }
+        seq.end()
+    }
+}
+
+/// Reads a stream written by a [`SceneWriter`] chunk by chunk, so only one chunk is held in memory at a time.
+/// Components are merged into the scene: entities are recreated with their saved indices and versions
+/// when they first appear, and components replace components of the same type.
+pub struct SceneReader<'a, R: BufRead> {
+    reader: R,
+    registry: &'a ComponentRegistry,
+    version: u32,
+    line: String,
+}
+
+impl<'a, R: BufRead> SceneReader<'a, R> {
+    /// Creates a reader and reads the header, failing if the stream was written with a newer schema version.
+    pub fn new(reader: R, registry: &'a ComponentRegistry) -> Result<Self, RecsError> {
+        let mut scene_reader = SceneReader { reader, registry, version: 0, line: String::new() };
+        if !scene_reader.next_line()? {
+            return Err(RecsError::Other("scene stream has no header".into()));
+        }
+        let header = serde_json::from_str::<serde_json::Value>(&scene_reader.line)
+            .map_err(|e| RecsError::Other(Box::new(e)))?;
+        let version = header
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| RecsError::Other("scene stream header has no valid version".into()))?;
+        scene_reader.version = checked_version::<serde_json::Error>(registry, version)
+            .map_err(|e| RecsError::Other(Box::new(e)))?;
+        Ok(scene_reader)
+    }
+
+    /// Reads the next chunk into the scene. Returns false once the stream has ended.
+    pub fn read_chunk_into(&mut self, scene: &mut Scene) -> Result<bool, RecsError> {
+        if !self.next_line()? {
+            return Ok(false);
+        }
+        let mut deserializer = serde_json::Deserializer::from_str(&self.line);
+        let entities = ChunkSeed(self.registry, self.version)
+            .deserialize(&mut deserializer)
+            .map_err(|e| RecsError::Other(Box::new(e)))?;
+        merge_entities(scene, entities)?;
+        Ok(true)
+    }
+
+    /// Reads all remaining chunks into the scene.
+    pub fn read_into(mut self, scene: &mut Scene) -> Result<(), RecsError> {
+        while self.read_chunk_into(scene)? {}
+        Ok(())
+    }
+
+    /// Reads the next non-empty line. Returns false at the end of the stream.
+    fn next_line(&mut self) -> Result<bool, RecsError> {
+        loop {
+            self.line.clear();
+            let read = self.reader.read_line(&mut self.line).map_err(|e| RecsError::Other(Box::new(e)))?;
+            if read == 0 {
+                return Ok(false);
+            }
+            if !self.line.trim().is_empty() {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> fmt::Debug for SceneReader<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SceneReader").field("version", &self.version).finish()
+    }
+}
+
+/// Deserializes a chunk of either kind into entities with their components.
+struct ChunkSeed<'a>(&'a ComponentRegistry, u32);
+
+impl<'de> DeserializeSeed<'de> for ChunkSeed<'_> {
+    type Value = Vec<(Entity, Vec<DeferredAssign>)>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ChunkSeed<'_> {
+    type Value = Vec<(Entity, Vec<DeferredAssign>)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a chunk of entities or of a pool")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        const FIELDS: &[&str] = &["entities", "pool", "components"];
+        let mut registration = None;
+        let mut entities = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if entities.is_some() {
+                return Err(de::Error::custom("a chunk can only hold one list of entities or components"));
+            }
+            match key.as_str() {
+                "entities" => entities = Some(map.next_value_seed(EntitiesSeed(self.0, self.1))?),
+                "pool" => {
+                    let name = map.next_value::<String>()?;
+                    let found = self.0.get(&name);
+                    let message = || de::Error::custom(format!("component type {name} is not registered"));
+                    registration = Some(found.ok_or_else(message)?);
+                }
+                "components" => {
+                    let registration =
+                        registration.ok_or_else(|| de::Error::custom("the pool has to come before its components"))?;
+                    entities = Some(map.next_value_seed(PoolComponentsSeed(registration, self.1))?);
+                }
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+        entities.ok_or_else(|| de::Error::missing_field("entities"))
+    }
+}
+
+struct PoolComponentsSeed<'a>(&'a Registration, u32);
+
+impl<'de> DeserializeSeed<'de> for PoolComponentsSeed<'_> {
+    type Value = Vec<(Entity, Vec<DeferredAssign>)>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PoolComponentsSeed<'_> {
+    type Value = Vec<(Entity, Vec<DeferredAssign>)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of entities and components")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(pair) = seq.next_element_seed(PairSeed(self.0.deserializer_for(self.1)))? {
+            components.push(pair);
+        }
+        Ok(components)
+    }
+}
+
+/// Deserializes an entity and one component of it, saved as a pair.
+struct PairSeed<'a>(&'a crate::serialization::DeserializeFn);
+
+impl<'de> DeserializeSeed<'de> for PairSeed<'_> {
+    type Value = (Entity, Vec<DeferredAssign>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+impl<'de> Visitor<'de> for PairSeed<'_> {
+    type Value = (Entity, Vec<DeferredAssign>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entity and a component")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let entity = seq.next_element::<Entity>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let component = seq
+            .next_element_seed(ComponentSeed(self.0))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok((entity, vec![component]))
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::{
+    component::{get_type_id, TypeIdSet},
+    entity::Entity,
+    error::RecsError,
+    hierarchy::{Children, Parent},
+    mapping::EntityMapper,
+    name::Name,
+    persistent::PersistentId,
+    prefab::Prefab,
+    registry::TypeRegistry,
+    relationship::{OwnedBy, Targets},
+    scene::Scene,
+    tag::Tags,
+};
+
+/// A scene used as a reusable chunk of another one, like a room, a building or a vehicle saved on its own.
+/// Every [`SubScene::instantiate`] copies all entities of the chunk into a target scene and attaches the
+/// entities without a parent to a root entity, so the copy can be moved, hidden or despawned as one subtree.
+///
+/// Components of the types registered in the [`TypeRegistry`] are cloned, so the types have to be registered
+/// with cloning; components of other types are left out. Hierarchies, names, string tags and relationships between
+/// entities of the chunk are rebuilt between their copies. Persistent IDs aren't copied, so that every instance
+/// gets IDs of its own. Overrides replace or remove components of specific entities in every instance,
+/// like the key a locked door of the chunk needs in one level.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::prefab::Prefab;
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+/// use yarecs::subscene::SubScene;
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Door { locked: bool }
+///
+/// let mut types = TypeRegistry::new();
+/// types.register::<Door>("Door").with_clone();
+///
+/// let mut room = Scene::new();
+/// let walls = room.create_entity();
+/// let door = room.create_entity();
+/// room.set_name(door, "Door").unwrap();
+/// room.assign(door, Door { locked: false }).unwrap();
+/// room.set_parent(door, walls).unwrap();
+///
+/// let mut level = Scene::new();
+/// let first_room = level.create_entity();
+/// let vault = level.create_entity();
+/// SubScene::new(&room, &types).instantiate(&mut level, first_room).unwrap();
+/// let locked = SubScene::new(&room, &types)
+///     .with_override(room.find_by_name("Door").unwrap(), Prefab::new().with(Door { locked: true }));
+/// let mapper = locked.instantiate(&mut level, vault).unwrap();
+///
+/// let vault_door = mapper.local(door).unwrap();
+/// assert_eq!(level.get::<Door>(vault_door).unwrap(), Some(&Door { locked: true }));
+/// assert_eq!(level.root_of(vault_door), vault);
+/// assert_eq!(level.view::<(Door, ())>().count(), 2);
+/// ```
+#[derive(Debug)]
+pub struct SubScene<'a> {
+    source: &'a Scene,
+    types: &'a TypeRegistry,
+    overrides: HashMap<Entity, Prefab>,
+    removals: Vec<(Entity, RemoveFn)>,
+}
+
+/// Removes the component of a type from an entity
+type RemoveFn = fn(&mut Scene, Entity) -> Result<(), RecsError>;
+
+impl<'a> SubScene<'a> {
+    pub fn new(source: &'a Scene, types: &'a TypeRegistry) -> Self {
+        SubScene { source, types, overrides: HashMap::new(), removals: Vec::new() }
+    }
+
+    /// Applies the components of a prefab to the copies of an entity of the chunk after copying it,
+    /// replacing the components it has. Overriding the same entity again replaces the previous override.
+    pub fn with_override(mut self, entity: Entity, overrides: Prefab) -> Self {
+        self.overrides.insert(entity, overrides);
+        self
+    }
+
+    /// Removes the component of type `T` from the copies of an entity of the chunk after copying it.
+    pub fn without<T: 'static>(mut self, entity: Entity) -> Self {
+        self.removals.push((entity, |scene, entity| scene.remove::<T>(entity)));
+        self
+    }
+
+    /// Copies the entities of the chunk into the target scene below `root` and returns which copy
+    /// stands for which entity of the chunk, with the entities of the chunk as the remote side.
+    /// Fails if `root` isn't alive or a component of a registered type can't be cloned.
+    pub fn instantiate(&self, target: &mut Scene, root: Entity) -> Result<EntityMapper, RecsError> {
+        if !target.is_alive(root) {
+            return Err(RecsError::InvalidEntityError);
+        }
+        // Components that refer to other entities or are kept in indexes are rebuilt instead of cloned
+        let rebuilt = [
+            get_type_id::<Parent>(),
+            get_type_id::<Children>(),
+            get_type_id::<Name>(),
+            get_type_id::<Tags>(),
+            get_type_id::<Targets>(),
+            get_type_id::<OwnedBy>(),
+            get_type_id::<PersistentId>(),
+        ]
+        .into_iter()
+        .collect::<TypeIdSet>();
+        let mut entities = self.source.entities().to_vec();
+        entities.sort_by_key(Entity::index);
+        let mut mapper = EntityMapper::new();
+        for &entity in &entities {
+            let copy = target.create_entity();
+            mapper.insert(entity, copy);
+            for (info, value) in self.types.components_of(self.source, entity) {
+                if rebuilt.contains(&info.type_id()) {
+                    continue;
+                }
+                let value = info.clone_value(value).ok_or_else(|| {
+                    RecsError::Other(format!("component type {} is registered without cloning", info.name()).into())
+                })?;
+                info.insert(target, copy, value)?;
+            }
+        }
+        for &entity in &entities {
+            let copy = mapper.local(entity).expect("every entity was copied");
+            self.rebuild(target, &mapper, entity, copy, root)?;
+        }
+        for (&entity, overrides) in &self.overrides {
+            if let Some(copy) = mapper.local(entity) {
+                overrides.apply(target, copy)?;
+            }
+        }
+        for &(entity, remove) in &self.removals {
+            if let Some(copy) = mapper.local(entity) {
+                remove(target, copy)?;
+            }
+        }
+        Ok(mapper)
+    }
+
+    /// Gives the copy of an entity the parent, name, tags and relationships the entity has in the chunk.
+    fn rebuild(
+        &self,
+        target: &mut Scene,
+        mapper: &EntityMapper,
+        entity: Entity,
+        copy: Entity,
+        root: Entity,
+    ) -> Result<(), RecsError> {
+        let source = self.source;
+        let local = |other: Entity| mapper.local(other).expect("every entity was copied");
+        match source.get::<Parent>(entity)? {
+            Some(parent) => target.set_parent(copy, local(parent.get()))?,
+            None => target.set_parent(copy, root)?,
+        }
+        if let Some(name) = source.get::<Name>(entity)? {
+            target.set_name(copy, name.as_str())?;
+        }
+        if let Some(tags) = source.get::<Tags>(entity)? {
+            for tag in tags.iter() {
+                target.add_tag(copy, tag)?;
+            }
+        }
+        if let Some(target_entity) = source.get::<Targets>(entity)?.map(Targets::get) {
+            if let Some(local_target) = mapper.local(target_entity) {
+                target.set_target(copy, local_target)?;
+            }
+        }
+        if let Some(owner) = source.get::<OwnedBy>(entity)?.map(OwnedBy::get) {
+            if let Some(local_owner) = mapper.local(owner) {
+                target.set_owner(copy, local_owner)?;
+            }
+        }
+        Ok(())
+    }
+}
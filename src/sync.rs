@@ -0,0 +1,224 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, RwLock,
+    },
+};
+
+use crate::{
+    component::{get_type_id, TypeIdMap},
+    entity::Entity,
+    error::RecsError,
+    pool::{ErasedPool, Pool},
+};
+
+type SyncPool = RwLock<Box<dyn ErasedPool + Send + Sync>>;
+
+/// Entities whose index falls into one shard of the entity table.
+#[derive(Debug, Default)]
+struct EntityShard {
+    /// Current version of each local index
+    versions: Vec<u32>,
+    /// Whether each local index is in use
+    alive: Vec<bool>,
+    free_list: Vec<u32>,
+}
+
+/// A scene that can be shared between threads, e.g. behind an `Arc`, for server workloads that want
+/// coarse-grained concurrent access without a scheduler.
+/// Each component pool sits behind its own lock, so threads working on different component types
+/// never block each other, and the entity table is split into shards, so threads creating and destroying
+/// entities rarely contend.
+///
+/// Components have to be `Send + Sync`. Closures passed to [`SyncScene::with_pool`] and
+/// [`SyncScene::with_pool_mut`] hold the lock of that pool, so they must not access the same pool
+/// through the scene again.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use yarecs::sync::SyncScene;
+///
+/// let scene = Arc::new(SyncScene::new());
+/// let workers = (0..4)
+///     .map(|_| {
+///         let scene = scene.clone();
+///         thread::spawn(move || {
+///             for i in 0..100 {
+///                 let entity = scene.create_entity();
+///                 scene.assign(entity, i as u64).unwrap();
+///             }
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+///
+/// let sum = scene.with_pool::<u64, _>(|pool| pool.as_slice().iter().sum::<u64>());
+/// assert_eq!(sum, Some(4 * 4950));
+/// ```
+pub struct SyncScene {
+    /// Entity table, split by entity index modulo the number of shards
+    entity_shards: Vec<Mutex<EntityShard>>,
+    /// Shard that the next entity is created in
+    next_shard: AtomicUsize,
+    /// Component pools keyed by type ID, each behind its own lock
+    pools: RwLock<TypeIdMap<SyncPool>>,
+}
+
+impl Default for SyncScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncScene {
+    /// Creates an empty scene with one entity shard per available CPU.
+    pub fn new() -> Self {
+        Self::with_entity_shards(std::thread::available_parallelism().map_or(1, usize::from))
+    }
+
+    /// Creates an empty scene whose entity table is split into the given number of shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_count` is 0.
+    pub fn with_entity_shards(shard_count: usize) -> Self {
+        assert!(shard_count > 0, "a scene needs at least one entity shard");
+        SyncScene {
+            entity_shards: (0..shard_count).map(|_| Mutex::default()).collect(),
+            next_shard: AtomicUsize::new(0),
+            pools: RwLock::default(),
+        }
+    }
+
+    pub fn create_entity(&self) -> Entity {
+        let shard_count = self.entity_shards.len();
+        let shard_index = self.next_shard.fetch_add(1, Ordering::Relaxed) % shard_count;
+        let mut shard = self.entity_shards[shard_index].lock().unwrap();
+        let local = match shard.free_list.pop() {
+            Some(local) => local,
+            None => {
+                shard.versions.push(0);
+                shard.alive.push(false);
+                shard.versions.len() as u32 - 1
+            }
+        };
+        shard.alive[local as usize] = true;
+        let index = local as usize * shard_count + shard_index;
+        Entity::with_version(index as u32, shard.versions[local as usize])
+    }
+
+    /// Destroys an entity and removes its components from all pools.
+    pub fn destroy_entity(&self, entity: Entity) -> Result<(), RecsError> {
+        {
+            let (mut shard, local) = self.entity_shard(entity);
+            if !shard.alive.get(local).copied().unwrap_or(false) || shard.versions[local] != entity.version() {
+                return Err(RecsError::InvalidEntityError);
+            }
+            shard.alive[local] = false;
+            shard.versions[local] = shard.versions[local].wrapping_add(1) % u32::MAX;
+            shard.free_list.push(local as u32);
+        }
+        for pool in self.pools.read().unwrap().values() {
+            pool.write().unwrap().free(entity);
+        }
+        Ok(())
+    }
+
+    /// Returns true if the entity was created by this scene and not destroyed yet.
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let (shard, local) = self.entity_shard(entity);
+        shard.alive.get(local).copied().unwrap_or(false) && shard.versions[local] == entity.version()
+    }
+
+    /// Assigns a component to an entity, replacing the component it already has.
+    pub fn assign<T: Send + Sync + 'static>(&self, entity: Entity, component: T) -> Result<(), RecsError> {
+        if !self.is_alive(entity) {
+            return Err(RecsError::InvalidEntityError);
+        }
+        // Checked again under the pool's lock: a concurrent destroy either comes first or frees the component after
+        let assigned = self
+            .with_pool_or_create(|pool: &mut Pool<T>| {
+                if !self.is_alive(entity) {
+                    return false;
+                }
+                match pool.get_mut(entity) {
+                    Some(existing) => *existing = component,
+                    None => {
+                        pool.assign(entity, component);
+                    }
+                }
+                true
+            })
+            .ok_or(RecsError::PoolAccessOrCreationError)?;
+        assigned.then_some(()).ok_or(RecsError::InvalidEntityError)
+    }
+
+    /// Removes a component from an entity.
+    pub fn remove<T: Send + Sync + 'static>(&self, entity: Entity) -> Result<(), RecsError> {
+        if !self.is_alive(entity) {
+            return Err(RecsError::InvalidEntityError);
+        }
+        let removed = self.with_pool_mut(|pool: &mut Pool<T>| {
+            let alive = self.is_alive(entity);
+            if alive {
+                pool.free(entity);
+            }
+            alive
+        });
+        match removed {
+            Some(false) => Err(RecsError::InvalidEntityError),
+            _ => Ok(()),
+        }
+    }
+
+    /// Calls `f` with shared access to the pool of `T`, if a component of this type was ever assigned.
+    /// Other threads can read the same pool at the same time.
+    pub fn with_pool<T: Send + Sync + 'static, R>(&self, f: impl FnOnce(&Pool<T>) -> R) -> Option<R> {
+        let pools = self.pools.read().unwrap();
+        let pool = pools.get(&get_type_id::<T>())?.read().unwrap();
+        pool.as_any().downcast_ref().map(f)
+    }
+
+    /// Calls `f` with exclusive access to the pool of `T`, if a component of this type was ever assigned.
+    /// Only threads accessing the same pool are blocked.
+    pub fn with_pool_mut<T: Send + Sync + 'static, R>(&self, f: impl FnOnce(&mut Pool<T>) -> R) -> Option<R> {
+        let pools = self.pools.read().unwrap();
+        let mut pool = pools.get(&get_type_id::<T>())?.write().unwrap();
+        pool.as_any_mut().downcast_mut().map(f)
+    }
+
+    fn with_pool_or_create<T: Send + Sync + 'static, R>(&self, f: impl FnOnce(&mut Pool<T>) -> R) -> Option<R> {
+        let type_id = get_type_id::<T>();
+        if !self.pools.read().unwrap().contains_key(&type_id) {
+            self.pools
+                .write()
+                .unwrap()
+                .entry(type_id)
+                .or_insert_with(|| RwLock::new(Box::new(Pool::<T>::new())));
+        }
+        self.with_pool_mut(f)
+    }
+
+    /// Locks the shard holding the entity and returns it with the entity's index within the shard.
+    fn entity_shard(&self, entity: Entity) -> (std::sync::MutexGuard<'_, EntityShard>, usize) {
+        let shard_count = self.entity_shards.len();
+        let index = entity.index() as usize;
+        (self.entity_shards[index % shard_count].lock().unwrap(), index / shard_count)
+    }
+}
+
+impl fmt::Debug for SyncScene {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SyncScene")
+            .field("entity_shards", &self.entity_shards.len())
+            .field("pools", &self.pools.read().unwrap().len())
+            .finish()
+    }
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashMap, sync::Arc};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::entity::Entity;
+
+/// The string tags of an entity, added with [`Scene::add_tag`] for grouping entities without defining a type per
+/// group. Every tag string is stored once per scene and shared by all entities that have it.
+///
+/// [`Scene::add_tag`]: crate::scene::Scene::add_tag
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags(pub(crate) Vec<Arc<str>>);
+
+impl Tags {
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|own| &**own == tag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|tag| &**tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Index from tags to the entities that have them, which also interns the tag strings.
+/// Entries may be stale if tags are removed directly, so lookups have to verify them.
+#[derive(Debug, Default)]
+pub(crate) struct TagIndex {
+    entities: FxHashMap<Arc<str>, FxHashSet<Entity>>,
+}
+
+impl TagIndex {
+    /// Adds the entity to the entities with the tag and returns the interned tag.
+    pub(crate) fn insert(&mut self, tag: &str, entity: Entity) -> Arc<str> {
+        match self.entities.get_key_value(tag) {
+            Some((interned, _)) => {
+                let interned = interned.clone();
+                self.entities.get_mut(tag).expect("the tag was just found").insert(entity);
+                interned
+            }
+            None => {
+                let interned = Arc::<str>::from(tag);
+                self.entities.insert(interned.clone(), FxHashSet::from_iter([entity]));
+                interned
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, tag: &str, entity: Entity) {
+        if let Some(entities) = self.entities.get_mut(tag) {
+            entities.remove(&entity);
+            if entities.is_empty() {
+                self.entities.remove(tag);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, tag: &str) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.get(tag).into_iter().flatten().copied()
+    }
+
+    /// Replaces the entities of all entries after the entities of the scene were compacted.
+    pub(crate) fn remap_entities(&mut self, remap: &HashMap<Entity, Entity>) {
+        for entities in self.entities.values_mut() {
+            *entities = entities.iter().filter_map(|entity| remap.get(entity).copied()).collect();
+        }
+        self.entities.retain(|_, entities| !entities.is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+use rustc_hash::FxHashSet;
+
+use crate::{entity::Entity, hierarchy::Parent, scene::Scene};
+
+/// The translation, rotation and scale of an entity relative to its parent, or to the world if it has none.
+/// Rotations are unit quaternions stored as `[x, y, z, w]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTransform {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl LocalTransform {
+    pub const IDENTITY: LocalTransform = LocalTransform {
+        translation: [0.0; 3],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+        scale: [1.0; 3],
+    };
+
+    pub fn from_translation(translation: [f32; 3]) -> Self {
+        LocalTransform { translation, ..Self::IDENTITY }
+    }
+
+    /// Returns the transform rotated by `angle` radians around the Z axis, the only rotation of 2D games.
+    pub fn with_rotation_z(self, angle: f32) -> Self {
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        LocalTransform { rotation: [0.0, 0.0, sin, cos], ..self }
+    }
+
+    pub fn with_scale(self, scale: [f32; 3]) -> Self {
+        LocalTransform { scale, ..self }
+    }
+
+    /// Returns the transform as a matrix.
+    pub fn to_global(&self) -> GlobalTransform {
+        let [x, y, z, w] = self.rotation;
+        let rotation = [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + w * z), 2.0 * (x * z - w * y)],
+            [2.0 * (x * y - w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + w * x)],
+            [2.0 * (x * z + w * y), 2.0 * (y * z - w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ];
+        let axes = [0, 1, 2].map(|axis| rotation[axis].map(|value| value * self.scale[axis]));
+        GlobalTransform { axes, translation: self.translation }
+    }
+}
+
+impl Default for LocalTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// The transform of an entity relative to the world, computed from its [`LocalTransform`] and those of its
+/// ancestors by [`TransformPropagation`]. Stored as an affine matrix, so non-uniform scales of parents are kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform {
+    /// The images of the X, Y and Z axes
+    pub axes: [[f32; 3]; 3],
+    pub translation: [f32; 3],
+}
+
+impl GlobalTransform {
+    pub const IDENTITY: GlobalTransform = GlobalTransform {
+        axes: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        translation: [0.0; 3],
+    };
+
+    /// Applies the rotation and scale of the transform to a direction.
+    pub fn transform_vector(&self, vector: [f32; 3]) -> [f32; 3] {
+        [0, 1, 2].map(|row| (0..3).map(|axis| self.axes[axis][row] * vector[axis]).sum())
+    }
+
+    /// Maps a point from the local space of the entity to the world.
+    pub fn transform_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let vector = self.transform_vector(point);
+        [0, 1, 2].map(|row| vector[row] + self.translation[row])
+    }
+
+    /// Returns the transform that applies `local` first and then `self`.
+    pub fn mul(&self, local: &GlobalTransform) -> GlobalTransform {
+        GlobalTransform {
+            axes: local.axes.map(|axis| self.transform_vector(axis)),
+            translation: self.transform_point(local.translation),
+        }
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Keeps the [`GlobalTransform`] of every entity with a [`LocalTransform`] up to date. Each run only visits the
+/// entities changed since the previous one, through assignments, mutable access or changes of their parent,
+/// together with their descendants. Entities without a local transform have no global transform either,
+/// and their children are placed relative to the world.
+///
+/// A run advances the change tick of the scene once it's done, so its own updates don't count as changes
+/// for the next run while later changes in the same frame do.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+/// use yarecs::transform::{GlobalTransform, LocalTransform, TransformPropagation};
+///
+/// let mut scene = Scene::new();
+/// let ship = scene.create_entity();
+/// let turret = scene.create_entity();
+/// scene.assign(ship, LocalTransform::from_translation([10.0, 0.0, 0.0])).unwrap();
+/// scene.assign(turret, LocalTransform::from_translation([0.0, 2.0, 0.0])).unwrap();
+/// scene.set_parent(turret, ship).unwrap();
+///
+/// let mut propagation = TransformPropagation::new();
+/// assert_eq!(propagation.run(&mut scene), 2);
+/// assert_eq!(scene.get::<GlobalTransform>(turret).unwrap().unwrap().translation, [10.0, 2.0, 0.0]);
+///
+/// // Scaling the ship moves the turret away from its center
+/// scene.get_mut::<LocalTransform>(ship).unwrap().unwrap().scale = [1.0, 3.0, 1.0];
+/// assert_eq!(propagation.run(&mut scene), 2);
+/// assert_eq!(scene.get::<GlobalTransform>(turret).unwrap().unwrap().translation, [10.0, 6.0, 0.0]);
+/// assert_eq!(propagation.run(&mut scene), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct TransformPropagation {
+    last_tick: Option<u32>,
+}
+
+impl TransformPropagation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the global transforms of the changed entities and their descendants and returns how many
+    /// entities were visited. The first run visits all entities.
+    pub fn run(&mut self, scene: &mut Scene) -> usize {
+        let changed = match self.last_tick {
+            Some(tick) => scene.entities_changed_since(tick),
+            None => scene.entities().to_vec(),
+        };
+        let mut roots = changed
+            .into_iter()
+            .map(|entity| (scene.ancestors(entity).count(), entity))
+            .collect::<Vec<_>>();
+        // Parents are updated before their descendants, which then don't have to be visited again
+        roots.sort_by_key(|&(depth, _)| depth);
+        let mut visited = FxHashSet::default();
+        for (_, root) in roots {
+            if visited.contains(&root) {
+                continue;
+            }
+            let subtree = std::iter::once(root).chain(scene.descendants(root)).collect::<Vec<_>>();
+            for entity in subtree {
+                update_global(scene, entity);
+                visited.insert(entity);
+            }
+        }
+        self.last_tick = Some(scene.change_tick());
+        scene.increment_change_tick();
+        visited.len()
+    }
+}
+
+/// Computes the global transform of an entity from its local transform and the global transform of its parent.
+fn update_global(scene: &mut Scene, entity: Entity) {
+    let Some(local) = scene.get::<LocalTransform>(entity).ok().flatten().copied() else {
+        if matches!(scene.get::<GlobalTransform>(entity), Ok(Some(_))) {
+            scene.remove::<GlobalTransform>(entity).ok();
+        }
+        return;
+    };
+    let parent = scene.get::<Parent>(entity).ok().flatten().map(Parent::get);
+    let parent_global = parent
+        .and_then(|parent| scene.get::<GlobalTransform>(parent).ok().flatten().copied())
+        .unwrap_or(GlobalTransform::IDENTITY);
+    let global = parent_global.mul(&local.to_global());
+    match scene.get_mut::<GlobalTransform>(entity) {
+        Ok(Some(current)) => *current = global,
+        Ok(None) => {
+            scene.assign(entity, global).ok();
+        }
+        Err(_) => {}
+    }
+}
@@ -0,0 +1,550 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt,
+    rc::Rc,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+};
+
+use crate::{entity::Entity, error::RecsError, mapping::EntityMapper, scene::Scene};
+
+/// Number of sent or received states kept as baselines for deltas
+const DEFAULT_HISTORY_LEN: usize = 32;
+/// Baseline sequence number of frames that carry the whole state
+const NO_BASELINE: u32 = u32::MAX;
+/// First byte of frames sent by the server
+const STATE_FRAME: u8 = b'S';
+/// First byte of frames acknowledging a state, sent by clients
+const ACK_FRAME: u8 = b'A';
+
+/// A connection between a server and one client that carries frames of bytes, like a UDP socket or a channel.
+/// Frames may be lost, duplicated or reordered: the replication protocol only ever builds on states the other side
+/// acknowledged, so lost frames are made up for by the next one.
+pub trait Transport {
+    /// Sends one frame to the other side.
+    fn send(&mut self, frame: &[u8]) -> Result<(), RecsError>;
+
+    /// Returns the next frame received from the other side, or `None` if none is waiting.
+    fn recv(&mut self) -> Result<Option<Vec<u8>>, RecsError>;
+}
+
+/// One end of a connection within the same process, for tests, listen servers and local multiplayer.
+#[derive(Debug)]
+pub struct LocalTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl LocalTransport {
+    /// Creates both ends of a connection.
+    pub fn pair() -> (LocalTransport, LocalTransport) {
+        let (first_sender, second_receiver) = mpsc::channel();
+        let (second_sender, first_receiver) = mpsc::channel();
+        (
+            LocalTransport { sender: first_sender, receiver: first_receiver },
+            LocalTransport { sender: second_sender, receiver: second_receiver },
+        )
+    }
+}
+
+impl Transport for LocalTransport {
+    fn send(&mut self, frame: &[u8]) -> Result<(), RecsError> {
+        self.sender.send(frame.to_vec()).map_err(|_| disconnected())
+    }
+
+    fn recv(&mut self) -> Result<Option<Vec<u8>>, RecsError> {
+        match self.receiver.try_recv() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(disconnected()),
+        }
+    }
+}
+
+fn disconnected() -> RecsError {
+    RecsError::Other("the other end of the transport was dropped".into())
+}
+
+/// Identifies a client connected to a [`ReplicationServer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClientId(u32);
+
+/// Encoded replicated components of the server scene, keyed by entity bits and component name
+type ServerState = BTreeMap<(u64, &'static str), Rc<[u8]>>;
+
+/// A connected client and the newest state it acknowledged
+struct Connection<T> {
+    transport: T,
+    acked: Option<u32>,
+}
+
+/// Sends the replicated components of an authoritative scene to clients, see [`Scene::register_replicated`].
+/// Every update sends each client only the components that differ from the newest state it acknowledged,
+/// or the whole state if it hasn't acknowledged one that is still kept. Clients apply the frames with a
+/// [`ReplicationClient`].
+///
+/// The server drains the scene's replication updates, so they can't be drained elsewhere as well.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::scene::Scene;
+/// use yarecs::transport::{LocalTransport, ReplicationClient, ReplicationServer};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Health(u32);
+///
+/// let mut server_scene = Scene::new();
+/// server_scene.register_replicated::<Health>("health", |health| health.0.to_le_bytes().to_vec());
+/// let player = server_scene.create_entity();
+/// server_scene.assign(player, Health(100)).unwrap();
+///
+/// let (server_end, client_end) = LocalTransport::pair();
+/// let mut server = ReplicationServer::new();
+/// server.connect(server_end);
+///
+/// let mut client_scene = Scene::new();
+/// let mut client = ReplicationClient::new(client_end);
+/// client.register::<Health>("health", |bytes| Some(Health(u32::from_le_bytes(bytes.try_into().ok()?))));
+///
+/// // One tick of the sync loop on each side
+/// server.update(&mut server_scene);
+/// client.update(&mut client_scene).unwrap();
+/// let local_player = client.mapper().local(player).unwrap();
+/// assert_eq!(client_scene.get::<Health>(local_player).unwrap(), Some(&Health(100)));
+///
+/// // Only the change is sent, relative to the state the client acknowledged
+/// server_scene.get_mut::<Health>(player).unwrap().unwrap().0 -= 30;
+/// server.update(&mut server_scene);
+/// client.update(&mut client_scene).unwrap();
+/// assert_eq!(client_scene.get::<Health>(local_player).unwrap(), Some(&Health(70)));
+///
+/// server_scene.destroy_entity(player);
+/// server.update(&mut server_scene);
+/// client.update(&mut client_scene).unwrap();
+/// assert!(!client_scene.is_alive(local_player));
+/// ```
+pub struct ReplicationServer<T> {
+    clients: BTreeMap<ClientId, Connection<T>>,
+    next_client: u32,
+    /// The state of the scene as of the last update
+    state: ServerState,
+    /// Sent states with their sequence numbers, oldest first
+    history: VecDeque<(u32, Rc<ServerState>)>,
+    history_len: usize,
+    /// Sequence number of the last sent state
+    sequence: u32,
+    /// Whether `state` was filled with the whole scene yet
+    synced: bool,
+}
+
+impl<T: Transport> Default for ReplicationServer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Transport> ReplicationServer<T> {
+    pub fn new() -> Self {
+        Self::with_history_len(DEFAULT_HISTORY_LEN)
+    }
+
+    /// Creates a server that keeps the last `len` sent states as baselines. Clients whose newest acknowledged state
+    /// is older, for example because their acknowledgements take longer than `len` updates to arrive, are sent
+    /// the whole state.
+    pub fn with_history_len(len: usize) -> Self {
+        ReplicationServer {
+            clients: BTreeMap::new(),
+            next_client: 0,
+            state: ServerState::new(),
+            history: VecDeque::with_capacity(len),
+            history_len: len.max(1),
+            sequence: 0,
+            synced: false,
+        }
+    }
+
+    /// Adds a client, which is sent the whole state on the next update.
+    pub fn connect(&mut self, transport: T) -> ClientId {
+        let id = ClientId(self.next_client);
+        self.next_client += 1;
+        self.clients.insert(id, Connection { transport, acked: None });
+        id
+    }
+
+    /// Removes a client and returns its transport.
+    pub fn disconnect(&mut self, client: ClientId) -> Option<T> {
+        self.clients.remove(&client).map(|connection| connection.transport)
+    }
+
+    /// Returns the connected clients.
+    pub fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.clients.keys().copied()
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Returns the sequence number of the newest state the client acknowledged.
+    pub fn acked(&self, client: ClientId) -> Option<u32> {
+        self.clients.get(&client)?.acked
+    }
+
+    /// Returns the sequence number of the last state sent, which is the number of updates so far.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Reads the clients' acknowledgements, then sends every client the changes of the scene since the newest
+    /// state it acknowledged. Clients whose transport fails or who send a malformed frame are disconnected;
+    /// they are returned with the error.
+    pub fn update(&mut self, scene: &mut Scene) -> Vec<(ClientId, RecsError)> {
+        let mut failed = Vec::new();
+        for (&id, connection) in &mut self.clients {
+            if let Err(error) = receive_acks(connection, self.sequence) {
+                failed.push((id, error));
+            }
+        }
+
+        let updates = scene.drain_replication_updates();
+        if self.synced {
+            for update in updates {
+                let key = (update.entity.to_bits(), update.component);
+                match update.bytes {
+                    Some(bytes) => self.state.insert(key, bytes.into()),
+                    None => self.state.remove(&key),
+                };
+            }
+        } else {
+            self.state = scene
+                .encode_replicated()
+                .into_iter()
+                .filter_map(|update| Some(((update.entity.to_bits(), update.component), update.bytes?.into())))
+                .collect();
+            self.synced = true;
+        }
+        self.sequence += 1;
+        let state = Rc::new(self.state.clone());
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back((self.sequence, Rc::clone(&state)));
+
+        let empty = ServerState::new();
+        for (&id, connection) in &mut self.clients {
+            if failed.iter().any(|(failed_id, _)| *failed_id == id) {
+                continue;
+            }
+            let (baseline_sequence, baseline) = connection
+                .acked
+                .and_then(|acked| self.history.iter().find(|(sequence, _)| *sequence == acked))
+                .map_or((NO_BASELINE, &empty), |(sequence, baseline)| (*sequence, &**baseline));
+            let frame = encode_state_frame(self.sequence, baseline_sequence, baseline, &state);
+            if let Err(error) = connection.transport.send(&frame) {
+                failed.push((id, error));
+            }
+        }
+        for (id, _) in &failed {
+            self.clients.remove(id);
+        }
+        failed
+    }
+}
+
+/// Records the newest state the client acknowledged. Late acknowledgements of older states are ignored.
+fn receive_acks<T: Transport>(connection: &mut Connection<T>, sequence: u32) -> Result<(), RecsError> {
+    while let Some(frame) = connection.transport.recv()? {
+        let mut reader = FrameReader(&frame);
+        if reader.u8()? != ACK_FRAME {
+            return Err(malformed());
+        }
+        let acked = reader.u32()?;
+        if acked <= sequence && connection.acked.is_none_or(|newest| acked > newest) {
+            connection.acked = Some(acked);
+        }
+    }
+    Ok(())
+}
+
+/// Encodes the components of `state` that differ from `baseline`, and the components of `baseline` that are gone.
+fn encode_state_frame(sequence: u32, baseline_sequence: u32, baseline: &ServerState, state: &ServerState) -> Vec<u8> {
+    let changed = state
+        .iter()
+        .filter(|&(key, bytes)| baseline.get(key) != Some(bytes))
+        .map(|(key, bytes)| (key, Some(bytes)));
+    let removed = baseline.keys().filter(|key| !state.contains_key(key)).map(|key| (key, None));
+    let entries = changed.chain(removed).collect::<Vec<_>>();
+
+    let mut frame = vec![STATE_FRAME];
+    frame.extend_from_slice(&sequence.to_le_bytes());
+    frame.extend_from_slice(&baseline_sequence.to_le_bytes());
+    frame.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for ((entity, name), bytes) in entries {
+        frame.extend_from_slice(&entity.to_le_bytes());
+        frame.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        frame.extend_from_slice(name.as_bytes());
+        match bytes {
+            Some(bytes) => {
+                frame.push(1);
+                frame.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                frame.extend_from_slice(bytes);
+            }
+            None => frame.push(0),
+        }
+    }
+    frame
+}
+
+/// Remote entity bits and name of a component received from the server
+type ComponentKey = (u64, Rc<str>);
+
+/// Encoded replicated components received from the server
+type ClientState = BTreeMap<ComponentKey, Rc<[u8]>>;
+
+/// Decodes a component and assigns it to an entity, replacing the component it has
+type ApplyFn = Box<dyn Fn(&mut Scene, Entity, &[u8]) -> Result<(), RecsError>>;
+
+/// A component type the client knows how to decode.
+struct ReplicatedType {
+    apply: ApplyFn,
+    remove: fn(&mut Scene, Entity) -> Result<(), RecsError>,
+}
+
+/// Applies the frames of a [`ReplicationServer`] to a client scene and acknowledges them.
+/// Server entities get local entities through an [`EntityMapper`]. A local entity is destroyed once its
+/// server entity has no replicated components left, since that can't be told apart from a destroyed entity.
+/// Components of types the client didn't register are ignored.
+///
+/// See [`ReplicationServer`] for an example.
+pub struct ReplicationClient<T> {
+    transport: T,
+    /// Decodable component types, keyed by the names they are replicated under
+    types: HashMap<String, ReplicatedType>,
+    mapper: EntityMapper,
+    /// Received states with their sequence numbers, oldest first
+    history: VecDeque<(u32, Rc<ClientState>)>,
+    history_len: usize,
+}
+
+impl<T: Transport> ReplicationClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self::with_history_len(transport, DEFAULT_HISTORY_LEN)
+    }
+
+    /// Creates a client that keeps the last `len` received states, which the server may send deltas against.
+    /// Use the same length as the server.
+    pub fn with_history_len(transport: T, len: usize) -> Self {
+        ReplicationClient {
+            transport,
+            types: HashMap::new(),
+            mapper: EntityMapper::new(),
+            history: VecDeque::with_capacity(len),
+            history_len: len.max(1),
+        }
+    }
+
+    /// Decodes components replicated under the given name with `decode`, which returns `None` for invalid bytes.
+    pub fn register<C: 'static>(&mut self, name: &str, decode: impl Fn(&[u8]) -> Option<C> + 'static) {
+        let type_name = name.to_owned();
+        let apply = move |scene: &mut Scene, entity, bytes: &[u8]| {
+            let component = decode(bytes)
+                .ok_or_else(|| RecsError::Other(format!("invalid {type_name} component received").into()))?;
+            match scene.get_mut::<C>(entity)? {
+                Some(existing) => *existing = component,
+                None => {
+                    scene.assign(entity, component)?;
+                }
+            }
+            Ok(())
+        };
+        let remove = |scene: &mut Scene, entity| scene.remove::<C>(entity);
+        self.types.insert(name.to_owned(), ReplicatedType { apply: Box::new(apply), remove });
+    }
+
+    /// Applies all received frames to the scene and acknowledges them. Frames older than the newest applied one
+    /// are skipped. Returns the number of applied frames.
+    pub fn update(&mut self, scene: &mut Scene) -> Result<usize, RecsError> {
+        let mut applied = 0;
+        while let Some(frame) = self.transport.recv()? {
+            let frame = StateFrame::decode(&frame)?;
+            if self.history.back().is_some_and(|(newest, _)| frame.sequence <= *newest) {
+                continue;
+            }
+            let mut state = if frame.baseline == NO_BASELINE {
+                ClientState::new()
+            } else {
+                match self.history.iter().find(|(sequence, _)| *sequence == frame.baseline) {
+                    Some((_, baseline)) => (**baseline).clone(),
+                    // The baseline is no longer kept, a later frame will build on a newer one
+                    None => continue,
+                }
+            };
+            for (key, bytes) in frame.entries {
+                match bytes {
+                    Some(bytes) => state.insert(key, bytes),
+                    None => state.remove(&key),
+                };
+            }
+
+            let empty = ClientState::new();
+            let current = self.history.back().map_or(&empty, |(_, current)| &**current);
+            apply_state(scene, &self.types, &mut self.mapper, current, &state)?;
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back((frame.sequence, Rc::new(state)));
+
+            let mut ack = vec![ACK_FRAME];
+            ack.extend_from_slice(&frame.sequence.to_le_bytes());
+            self.transport.send(&ack)?;
+            applied += 1;
+        }
+        Ok(applied)
+    }
+
+    /// Returns the mapping between server entities and the local entities standing for them.
+    pub fn mapper(&self) -> &EntityMapper {
+        &self.mapper
+    }
+
+    /// Returns the sequence number of the newest applied state.
+    pub fn sequence(&self) -> Option<u32> {
+        self.history.back().map(|(sequence, _)| *sequence)
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+}
+
+/// Brings the scene from the `current` state to the `new` one.
+fn apply_state(
+    scene: &mut Scene,
+    types: &HashMap<String, ReplicatedType>,
+    mapper: &mut EntityMapper,
+    current: &ClientState,
+    new: &ClientState,
+) -> Result<(), RecsError> {
+    mapper.retain_alive(scene);
+    for (key, bytes) in new {
+        let Some(replicated) = types.get(&*key.1) else {
+            continue;
+        };
+        if current.get(key) == Some(bytes) && mapper.local(remote_entity(key.0)?).is_some() {
+            continue;
+        }
+        let local = mapper.local_or_create(remote_entity(key.0)?, scene);
+        (replicated.apply)(scene, local, bytes)?;
+    }
+    for key in current.keys().filter(|key| !new.contains_key(*key)) {
+        let local = mapper.local(remote_entity(key.0)?);
+        if let (Some(replicated), Some(local)) = (types.get(&*key.1), local) {
+            (replicated.remove)(scene, local)?;
+        }
+    }
+    let remaining = new.keys().map(|(entity, _)| *entity).collect::<BTreeSet<_>>();
+    for &(entity, _) in current.keys() {
+        if !remaining.contains(&entity) {
+            mapper.destroy_remote(remote_entity(entity)?, scene);
+        }
+    }
+    Ok(())
+}
+
+fn remote_entity(bits: u64) -> Result<Entity, RecsError> {
+    Entity::from_bits(bits).ok_or_else(malformed)
+}
+
+/// A decoded frame sent by the server
+struct StateFrame {
+    sequence: u32,
+    /// Sequence number of the state the entries are relative to, or `NO_BASELINE`
+    baseline: u32,
+    /// Changed components, and removed ones without bytes
+    entries: Vec<(ComponentKey, Option<Rc<[u8]>>)>,
+}
+
+impl StateFrame {
+    fn decode(frame: &[u8]) -> Result<Self, RecsError> {
+        let mut reader = FrameReader(frame);
+        if reader.u8()? != STATE_FRAME {
+            return Err(malformed());
+        }
+        let sequence = reader.u32()?;
+        let baseline = reader.u32()?;
+        let len = reader.u32()?;
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            let entity = reader.u64()?;
+            let name_len = reader.u16()? as usize;
+            let name = std::str::from_utf8(reader.take(name_len)?).map_err(|_| malformed())?;
+            let bytes = match reader.u8()? {
+                0 => None,
+                _ => {
+                    let len = reader.u32()? as usize;
+                    Some(reader.take(len)?.into())
+                }
+            };
+            entries.push(((entity, name.into()), bytes));
+        }
+        Ok(StateFrame { sequence, baseline, entries })
+    }
+}
+
+/// Reads little-endian values from the front of a frame
+struct FrameReader<'a>(&'a [u8]);
+
+impl<'a> FrameReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RecsError> {
+        if self.0.len() < len {
+            return Err(malformed());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RecsError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RecsError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().map_err(|_| malformed())?))
+    }
+
+    fn u32(&mut self) -> Result<u32, RecsError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().map_err(|_| malformed())?))
+    }
+
+    fn u64(&mut self) -> Result<u64, RecsError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().map_err(|_| malformed())?))
+    }
+}
+
+fn malformed() -> RecsError {
+    RecsError::Other("malformed replication frame".into())
+}
+
+impl<T> fmt::Debug for ReplicationServer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReplicationServer")
+            .field("clients", &self.clients.iter().map(|(id, connection)| (id, connection.acked)).collect::<Vec<_>>())
+            .field("components", &self.state.len())
+            .field("sequence", &self.sequence)
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for ReplicationClient<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReplicationClient")
+            .field("types", &self.types.keys().collect::<Vec<_>>())
+            .field("entities", &self.mapper.len())
+            .field("sequence", &self.history.back().map(|(sequence, _)| sequence))
+            .finish()
+    }
+}
@@ -0,0 +1,329 @@
+use std::{any::Any, fmt};
+
+use crate::{
+    component::{get_type_id, get_type_name},
+    entity::Entity,
+    error::RecsError,
+    registry::{clone_fn, insert_fn, CloneFn, InsertFn, RemoveFn, TypeRegistry},
+    scene::Scene,
+};
+
+/// A copy of a component, with the functions to put it back
+struct SavedComponent {
+    type_id: usize,
+    value: Box<dyn Any>,
+    clone: CloneFn,
+    insert: InsertFn,
+}
+
+impl SavedComponent {
+    fn restore(&self, scene: &mut Scene, entity: Entity) -> Result<(), RecsError> {
+        (self.insert)(scene, entity, (self.clone)(&*self.value))
+    }
+}
+
+/// A change to a scene that can be undone and redone
+enum Change {
+    Created(Entity),
+    Destroyed { entity: Entity, components: Vec<SavedComponent> },
+    /// A component was assigned, removed or edited. `None` means the entity had no component of the type.
+    Component { entity: Entity, before: Option<SavedComponent>, after: Option<SavedComponent>, remove: RemoveFn },
+}
+
+impl Change {
+    fn undo(&self, scene: &mut Scene) -> Result<(), RecsError> {
+        match self {
+            Change::Created(entity) => scene.destroy_entity(*entity),
+            Change::Destroyed { entity, components } => {
+                scene.create_entity_at(*entity)?;
+                for component in components {
+                    component.restore(scene, *entity)?;
+                }
+            }
+            Change::Component { entity, before, remove, .. } => set_component(scene, *entity, before, *remove)?,
+        }
+        Ok(())
+    }
+
+    fn redo(&self, scene: &mut Scene) -> Result<(), RecsError> {
+        match self {
+            Change::Created(entity) => scene.create_entity_at(*entity)?,
+            Change::Destroyed { entity, .. } => scene.destroy_entity(*entity),
+            Change::Component { entity, after, remove, .. } => set_component(scene, *entity, after, *remove)?,
+        }
+        Ok(())
+    }
+}
+
+fn set_component(
+    scene: &mut Scene,
+    entity: Entity,
+    component: &Option<SavedComponent>,
+    remove: RemoveFn,
+) -> Result<(), RecsError> {
+    match component {
+        Some(component) => component.restore(scene, entity),
+        None => remove(scene, entity),
+    }
+}
+
+/// Records the changes made to a scene through it, so they can be undone and redone, as editors need.
+/// Changes are made through the stack, which applies them to the scene and remembers how to revert them.
+/// Changes made to the scene directly aren't recorded and shouldn't be mixed with recorded ones,
+/// since undoing relies on the scene being in the state the recorded change left it in.
+///
+/// Several changes can be grouped into one undo step with [`UndoStack::begin_group`] and
+/// [`UndoStack::end_group`]. Recording a new change discards the changes that could be redone.
+///
+/// # Example
+///
+/// ```
+/// use yarecs::registry::TypeRegistry;
+/// use yarecs::scene::Scene;
+/// use yarecs::undo::UndoStack;
+///
+/// #[derive(Clone)]
+/// struct Position(f32, f32);
+/// #[derive(Clone)]
+/// struct Name(String);
+///
+/// let mut types = TypeRegistry::new();
+/// types.register::<Position>("Position").with_clone();
+/// types.register::<Name>("Name").with_clone();
+///
+/// let mut scene = Scene::new();
+/// let mut history = UndoStack::new();
+///
+/// history.begin_group();
+/// let tree = history.create_entity(&mut scene);
+/// history.assign(&mut scene, tree, Name("oak".to_string())).unwrap();
+/// history.assign(&mut scene, tree, Position(0.0, 0.0)).unwrap();
+/// history.end_group();
+///
+/// // Drag the tree, then delete it
+/// history.edit(&mut scene, tree, |position: &mut Position| position.0 = 4.0).unwrap();
+/// history.destroy_entity(&mut scene, tree, &types);
+/// assert!(!scene.is_alive(tree));
+///
+/// history.undo(&mut scene).unwrap();
+/// assert_eq!(scene.get::<Name>(tree).unwrap().unwrap().0, "oak");
+/// history.undo(&mut scene).unwrap();
+/// assert_eq!(scene.get::<Position>(tree).unwrap().unwrap().0, 0.0);
+/// history.undo(&mut scene).unwrap();
+/// assert!(!scene.is_alive(tree));
+/// assert!(!history.can_undo());
+///
+/// history.redo(&mut scene).unwrap();
+/// assert!(scene.get::<Position>(tree).unwrap().is_some());
+/// ```
+#[derive(Default)]
+pub struct UndoStack {
+    /// Undo steps, most recent last, each a list of changes in the order they were made
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    /// Changes of the group being recorded
+    group: Option<Vec<Change>>,
+}
+
+impl UndoStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an entity and records it.
+    pub fn create_entity(&mut self, scene: &mut Scene) -> Entity {
+        let entity = scene.create_entity();
+        self.record(Change::Created(entity));
+        entity
+    }
+
+    /// Destroys an entity and records copies of its components, so undoing brings it back.
+    /// Only components of types registered with cloning enabled are brought back.
+    pub fn destroy_entity(&mut self, scene: &mut Scene, entity: Entity, types: &TypeRegistry) {
+        let components = types
+            .components_of(scene, entity)
+            .filter_map(|(info, value)| {
+                Some(SavedComponent {
+                    type_id: info.type_id(),
+                    value: info.clone_value(value)?,
+                    clone: info.clone?,
+                    insert: info.insert,
+                })
+            })
+            .collect();
+        scene.destroy_entity(entity);
+        self.record(Change::Destroyed { entity, components });
+    }
+
+    /// Assigns a component, replacing the component the entity already has, and records it.
+    pub fn assign<T: Clone + 'static>(
+        &mut self,
+        scene: &mut Scene,
+        entity: Entity,
+        component: T,
+    ) -> Result<(), RecsError> {
+        let before = saved(scene.get::<T>(entity)?);
+        insert_fn::<T>()(scene, entity, Box::new(component.clone()))?;
+        self.record(Change::Component {
+            entity,
+            before,
+            after: saved(Some(&component)),
+            remove: |scene, entity| scene.remove::<T>(entity),
+        });
+        Ok(())
+    }
+
+    /// Removes a component and records a copy of it.
+    pub fn remove<T: Clone + 'static>(&mut self, scene: &mut Scene, entity: Entity) -> Result<(), RecsError> {
+        let before = saved(scene.get::<T>(entity)?);
+        scene.remove::<T>(entity)?;
+        if before.is_some() {
+            self.record(Change::Component {
+                entity,
+                before,
+                after: None,
+                remove: |scene, entity| scene.remove::<T>(entity),
+            });
+        }
+        Ok(())
+    }
+
+    /// Changes a component in place and records its value before and after. Fails if the entity has no
+    /// component of type `T`.
+    pub fn edit<T: Clone + 'static, R>(
+        &mut self,
+        scene: &mut Scene,
+        entity: Entity,
+        edit: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, RecsError> {
+        let component = scene.get_mut::<T>(entity)?.ok_or_else(|| {
+            RecsError::Other(format!("{entity:?} has no component of type {}", std::any::type_name::<T>()).into())
+        })?;
+        let before = saved(Some(&*component));
+        let result = edit(component);
+        let after = saved(Some(&*component));
+        self.record(Change::Component { entity, before, after, remove: |scene, entity| scene.remove::<T>(entity) });
+        Ok(result)
+    }
+
+    /// Starts a group of changes that are undone and redone together. Groups don't nest:
+    /// starting a group while one is open continues the open group.
+    pub fn begin_group(&mut self) {
+        self.group.get_or_insert_with(Vec::new);
+    }
+
+    /// Ends the group of changes started with [`UndoStack::begin_group`].
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take() {
+            if !group.is_empty() {
+                self.undo.push(group);
+            }
+        }
+    }
+
+    /// Reverts the most recent undo step. Returns false if there is nothing to undo.
+    /// An open group is ended first. If reverting a change fails, the changes of the step that were already
+    /// reverted are made again, as far as possible, and the step stays on the undo stack.
+    pub fn undo(&mut self, scene: &mut Scene) -> Result<bool, RecsError> {
+        self.end_group();
+        let Some(step) = self.undo.pop() else {
+            return Ok(false);
+        };
+        for (index, change) in step.iter().enumerate().rev() {
+            if let Err(error) = change.undo(scene) {
+                for change in &step[index + 1..] {
+                    change.redo(scene).ok();
+                }
+                self.undo.push(step);
+                return Err(error);
+            }
+        }
+        self.redo.push(step);
+        Ok(true)
+    }
+
+    /// Makes the most recently undone step again. Returns false if there is nothing to redo.
+    /// If making a change fails, the changes of the step that were already made are reverted again,
+    /// as far as possible, and the step stays on the redo stack.
+    pub fn redo(&mut self, scene: &mut Scene) -> Result<bool, RecsError> {
+        self.end_group();
+        let Some(step) = self.redo.pop() else {
+            return Ok(false);
+        };
+        for (index, change) in step.iter().enumerate() {
+            if let Err(error) = change.redo(scene) {
+                for change in step[..index].iter().rev() {
+                    change.undo(scene).ok();
+                }
+                self.redo.push(step);
+                return Err(error);
+            }
+        }
+        self.undo.push(step);
+        Ok(true)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.group.as_ref().is_some_and(|group| !group.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forgets all recorded changes.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+    }
+
+    fn record(&mut self, change: Change) {
+        self.redo.clear();
+        match &mut self.group {
+            Some(group) => group.push(change),
+            None => self.undo.push(vec![change]),
+        }
+    }
+}
+
+/// Saves a copy of a component, if there is one.
+fn saved<T: Clone + 'static>(component: Option<&T>) -> Option<SavedComponent> {
+    component.map(|component| SavedComponent {
+        type_id: get_type_id::<T>(),
+        value: Box::new(component.clone()),
+        clone: clone_fn::<T>(),
+        insert: insert_fn::<T>(),
+    })
+}
+
+impl fmt::Debug for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Created(entity) => f.debug_tuple("Created").field(entity).finish(),
+            Change::Destroyed { entity, components } => f
+                .debug_tuple("Destroyed")
+                .field(entity)
+                .field(&components.iter().map(|component| type_name(component.type_id)).collect::<Vec<_>>())
+                .finish(),
+            Change::Component { entity, before, after, .. } => {
+                let type_id = before.as_ref().or(after.as_ref()).map_or(usize::MAX, |component| component.type_id);
+                f.debug_tuple("Component").field(entity).field(&type_name(type_id)).finish()
+            }
+        }
+    }
+}
+
+fn type_name(type_id: usize) -> &'static str {
+    get_type_name(type_id).unwrap_or("?")
+}
+
+impl fmt::Debug for UndoStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UndoStack")
+            .field("undo", &self.undo)
+            .field("redo", &self.redo)
+            .field("group", &self.group)
+            .finish()
+    }
+}
@@ -0,0 +1,208 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, Index, LitStr, Member};
+
+/// Derives `yarecs::soa::SoAComponent` for a struct with named fields.
+/// Generates a `<Name>Columns` struct holding one `Vec` per field, with the same visibility as the struct.
+#[proc_macro_derive(SoAComponent)]
+pub fn derive_soa_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    soa_component(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn soa_component(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "SoAComponent can't be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "SoAComponent can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "SoAComponent can only be derived for structs",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let visibility = &input.vis;
+    let columns = format_ident!("{}Columns", name);
+    let field_names = fields.iter().map(|field| &field.ident).collect::<Vec<_>>();
+    let field_types = fields.iter().map(|field| &field.ty);
+    let doc = format!("Columns of [`{}`] components, one `Vec` per field.", name);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug)]
+        #visibility struct #columns {
+            #(pub #field_names: ::std::vec::Vec<#field_types>,)*
+        }
+
+        impl ::std::default::Default for #columns {
+            fn default() -> Self {
+                #columns {
+                    #(#field_names: ::std::vec::Vec::new(),)*
+                }
+            }
+        }
+
+        impl ::yarecs::soa::SoAColumns for #columns {
+            type Item = #name;
+
+            fn push(&mut self, value: #name) {
+                #(self.#field_names.push(value.#field_names);)*
+            }
+
+            fn swap_remove(&mut self, row: usize) -> #name {
+                #name {
+                    #(#field_names: self.#field_names.swap_remove(row),)*
+                }
+            }
+
+            fn clear(&mut self) {
+                #(self.#field_names.clear();)*
+            }
+
+            fn shrink_to_fit(&mut self) {
+                #(self.#field_names.shrink_to_fit();)*
+            }
+        }
+
+        impl ::yarecs::soa::SoAComponent for #name {
+            type Columns = #columns;
+        }
+    })
+}
+
+/// Derives `yarecs::component::Component`.
+/// The `#[component(...)]` attribute sets the name (`name = "..."`, the type name by default),
+/// the preferred storage (`storage = Table`) and the functions to enable when registering the type
+/// (`default`, `clone`, `debug`, `serde`, `reflect`).
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    component(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn component(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Component can't be derived for generic types",
+        ));
+    }
+
+    let ident = &input.ident;
+    let mut name = ident.to_string();
+    let mut storage = None;
+    let mut enabled = Vec::new();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("component")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("storage") {
+                let value = meta.value()?.parse::<Ident>()?;
+                match value.to_string().as_str() {
+                    "Sparse" | "Table" | "Slab" | "Small" => storage = Some(value),
+                    _ => return Err(Error::new_spanned(value, "expected Sparse, Table, Slab or Small")),
+                }
+            } else if let Some(function) = ["default", "clone", "debug", "serde", "reflect"]
+                .into_iter()
+                .find(|function| meta.path.is_ident(function))
+            {
+                enabled.push(format_ident!("with_{}", function));
+            } else {
+                return Err(meta.error("unknown component attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    let storage = match storage {
+        Some(storage) => quote!(::std::option::Option::Some(::yarecs::archetype::StorageType::#storage)),
+        None => quote!(::std::option::Option::None),
+    };
+
+    Ok(quote! {
+        impl ::yarecs::component::Component for #ident {
+            const NAME: &'static str = #name;
+            const STORAGE: ::std::option::Option<::yarecs::archetype::StorageType> = #storage;
+
+            fn register(
+                registration: ::yarecs::registry::TypeRegistration<'_, Self>,
+            ) -> ::yarecs::registry::TypeRegistration<'_, Self> {
+                registration #(.#enabled())*
+            }
+        }
+    })
+}
+
+/// Derives `yarecs::reflect::Reflect` for a struct, describing each of its fields.
+#[proc_macro_derive(Reflect)]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    reflect(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn reflect(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Reflect can't be derived for generic types",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Reflect can only be derived for structs",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        })
+        .collect::<Vec<_>>();
+    let field_names = members.iter().map(|member| match member {
+        Member::Named(ident) => ident.to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    });
+    let field_types = fields.iter().map(|field| &field.ty);
+
+    Ok(quote! {
+        impl ::yarecs::reflect::Reflect for #name {
+            fn fields() -> ::std::vec::Vec<::yarecs::reflect::FieldInfo> {
+                ::std::vec![
+                    #(::yarecs::reflect::FieldInfo::new::<#field_types>(
+                        #field_names,
+                        |value| ::std::option::Option::Some(&value.downcast_ref::<Self>()?.#members),
+                        |value| ::std::option::Option::Some(&mut value.downcast_mut::<Self>()?.#members),
+                    ),)*
+                ]
+            }
+        }
+    })
+}